@@ -0,0 +1,25 @@
+// addresses differ between runs, so unlike most examples this one isn't checked by a test
+
+use std::rc::Rc;
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let shared = Rc::new(vec![1, 2, 3]);
+    same(Rc::clone(&shared), Rc::clone(&shared));
+    same(shared, Rc::new(vec![1, 2, 3]));
+    let mut buf = [0u8; 4];
+    fill(&mut buf, 7);
+}
+
+#[trace(addr(a, b))]
+fn same(a: Rc<Vec<i32>>, b: Rc<Vec<i32>>) -> bool {
+    Rc::ptr_eq(&a, &b)
+}
+
+#[trace(addr_only(buf))]
+fn fill(buf: &mut [u8], value: u8) {
+    buf.fill(value);
+}
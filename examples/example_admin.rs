@@ -0,0 +1,43 @@
+use trace_runtime::admin::{handle, Request};
+
+trace::init_depth_var!();
+
+// a server would call `handle` with the requests it gets on its debug port
+fn request(method: &str, path: &str) {
+    let response = handle(&Request { method, path });
+    println!(
+        "{} {} -> {} {}",
+        method,
+        path,
+        response.status,
+        response.body.trim_end()
+    );
+}
+
+fn main() {
+    jobs::run(2);
+    request("GET", "/functions");
+    request("POST", "/functions/example_admin::jobs::step/disable");
+    request("POST", "/functions/example_admin::jobs::missing/disable");
+    jobs::run(1);
+}
+
+#[trace::trace(registry)]
+mod jobs {
+    pub(super) fn run(steps: u32) {
+        for i in 0..steps {
+            step(i);
+        }
+    }
+
+    fn step(i: u32) -> u32 {
+        i + 1
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_admin, main());
@@ -0,0 +1,35 @@
+use trace::trace;
+
+#[global_allocator]
+static ALLOCATOR: trace_runtime::TrackingAllocator = trace_runtime::TrackingAllocator;
+
+trace::init_depth_var!();
+
+fn main() {
+    build(3);
+    sum_squares(100);
+}
+
+#[trace(alloc_peak)]
+fn build(n: usize) -> usize {
+    (0..n).map(|i| scratch(i * 256)).sum()
+}
+
+// the buffer is freed before returning, but still counts towards the peak of the caller
+#[trace(alloc_peak)]
+fn scratch(size: usize) -> usize {
+    let buf = vec![0u8; size];
+    buf.len()
+}
+
+#[trace(alloc_peak)]
+fn sum_squares(n: u64) -> u64 {
+    (0..n).map(|i| i * i).sum()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_alloc_peak, main());
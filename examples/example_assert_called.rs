@@ -0,0 +1,32 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let user = load_user(7);
+    save_user(&user);
+    save_user("guest");
+
+    trace::assert_called!(load_user, times = 1);
+    trace::assert_called!(save_user, times = 2);
+    trace::assert_called_with!(load_user, id = 7);
+    trace::assert_called_with!(save_user, name = "guest");
+    println!("all assertions passed");
+}
+
+#[trace(record_calls)]
+fn load_user(id: u32) -> String {
+    format!("user{}", id)
+}
+
+#[trace(record_calls)]
+fn save_user(name: &str) {
+    let _ = name;
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_assert_called, main());
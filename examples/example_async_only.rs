@@ -0,0 +1,58 @@
+use async_std::task;
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    task::block_on(async {
+        let mut cache = Cache { hits: 0 };
+        cache.fetch("a").await;
+        cache.fetch("b").await;
+        cache.record_hit();
+        storage::read("c").await;
+    });
+}
+
+struct Cache {
+    hits: u32,
+}
+
+#[trace(async_only)]
+impl Cache {
+    async fn fetch(&mut self, key: &str) -> String {
+        if self.is_cached(key) {
+            self.record_hit();
+        }
+        load(key).await
+    }
+
+    fn is_cached(&self, key: &str) -> bool {
+        key == "a"
+    }
+
+    fn record_hit(&mut self) {
+        self.hits += 1;
+    }
+}
+
+#[trace(sync_only)]
+mod storage {
+    pub(super) async fn read(key: &str) -> String {
+        format!("value of {}", path(key))
+    }
+
+    fn path(key: &str) -> String {
+        format!("/data/{}", key)
+    }
+}
+
+async fn load(key: &str) -> String {
+    format!("value of {}", key)
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_async_only, main());
@@ -0,0 +1,56 @@
+use std::{
+    future::{self, Future},
+    pin::pin,
+    task::Poll,
+};
+
+use async_std::task;
+use trace::trace;
+
+trace::init_depth_var!();
+
+#[trace]
+async fn fetch(id: u32) -> u32 {
+    task::yield_now().await;
+    parse(id).await
+}
+
+#[trace]
+async fn parse(id: u32) -> u32 {
+    task::yield_now().await;
+    id * 10
+}
+
+// polls both futures in turn on the current thread until both are done
+async fn join(a: impl Future<Output = u32>, b: impl Future<Output = u32>) -> (u32, u32) {
+    let (mut a, mut b) = (pin!(a), pin!(b));
+    let (mut a_out, mut b_out) = (None, None);
+    future::poll_fn(|cx| {
+        if a_out.is_none() {
+            if let Poll::Ready(out) = a.as_mut().poll(cx) {
+                a_out = Some(out);
+            }
+        }
+        if b_out.is_none() {
+            if let Poll::Ready(out) = b.as_mut().poll(cx) {
+                b_out = Some(out);
+            }
+        }
+        match (a_out, b_out) {
+            (Some(a), Some(b)) => Poll::Ready((a, b)),
+            _ => Poll::Pending,
+        }
+    })
+    .await
+}
+
+fn main() {
+    task::block_on(join(fetch(1), fetch(2)));
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_async_tasks, main());
@@ -0,0 +1,74 @@
+use async_std::task;
+use trace::trace;
+
+trace::init_depth_var!();
+
+#[async_trait::async_trait]
+trait Store {
+    async fn get(&self, key: &str) -> Option<u32>;
+
+    async fn get_or(&self, key: &str, default: u32) -> u32 {
+        self.get(key).await.unwrap_or(default)
+    }
+}
+
+struct Memory {
+    entries: Vec<(String, u32)>,
+}
+
+struct Empty;
+
+// `#[async_trait]` is expanded first here, and the method it returns a boxed future from is still
+// traced like the `async fn` it was written as
+#[async_trait::async_trait]
+#[trace]
+impl Store for Memory {
+    async fn get(&self, key: &str) -> Option<u32> {
+        lookup(&self.entries, key).await
+    }
+}
+
+#[async_trait::async_trait]
+impl Store for Empty {
+    #[trace]
+    async fn get(&self, key: &str) -> Option<u32> {
+        if key.is_empty() {
+            return Some(0);
+        }
+        None
+    }
+
+    // moved after `#[allow]` (and any other attribute that follows it) before it's applied
+    #[trace(apply = "last")]
+    #[allow(clippy::needless_return)]
+    async fn get_or(&self, key: &str, default: u32) -> u32 {
+        return self.get(key).await.unwrap_or(default);
+    }
+}
+
+#[trace]
+async fn lookup(entries: &[(String, u32)], key: &str) -> Option<u32> {
+    entries
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|&(_, value)| value)
+}
+
+fn main() {
+    task::block_on(async {
+        let memory = Memory {
+            entries: vec![("a".to_string(), 1)],
+        };
+        memory.get("a").await;
+        memory.get_or("b", 2).await;
+        Empty.get("").await;
+        Empty.get_or("c", 3).await;
+    });
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_attribute_order, main());
@@ -0,0 +1,76 @@
+use std::fmt::Display;
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    largest(&[1, 5, 3]);
+    largest_bounded(&[1, 5, 3]);
+    label("count", 3);
+
+    let mut stack = Stack { items: vec![] };
+    stack.push(1);
+    stack.peek();
+    // a `Stack` of values that don't implement `Debug` can still use the methods that don't
+    // print them
+    let mut handles = Stack { items: vec![] };
+    handles.items.push(Handle);
+    handles.len();
+}
+
+// without a `Debug` bound, values of type `T` are printed as the name of their type
+#[trace]
+fn largest<T: PartialOrd + Copy>(items: &[T]) -> T {
+    items
+        .iter()
+        .copied()
+        .fold(items[0], |max, item| if item > max { item } else { max })
+}
+
+#[trace(auto_bound)]
+fn largest_bounded<T: PartialOrd + Copy>(items: &[T]) -> T {
+    items
+        .iter()
+        .copied()
+        .fold(items[0], |max, item| if item > max { item } else { max })
+}
+
+// `value` isn't printed, so `V` doesn't need to implement `Debug`
+#[trace(auto_bound, disable(value))]
+fn label<K, V: Display>(key: K, value: V) -> String
+where
+    K: Display,
+{
+    format!("{key}: {value}")
+}
+
+struct Stack<T> {
+    items: Vec<T>,
+}
+
+#[derive(Clone)]
+struct Handle;
+
+// the methods that print a `T` get a `where T: Debug` clause, the others don't
+#[trace(auto_bound)]
+impl<T: Clone> Stack<T> {
+    fn push(&mut self, item: T) {
+        self.items.push(item);
+    }
+
+    fn peek(&self) -> Option<T> {
+        self.items.last().cloned()
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_auto_bound, main());
@@ -0,0 +1,30 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // the first run saves its stats as the baseline, and later runs are compared to it
+    let baseline = std::env::temp_dir().join("trace_example_baseline.stats");
+    let has_baseline = trace::load_baseline!(&baseline).is_ok();
+
+    let input = "1 2 3";
+    sum(&tokenize(input));
+
+    trace::print_stats!();
+    if !has_baseline {
+        trace::save_stats!(&baseline).expect("couldn't save the baseline");
+    }
+}
+
+#[trace(stats)]
+fn tokenize(input: &str) -> Vec<u32> {
+    input
+        .split(' ')
+        .map(|token| token.parse().unwrap())
+        .collect()
+}
+
+#[trace(stats)]
+fn sum(numbers: &[u32]) -> u32 {
+    numbers.iter().sum()
+}
@@ -0,0 +1,34 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+// the report is printed when `main` returns, since it's traced with `bench` as well
+#[trace(bench)]
+fn main() {
+    for n in 0..20 {
+        collatz_steps(n * 7 + 1);
+    }
+    // the report so far can also be taken at any time
+    let report = trace::bench_report!();
+    assert!(report.contains("collatz_steps"));
+}
+
+#[trace(bench)]
+fn collatz_steps(n: u64) -> u32 {
+    let mut steps = 0;
+    let mut n = n;
+    while n != 1 {
+        n = next(n);
+        steps += 1;
+    }
+    steps
+}
+
+#[trace(bench)]
+fn next(n: u64) -> u64 {
+    if n & 1 == 0 {
+        n / 2
+    } else {
+        3 * n + 1
+    }
+}
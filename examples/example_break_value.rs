@@ -0,0 +1,103 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    first_multiple(&[3, 5, 10, 12], 5);
+    first_multiple(&[3, 7], 5);
+    classify(-4);
+    classify(0);
+    classify(9);
+    find_pair(&[1, 4, 6, 9], 10);
+    find_pair(&[1, 2], 10);
+    let _ = parse_all(&["1", "2"]);
+    let _ = parse_all(&["1", "x"]);
+    countdown(3);
+}
+
+// the body is a `loop` that's only left by `break` with a value
+#[trace]
+fn first_multiple(numbers: &[i32], of: i32) -> Option<i32> {
+    let mut i = 0;
+    loop {
+        if i == numbers.len() {
+            break None;
+        }
+        if numbers[i] % of == 0 {
+            break Some(numbers[i]);
+        }
+        i += 1;
+    }
+}
+
+// a labeled block left early with `break 'label value`
+#[trace]
+fn classify(n: i32) -> &'static str {
+    'class: {
+        if n < 0 {
+            break 'class "negative";
+        }
+        if n == 0 {
+            break 'class "zero";
+        }
+        "positive"
+    }
+}
+
+// `break` out of an outer loop from an inner one, with a closure in between whose `return` only
+// leaves the closure
+#[trace]
+fn find_pair(numbers: &[i32], sum: i32) -> Option<(i32, i32)> {
+    let is_pair = |a: i32, b: i32| {
+        if a + b != sum {
+            return false;
+        }
+        true
+    };
+    let mut i = 0;
+    'outer: loop {
+        if i == numbers.len() {
+            break None;
+        }
+        for &b in &numbers[i + 1..] {
+            if is_pair(numbers[i], b) {
+                break 'outer Some((numbers[i], b));
+            }
+        }
+        i += 1;
+    }
+}
+
+// `?` inside a labeled block returns from the whole function
+#[trace]
+fn parse_all(strings: &[&str]) -> Result<Vec<i32>, std::num::ParseIntError> {
+    let parsed = 'parse: {
+        if strings.is_empty() {
+            break 'parse vec![];
+        }
+        let mut parsed = vec![];
+        for s in strings {
+            parsed.push(s.parse()?);
+        }
+        parsed
+    };
+    Ok(parsed)
+}
+
+// a `while` loop can't break with a value, so the value comes after it
+#[trace]
+fn countdown(mut n: u32) -> u32 {
+    let mut steps = 0;
+    while n > 0 {
+        n -= 1;
+        steps += 1;
+    }
+    steps
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_break_value, main());
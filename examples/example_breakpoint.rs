@@ -0,0 +1,29 @@
+// Every call of `fib(1)` stops in the debugger, e.g. with GDB:
+//
+//     cargo build --example example_breakpoint
+//     gdb target/debug/examples/example_breakpoint -ex run
+//
+// Without a debugger attached, the calls are only traced.
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    fib(3);
+}
+
+#[trace(breakpoint, only_when(n = 1))]
+fn fib(n: u32) -> u32 {
+    if n < 2 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_breakpoint, main());
@@ -0,0 +1,26 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let total = (0..3).map(square).sum::<u64>();
+    println!("total = {}", total);
+}
+
+// the lines of a call are written together once it returns, since it's the outermost traced call
+#[trace(buffered)]
+fn square(n: u64) -> u64 {
+    multiply(n, n)
+}
+
+#[trace(buffered)]
+fn multiply(a: u64, b: u64) -> u64 {
+    a * b
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_buffered, main());
@@ -0,0 +1,32 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let total = (1..3).map(checked_square).sum::<u64>();
+    println!("total = {}", total);
+}
+
+#[trace]
+fn checked_square(n: u64) -> u64 {
+    square(n)
+}
+
+// the lines held back by `square` are written before `multiply` and `checked_square` print theirs,
+// so they're still in the order the calls were made in
+#[trace(buffered)]
+fn square(n: u64) -> u64 {
+    multiply(n, n)
+}
+
+#[trace]
+fn multiply(a: u64, b: u64) -> u64 {
+    a * b
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_buffered_mixed, main());
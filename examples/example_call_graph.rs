@@ -0,0 +1,33 @@
+use trace::trace;
+
+fn main() {
+    let tokens = parse("1 + 2 * 3");
+    println!("{} tokens", tokens);
+    parse("4");
+    print!("{}", trace_runtime::call_graph::dot());
+}
+
+#[trace(output = "call_graph")]
+fn parse(source: &str) -> usize {
+    source.split_whitespace().map(lex).count()
+}
+
+#[trace(output = "call_graph")]
+fn lex(word: &str) -> &str {
+    if word.chars().all(|c| c.is_ascii_digit()) {
+        number(word);
+    }
+    word
+}
+
+#[trace(output = "call_graph")]
+fn number(word: &str) -> u64 {
+    word.parse().unwrap()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_call_graph, main());
@@ -0,0 +1,57 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    orders::checkout(&["apple", "pear"]);
+    orders::checkout(&[]);
+    fib(3);
+}
+
+#[trace(call_id)]
+fn fib(n: u32) -> u32 {
+    if n < 2 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+#[trace(
+    prefix_enter = "[+] #{call_id}",
+    prefix_exit = "[-] #{call_id}",
+    disable(log)
+)]
+mod orders {
+    pub(super) fn checkout(items: &[&str]) -> u32 {
+        let mut total = 0;
+        for item in items {
+            total += price(item);
+        }
+        log("checked out");
+        total
+    }
+
+    fn price(item: &str) -> u32 {
+        log(&format!("looking up {}", item));
+        item.len() as u32
+    }
+
+    // not traced, so it logs as part of whichever call it's in
+    fn log(message: &str) {
+        println!(
+            "{:depth$}call #{}: {}",
+            "",
+            trace::current_call_id!().unwrap(),
+            message,
+            depth = trace::current_depth!()
+        );
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_call_id, main());
@@ -0,0 +1,43 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let mut connection = connection::connect("db.local");
+    connection::send(&mut connection, "BEGIN");
+    connection::send(&mut connection, "COMMIT");
+    connection::close(connection);
+
+    trace::assert_call_order!(connect, send, close);
+    trace::assert_call_order!(connect, send, send, close);
+    println!("calls were made in order");
+}
+
+// a traced `mod` declares its own variables, so it's pointed at the ones `main` checks
+#[trace(record_calls, depth_path = "crate::DEPTH")]
+mod connection {
+    #[derive(Debug)]
+    pub(super) struct Connection {
+        pub(super) host: &'static str,
+        pub(super) sent: usize,
+    }
+
+    pub(super) fn connect(host: &'static str) -> Connection {
+        Connection { host, sent: 0 }
+    }
+
+    pub(super) fn send(connection: &mut Connection, query: &str) {
+        connection.sent += query.len();
+    }
+
+    pub(super) fn close(connection: Connection) -> (&'static str, usize) {
+        (connection.host, connection.sent)
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_call_order, main());
@@ -0,0 +1,43 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    checkout(2);
+    total(1);
+}
+
+#[trace(
+    prefix_enter = "[{caller_fn} -> {fn}]",
+    prefix_exit = "[{caller_fn} <- {fn}]"
+)]
+fn checkout(items: u32) -> u32 {
+    total(items) + shipping(items)
+}
+
+#[trace(
+    prefix_enter = "[{caller_fn} -> {fn}]",
+    prefix_exit = "[{caller_fn} <- {fn}]"
+)]
+fn total(items: u32) -> u32 {
+    items * 10
+}
+
+#[trace(
+    prefix_enter = "[{caller_fn} -> {fn}]",
+    prefix_exit = "[{caller_fn} <- {fn}]"
+)]
+fn shipping(items: u32) -> u32 {
+    if items > 1 {
+        0
+    } else {
+        5
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_caller_fn, main());
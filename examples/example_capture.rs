@@ -0,0 +1,45 @@
+// requires the `runtime` feature: cargo run --example example_capture --features runtime
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // nothing is printed while the lines are captured
+    let lines = trace_runtime::capture(|| {
+        checkout(&[3, 4]);
+    });
+    assert_eq!(
+        lines,
+        [
+            "[+] Entering checkout(prices = [3, 4])",
+            " [+] Entering discount(total = 7)",
+            " [-] Exiting discount = 7",
+            "[-] Exiting checkout = 7",
+        ]
+    );
+    println!("captured {} lines", lines.len());
+
+    // and everything is printed again afterwards
+    checkout(&[10, 20]);
+}
+
+#[trace]
+fn checkout(prices: &[u32]) -> u32 {
+    discount(prices.iter().sum())
+}
+
+#[trace]
+fn discount(total: u32) -> u32 {
+    if total >= 20 {
+        total - 5
+    } else {
+        total
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_capture, main());
@@ -0,0 +1,27 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    traced(1);
+    untraced(2);
+}
+
+#[trace(cfg = "all()")]
+fn traced(a: i32) -> i32 {
+    a
+}
+
+// `any()` never holds, so this is compiled as if `#[trace]` wasn't there, like a function traced
+// with `cfg = "debug_assertions"` in a release build
+#[trace(cfg = "any()")]
+fn untraced(a: i32) -> i32 {
+    a
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_cfg, main());
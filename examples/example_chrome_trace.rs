@@ -0,0 +1,22 @@
+// Writes `trace.json`, which can be opened in `chrome://tracing` or https://ui.perfetto.dev
+use std::thread;
+use trace::trace;
+
+fn main() {
+    let handles = (0..2)
+        .map(|i| thread::spawn(move || fib(10 + i)))
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    fib(12);
+}
+
+#[trace(output = "chrome_trace")]
+fn fib(n: u32) -> u32 {
+    if n < 2 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
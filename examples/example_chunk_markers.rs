@@ -0,0 +1,16 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    fib(4);
+}
+
+#[trace(chunk_markers = 4)]
+fn fib(n: u32) -> u32 {
+    if n < 2 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
@@ -0,0 +1,44 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    polling::wait(3);
+    wait_all(2);
+}
+
+// a traced `mod` declares its own variables, so it's pointed at the ones `wait_all` uses, to be
+// indented along with it
+#[trace(collapse_repeats, depth_path = "crate::DEPTH")]
+mod polling {
+    pub(super) fn wait(ready_at: u32) -> u32 {
+        let mut polls = 0;
+        while !is_ready(polls >= ready_at) {
+            polls += 1;
+        }
+        for _ in 0..2 {
+            is_ready(true);
+        }
+        polls
+    }
+
+    pub(super) fn is_ready(ready: bool) -> bool {
+        ready
+    }
+}
+
+// the repeats at the end of a call that isn't traced with `collapse_repeats` are still reported,
+// once the outermost traced call returns
+#[trace]
+fn wait_all(times: u32) {
+    for _ in 0..times {
+        polling::is_ready(true);
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_collapse_repeats, main());
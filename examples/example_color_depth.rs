@@ -0,0 +1,31 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+// colors are only used when stdout is a terminal, so piping the output (like the test does) prints
+// plain lines
+fn main() {
+    fib(3);
+    greet("Ferris");
+}
+
+#[trace(color = "depth")]
+fn fib(n: u32) -> u32 {
+    if n < 2 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+#[trace(color = "fn")]
+fn greet(name: &str) -> String {
+    format!("Hello, {}!", name)
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_color_depth, main());
@@ -0,0 +1,34 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+// `CLICOLOR_FORCE` colors the lines even though the test captures them, so the escape codes are
+// part of the output
+fn main() {
+    fib(2);
+    greet("Ferris");
+}
+
+#[trace(color = "depth")]
+fn fib(n: u32) -> u32 {
+    if n < 2 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+#[trace(color = "fn")]
+fn greet(name: &str) -> String {
+    format!("Hello, {}!", name)
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_color_force, {
+    std::env::set_var("CLICOLOR_FORCE", "1");
+    main()
+});
@@ -0,0 +1,28 @@
+use std::thread;
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    foo(1);
+    thread::spawn(|| foo(2)).join().unwrap();
+    foo(3);
+}
+
+#[trace(color = "thread")]
+fn foo(a: i32) -> i32 {
+    bar(a) + 1
+}
+
+#[trace(color = "thread")]
+fn bar(a: i32) -> i32 {
+    a * 2
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_color_thread, main());
@@ -0,0 +1,37 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let total = total(&[1, 2, 3]);
+    let _ = checked_div(total, 0);
+    std::panic::set_hook(Box::new(|_| {}));
+    let _ = std::panic::catch_unwind(explode);
+}
+
+#[trace(compact)]
+fn total(values: &[i32]) -> i32 {
+    values.iter().map(|&value| square(value, 1)).sum()
+}
+
+#[trace(compact)]
+fn square(value: i32, offset: i32) -> i32 {
+    value * value + offset
+}
+
+#[trace(compact)]
+fn checked_div(a: i32, b: i32) -> Option<i32> {
+    a.checked_div(b)
+}
+
+#[trace(compact)]
+fn explode() {
+    panic!("boom");
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_compact, main());
@@ -0,0 +1,31 @@
+use std::thread;
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    handle("/");
+    let worker = thread::spawn(|| trace::with_correlation_id!(2, || handle("/about")));
+    worker.join().unwrap();
+    trace::with_correlation_id!("req-1", || {
+        handle("/index.html");
+    });
+}
+
+#[trace(correlation_id)]
+fn handle(path: &str) -> usize {
+    render(path.trim_start_matches('/'))
+}
+
+#[trace(correlation_id)]
+fn render(page: &str) -> usize {
+    page.len()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_correlation_id, main());
@@ -0,0 +1,33 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    println!("{}", checksum(&[3, 1, 4, 1, 5, 9, 2, 6]));
+    trace::print_stats!();
+}
+
+#[trace]
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |sum, &byte| mix(sum, byte))
+}
+
+#[trace(count_only)]
+fn mix(sum: u32, byte: u8) -> u32 {
+    if byte == 1 {
+        return sum;
+    }
+    rotate(sum) ^ byte as u32
+}
+
+#[trace(count_only)]
+fn rotate(sum: u32) -> u32 {
+    sum.rotate_left(5)
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_count_only, main());
@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let mut cache = HashMap::new();
+    for key in [1, 2, 1, 1] {
+        lookup(&mut cache, key);
+    }
+}
+
+#[trace(
+    disable(cache),
+    format_enter = "key = {key}, queue = {gauge:queue_len}",
+    format_exit = "{r} after {counter:cache_hits} hits"
+)]
+fn lookup(cache: &mut HashMap<u32, u32>, key: u32) -> u32 {
+    trace::gauge!("queue_len", cache.len());
+    if let Some(&value) = cache.get(&key) {
+        trace::counter!("cache_hits");
+        value
+    } else {
+        cache.insert(key, key * 10);
+        key * 10
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_counters, main());
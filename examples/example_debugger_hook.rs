@@ -0,0 +1,27 @@
+// Every traced call stops at a breakpoint on the hook, e.g. with GDB:
+//
+//     cargo build --example example_debugger_hook
+//     gdb target/debug/examples/example_debugger_hook -ex 'break trace_runtime::debugger_hook'
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    fib(3);
+}
+
+#[trace(debugger_hook)]
+fn fib(n: u32) -> u32 {
+    if n < 2 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_debugger_hook, main());
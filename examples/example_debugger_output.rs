@@ -0,0 +1,16 @@
+// On Windows, the lines show up in the output window of the debugger, or in DebugView when no
+// debugger is attached. Elsewhere, they're written to stderr.
+#![cfg_attr(windows, windows_subsystem = "windows")]
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    on_click(3, 4);
+}
+
+#[trace(writer = "debugger")]
+fn on_click(x: u32, y: u32) -> bool {
+    x < 10 && y < 10
+}
@@ -0,0 +1,41 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Config {
+    name: &'static str,
+    retries: u32,
+    verbose: bool,
+}
+
+fn main() {
+    let default = Config {
+        name: "default",
+        retries: 3,
+        verbose: false,
+    };
+    let custom = Config {
+        name: "custom",
+        retries: 5,
+        verbose: true,
+    };
+    run(&default, 1);
+    run(&default, 2);
+    run(&custom, 3);
+    run(&default, 4);
+    run(&custom, 5);
+}
+
+#[trace(dedup(cfg))]
+fn run(cfg: &Config, job: u32) -> u32 {
+    job * cfg.retries
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_dedup, main());
@@ -0,0 +1,57 @@
+use std::{thread, time::Duration};
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    parse("1").unwrap();
+    parse("x").unwrap_err();
+    fast();
+    slow();
+    // keep the default hook from printing the panic message to stderr
+    std::panic::set_hook(Box::new(|_| {}));
+    let _ = std::panic::catch_unwind(|| explode(3));
+    parse("2").unwrap();
+    parse_hex("ff").unwrap();
+    parse_hex("zz").unwrap_err();
+}
+
+#[trace(deferred)]
+fn parse(s: &str) -> Result<u32, String> {
+    s.parse().map_err(|_| format!("invalid number {s:?}"))
+}
+
+type ParseResult<T> = Result<T, String>;
+
+// an `Err` is reported through an alias of `Result` too
+#[trace(deferred)]
+fn parse_hex(s: &str) -> ParseResult<u32> {
+    u32::from_str_radix(s, 16).map_err(|_| format!("invalid hex number {s:?}"))
+}
+
+#[trace(deferred = 20)]
+fn fast() -> u32 {
+    1
+}
+
+#[trace(deferred = 20)]
+fn slow() -> u32 {
+    thread::sleep(Duration::from_millis(50));
+    parse("3").unwrap()
+}
+
+#[trace(deferred)]
+fn explode(n: u32) -> u32 {
+    if n > 2 {
+        panic!("too big");
+    }
+    n
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_deferred, main());
@@ -0,0 +1,51 @@
+use trace::trace;
+
+// declared once, for the traced functions of every module to share
+trace::init_depth_var!(pub);
+
+fn main() {
+    let tokens = lexer::tokenize("1 + 2");
+    parser::parse(&tokens);
+}
+
+mod lexer {
+    use trace::trace;
+
+    #[trace(depth_path = "crate::DEPTH")]
+    pub fn tokenize(input: &str) -> Vec<String> {
+        input.split(' ').map(str::to_string).collect()
+    }
+}
+
+#[trace(depth_path = "crate::DEPTH")]
+mod parser {
+    pub fn parse(tokens: &[String]) -> i32 {
+        eval::sum(tokens)
+    }
+
+    pub mod eval {
+        pub fn sum(tokens: &[String]) -> i32 {
+            tokens
+                .iter()
+                .filter_map(|token| token.parse::<i32>().ok())
+                .map(crate::double)
+                .sum()
+        }
+    }
+}
+
+#[trace]
+fn double(n: i32) -> i32 {
+    println!(
+        "depth inside `double`: {}",
+        trace::current_depth!(crate::DEPTH)
+    );
+    n * 2
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_depth_path, main());
@@ -0,0 +1,52 @@
+use trace::trace;
+
+trace::init_depth_var!();
+trace::init_depth_var!(PARSER_DEPTH);
+
+fn main() {
+    let result = run("1+2");
+    stats::count(&[result]);
+}
+
+#[trace]
+fn run(input: &str) -> i32 {
+    let tokens = tokenize(input);
+    evaluate(&tokens)
+}
+
+#[trace]
+fn evaluate(tokens: &[i32]) -> i32 {
+    tokens.iter().sum()
+}
+
+// the parser is indented on its own, as if nothing else was traced
+#[trace(depth_var = "PARSER_DEPTH")]
+fn tokenize(input: &str) -> Vec<i32> {
+    input.split('+').map(number).collect()
+}
+
+#[trace(depth_var = "PARSER_DEPTH")]
+fn number(digits: &str) -> i32 {
+    digits.parse().unwrap()
+}
+
+// a traced `mod` declares its own depth variable with the given name
+#[trace(depth_var = "STATS_DEPTH")]
+mod stats {
+    pub(super) fn count(items: &[i32]) -> usize {
+        inner::len(items)
+    }
+
+    mod inner {
+        pub(super) fn len(items: &[i32]) -> usize {
+            items.len()
+        }
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_depth_var, main());
@@ -0,0 +1,46 @@
+use std::{
+    cell::{Ref, RefCell},
+    ops::Deref,
+    sync::{Mutex, MutexGuard},
+};
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let flags = Box::new(0x2a);
+    let config = RefCell::new(vec![1, 2]);
+    let counter = Mutex::new(7);
+    read(flags, config.borrow(), counter.lock().unwrap());
+    let meters = Meters(3);
+    convert(&meters, 2);
+}
+
+#[trace(deref, hex(flags))]
+fn read(flags: Box<u8>, config: Ref<Vec<i32>>, counter: MutexGuard<i32>) {
+    let _ = (flags, config, counter);
+}
+
+#[derive(Debug)]
+struct Meters(u32);
+
+impl Deref for Meters {
+    type Target = u32;
+
+    fn deref(&self) -> &u32 {
+        &self.0
+    }
+}
+
+#[trace(deref(meters))]
+fn convert(meters: &Meters, factor: u32) -> u32 {
+    **meters * factor
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_deref, main());
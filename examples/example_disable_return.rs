@@ -0,0 +1,38 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let conn = connect("db.local", 5432);
+    send(&conn, "SELECT 1");
+    close(conn);
+}
+
+// doesn't implement `Debug`
+struct Connection {
+    host: String,
+}
+
+#[trace(disable(return))]
+fn connect(host: &str, port: u16) -> Connection {
+    Connection {
+        host: format!("{host}:{port}"),
+    }
+}
+
+#[trace(no_args)]
+fn send(conn: &Connection, query: &str) -> usize {
+    conn.host.len() + query.len()
+}
+
+#[trace(no_args, disable(return))]
+fn close(conn: Connection) -> Connection {
+    conn
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_disable_return, main());
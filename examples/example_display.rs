@@ -0,0 +1,43 @@
+use std::fmt;
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let path = Path(vec!["usr".to_string(), "bin".to_string()]);
+    resolve(&path, 2);
+    label(&path);
+    describe(&path);
+}
+
+// only implements `Display`
+struct Path(Vec<String>);
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "/{}", self.0.join("/"))
+    }
+}
+
+#[trace(display(path))]
+fn resolve(path: &Path, depth: usize) -> usize {
+    path.0.len().min(depth)
+}
+
+#[trace(display(path, r))]
+fn label(path: &Path) -> impl fmt::Display {
+    format!("<{}>", path)
+}
+
+#[trace(pretty, format_enter = "{path:}", format_exit = "{r}")]
+fn describe(path: &Path) -> Vec<usize> {
+    path.0.iter().map(String::len).collect()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_display, main());
@@ -0,0 +1,54 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let _ = sum(&["1", "2", "three"]);
+    let mut stack = Stack(vec![]);
+    stack.push(4);
+    stack.push(4);
+    halve(4);
+    halve(5);
+}
+
+#[trace]
+fn sum(numbers: &[&str]) -> Result<i32, std::num::ParseIntError> {
+    let mut total = 0;
+    for n in numbers {
+        total += parse(n)?;
+    }
+    Ok(total)
+}
+
+#[trace]
+fn parse(s: &str) -> Result<i32, std::num::ParseIntError> {
+    s.parse()
+}
+
+#[trace]
+fn halve(n: i32) -> Option<i32> {
+    if n % 2 != 0 {
+        return None;
+    }
+    Some(n / 2)
+}
+
+struct Stack(Vec<i32>);
+
+impl Stack {
+    #[trace]
+    fn push(&mut self, n: i32) -> usize {
+        if self.0.last() == Some(&n) {
+            return self.0.len();
+        }
+        self.0.push(n);
+        self.0.len()
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_early_return, main());
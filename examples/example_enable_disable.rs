@@ -2,9 +2,10 @@ use std::cell::Cell;
 use trace::trace;
 
 thread_local! {
-    static DEPTH: Cell<usize> = Cell::new(0);
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
 }
 
+#[allow(clippy::disallowed_names)]
 fn main() {
     let foo = Foo;
     Foo::foo(2);
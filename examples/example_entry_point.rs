@@ -0,0 +1,23 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+// `#[trace]` could also come after `#[async_std::main]`
+#[trace]
+#[async_std::main]
+async fn main() {
+    let area = area(2, 3).await;
+    println!("area = {}", area);
+}
+
+#[trace]
+async fn area(width: u32, height: u32) -> u32 {
+    width * height
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_entry_point, main());
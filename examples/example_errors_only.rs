@@ -0,0 +1,44 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let _ = config::load("config.toml");
+    let _ = config::load("missing.toml");
+}
+
+#[trace(errors_only, prefix_error = "[!]")]
+mod config {
+    // calls are told apart by the type they return, so an alias of `Result` works like `Result`
+    pub(super) type ConfigResult<T> = Result<T, String>;
+
+    pub(super) fn load(path: &str) -> ConfigResult<u16> {
+        let _ = name(path);
+        let contents = read(path)?;
+        parse(&contents)
+    }
+
+    fn read(path: &str) -> Result<String, String> {
+        if path.starts_with("missing") {
+            Err(format!("{} not found", path))
+        } else {
+            Ok("port = 80".to_string())
+        }
+    }
+
+    fn parse(contents: &str) -> Result<u16, String> {
+        let port = contents.trim_start_matches("port = ");
+        port.parse().map_err(|_| format!("invalid port {}", port))
+    }
+
+    fn name(path: &str) -> &str {
+        path.trim_end_matches(".toml")
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_errors_only, main());
@@ -0,0 +1,35 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let mut queue = vec![3, 1, 2];
+    drain(&mut queue, 2);
+    drain(&mut queue, 5);
+}
+
+// the exit line shows the arguments as they were when the call was entered, so it's clear which
+// call it belongs to even though the body changed them
+#[trace(exit_args)]
+fn drain(queue: &mut Vec<i32>, max: usize) -> Vec<i32> {
+    let mut drained = vec![];
+    while drained.len() < max {
+        match pop(queue) {
+            Some(item) => drained.push(item),
+            None => break,
+        }
+    }
+    drained
+}
+
+#[trace(exit_args)]
+fn pop(queue: &mut Vec<i32>) -> Option<i32> {
+    queue.pop()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_exit_args, main());
@@ -0,0 +1,21 @@
+use trace::trace;
+
+fn main() {
+    let mut stack = stack::Stack::default();
+    stack.push(1);
+    stack.push(2);
+    stack.pop();
+}
+
+#[trace(expand_macros)]
+mod stack {
+    // the path is relative to this file, like it is for `include!` itself
+    include!("expand_include/stack.rs");
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_expand_include, main());
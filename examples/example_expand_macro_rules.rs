@@ -0,0 +1,39 @@
+use trace::trace;
+
+fn main() {
+    shapes::print_areas();
+}
+
+#[trace(expand_macros)]
+mod shapes {
+    // rules can use any kind of brackets, and the last one doesn't need a semicolon
+    macro_rules! area_fns {
+        [$($name:ident($side:ident) = $area:expr),* $(,)?] => {
+            $(
+                pub fn $name($side: u32) -> u32 {
+                    $area
+                }
+            )*
+        };
+        {$name:ident} => (
+            pub fn $name() -> u32 {
+                0
+            }
+        )
+    }
+
+    area_fns![square(side) = side * side, triangle(side) = side * side / 2,];
+
+    area_fns! { point }
+
+    pub fn print_areas() {
+        println!("{} {} {}", square(3), triangle(4), point());
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_expand_macro_rules, main());
@@ -0,0 +1,43 @@
+use trace::trace;
+
+fn main() {
+    calc::eval();
+}
+
+#[trace(expand_macros)]
+mod calc {
+    macro_rules! binary_ops {
+        ($($name:ident => $op:tt),* $(,)?) => {
+            $(
+                pub fn $name(a: i32, b: i32) -> i32 {
+                    a $op b
+                }
+            )*
+        };
+    }
+
+    // expands to an expression, which is left as it is
+    macro_rules! square {
+        ($e:expr) => {
+            $e * $e
+        };
+    }
+
+    binary_ops! {
+        add => +,
+        mul => *,
+    }
+
+    include!("expand_macros/ops.rs");
+
+    pub fn eval() -> i32 {
+        neg(add(mul(2, 3), square!(2)))
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_expand_macros, main());
@@ -0,0 +1,45 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+// the functions are looked up by the names they're exported with, as C code linked with this
+// program would
+mod exported {
+    extern "C" {
+        pub(super) fn trace_example_add(a: i32, b: i32) -> i32;
+        pub(super) fn trace_example_scale(value: *const i32, factor: i32) -> i32;
+    }
+}
+
+fn main() {
+    let value = 7;
+    unsafe {
+        exported::trace_example_add(1, 2);
+        exported::trace_example_scale(&value, 3);
+        read(&value);
+    }
+}
+
+#[trace]
+#[no_mangle]
+pub extern "C" fn trace_example_add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[trace(disable(value))]
+#[unsafe(export_name = "trace_example_scale")]
+pub unsafe extern "C" fn scale(value: *const i32, factor: i32) -> i32 {
+    read(value) * factor
+}
+
+#[trace(disable(value))]
+unsafe fn read(value: *const i32) -> i32 {
+    *value
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_ffi, main());
@@ -0,0 +1,39 @@
+trace::init_depth_var!();
+
+fn main() {
+    parsing::parse_all("1 2");
+    checks::run();
+}
+
+#[trace::trace(enable("parse_*"))]
+mod parsing {
+    pub(super) fn parse_all(input: &str) -> Vec<u32> {
+        input.split(' ').map(parse_one).collect()
+    }
+
+    fn parse_one(word: &str) -> u32 {
+        to_digit(word)
+    }
+
+    fn to_digit(word: &str) -> u32 {
+        word.parse().unwrap()
+    }
+}
+
+#[trace::trace(disable("/^check_/"))]
+mod checks {
+    pub(super) fn run() {
+        check_twice(2);
+    }
+
+    fn check_twice(n: u32) -> bool {
+        n * 2 == n + n
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_filter_patterns, main());
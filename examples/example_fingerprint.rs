@@ -0,0 +1,29 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    checkout(&[3, 4]);
+    let first = trace::fingerprint!();
+    checkout(&[3, 4]);
+    let second = trace::fingerprint!();
+    println!("fingerprint after one checkout:  {first:016x}");
+    println!("fingerprint after two checkouts: {second:016x}");
+}
+
+#[trace(fingerprint = "args")]
+fn checkout(items: &[u32]) -> u32 {
+    items.iter().map(|&item| price(item)).sum()
+}
+
+#[trace(fingerprint)]
+fn price(item: u32) -> u32 {
+    item * 100
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_fingerprint, main());
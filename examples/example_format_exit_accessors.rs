@@ -0,0 +1,44 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let _ = min_max(&[4, 1, 9]);
+    let _ = parse_port("8080");
+    let _ = parse_port("http");
+    let _ = find(&["a", "b"], "b");
+    let _ = find(&["a", "b"], "c");
+    let _ = lookup(Some(2));
+}
+
+#[trace(format_exit = "min {r.0}, max {r.1}")]
+fn min_max(values: &[i32]) -> (i32, i32) {
+    let min = *values.iter().min().unwrap();
+    let max = *values.iter().max().unwrap();
+    (min, max)
+}
+
+#[trace(format_exit = "ok: {r.ok:?}, err: {r.err:?}")]
+fn parse_port(port: &str) -> Result<u16, std::num::ParseIntError> {
+    port.parse()
+}
+
+#[trace(format_exit = "found at {r?}")]
+fn find(items: &[&str], item: &str) -> Option<usize> {
+    items.iter().position(|i| *i == item)
+}
+
+#[trace(format_enter = "looking up {key?}", format_exit = "{r.0?} ({r.1})")]
+fn lookup(key: Option<u32>) -> (Result<String, String>, bool) {
+    match key {
+        Some(key) => (Ok(key.to_string()), true),
+        None => (Err("no key".to_string()), false),
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_format_exit_accessors, main());
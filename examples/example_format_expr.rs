@@ -0,0 +1,39 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let order = Order {
+        id: 7,
+        items: vec!["apple".to_string(), "pear".to_string()],
+    };
+    ship(&order, &[3, 5]);
+    order.labels();
+}
+
+struct Order {
+    id: u32,
+    items: Vec<String>,
+}
+
+#[trace(
+    format_enter = "order #{order.id} with {order.items.len()} items, first box {boxes[0]:?}",
+    format_exit = "shipped {r.len()} boxes"
+)]
+fn ship(order: &Order, boxes: &[u32]) -> Vec<u32> {
+    boxes.iter().take(order.items.len()).copied().collect()
+}
+
+impl Order {
+    #[trace(format_enter = "{self.items:?}", format_exit = "{r.join(\"+\")}")]
+    fn labels(&self) -> Vec<String> {
+        self.items.iter().map(|item| item.to_uppercase()).collect()
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_format_expr, main());
@@ -0,0 +1,114 @@
+use std::fmt::Debug;
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let mut engine = Engine::new(Secret);
+    engine.run(Secret);
+    Engine::new(3).run(4);
+
+    let mut pair = Pair { a: "a", b: 1 };
+    pair.swap_b(2);
+    pair.first("b");
+
+    let words = Words(vec!["hello", "world"]);
+    words.get(1);
+
+    describe(Secret, 5);
+}
+
+struct Secret;
+
+trait Config {
+    fn name(&self) -> &'static str;
+}
+
+impl Config for Secret {
+    fn name(&self) -> &'static str {
+        "secret"
+    }
+}
+
+impl Config for i32 {
+    fn name(&self) -> &'static str {
+        "i32"
+    }
+}
+
+struct Engine<T> {
+    config: T,
+    runs: usize,
+}
+
+// `T` isn't known to implement `Debug`, so values of type `T` (or `Self`) are printed by type name
+#[trace]
+impl<T: Config> Engine<T> {
+    fn new(config: T) -> Self {
+        Engine { config, runs: 0 }
+    }
+
+    fn run(&mut self, input: T) -> &'static str {
+        self.runs += 1;
+        let _ = input.name();
+        self.config.name()
+    }
+}
+
+struct Pair<'a, B> {
+    a: &'a str,
+    b: B,
+}
+
+// values are printed with `Debug` when the bounds (including where clauses) say it's implemented
+#[trace]
+impl<'a, B> Pair<'a, B>
+where
+    B: Debug + Copy,
+{
+    fn swap_b(&mut self, b: B) -> B {
+        std::mem::replace(&mut self.b, b)
+    }
+
+    fn first<'b>(&self, other: &'b str) -> &'b str
+    where
+        'a: 'b,
+    {
+        if self.a < other {
+            self.a
+        } else {
+            other
+        }
+    }
+}
+
+trait Lookup {
+    type Item<'a>
+    where
+        Self: 'a;
+
+    fn get(&self, index: usize) -> Option<Self::Item<'_>>;
+}
+
+struct Words(Vec<&'static str>);
+
+#[trace]
+impl Lookup for Words {
+    type Item<'a> = &'a str;
+
+    fn get(&self, index: usize) -> Option<Self::Item<'_>> {
+        self.0.get(index).copied()
+    }
+}
+
+#[trace]
+fn describe<C: Config, N: Debug>(config: C, n: N) -> String {
+    format!("{} {:?}", config.name(), n)
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_generic_impl, main());
@@ -0,0 +1,35 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let store = Store;
+    store.save(1, 2);
+    trace::disable_group!("io");
+    store.save(3, 4);
+    trace::enable_group!("io");
+    trace::disable_group!("math");
+    store.save(5, 6);
+}
+
+struct Store;
+
+#[trace(groups(io(read, write), math(checksum)))]
+impl Store {
+    fn save(&self, key: u32, value: u32) {
+        self.write(key, value + self.checksum(value));
+    }
+
+    fn write(&self, _key: u32, _value: u32) {}
+
+    fn checksum(&self, value: u32) -> u32 {
+        value % 7
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_groups, main());
@@ -0,0 +1,47 @@
+use trace::trace;
+use trace_runtime::happens_before::{record, spawn};
+
+fn main() {
+    let ((), recording) = record(|| {
+        let writer = spawn(|| write(1));
+        let reader = spawn(read);
+        writer.join().unwrap();
+        reader.join().unwrap();
+        // everything the threads did happens before this
+        write(2);
+    });
+    // the threads ran at the same time, so their calls are listed by thread
+    let mut calls = recording.calls().to_vec();
+    calls.sort_by_key(|call| call.thread);
+    for call in calls {
+        println!("{}", call);
+    }
+    for (write_call, read_call) in recording.concurrent("write", "read") {
+        println!("{} may run concurrently with {}", write_call, read_call);
+    }
+    let last_write = recording.calls_of("write").last().unwrap();
+    assert!(recording
+        .calls_of("read")
+        .all(|read_call| read_call.happens_before(last_write)));
+    recording.assert_never_concurrent("read", "read");
+}
+
+#[trace(output = "happens_before")]
+fn write(n: u32) {
+    flush(n);
+}
+
+#[trace(output = "happens_before")]
+fn read() {}
+
+#[trace(output = "happens_before")]
+fn flush(n: u32) {
+    let _ = n;
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_happens_before, main());
@@ -0,0 +1,25 @@
+use std::{thread, time::Duration};
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+// the rates depend on how fast the program runs, so there's no expected output to test against
+fn main() {
+    for i in 0..3 {
+        tick(i);
+        thread::sleep(Duration::from_millis(10));
+    }
+    let total = (0..10_000).map(hot).sum::<u64>();
+    println!("total = {}", total);
+}
+
+#[trace(heat)]
+fn tick(i: u64) -> u64 {
+    i
+}
+
+#[trace(heat, sample = 2500)]
+fn hot(i: u64) -> u64 {
+    i % 7
+}
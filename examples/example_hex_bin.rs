@@ -0,0 +1,19 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    write_register(0xdead_0000, 0x2a, 0b1010_0101);
+}
+
+#[trace(hex(addr, value), bin(mask))]
+fn write_register(addr: u32, value: u8, mask: u8) -> u8 {
+    value & mask
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_hex_bin, main());
@@ -0,0 +1,35 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let evens = evens(3).collect::<Vec<_>>();
+    let handle = open(evens.len());
+    close(handle);
+}
+
+struct Handle {
+    fd: usize,
+}
+
+#[trace]
+fn evens(n: u32) -> impl Iterator<Item = u32> {
+    (0..n).map(|i| i * 2)
+}
+
+#[trace(hide_ret)]
+fn open(fd: usize) -> Handle {
+    Handle { fd }
+}
+
+#[trace(hide_ret, disable(handle), format_exit = "closed {r}")]
+fn close(handle: Handle) {
+    let _ = handle.fd;
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_hide_ret, main());
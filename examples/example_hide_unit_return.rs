@@ -0,0 +1,30 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // keeps the message of the panic below out of the output
+    std::panic::set_hook(Box::new(|_| {}));
+
+    log("starting");
+    let _ = std::panic::catch_unwind(|| fail("out of memory"));
+}
+
+#[trace(hide_unit_return)]
+fn log(message: &str) {
+    let _ = message;
+}
+
+// never returns, so only the panic is printed when it exits
+#[trace]
+fn fail(reason: &str) -> ! {
+    log(reason);
+    panic!("{}", reason)
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_hide_unit_return, main());
@@ -0,0 +1,43 @@
+use std::sync::Mutex;
+use trace::trace;
+
+trace::init_depth_var!();
+
+// what the hooks collected, e.g. to be sent to a telemetry service later
+static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn on_enter(name: &str, depth: usize, args: &str) {
+    let event = format!("{}> {}({})", " ".repeat(depth), name, args);
+    EVENTS.lock().unwrap().push(event);
+}
+
+fn on_exit(name: &str, depth: usize, ret: &str) {
+    let event = format!("{}< {} = {}", " ".repeat(depth), name, ret);
+    EVENTS.lock().unwrap().push(event);
+}
+
+fn main() {
+    resolve("example.com");
+    for event in EVENTS.lock().unwrap().iter() {
+        println!("{}", event);
+    }
+}
+
+// printed as usual, and passed to the hooks
+#[trace(on_enter = "on_enter", on_exit = "on_exit")]
+fn resolve(host: &str) -> Option<[u8; 4]> {
+    lookup(host, 1)
+}
+
+// only passed to the hooks
+#[trace(on_enter = "on_enter", on_exit = "on_exit", silent)]
+fn lookup(host: &str, attempt: u32) -> Option<[u8; 4]> {
+    (host.ends_with(".com") && attempt > 0).then_some([93, 184, 216, 34])
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_hooks, main());
@@ -0,0 +1,52 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    shadow(7);
+    early(3);
+    scaled(2);
+    only_nested(0);
+    only_nested(1);
+    squared(4);
+}
+
+// the locals of the traced block have the same names, but can't be seen by the body
+#[trace]
+fn shadow(fn_depth: u32) -> u32 {
+    let fn_return_value = fn_depth * 2;
+    println!("fn_depth = {fn_depth}");
+    fn_return_value
+}
+
+#[trace(format_enter = "{fn_start}")]
+fn early(fn_start: u32) -> u32 {
+    let fn_silenced = fn_start + 1;
+    if fn_silenced > 2 {
+        return fn_silenced;
+    }
+    fn_start
+}
+
+// expressions given to `#[trace]` see the parameters too, not the locals of the traced block
+#[trace(transform(fn_depth = "fn_depth * 100"))]
+fn scaled(fn_depth: i32) -> i32 {
+    fn_depth
+}
+
+#[trace(if = "fn_depth > 0")]
+fn only_nested(fn_depth: i32) -> i32 {
+    fn_depth
+}
+
+#[trace(format_enter = "{fn_start.pow(2)}")]
+fn squared(fn_start: u32) -> u32 {
+    fn_start.pow(2)
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_hygiene, main());
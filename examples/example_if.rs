@@ -0,0 +1,30 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    fib(5);
+    describe(&[1, 2, 3]);
+    describe(&[]);
+}
+
+#[trace(if = "n >= 3")]
+fn fib(n: u32) -> u32 {
+    if n <= 1 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+#[trace(if = "!items.is_empty()")]
+fn describe(items: &[i32]) -> usize {
+    items.len()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_if, main());
@@ -2,6 +2,7 @@ use trace::trace;
 
 trace::init_depth_var!();
 
+#[allow(clippy::disallowed_names)]
 fn main() {
     let foo = Foo;
     Foo::foo(2);
@@ -0,0 +1,81 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let point = Point { x: 1, y: 2 };
+    Area::measure(&point);
+    Perimeter::measure(&point);
+    point.area();
+    Point::origin();
+    let _ = Point::ORIGIN;
+}
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+macro_rules! origin_fn {
+    () => {
+        fn origin() -> Self {
+            Self::ORIGIN
+        }
+    };
+}
+
+trait Shape {
+    type Unit;
+    const SIDES: u32;
+
+    fn area(&self) -> Self::Unit;
+    fn origin() -> Self;
+}
+
+// associated types, consts, and macro invocations are left as they are
+#[trace]
+impl Shape for Point {
+    type Unit = i32;
+    const SIDES: u32 = 0;
+
+    fn area(&self) -> Self::Unit {
+        self.x * self.y * Self::SIDES as i32
+    }
+
+    origin_fn!();
+}
+
+#[trace]
+impl Point {
+    const ORIGIN: Point = Point { x: 0, y: 0 };
+}
+
+trait Area {
+    fn measure(&self) -> i32;
+}
+
+trait Perimeter {
+    fn measure(&self) -> i32;
+}
+
+// `Area::measure` only matches `measure` in this `impl`, so the `Perimeter` impl below isn't traced
+#[trace(enable(Area::measure))]
+impl Area for Point {
+    fn measure(&self) -> i32 {
+        self.x * self.y
+    }
+}
+
+#[trace(enable(Area::measure))]
+impl Perimeter for Point {
+    fn measure(&self) -> i32 {
+        2 * (self.x + self.y)
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_impl_items, main());
@@ -0,0 +1,28 @@
+use std::fmt::Debug;
+use trace::trace;
+
+thread_local! {
+    static DEPTH: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+fn main() {
+    handle(|n| println!("got {}", n));
+    show(vec![1, 2]);
+}
+
+#[trace]
+fn handle(cb: impl Fn(u32)) {
+    cb(7);
+}
+
+#[trace]
+fn show(value: impl Debug) -> usize {
+    format!("{:?}", value).len()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_impl_trait_args, main());
@@ -0,0 +1,38 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    fib(3);
+    walk(2);
+    let sum = trace::trace_expr!(double(1) + double(2), indent = "│ ");
+    assert_eq!(sum, 6);
+}
+
+#[trace(indent = "│ ")]
+fn fib(n: u32) -> u32 {
+    if n < 2 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+#[trace(indent_width = 4)]
+fn walk(steps: u32) {
+    if steps > 0 {
+        walk(steps - 1);
+    }
+}
+
+#[trace(indent = "│ ")]
+fn double(n: u32) -> u32 {
+    n * 2
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_indent, main());
@@ -0,0 +1,48 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let mut first = Counter::new("clicks");
+    let mut second = Counter::new("clicks");
+    first.increment();
+    second.increment();
+    first.increment();
+    second.get();
+
+    let boxed = Counter::boxed("views");
+    boxed.get();
+}
+
+#[derive(Debug)]
+struct Counter {
+    name: &'static str,
+    count: u32,
+}
+
+#[trace(instance_ids)]
+impl Counter {
+    fn new(name: &'static str) -> Self {
+        Counter { name, count: 0 }
+    }
+
+    fn boxed(name: &'static str) -> Box<Self> {
+        Box::new(Counter::new(name))
+    }
+
+    fn increment(&mut self) {
+        self.count += 1;
+    }
+
+    fn get(&self) -> u32 {
+        let _ = self.name;
+        self.count
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_instance_ids, main());
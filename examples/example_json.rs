@@ -0,0 +1,38 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // the thread is named, since the name of the main thread depends on how the program is run
+    std::thread::Builder::new()
+        .name("worker".to_string())
+        .spawn(|| {
+            count(&["a \"quoted\"", "tab\there"]);
+            let _ = parse("x");
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[trace(format = "json")]
+fn count(words: &[&str]) -> usize {
+    words.iter().map(|word| len(word)).sum()
+}
+
+#[trace(format = "json")]
+fn len(word: &str) -> usize {
+    word.len()
+}
+
+#[trace(format = "json")]
+fn parse(s: &str) -> Result<u8, std::num::ParseIntError> {
+    s.parse()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_json, main());
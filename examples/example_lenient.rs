@@ -0,0 +1,32 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let conn = connect("db.local");
+    query(&conn, "SELECT 1");
+}
+
+// doesn't implement `Debug`
+struct Connection {
+    host: String,
+}
+
+#[trace(lenient)]
+fn connect(host: &str) -> Connection {
+    Connection {
+        host: host.to_string(),
+    }
+}
+
+#[trace(lenient)]
+fn query(conn: &Connection, sql: &str) -> usize {
+    conn.host.len() + sql.len()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_lenient, main());
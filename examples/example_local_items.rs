@@ -0,0 +1,43 @@
+use trace::trace;
+
+fn main() {
+    trace::init_depth_var!();
+
+    struct Counter(u32);
+
+    #[trace]
+    impl Counter {
+        fn bump(&mut self, by: u32) -> u32 {
+            fn clamp(n: u32) -> u32 {
+                n.min(10)
+            }
+            self.0 = clamp(self.0 + by);
+            self.0
+        }
+    }
+
+    #[trace]
+    mod local {
+        pub fn area(w: u32, h: u32) -> u32 {
+            struct Rect(u32, u32);
+            impl Rect {
+                fn area(&self) -> u32 {
+                    self.0 * self.1
+                }
+            }
+            Rect(w, h).area()
+        }
+    }
+
+    let mut counter = Counter(0);
+    counter.bump(4);
+    counter.bump(8);
+    local::area(2, 3);
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_local_items, main());
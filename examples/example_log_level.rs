@@ -0,0 +1,41 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+// prints every record to stdout along with its level and target
+struct StdoutLogger;
+
+impl log::Log for StdoutLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        println!("{} {}: {}", record.level(), record.target(), record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+fn main() {
+    let _ = log::set_boxed_logger(Box::new(StdoutLogger));
+    log::set_max_level(log::LevelFilter::Trace);
+    parse("42");
+}
+
+#[trace(logging = "debug", log_target = "calls")]
+fn parse(input: &str) -> u32 {
+    checked(input.parse().unwrap())
+}
+
+#[trace(logging)]
+fn checked(n: u32) -> u32 {
+    n
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_log_level, main());
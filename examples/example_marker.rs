@@ -0,0 +1,27 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    println!("starting");
+    foo(1);
+    println!("done");
+}
+
+#[trace(marker = "TRACE|")]
+fn foo(a: i32) -> i32 {
+    println!("in foo");
+    bar(a) + 1
+}
+
+#[trace(marker = "TRACE|", prefix_enter = "{{+}}")]
+fn bar(a: i32) -> i32 {
+    a * 2
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_marker, main());
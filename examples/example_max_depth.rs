@@ -0,0 +1,31 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    fib(5);
+    walk(3);
+}
+
+#[trace(max_depth = 2, max_depth_summary)]
+fn fib(n: u32) -> u32 {
+    if n < 2 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+#[trace(max_depth = 3)]
+fn walk(n: u32) {
+    if n > 0 {
+        walk(n - 1);
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_max_depth, main());
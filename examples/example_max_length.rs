@@ -0,0 +1,46 @@
+use std::fmt;
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let numbers = (1..=100).collect::<Vec<_>>();
+    double(&numbers);
+    greet("Ferris");
+    greet("a name that's much too long to print");
+    follow(&Cycle);
+}
+
+// a value whose `Debug` output never ends
+struct Cycle;
+
+impl fmt::Debug for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        loop {
+            f.write_str("-> Cycle ")?;
+        }
+    }
+}
+
+#[trace(max_length = 10)]
+fn double(numbers: &[i32]) -> Vec<i32> {
+    numbers.iter().map(|n| n * 2).collect()
+}
+
+#[trace(max_length = 20)]
+fn greet(name: &str) -> String {
+    format!("Hello, {}!", name)
+}
+
+#[trace(max_length = 30)]
+fn follow(cycle: &Cycle) {
+    let _ = cycle;
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_max_length, main());
@@ -0,0 +1,36 @@
+use trace::trace;
+
+fn main() {
+    let tokens = lexer::tokens("1+2");
+    lexer::Parser.sum(&tokens);
+}
+
+#[trace(prefix_enter = "[lexer]", prefix_exit = "[lexer]")]
+mod lexer {
+    pub(super) fn tokens(input: &str) -> Vec<i32> {
+        input.split('+').map(number).collect()
+    }
+
+    // adds to the arguments of the `mod`
+    #[trace(disable(token))]
+    fn number(token: &str) -> i32 {
+        token.parse().unwrap()
+    }
+
+    pub(super) struct Parser;
+
+    impl Parser {
+        // replaces the prefixes of the `mod`
+        #[trace(prefix_enter = "[parser]", prefix_exit = "[parser]")]
+        pub(super) fn sum(&self, tokens: &[i32]) -> i32 {
+            tokens.iter().sum()
+        }
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_merged_args, main());
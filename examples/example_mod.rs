@@ -1,5 +1,6 @@
 use trace::trace;
 
+#[allow(clippy::disallowed_names)]
 fn main() {
     foo::foo();
     let foo = foo::Foo;
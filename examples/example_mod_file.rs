@@ -0,0 +1,14 @@
+#[path = "mod_file/store.rs"]
+mod store;
+
+fn main() {
+    store::get("id-7");
+    store::get("name");
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_mod_file, main());
@@ -1,32 +1,37 @@
-// This example is disabled due to the following error:
-// error: cannot determine resolution for the attribute macro `trace`
-//   --> examples/example_mod_inner_attribute_nightly.rs:10:4
-//    |
-// 11 | #![trace]
-//    |    ^^^^^
-//    |
-//    = note: import resolution is stuck, try simplifying macro imports
+// Needs nightly: `cargo +nightly run --example example_mod_inner_attribute_nightly --features nightly`
+#![feature(custom_inner_attributes, proc_macro_hygiene, prelude_import)]
+#![allow(internal_features)]
+#![trace::trace]
 
-// #![feature(custom_inner_attributes)]
-// #![trace]
+fn main() {
+    foo::foo();
+    let foo = foo::Foo::new();
+    foo.bar();
+}
 
-// use trace::trace;
+mod foo {
+    // `Vec` comes from the standard prelude, which every module of the crate keeps
+    pub(super) fn foo() -> Vec<u8> {
+        println!("I'm in foo!");
+        Vec::new()
+    }
 
-// fn main() {
-//     foo::foo();
-//     let foo = foo::Foo;
-//     foo.bar();
-// }
+    #[derive(Debug)]
+    pub(super) struct Foo(Vec<u8>);
+    impl Foo {
+        pub(super) fn new() -> Foo {
+            Foo(vec![1, 2])
+        }
 
-// mod foo {
-//     pub(super) fn foo() {
-//         println!("I'm in foo!");
-//     }
+        pub(super) fn bar(&self) -> usize {
+            self.0.len()
+        }
+    }
+}
 
-//     pub(super) struct Foo;
-//     impl Foo {
-//         pub(super) fn bar(&self) {}
-//     }
-// }
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
 
-fn main() {}
+#[cfg(test)]
+trace_test!(test_mod_inner_attribute_nightly, main());
@@ -0,0 +1,49 @@
+trace::init_depth_var!();
+
+fn main() {
+    outer::run();
+    picky::run();
+}
+
+#[trace::trace(disable(inner::helper))]
+mod outer {
+    pub(super) fn run() -> u32 {
+        inner::work(2)
+    }
+
+    mod inner {
+        pub(super) fn work(n: u32) -> u32 {
+            helper(n) + 1
+        }
+
+        fn helper(n: u32) -> u32 {
+            n * 2
+        }
+    }
+}
+
+#[trace::trace(enable(deep::inner::helper))]
+mod picky {
+    pub(super) fn run() -> u32 {
+        deep::inner::work(3)
+    }
+
+    mod deep {
+        pub(super) mod inner {
+            pub(crate) fn work(n: u32) -> u32 {
+                helper(n) + 1
+            }
+
+            fn helper(n: u32) -> u32 {
+                n * 2
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_nested_filter, main());
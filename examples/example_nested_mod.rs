@@ -0,0 +1,31 @@
+use trace::trace;
+
+fn main() {
+    parser::parse("1+2");
+}
+
+#[trace]
+mod parser {
+    pub(super) fn parse(input: &str) -> i32 {
+        lexer::tokens(input).iter().sum()
+    }
+
+    mod lexer {
+        pub(super) fn tokens(input: &str) -> Vec<i32> {
+            input.split('+').map(number::parse).collect()
+        }
+
+        mod number {
+            pub(super) fn parse(token: &str) -> i32 {
+                token.parse().unwrap()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_nested_mod, main());
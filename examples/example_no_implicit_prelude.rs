@@ -0,0 +1,79 @@
+fn main() {
+    no_prelude::traced::foo(2);
+
+    let cache = std::sync::Mutex::new(7);
+    no_prelude::options::lookup(&cache, "secret");
+    for _ in 0..3 {
+        no_prelude::options::tick();
+    }
+    no_prelude::options::countdown(2);
+    no_prelude::options::read();
+    no_prelude::options::check_calls();
+}
+
+mod no_prelude {
+    #![no_implicit_prelude]
+
+    // Shadow `std` so that any non-absolute `std::` path in the expansion would fail to resolve
+    #[allow(dead_code)]
+    mod std {}
+
+    #[::trace::trace]
+    pub mod traced {
+        pub fn foo(a: i32) -> i32 {
+            bar(a) * 2
+        }
+
+        fn bar(a: i32) -> i32 {
+            a + 1
+        }
+    }
+
+    // options that generate code of their own have to do without the prelude too
+    pub mod options {
+        ::trace::init_depth_var!();
+
+        #[::trace::trace(try_lock(cache), redact(token), record_calls)]
+        pub fn lookup(cache: &::std::sync::Mutex<i32>, token: &str) -> usize {
+            token.len()
+        }
+
+        #[::trace::trace(collapse_repeats)]
+        pub fn tick() {}
+
+        #[::trace::trace(collapse_repeats, summarize_below_depth = 1, record_calls)]
+        pub fn countdown(n: u32) -> u32 {
+            if n == 0 {
+                0
+            } else {
+                countdown(n - 1)
+            }
+        }
+
+        // nothing is printed, since the variable isn't set
+        #[::trace::trace(groups(io(read)), runtime_filter = "TRACE_NO_PRELUDE_FILTER")]
+        pub fn read() {}
+
+        // macros that read what was recorded are expanded here too
+        pub fn check_calls() {
+            ::trace::assert_called_with!(countdown, n = 2);
+            ::trace::assert_call_order!(lookup, countdown);
+            // there's no baseline to load, but parsing one is compiled all the same
+            ::std::assert!(::trace::load_baseline!("no_implicit_prelude_baseline.txt").is_err());
+            ::trace::trace_expr!(1 + 1, prefix_exit = "[-] #{call_id}");
+            ::trace::trace_scope!("check", {}, prefix_exit = "[-] #{call_id}");
+        }
+
+        // not called, since the time it would print changes from run to run
+        #[allow(dead_code)]
+        #[::trace::trace(timestamp)]
+        pub fn stamped() {}
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_no_implicit_prelude, main());
@@ -0,0 +1,28 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    foo(1);
+    bar(2);
+    bar(3);
+    println!("dropped {} trace lines", trace::dropped_events!());
+}
+
+#[trace(on_error = "stderr")]
+fn foo(a: i32) -> i32 {
+    a
+}
+
+// the directory doesn't exist, so the file can't be opened and every line is dropped
+#[trace(writer = "file(target/missing-dir/trace.log)", on_error = "ignore")]
+fn bar(a: i32) -> i32 {
+    a
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_on_error, main());
@@ -0,0 +1,37 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    for user_id in [7, 42] {
+        for status in [Status::Pending, Status::Done] {
+            update(user_id, status, "note");
+        }
+    }
+    tag("urgent");
+    tag("later");
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Status {
+    Pending,
+    Done,
+}
+
+#[trace(only_when(user_id = 42, status = "Pending"))]
+fn update(user_id: u32, status: Status, note: &str) -> bool {
+    let _ = (user_id, status, note);
+    true
+}
+
+#[trace(only_when(label = "urgent"))]
+fn tag(label: &str) -> usize {
+    label.len()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_only_when, main());
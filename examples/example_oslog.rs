@@ -0,0 +1,23 @@
+// On macOS and iOS, the lines show up in Console.app (with debug messages included) under the
+// `trace` subsystem and the `requests` category, which requires `oslog` as a dependency there:
+//
+//     [target.'cfg(target_vendor = "apple")'.dependencies]
+//     oslog = "0.2"
+//
+// Elsewhere, they're written to stderr.
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    handle("/index.html");
+}
+
+#[trace(writer = "oslog", log_target = "requests")]
+fn handle(path: &str) -> u16 {
+    if path.ends_with(".html") {
+        200
+    } else {
+        404
+    }
+}
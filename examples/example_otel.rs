@@ -0,0 +1,62 @@
+use opentelemetry::trace::SpanId;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{SdkTracerProvider, SpanData, SpanExporter};
+use trace::trace;
+
+fn main() {
+    // a real program would export to Jaeger or an OTLP collector instead
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(PrintExporter)
+        .build();
+    opentelemetry::global::set_tracer_provider(provider.clone());
+
+    checkout(&["apple", "pear"]);
+
+    provider.shutdown().unwrap();
+}
+
+#[trace(otel)]
+fn checkout(items: &[&str]) -> u32 {
+    items.iter().map(|item| price(item)).sum()
+}
+
+#[trace(otel)]
+fn price(item: &str) -> u32 {
+    item.len() as u32
+}
+
+// prints the spans as they end
+#[derive(Debug)]
+struct PrintExporter;
+
+impl SpanExporter for PrintExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        for span in batch {
+            let attributes = span
+                .attributes
+                .iter()
+                .map(|attribute| format!("{} = {}", attribute.key, attribute.value))
+                .collect::<Vec<_>>();
+            let ret = span
+                .events
+                .iter()
+                .flat_map(|event| &event.attributes)
+                .map(|attribute| attribute.value.to_string())
+                .collect::<String>();
+            let root = if span.parent_span_id == SpanId::INVALID {
+                " (root)"
+            } else {
+                ""
+            };
+            println!("{}({}) = {}{}", span.name, attributes.join(", "), ret, root);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_otel, main());
@@ -0,0 +1,32 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // keep the default hook from printing the panic message to stderr
+    std::panic::set_hook(Box::new(|_| {}));
+    let _ = std::panic::catch_unwind(|| average(&[]));
+    average(&[1, 2, 3]);
+}
+
+#[trace]
+fn average(numbers: &[i32]) -> i32 {
+    divide(sum(numbers), numbers.len() as i32)
+}
+
+#[trace]
+fn sum(numbers: &[i32]) -> i32 {
+    numbers.iter().sum()
+}
+
+#[trace(prefix_panic = "[!]")]
+fn divide(a: i32, b: i32) -> i32 {
+    a / b
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_panic, main());
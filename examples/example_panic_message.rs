@@ -0,0 +1,30 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // keep the default hook from printing the panic message to stderr
+    std::panic::set_hook(Box::new(|_| {}));
+    let _ = std::panic::catch_unwind(|| checksum(&[3, 1, 4], 3));
+    let _ = std::panic::catch_unwind(|| checksum(&[3, 1, 4], 0));
+}
+
+#[trace(panic_message)]
+fn checksum(bytes: &[u8], len: usize) -> u8 {
+    if len == 0 {
+        panic!("empty message of {} bytes", bytes.len());
+    }
+    bytes[..len].iter().fold(0, |sum, &byte| sum ^ byte) ^ lookup(bytes, len)
+}
+
+#[trace(panic_message, prefix_panic = "[!]")]
+fn lookup(bytes: &[u8], i: usize) -> u8 {
+    bytes[i]
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_panic_message, main());
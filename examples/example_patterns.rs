@@ -0,0 +1,59 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    foo(1, (2, 3));
+    bar(4, (5, 6));
+    baz(Point { x: 7, y: 8 }, Meters(9), &10, 11);
+    qux([12, 13, 14], Box::new(15));
+    quux(Ok(16), 17);
+}
+
+macro_rules! ignored {
+    () => {
+        _
+    };
+}
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Meters(i32);
+
+#[trace]
+fn foo(mut a: i32, (ref b, mut c): (i32, i32)) -> i32 {
+    a += 1;
+    c += 1;
+    a + b + c
+}
+
+#[trace]
+fn bar(a: i32, pair @ (b, c): (i32, i32)) -> i32 {
+    a + b + c + pair.0
+}
+
+#[trace]
+fn baz(Point { x, y: mut height }: Point, Meters(m): Meters, &r: &i32, _: i32) -> i32 {
+    height += 1;
+    x + height + m + r
+}
+
+#[trace]
+fn qux([first, .., last]: [i32; 3], b: Box<i32>) -> i32 {
+    first + last + *b
+}
+
+#[trace]
+fn quux((Ok(n) | Err(n)): Result<i32, i32>, ignored!(): i32) -> i32 {
+    n
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_patterns, main());
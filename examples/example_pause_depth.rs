@@ -0,0 +1,22 @@
+use trace::trace;
+
+// only the calls at depth 1 (`middle`) wait for enter to be pressed, while `outer` and `inner` run
+// freely
+fn main() {
+    calls::outer(3);
+}
+
+#[trace(pause(min_depth = 1, max_depth = 1))]
+mod calls {
+    pub(super) fn outer(n: u32) -> u32 {
+        middle(n) + 1
+    }
+
+    fn middle(n: u32) -> u32 {
+        inner(n) * 2
+    }
+
+    fn inner(n: u32) -> u32 {
+        n
+    }
+}
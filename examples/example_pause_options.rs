@@ -0,0 +1,33 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+// `outer` runs freely, `inner` stops after its exit line with a prompt, and `step` stops after its
+// enter line, or waits for half a second if stdin has nothing left to read (e.g. `< /dev/null`)
+fn main() {
+    outer(2);
+    step(1);
+}
+
+#[trace(
+    pause = "exit",
+    pause_prompt = "(press enter to continue)",
+    pause_if = "depth > 0"
+)]
+fn outer(n: u32) -> u32 {
+    inner(n) + 1
+}
+
+#[trace(
+    pause = "exit",
+    pause_prompt = "(press enter to continue)",
+    pause_if = "depth > 0"
+)]
+fn inner(n: u32) -> u32 {
+    n * 2
+}
+
+#[trace(pause(on = "enter", sleep_ms = 500))]
+fn step(n: u32) -> u32 {
+    n
+}
@@ -0,0 +1,59 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use async_std::task;
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let sum = task::block_on(sum_later(vec![1, 2, 3]));
+    println!("{}", sum);
+}
+
+// a future that isn't ready the first time it's polled, and wakes its task right away
+struct YieldOnce<T> {
+    value: Option<T>,
+    yielded: bool,
+}
+
+impl<T: Unpin> Future for YieldOnce<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        if self.yielded {
+            Poll::Ready(self.value.take().unwrap())
+        } else {
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[trace(poll)]
+fn yield_once(x: i32) -> impl Future<Output = i32> {
+    YieldOnce {
+        value: Some(x),
+        yielded: false,
+    }
+}
+
+#[trace(poll, disable(values))]
+fn sum_later(values: Vec<i32>) -> impl Future<Output = i32> {
+    let total = add(values.iter().sum(), 0);
+    async move { add(total, yield_once(10).await) }
+}
+
+#[trace]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_poll, main());
@@ -0,0 +1,31 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let _ = parse("7");
+    let _ = parse("seven");
+    // keep the default hook from printing the panic message to stderr
+    std::panic::set_hook(Box::new(|_| {}));
+    let _ = std::panic::catch_unwind(|| checked_div(1, 0));
+}
+
+// an alias of `Result` is told apart like `Result` itself
+type ParseResult = Result<i32, std::num::ParseIntError>;
+
+#[trace(prefix_error = "[!]")]
+fn parse(s: &str) -> ParseResult {
+    s.parse()
+}
+
+#[trace(deferred, prefix_panic = "[!!]")]
+fn checked_div(a: i32, b: i32) -> i32 {
+    a / b
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_prefix_error, main());
@@ -0,0 +1,31 @@
+use std::thread;
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    thread::Builder::new()
+        .name("worker".to_string())
+        .spawn(|| foo(1))
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[trace(prefix_enter = "[{thread}:{fn}]", prefix_exit = "[{thread}:{fn}]")]
+fn foo(a: i32) -> i32 {
+    bar(a) + 1
+}
+
+#[trace(prefix_enter = "{{{depth}}} >", prefix_exit = "{{{depth}}} <")]
+fn bar(a: i32) -> i32 {
+    a * 2
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_prefix_placeholders, main());
@@ -4,16 +4,30 @@ trace::init_depth_var!();
 
 fn main() {
     foo(Foo("Foo".to_string()));
+    split(255, 2);
 }
 
+#[allow(dead_code)]
 #[derive(Debug)]
 struct Foo(String);
 
 #[trace(pretty)]
 fn foo(a: Foo) -> Foo {
+    bar(&a);
     a
 }
 
+#[trace(pretty)]
+fn bar(a: &Foo) -> (usize, Vec<u8>) {
+    (a.0.len(), a.0.bytes().take(2).collect())
+}
+
+// `{r}` is pretty printed, while `{n:x}` keeps its own format spec
+#[trace(pretty, format_enter = "{n:x} into {parts}", format_exit = "parts {r}")]
+fn split(n: u32, parts: u32) -> Vec<u32> {
+    vec![n / parts; parts as usize]
+}
+
 #[cfg(test)]
 #[macro_use]
 mod trace_test;
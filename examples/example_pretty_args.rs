@@ -0,0 +1,71 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let config = Config {
+        name: "server".to_string(),
+        port: 8080,
+    };
+    let addr = bind(&config, 3);
+    connect(addr, &[1, 2]);
+    lookup("server", &config);
+    report(&config);
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Config {
+    name: String,
+    port: u16,
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Addr {
+    host: String,
+    port: u16,
+}
+
+// only `config` is pretty printed, so `retries` and the return value stay on one line
+#[trace(pretty(config))]
+fn bind(config: &Config, retries: u32) -> Addr {
+    let _ = retries;
+    Addr {
+        host: config.name.clone(),
+        port: config.port,
+    }
+}
+
+#[trace(pretty(args))]
+fn connect(addr: Addr, payload: &[u8]) -> usize {
+    let _ = addr;
+    payload.len()
+}
+
+// `pretty(return)` leaves what `format_enter` interpolates as it is
+#[trace(pretty(return), format_enter = "{name} in {config:?}")]
+fn lookup(name: &str, config: &Config) -> Option<Addr> {
+    (name == config.name).then(|| Addr {
+        host: config.name.clone(),
+        port: config.port,
+    })
+}
+
+#[trace]
+fn report(config: &Config) {
+    describe(config);
+}
+
+// values that span several lines without `pretty` are indented to the depth of the call too
+#[trace(format_enter = "{config:#?}", display(r))]
+fn describe(config: &Config) -> String {
+    format!("name: {}\nport: {}", config.name, config.port)
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_pretty_args, main());
@@ -0,0 +1,31 @@
+use trace::trace;
+
+// stands in for a logging macro of an embedded target, like `defmt::trace!`
+macro_rules! log_trace {
+    ($($arg:tt)*) => {
+        println!("TRACE {}", format_args!($($arg)*))
+    };
+}
+
+trace::init_atomic_depth_var!();
+
+fn main() {
+    checksum(&[1, 2, 3]);
+}
+
+#[trace(printer = "log_trace")]
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |sum, &byte| add(sum, byte))
+}
+
+#[trace(printer = "log_trace", prefix_enter = "[{fn}]", disable(b))]
+fn add(a: u8, b: u8) -> u8 {
+    a.wrapping_add(b)
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_printer, main());
@@ -0,0 +1,33 @@
+trace::init_depth_var!();
+
+fn main() {
+    parser::parse("1");
+    lexer::parse("2");
+}
+
+mod parser {
+    use crate::DEPTH;
+    use trace::trace;
+
+    #[trace(qualified)]
+    pub(super) fn parse(input: &str) -> u32 {
+        super::lexer::parse(input) + 1
+    }
+}
+
+mod lexer {
+    use crate::DEPTH;
+    use trace::trace;
+
+    #[trace(qualified = "location")]
+    pub(super) fn parse(input: &str) -> u32 {
+        input.parse().unwrap()
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_qualified, main());
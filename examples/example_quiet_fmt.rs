@@ -0,0 +1,55 @@
+use std::fmt;
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let account = Account { cents: 1050 };
+    deposit(&account, 5);
+    audit(&Ledger, 1);
+}
+
+struct Account {
+    cents: u64,
+}
+
+#[trace(quiet_fmt)]
+impl Account {
+    fn dollars(&self) -> u64 {
+        self.cents / 100
+    }
+}
+
+impl fmt::Debug for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Account(${})", self.dollars())
+    }
+}
+
+// the depth is left alone while `audit` formats its arguments, so a `Debug` impl sees the real one
+struct Ledger;
+
+impl fmt::Debug for Ledger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        trace::section!("formatting");
+        write!(f, "Ledger(depth {})", trace::current_depth!())
+    }
+}
+
+#[trace(quiet_fmt, format_enter = "auditing {ledger:?} with {entries} entries")]
+fn audit(ledger: &Ledger, entries: u64) -> u64 {
+    entries
+}
+
+#[trace(quiet_fmt)]
+fn deposit(account: &Account, dollars: u64) -> u64 {
+    account.dollars() + dollars
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_quiet_fmt, main());
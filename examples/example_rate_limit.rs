@@ -0,0 +1,36 @@
+use std::{thread, time::Duration};
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // a burst of events, of which only the first few are printed
+    for event in 0..20 {
+        on_event(event);
+    }
+    // by now the bucket has refilled, so the next event is printed after a note about the
+    // dropped ones
+    thread::sleep(Duration::from_millis(1100));
+    on_event(20);
+    // the events dropped at the end of a traced call are reported before it returns
+    dispatch(21..30);
+}
+
+#[trace]
+fn dispatch(events: std::ops::Range<u32>) {
+    for event in events {
+        on_event(event);
+    }
+}
+
+#[trace(rate_limit = "3/s")]
+fn on_event(event: u32) -> u32 {
+    event * 10
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_rate_limit, main());
@@ -0,0 +1,34 @@
+use std::{
+    rc::{Rc, Weak},
+    sync::Arc,
+};
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let state = Arc::new(5);
+    let shared = share(&state);
+    let node = Rc::new(String::from("root"));
+    let parent = Rc::downgrade(&node);
+    attach(Rc::clone(&node), parent);
+    drop(shared);
+}
+
+#[trace(rc_counts)]
+fn share(state: &Arc<i32>) -> Arc<i32> {
+    Arc::clone(state)
+}
+
+#[trace(rc_counts)]
+fn attach(node: Rc<String>, parent: Weak<String>) {
+    let _ = (node, parent);
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_rc_counts, main());
@@ -0,0 +1,41 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let mut stack = Stack::default();
+    stack.push(1);
+    stack.push(2);
+    stack.peek();
+    stack.into_items();
+}
+
+#[derive(Debug, Default)]
+struct Stack {
+    items: Vec<i32>,
+}
+
+#[trace(receiver)]
+impl Stack {
+    fn push(&mut self, item: i32) {
+        self.items.push(item);
+    }
+
+    fn into_items(self) -> Vec<i32> {
+        self.items
+    }
+}
+
+impl Stack {
+    #[trace(format_enter = "peeking at {self:?}")]
+    fn peek(&self) -> Option<&i32> {
+        self.items.last()
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_receiver, main());
@@ -0,0 +1,32 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let token = login("alice", "hunter2");
+    fetch(&token, "/orders");
+    logout(&token);
+}
+
+#[trace(redact(password))]
+fn login(user: &str, password: &str) -> String {
+    format!("{user}-{}", password.len())
+}
+
+#[trace(redact(token))]
+fn fetch(token: &str, path: &str) -> usize {
+    token.len() + path.len()
+}
+
+// unlike `redact`, `disable` leaves the argument out of the line
+#[trace(disable(token))]
+fn logout(token: &str) {
+    let _ = token;
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_redact, main());
@@ -0,0 +1,40 @@
+trace::init_depth_var!();
+
+fn main() {
+    let mut traced_fns = trace_runtime::registry().collect::<Vec<_>>();
+    traced_fns.sort_by_key(|traced_fn| traced_fn.line);
+    for traced_fn in &traced_fns {
+        println!(
+            "{} at line {} with ({})",
+            traced_fn.path(),
+            traced_fn.line,
+            traced_fn.options
+        );
+    }
+
+    shapes::area(3);
+    for traced_fn in &traced_fns {
+        if traced_fn.name == "square" {
+            traced_fn.set_enabled(false);
+        }
+    }
+    shapes::area(4);
+}
+
+#[trace::trace(registry, prefix_enter = "[in]")]
+mod shapes {
+    pub(super) fn area(side: u32) -> u32 {
+        square(side)
+    }
+
+    fn square(n: u32) -> u32 {
+        n * n
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_registry, main());
@@ -0,0 +1,35 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    flags(0b1010);
+    names(3);
+    padded(7);
+}
+
+#[trace(ret_format = "{:#x}")]
+fn flags(bits: u32) -> u32 {
+    bits << 4
+}
+
+#[trace(ret_fmt_with = "summarize")]
+fn names(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("name{i}")).collect()
+}
+
+#[trace(ret_fmt_with = "str::len", ret_format = "{:>4} bytes")]
+fn padded(a: usize) -> String {
+    "#".repeat(a)
+}
+
+fn summarize(names: &[String]) -> String {
+    format!("{} names, first is {:?}", names.len(), names.first())
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_ret_format, main());
@@ -0,0 +1,36 @@
+// requires the `runtime` feature: cargo run --example example_runtime --features runtime
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // without `trace-runtime`, `inner::bar` would be indented as if it wasn't called by `foo`
+    foo(1);
+
+    trace_runtime::set_enabled(false);
+    foo(2);
+    trace_runtime::set_enabled(true);
+
+    trace_runtime::set_writer(|line| println!("trace: {}", line));
+    foo(3);
+    trace_runtime::reset_writer();
+}
+
+#[trace]
+fn foo(a: i32) -> i32 {
+    inner::bar(a) + 1
+}
+
+#[trace]
+mod inner {
+    pub(super) fn bar(a: i32) -> i32 {
+        a * 2
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_runtime, main());
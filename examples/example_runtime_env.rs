@@ -0,0 +1,32 @@
+// requires the `runtime` feature: cargo run --example example_runtime_env --features runtime
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // the variables are usually set when running the program, but they only have to be set before
+    // the first traced call
+    std::env::set_var("TRACE_PREFIX_ENTER", "-->");
+    std::env::set_var("TRACE_PREFIX_EXIT", "<--");
+    std::env::set_var("TRACE_WORD_ENTER", "Calling");
+    std::env::set_var("TRACE_FORMAT_ENTER", "{args}; …");
+    std::env::set_var("TRACE_FORMAT_EXIT", "`{r}`");
+    foo(1, "a");
+}
+
+#[trace]
+fn foo(a: i32, b: &str) -> String {
+    bar(a).to_string() + b
+}
+
+#[trace(prefix_enter = "[{depth}]", format_exit = "{r:?}!")]
+fn bar(a: i32) -> i32 {
+    a + 1
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_runtime_env, main());
@@ -0,0 +1,40 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // usually set outside of the program, e.g. `RUST_TRACE=foo,parser::* cargo run`
+    std::env::set_var("RUST_TRACE", "foo, parser::*");
+
+    foo(1);
+    bar(2);
+    parser::parse("1 + 2");
+}
+
+#[trace(runtime_filter = "RUST_TRACE")]
+fn foo(a: i32) -> i32 {
+    a
+}
+
+#[trace(runtime_filter = "RUST_TRACE")]
+fn bar(a: i32) -> i32 {
+    a
+}
+
+#[trace(runtime_filter = "RUST_TRACE")]
+mod parser {
+    pub(super) fn parse(input: &str) -> Vec<&str> {
+        input.split(' ').filter(|token| is_token(token)).collect()
+    }
+
+    fn is_token(token: &str) -> bool {
+        !token.is_empty()
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_runtime_filter, main());
@@ -0,0 +1,23 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let mut total = 0;
+    for i in 0..10 {
+        total += step(i);
+    }
+    println!("total = {}", total);
+}
+
+#[trace(sample = 4)]
+fn step(i: u64) -> u64 {
+    i * 2
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_sample, main());
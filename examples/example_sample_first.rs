@@ -0,0 +1,32 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let mut total = 0;
+    for i in 0..12 {
+        total += step(i);
+    }
+    for i in 0..5 {
+        total += warm_up(i);
+    }
+    println!("total = {}", total);
+}
+
+// the first 3 calls, and every 5th call
+#[trace(sample = 5, sample_first = 3)]
+fn step(i: u64) -> u64 {
+    i * 2
+}
+
+#[trace(sample_first = 2)]
+fn warm_up(i: u64) -> u64 {
+    i + 1
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_sample_first, main());
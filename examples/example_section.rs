@@ -0,0 +1,27 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    compile("1 + 2");
+}
+
+#[trace]
+fn compile(source: &str) -> usize {
+    trace::section!("lex");
+    let tokens = lex(source);
+    trace::section!("parse");
+    tokens.len()
+}
+
+#[trace]
+fn lex(source: &str) -> Vec<&str> {
+    source.split_whitespace().collect()
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_section, main());
@@ -0,0 +1,21 @@
+use std::{thread, time::Duration};
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    connect();
+    thread::sleep(Duration::from_millis(50));
+    connect();
+}
+
+#[trace(since_start)]
+fn connect() {
+    handshake();
+}
+
+#[trace(since_start)]
+fn handshake() {
+    thread::sleep(Duration::from_millis(10));
+}
@@ -0,0 +1,38 @@
+use trace::trace;
+
+fn main() {
+    shapes::area(&shapes::Shape::Square(3));
+    shapes::area(&shapes::Shape::Circle(2));
+}
+
+#[trace(skip_cfg)]
+mod shapes {
+    #[derive(Debug)]
+    pub(super) enum Shape {
+        Square(u32),
+        Circle(u32),
+    }
+
+    pub(super) fn area(shape: &Shape) -> u32 {
+        check(shape);
+        match *shape {
+            Shape::Square(side) => side * side,
+            Shape::Circle(radius) => 3 * radius * radius,
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn check(shape: &Shape) {
+        assert!(!matches!(*shape, Shape::Square(0) | Shape::Circle(0)));
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn check(_: &Shape) {}
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_skip_cfg, main());
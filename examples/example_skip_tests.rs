@@ -0,0 +1,30 @@
+use trace::trace;
+
+fn main() {
+    println!("{}", temperature::to_fahrenheit(100));
+}
+
+// `cargo test --example example_skip_tests` only prints the calls `converts_boiling_point` makes,
+// not the test itself
+#[trace(skip_tests)]
+mod temperature {
+    pub(super) fn to_fahrenheit(celsius: i32) -> i32 {
+        scale(celsius) + 32
+    }
+
+    fn scale(celsius: i32) -> i32 {
+        celsius * 9 / 5
+    }
+
+    #[test]
+    fn converts_boiling_point() {
+        assert_eq!(to_fahrenheit(100), 212);
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_skip_tests, main());
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let mut names = Cache::default();
+    names.insert("a".to_string(), 1);
+    names.get(&"a".to_string());
+    names.get(&"b".to_string());
+
+    let mut ids = Cache::default();
+    ids.insert(7_u64, 2);
+    ids.get(&7);
+
+    largest(&[1, 5, 3]);
+    largest(&["x", "y"]);
+
+    fib(5);
+
+    trace::print_stats!();
+}
+
+#[derive(Default)]
+struct Cache<K> {
+    entries: HashMap<K, i32>,
+}
+
+#[trace(stats)]
+impl<K: Hash + Eq + std::fmt::Debug> Cache<K> {
+    fn insert(&mut self, key: K, value: i32) {
+        self.entries.insert(key, value);
+    }
+
+    fn get(&self, key: &K) -> Option<i32> {
+        self.entries.get(key).copied()
+    }
+}
+
+#[trace(stats)]
+fn largest<T: PartialOrd + Copy + std::fmt::Debug>(values: &[T]) -> T {
+    let mut largest = values[0];
+    for &value in values {
+        if value > largest {
+            largest = value;
+        }
+    }
+    largest
+}
+
+// recursive calls are all counted under the same name, so they show up near the top of the table
+#[trace(stats)]
+fn fib(n: u32) -> u32 {
+    if n < 2 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
@@ -0,0 +1,41 @@
+fn main() {
+    outer::t::parse("1,2,3");
+    outer::t::parse("4");
+    // the statistics are the ones declared by the traced `mod`. Only the number of calls is
+    // printed here, by name, since the times (and so the order of the rows) change between runs
+    let mut rows = outer::t::report()
+        .lines()
+        .skip(1)
+        .map(|row| row.split_whitespace().take(3).collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>();
+    rows.sort();
+    for row in rows {
+        println!("{}", row);
+    }
+}
+
+mod outer {
+    use trace::trace;
+
+    #[trace(stats, disable(report))]
+    pub mod t {
+        pub fn parse(input: &str) -> Vec<u32> {
+            input.split(',').map(number).collect()
+        }
+
+        fn number(digits: &str) -> u32 {
+            digits.parse().unwrap()
+        }
+
+        pub fn report() -> String {
+            trace::bench_report!()
+        }
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_stats_mod, main());
@@ -0,0 +1,45 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    // keep the default hook from printing the panic message to stderr
+    std::panic::set_hook(Box::new(|_| {}));
+    let _ = std::panic::catch_unwind(|| load("GZ\x01"));
+    let _ = std::panic::catch_unwind(|| load("PK"));
+    let _ = std::panic::catch_unwind(|| checksum(&[1, 2]));
+    load("GZ");
+}
+
+#[trace(stubs)]
+fn load(file: &str) -> usize {
+    parse(file.as_bytes())
+}
+
+#[trace(stubs)]
+fn parse(bytes: &[u8]) -> usize {
+    match bytes {
+        [b'G', b'Z', rest @ ..] => parse_header(rest),
+        _ => unimplemented!(),
+    }
+}
+
+#[trace(stubs)]
+fn parse_header(rest: &[u8]) -> usize {
+    if !rest.is_empty() {
+        checksum(rest);
+    }
+    rest.len()
+}
+
+#[trace(stubs, compact)]
+fn checksum(bytes: &[u8]) -> u8 {
+    todo!("checksums of {} bytes", bytes.len())
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_stubs, main());
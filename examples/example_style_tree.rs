@@ -0,0 +1,33 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    eval(&Expr::Add(
+        Box::new(Expr::Num(1)),
+        Box::new(Expr::Mul(Box::new(Expr::Num(2)), Box::new(Expr::Num(3)))),
+    ));
+}
+
+#[derive(Debug)]
+enum Expr {
+    Num(i64),
+    Add(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+#[trace(style = "tree", format_enter = "{expr:?}")]
+fn eval(expr: &Expr) -> i64 {
+    match *expr {
+        Expr::Num(n) => n,
+        Expr::Add(ref a, ref b) => eval(a) + eval(b),
+        Expr::Mul(ref a, ref b) => eval(a) * eval(b),
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_style_tree, main());
@@ -0,0 +1,36 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let tokens = ["(", "1", "(", "2", "(", "3", ")", ")", ")"];
+    let (nesting, _) = parse_list(&tokens[1..]);
+    println!("nesting {}", nesting);
+}
+
+// only the two outermost levels are printed with their arguments and return values
+#[trace(summarize_below_depth = 2)]
+fn parse_list<'a>(tokens: &'a [&'a str]) -> (usize, &'a [&'a str]) {
+    let mut nesting = 1;
+    let mut rest = tokens;
+    while let Some((&token, tail)) = rest.split_first() {
+        rest = tail;
+        match token {
+            "(" => {
+                let (inner, tail) = parse_list(rest);
+                nesting = nesting.max(inner + 1);
+                rest = tail;
+            }
+            ")" => break,
+            _ => (),
+        }
+    }
+    (nesting, rest)
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_summarize_below_depth, main());
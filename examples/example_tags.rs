@@ -0,0 +1,47 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    compile("1 + 2");
+    // the whole parser is left out, without touching its attributes
+    trace_runtime::disable_tag("parser");
+    compile("3");
+    trace_runtime::enable_tag("parser");
+    trace_runtime::disable_tag("codegen");
+    compile("4");
+}
+
+#[trace]
+fn compile(source: &str) -> Vec<String> {
+    codegen::emit(&parser::parse(source))
+}
+
+// `depth_path` indents the calls in the `mod`s along with the others
+#[trace(tag = "parser", depth_path = "crate::DEPTH")]
+mod parser {
+    pub fn parse(source: &str) -> Vec<String> {
+        source.split_whitespace().map(token).collect()
+    }
+
+    fn token(word: &str) -> String {
+        word.to_string()
+    }
+}
+
+#[trace(tag = "codegen", depth_path = "crate::DEPTH")]
+mod codegen {
+    pub fn emit(tokens: &[String]) -> Vec<String> {
+        tokens
+            .iter()
+            .map(|token| format!("push {}", token))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_tags, main());
@@ -0,0 +1,32 @@
+use std::thread;
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    for name in ["worker-1", "worker-2"] {
+        thread::Builder::new()
+            .name(name.to_string())
+            .spawn(|| foo(2))
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
+
+#[trace(thread)]
+fn foo(a: i32) -> i32 {
+    bar(a) + 1
+}
+
+#[trace(thread)]
+fn bar(a: i32) -> i32 {
+    a * 2
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_thread_names, main());
@@ -0,0 +1,22 @@
+use std::{thread, time::Duration};
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    fetch("/index.html");
+    thread::sleep(Duration::from_millis(50));
+    retry(2);
+}
+
+#[trace(timestamp)]
+fn fetch(path: &str) -> usize {
+    thread::sleep(Duration::from_millis(10));
+    path.len()
+}
+
+#[trace(timestamp = "elapsed")]
+fn retry(attempts: u32) -> bool {
+    attempts > 1
+}
@@ -0,0 +1,23 @@
+use std::{thread, time::Duration};
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    load(3);
+    for ms in [1, 20] {
+        fetch(ms);
+    }
+}
+
+#[trace(timing)]
+fn load(n: u64) -> u64 {
+    thread::sleep(Duration::from_millis(n));
+    n * 2
+}
+
+#[trace(timing_threshold = "10ms")]
+fn fetch(ms: u64) {
+    thread::sleep(Duration::from_millis(ms));
+}
@@ -0,0 +1,37 @@
+// The tests are traced when running `cargo test --example example_trace_all_tests`, which only
+// prints the failing ones (here, the test expected to panic)
+
+fn main() {
+    println!("{}", parse_port("8080").unwrap());
+}
+
+fn parse_port(s: &str) -> Result<u16, std::num::ParseIntError> {
+    s.parse()
+}
+
+#[cfg(test)]
+#[trace::trace_all_tests]
+mod tests {
+    use super::parse_port;
+
+    fn ports(list: &str) -> Vec<u16> {
+        list.split(',').map(|s| parse_port(s).unwrap()).collect()
+    }
+
+    #[test]
+    fn parses_port() -> Result<(), std::num::ParseIntError> {
+        assert_eq!(parse_port("8080")?, 8080);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_words() {
+        ports("80,http");
+    }
+
+    #[async_std::test]
+    async fn parses_ports() {
+        assert_eq!(ports("80,443"), [80, 443]);
+    }
+}
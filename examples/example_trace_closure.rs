@@ -0,0 +1,36 @@
+trace::init_depth_var!();
+
+fn main() {
+    let offset = 10;
+    let shifted = [1, 2]
+        .into_iter()
+        .map(trace::trace_closure!(shift, |x: i32| x + offset))
+        .collect::<Vec<_>>();
+    println!("{:?}", shifted);
+
+    let mut total = 0;
+    let mut add = trace::trace_closure!((prefix_enter = "[adding]"), add, |n| {
+        total += n;
+        total
+    });
+    add(3);
+    add(4);
+    println!("total = {}", total);
+
+    let name = String::from("worker");
+    let greet = trace::trace_closure!(greet, move |greeting: &str| -> String {
+        if greeting.is_empty() {
+            return name.clone();
+        }
+        format!("{}, {}", greeting, name)
+    });
+    greet("hello");
+    greet("");
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_trace_closure, main());
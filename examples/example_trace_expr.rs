@@ -0,0 +1,27 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let x = 3;
+    let y = trace::trace_expr!(square(x) + 1);
+    let total = sum_of_squares(&[1, y]);
+    trace::trace_expr!(total / 2, prefix_enter = "[IN]", prefix_exit = "[OUT]");
+}
+
+#[trace]
+fn square(x: i32) -> i32 {
+    x * x
+}
+
+#[trace]
+fn sum_of_squares(values: &[i32]) -> i32 {
+    trace::trace_expr!(values.iter().copied().map(square).sum())
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_trace_expr, main());
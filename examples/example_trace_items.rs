@@ -0,0 +1,37 @@
+use trace::trace_items;
+
+trace::init_depth_var!();
+
+fn main() {
+    let total = sum(&[1, 2, 3]);
+    Stack::default().push(total);
+}
+
+trace_items! {
+    (prefix_enter = "[IN]", prefix_exit = "[OUT]", disable(double))
+
+    fn sum(values: &[i32]) -> i32 {
+        values.iter().map(|&v| double(v)).sum()
+    }
+
+    fn double(v: i32) -> i32 {
+        v * 2
+    }
+
+    #[derive(Default)]
+    struct Stack(Vec<i32>);
+
+    impl Stack {
+        fn push(&mut self, value: i32) -> usize {
+            self.0.push(value);
+            self.0.len()
+        }
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_trace_items, main());
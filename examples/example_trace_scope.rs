@@ -0,0 +1,45 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let lines = ["name = server", "port = 8080", "", "retries = 3"];
+    let settings = load(&lines);
+    let total = trace::trace_scope!(
+        "summing",
+        { settings.iter().map(|&(_, value)| value).sum::<usize>() },
+        prefix_enter = "[IN]",
+        prefix_exit = "[OUT]"
+    );
+    assert_eq!(total, 8083);
+}
+
+#[trace]
+fn load<'a>(lines: &[&'a str]) -> Vec<(&'a str, usize)> {
+    let mut settings = vec![];
+    for line in lines {
+        // leaving the block early still prints its exit line
+        trace::trace_scope!("parsing a line", {
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(setting) = parse(line) {
+                settings.push(setting);
+            }
+        });
+    }
+    settings
+}
+
+#[trace]
+fn parse(line: &str) -> Option<(&str, usize)> {
+    let (key, value) = line.split_once(" = ")?;
+    Some((key, value.parse().ok()?))
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_trace_scope, main());
@@ -0,0 +1,64 @@
+use std::fmt::{self, Write};
+
+use trace::trace;
+use tracing::{
+    field::{Field, Visit},
+    span, Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+fn main() {
+    let subscriber = tracing_subscriber::registry().with(PrintLayer);
+    tracing::subscriber::with_default(subscriber, || {
+        let total = sum(&[1, 2, 3]);
+        scale(total, 2);
+    });
+}
+
+#[trace(tracing)]
+fn sum(numbers: &[i32]) -> i32 {
+    numbers.iter().map(|n| scale(*n, 1)).sum()
+}
+
+#[trace(tracing)]
+fn scale(n: i32, factor: i32) -> i32 {
+    n * factor
+}
+
+// prints spans as they're created and recorded to, indented by how deeply they're nested
+struct PrintLayer;
+
+struct FieldPrinter(String);
+
+impl Visit for FieldPrinter {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push_str(", ");
+        }
+        write!(self.0, "{} = {:?}", field.name(), value).unwrap();
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for PrintLayer {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut fields = FieldPrinter(String::new());
+        attrs.record(&mut fields);
+        let depth = ctx.span_scope(id).map_or(0, |scope| scope.count() - 1);
+        println!("{:depth$}new {}({})", "", attrs.metadata().name(), fields.0);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let mut fields = FieldPrinter(String::new());
+        values.record(&mut fields);
+        let span = ctx.span(id).unwrap();
+        let depth = span.scope().count() - 1;
+        println!("{:depth$}record {}: {}", "", span.name(), fields.0);
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_tracing, main());
@@ -0,0 +1,40 @@
+use std::panic::Location;
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let (line, location) = (line!(), caller_location());
+    assert_eq!(location.line(), line);
+
+    let readings = Readings(vec![3, 5]);
+    let (line, location) = (line!(), readings.get_location(1));
+    assert_eq!(location.map(Location::line), Some(line));
+    // leaving the body with `?` still leaves the depth as it was
+    readings.get_location(2);
+    readings.get_location(0);
+}
+
+#[trace]
+#[track_caller]
+fn caller_location() -> &'static Location<'static> {
+    Location::caller()
+}
+
+struct Readings(Vec<u32>);
+
+#[trace]
+impl Readings {
+    #[track_caller]
+    fn get_location(&self, index: usize) -> Option<&'static Location<'static>> {
+        self.0.get(index)?;
+        Some(caller_location())
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_track_caller, main());
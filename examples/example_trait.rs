@@ -0,0 +1,54 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let circle = Circle { r: 2 };
+    circle.describe();
+    let square = Square { side: 3 };
+    square.describe();
+}
+
+#[trace(disable(name))]
+trait Shape {
+    fn area(&self) -> u32;
+
+    fn name(&self) -> &'static str {
+        "shape"
+    }
+
+    fn describe(&self) -> String {
+        format!("{} with area {}", self.name(), self.area())
+    }
+}
+
+struct Circle {
+    r: u32,
+}
+
+impl Shape for Circle {
+    fn area(&self) -> u32 {
+        3 * self.r * self.r
+    }
+
+    fn name(&self) -> &'static str {
+        "circle"
+    }
+}
+
+struct Square {
+    side: u32,
+}
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.side * self.side
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_trait, main());
@@ -0,0 +1,50 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let total: u32 = Countdown(2).sum();
+    println!("total {}", total);
+    let tokens = Tokens(vec!["let", "x"])
+        .map(|token| token.0)
+        .collect::<Vec<_>>();
+    println!("tokens {}", tokens.join(" "));
+}
+
+struct Countdown(u32);
+
+#[trace]
+impl Iterator for Countdown {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.0;
+        self.0 = n.checked_sub(1)?;
+        Some(n)
+    }
+}
+
+// doesn't implement `Debug`
+struct Token(&'static str);
+
+struct Tokens(Vec<&'static str>);
+
+#[trace]
+impl Iterator for Tokens {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            None
+        } else {
+            Some(Token(self.0.remove(0)))
+        }
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_trait_assoc_types, main());
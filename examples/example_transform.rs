@@ -0,0 +1,50 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let buf = vec![0u8; 4096];
+    checksum(&buf, 7);
+
+    let tree = Tree::Node(vec![
+        Tree::Leaf(1),
+        Tree::Node(vec![Tree::Leaf(2), Tree::Leaf(3)]),
+    ]);
+    sum(&tree);
+}
+
+#[trace(transform(buf = "buf.len()"))]
+fn checksum(buf: &[u8], seed: u32) -> u32 {
+    buf.iter()
+        .fold(seed, |acc, &b| acc.wrapping_mul(31).wrapping_add(b as u32))
+}
+
+#[derive(Debug)]
+enum Tree {
+    Leaf(i32),
+    Node(Vec<Tree>),
+}
+
+impl Tree {
+    fn size(&self) -> usize {
+        match self {
+            Tree::Leaf(_) => 1,
+            Tree::Node(children) => 1 + children.iter().map(Tree::size).sum::<usize>(),
+        }
+    }
+}
+
+#[trace(transform(tree = "tree.size()"))]
+fn sum(tree: &Tree) -> i32 {
+    match tree {
+        Tree::Leaf(value) => *value,
+        Tree::Node(children) => children.iter().map(sum).sum(),
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_transform, main());
@@ -0,0 +1,28 @@
+use std::sync::{Arc, Mutex, RwLock};
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    let cache = Arc::new(Mutex::new(vec![1, 2]));
+    let config = RwLock::new("debug");
+    update(Arc::clone(&cache), &config, 3);
+    let _held = cache.lock().unwrap();
+    update(Arc::clone(&cache), &config, 4);
+}
+
+#[trace(try_lock(cache, config))]
+fn update(cache: Arc<Mutex<Vec<i32>>>, config: &RwLock<&str>, value: i32) {
+    if let Ok(mut cache) = cache.try_lock() {
+        cache.push(value);
+    }
+    let _ = config;
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_try_lock, main());
@@ -0,0 +1,44 @@
+use std::fmt::Debug;
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    largest(&[3, 7, 5]);
+    largest(&["b", "a"]);
+    describe(1.5, (2, 'x'));
+    hint(vec![1u8]);
+    done();
+}
+
+#[trace(types)]
+fn largest<T: Debug + PartialOrd + Copy>(items: &[T]) -> T {
+    let mut largest = items[0];
+    for &item in items {
+        if item > largest {
+            largest = item;
+        }
+    }
+    largest
+}
+
+#[trace(types)]
+fn describe(ratio: f64, (count, c): (u32, char)) -> String {
+    format!("{} {} {}", ratio, count, c)
+}
+
+#[trace(types)]
+fn hint(bytes: impl Debug) -> Option<usize> {
+    let _ = bytes;
+    None
+}
+
+#[trace(types)]
+fn done() {}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_types, main());
@@ -0,0 +1,38 @@
+// requires the `runtime` feature: cargo run --example example_verbose_scope --features runtime
+use trace::trace;
+
+trace::init_depth_var!();
+
+#[derive(Debug)]
+struct Order {
+    id: u32,
+    items: Vec<&'static str>,
+}
+
+fn main() {
+    // the orders are only traced up to the first level, and without their details
+    place(Order {
+        id: 1,
+        items: vec!["tea"],
+    });
+
+    // until the one that needs a closer look
+    trace_runtime::verbose_scope(|| {
+        place(Order {
+            id: 2,
+            items: vec!["tea", "scones"],
+        })
+    });
+}
+
+#[trace(max_depth = 1, no_args)]
+fn place(order: Order) -> u32 {
+    let total = order.items.iter().map(|item| price(item)).sum();
+    println!("order {} costs {}", order.id, total);
+    total
+}
+
+#[trace(max_depth = 1)]
+fn price(item: &str) -> u32 {
+    item.len() as u32
+}
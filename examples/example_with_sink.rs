@@ -0,0 +1,41 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    setup(1);
+
+    let mut captured = String::new();
+    let result = trace::with_sink!(&mut captured, || run_scenario(3));
+    println!("scenario returned {}", result);
+
+    teardown();
+
+    println!("captured {} lines:", captured.lines().count());
+    print!("{}", captured);
+}
+
+#[trace(scoped_sink)]
+fn setup(a: i32) -> i32 {
+    a
+}
+
+#[trace(scoped_sink)]
+fn run_scenario(steps: u32) -> u32 {
+    (0..steps).map(step).sum()
+}
+
+#[trace(scoped_sink)]
+fn step(i: u32) -> u32 {
+    i * 10
+}
+
+#[trace(scoped_sink)]
+fn teardown() {}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_with_sink, main());
@@ -0,0 +1,29 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    suma(1, 2);
+}
+
+#[trace(word_enter = "Entrando en", word_exit = "Saliendo de")]
+fn suma(a: i32, b: i32) -> i32 {
+    doble(a) + b
+}
+
+#[trace(
+    prefix_enter = ">>",
+    prefix_exit = "<<",
+    word_enter = "CALL",
+    word_exit = "RET"
+)]
+fn doble(a: i32) -> i32 {
+    a * 2
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_words, main());
@@ -0,0 +1,62 @@
+use std::{cell::RefCell, fmt, fs};
+
+use trace::trace;
+
+trace::init_depth_var!();
+
+thread_local! {
+    static SINK: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn sink(line: fmt::Arguments<'_>) {
+    SINK.with(|sink| sink.borrow_mut().push(line.to_string()));
+}
+
+macro_rules! shout {
+    ($($arg:tt)*) => {
+        println!("{}", format!($($arg)*).to_uppercase())
+    };
+}
+
+const TRACE_FILE: &str = "target/example_writer.log";
+
+fn main() {
+    let _ = fs::remove_file(TRACE_FILE);
+    println!("program output");
+    to_stderr(1);
+    to_sink(2);
+    to_macro(3);
+    to_file(4);
+
+    println!("lines sent to the sink:");
+    SINK.with(|sink| sink.borrow().iter().for_each(|line| println!("{}", line)));
+    println!("lines written to the file:");
+    print!("{}", fs::read_to_string(TRACE_FILE).unwrap());
+}
+
+#[trace(writer = "stderr")]
+fn to_stderr(a: i32) -> i32 {
+    a
+}
+
+#[trace(writer = "sink")]
+fn to_sink(a: i32) -> i32 {
+    a
+}
+
+#[trace(writer = "shout!")]
+fn to_macro(a: i32) -> i32 {
+    a
+}
+
+#[trace(writer = "file(target/example_writer.log)")]
+fn to_file(a: i32) -> i32 {
+    a
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_writer, main());
@@ -0,0 +1,43 @@
+use trace::trace;
+
+trace::init_depth_var!();
+
+fn main() {
+    timer::tick();
+    let mut queue = Queue {
+        items: vec![1, 2, 3],
+    };
+    queue.pop();
+    queue.clear();
+}
+
+mod timer {
+    use crate::DEPTH;
+    use trace::trace;
+
+    #[trace(zero_args = "module")]
+    pub fn tick() {}
+}
+
+struct Queue {
+    items: Vec<u32>,
+}
+
+impl Queue {
+    #[trace(zero_args = "self.items.len()")]
+    fn pop(&mut self) -> Option<u32> {
+        self.items.pop()
+    }
+
+    #[trace(zero_args = "self_type")]
+    fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod trace_test;
+
+#[cfg(test)]
+trace_test!(test_zero_args, main());
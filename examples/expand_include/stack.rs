@@ -0,0 +1,17 @@
+// included into the traced `mod stack` of `example_expand_include.rs`, whose `expand_macros`
+// traces the methods in here as well
+
+#[derive(Debug, Default)]
+pub struct Stack {
+    items: Vec<i32>,
+}
+
+impl Stack {
+    pub fn push(&mut self, item: i32) {
+        self.items.push(item);
+    }
+
+    pub fn pop(&mut self) -> Option<i32> {
+        self.items.pop()
+    }
+}
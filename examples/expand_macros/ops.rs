@@ -0,0 +1,6 @@
+// included into the traced `mod calc` of `example_expand_macros.rs`, whose `expand_macros` traces
+// the functions in here as well
+
+fn neg(a: i32) -> i32 {
+    -a
+}
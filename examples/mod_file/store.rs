@@ -0,0 +1,15 @@
+// `#[trace]` can't see the contents of this file when applied to `mod store;`, so they're traced
+// from in here instead
+trace::init_depth_var!();
+
+trace::trace_items! {
+    (prefix_enter = "[store +]", prefix_exit = "[store -]")
+
+    pub fn get(key: &str) -> Option<u32> {
+        parse(key)
+    }
+
+    fn parse(key: &str) -> Option<u32> {
+        key.strip_prefix("id-")?.parse().ok()
+    }
+}
@@ -1,29 +1,452 @@
 use std::collections::HashSet;
 
+use quote::ToTokens;
 use syn::{self, spanned::Spanned};
 
 pub(crate) struct Args {
     pub(crate) prefix_enter: String,
     pub(crate) prefix_exit: String,
-    pub(crate) format_enter: Option<String>,
-    pub(crate) format_exit: Option<String>,
+    pub(crate) prefix_error: Option<String>,
+    pub(crate) prefix_panic: Option<String>,
+    /// Kept as the literal, so errors in it can point at the placeholder they're about
+    pub(crate) format_enter: Option<syn::LitStr>,
+    pub(crate) format_exit: Option<syn::LitStr>,
+    pub(crate) ret_format: Option<String>,
+    pub(crate) ret_fmt_with: Option<syn::Path>,
     pub(crate) filter: Filter,
-    pub(crate) pause: bool,
+    pub(crate) only_when: Vec<(proc_macro2::Ident, syn::Lit)>,
+    pub(crate) pause: Option<Pause>,
     pub(crate) pretty: bool,
-    pub(crate) logging: bool,
+    pub(crate) pretty_inputs: Option<PrettyArgs>,
+    pub(crate) logging: Option<LogLevel>,
+    pub(crate) hide_ret: bool,
+    pub(crate) quiet_fmt: bool,
+    pub(crate) correlation_id: bool,
+    pub(crate) deferred: Option<Deferred>,
+    pub(crate) color: Option<Color>,
+    pub(crate) hex: HashSet<proc_macro2::Ident>,
+    pub(crate) bin: HashSet<proc_macro2::Ident>,
+    pub(crate) addr: HashSet<proc_macro2::Ident>,
+    pub(crate) addr_only: HashSet<proc_macro2::Ident>,
+    pub(crate) rc_counts: bool,
+    pub(crate) deref: Option<Deref>,
+    pub(crate) try_lock: HashSet<proc_macro2::Ident>,
+    pub(crate) timing: bool,
+    pub(crate) timing_threshold: Option<std::time::Duration>,
+    pub(crate) since_start: bool,
+    pub(crate) tracing: bool,
+    pub(crate) groups: Vec<(String, HashSet<proc_macro2::Ident>)>,
+    pub(crate) writer: Option<Writer>,
+    pub(crate) max_depth: Option<u64>,
+    pub(crate) max_depth_summary: bool,
+    pub(crate) runtime_filter: Option<String>,
+    pub(crate) chunk_markers: Option<u64>,
+    pub(crate) thread: bool,
+    pub(crate) stats: bool,
+    pub(crate) transform: Vec<(proc_macro2::Ident, syn::Expr)>,
+    pub(crate) alloc_peak: bool,
+    pub(crate) on_error: Option<OnError>,
+    pub(crate) max_length: Option<u64>,
+    pub(crate) errors_only: bool,
+    pub(crate) scoped_sink: bool,
+    pub(crate) marker: Option<String>,
+    pub(crate) compact: bool,
+    pub(crate) receiver: bool,
+    pub(crate) record_calls: bool,
+    pub(crate) debugger_hook: bool,
+    pub(crate) log_target: Option<String>,
+    pub(crate) output: Option<Output>,
+    pub(crate) dedup: HashSet<proc_macro2::Ident>,
+    pub(crate) collapse_repeats: bool,
+    pub(crate) format: Option<Format>,
+    pub(crate) predicate: Option<syn::Expr>,
+    pub(crate) word_enter: String,
+    pub(crate) word_exit: String,
+    pub(crate) sample: Option<u64>,
+    pub(crate) cfg: Option<syn::Meta>,
+    pub(crate) instance_ids: bool,
+    pub(crate) depth_var: proc_macro2::Ident,
+    pub(crate) skip_cfg: bool,
+    pub(crate) indent: Option<String>,
+    pub(crate) timestamp: Option<Timestamp>,
+    pub(crate) zero_args: Option<ZeroArgs>,
+    pub(crate) async_only: bool,
+    pub(crate) sync_only: bool,
+    pub(crate) display: HashSet<proc_macro2::Ident>,
+    pub(crate) redact: HashSet<proc_macro2::Ident>,
+    pub(crate) no_args: bool,
+    pub(crate) auto_bound: bool,
+    pub(crate) fingerprint: Option<Fingerprint>,
+    pub(crate) sample_first: Option<u64>,
+    pub(crate) lenient: bool,
+    pub(crate) heat: bool,
+    pub(crate) count_only: bool,
+    pub(crate) poll: bool,
+    pub(crate) skip_tests: bool,
+    pub(crate) otel: bool,
+    pub(crate) qualified: Option<Qualified>,
+    pub(crate) registry: bool,
+    /// The arguments as they were written, for `registry`
+    pub(crate) options: String,
+    /// The arguments as they were given, for merging with the arguments of items that have a
+    /// `#[trace]` of their own inside a traced `mod`, `impl`, or `trait`
+    pub(crate) raw_args: syn::AttributeArgs,
+    pub(crate) pause_prompt: Option<String>,
+    pub(crate) pause_if: Option<syn::Expr>,
+    pub(crate) breakpoint: bool,
+    pub(crate) stubs: bool,
+    pub(crate) depth_path: Option<syn::Path>,
+    pub(crate) printer: Option<syn::Path>,
+    /// How many bytes of lines are held back before they're written, with `buffered`
+    pub(crate) buffered: Option<u64>,
+    pub(crate) hide_unit_return: bool,
+    pub(crate) types: bool,
+    /// How many calls per second are printed at most, with `rate_limit`
+    pub(crate) rate_limit: Option<u64>,
+    /// The function called with the name, depth, and formatted arguments of every printed call
+    pub(crate) on_enter: Option<syn::Path>,
+    /// The function called with the name, depth, and formatted return value of every printed call
+    pub(crate) on_exit: Option<syn::Path>,
+    pub(crate) silent: bool,
+    /// The depth from which arguments and return values are printed as `…`
+    pub(crate) summarize_below_depth: Option<u64>,
+    pub(crate) expand_macros: bool,
+    pub(crate) panic_message: bool,
+    pub(crate) tag: Option<String>,
+    pub(crate) bench: bool,
+    pub(crate) exit_args: bool,
+    pub(crate) emit: Option<Emit>,
+    pub(crate) apply: Option<Apply>,
+    /// Set by `trace_closure!` rather than parsed, since the body of a closure has to be run
+    /// without moving what the closure captures
+    pub(crate) closure: bool,
+    /// Set for functions marked `#[track_caller]` rather than parsed, since their body has to be
+    /// run inline for `Location::caller()` to see their caller
+    pub(crate) track_caller: bool,
 }
 
+/// How nesting is drawn, with `style`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Style {
+    /// Lines are indented by `indent` for every level
+    Plain,
+    /// Levels are drawn with box-drawing characters, with a line from every enter line down to
+    /// its exit line
+    Tree,
+}
+
+/// What the color of each trace line is chosen by
+pub(crate) enum Color {
+    /// Every thread gets its own color
+    Thread,
+    /// Every level of nesting gets its own color
+    Depth,
+    /// Every function gets its own color
+    Fn,
+}
+
+/// The level of the `log` macro used with `logging`
+pub(crate) enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// Pause after the lines of calls whose depth is in this (inclusive) range
+pub(crate) struct Pause {
+    pub(crate) min_depth: Option<u64>,
+    pub(crate) max_depth: Option<u64>,
+    /// Only pause after the enter lines or only after the exit lines, instead of both
+    pub(crate) on: Option<PauseOn>,
+    /// How long to wait instead once there's nothing left to read from stdin
+    pub(crate) sleep_ms: u64,
+}
+
+/// Which lines `pause` stops after
+pub(crate) enum PauseOn {
+    Enter,
+    Exit,
+}
+
+impl PauseOn {
+    fn parse(lit_str: &syn::LitStr) -> Result<Self, syn::Error> {
+        match lit_str.value().as_str() {
+            "enter" => Ok(PauseOn::Enter),
+            "exit" => Ok(PauseOn::Exit),
+            _ => Err(syn::Error::new_spanned(
+                lit_str,
+                "`pause` must be `\"enter\"` or `\"exit\"`",
+            )),
+        }
+    }
+}
+
+/// Only print calls that failed, panicked, or (if a threshold is given) were slow
+pub(crate) struct Deferred {
+    pub(crate) threshold_ms: Option<u64>,
+}
+
+/// Which arguments are printed by the value their smart pointer points to
+pub(crate) enum Deref {
+    /// Every argument whose type is a smart pointer
+    All,
+    /// Only the given arguments
+    Only(HashSet<proc_macro2::Ident>),
+}
+
+/// Which arguments are printed with `{:#?}`, given by a list in `pretty`
+pub(crate) enum PrettyArgs {
+    /// Every argument
+    All,
+    /// Only the given arguments, which may be none of them (e.g. with `pretty(return)`)
+    Only(HashSet<proc_macro2::Ident>),
+}
+
+/// Where the trace lines are written to instead of stdout
+pub(crate) enum Writer {
+    Stderr,
+    /// Passed to `OutputDebugStringW` on Windows, and written to stderr elsewhere
+    Debugger,
+    /// Logged with the `oslog` crate on Apple platforms, and written to stderr elsewhere
+    OsLog,
+    /// Appended to the file at the given path
+    File(String),
+    /// Passed to a user-supplied function as `fmt::Arguments`
+    Function(syn::Path),
+    /// Passed to a user-supplied macro like the arguments of `println!`
+    Macro(syn::Path),
+}
+
+/// The names given to `enable` or `disable`
+#[derive(Default)]
+pub(crate) struct FilterNames {
+    pub(crate) idents: HashSet<proc_macro2::Ident>,
+    /// Trait methods like `Display::fmt`, or items of nested `mod`s like `inner::helper`
+    pub(crate) paths: HashSet<Vec<proc_macro2::Ident>>,
+    pub(crate) patterns: Vec<NamePattern>,
+}
+
+impl FilterNames {
+    /// Whether `ident` is one of the names, or matches one of the patterns
+    pub(crate) fn matches(&self, ident: &proc_macro2::Ident) -> bool {
+        self.idents.contains(ident)
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| pattern.matches(&ident.to_string()))
+    }
+
+    /// The paths that start with the `mod` called `ident`, without it
+    fn nested(&self, ident: &proc_macro2::Ident) -> FilterNames {
+        let mut nested = FilterNames::default();
+        for path in &self.paths {
+            match path.split_first() {
+                Some((first, [rest])) if first == ident => {
+                    nested.idents.insert(rest.clone());
+                }
+                Some((first, rest)) if first == ident => {
+                    nested.paths.insert(rest.to_vec());
+                }
+                _ => (),
+            }
+        }
+        nested
+    }
+}
+
+impl Filter {
+    /// The filter for the items of the `mod` called `ident` nested in the `mod` this filter is
+    /// given to, if any paths given to `enable` or `disable` lead into it
+    pub(crate) fn nested(&self, ident: &proc_macro2::Ident) -> Option<Filter> {
+        let (names, enable) = match *self {
+            Filter::None => return None,
+            // everything in an enabled `mod` is traced
+            Filter::Enable(ref names) if names.matches(ident) => return None,
+            Filter::Enable(ref names) => (names, true),
+            // and nothing in a disabled one
+            Filter::Disable(ref names) if names.matches(ident) => return None,
+            Filter::Disable(ref names) => (names, false),
+        };
+        let nested = names.nested(ident);
+        if nested.idents.is_empty() && nested.paths.is_empty() {
+            None
+        } else if enable {
+            Some(Filter::Enable(nested))
+        } else {
+            Some(Filter::Disable(nested))
+        }
+    }
+}
+
+/// A pattern given to `enable` or `disable` as a string, e.g. `"parse_*"` or `"/^test_/"`
+pub(crate) enum NamePattern {
+    /// A name where `*` stands for any number of characters
+    Glob(String),
+    /// A regular expression, which can match any part of the name unless it is anchored
+    Regex(regex_lite::Regex),
+}
+
+impl NamePattern {
+    fn parse(lit_str: &syn::LitStr) -> Result<Self, syn::Error> {
+        let pattern = lit_str.value();
+        match pattern
+            .strip_prefix('/')
+            .and_then(|pattern| pattern.strip_suffix('/'))
+        {
+            Some(regex) => regex_lite::Regex::new(regex)
+                .map(NamePattern::Regex)
+                .map_err(|e| syn::Error::new_spanned(lit_str, e)),
+            None => Ok(NamePattern::Glob(pattern)),
+        }
+    }
+
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        fn glob_matches(pattern: &[u8], name: &[u8]) -> bool {
+            match pattern.split_first() {
+                None => name.is_empty(),
+                Some((b'*', rest)) => {
+                    (0..=name.len()).any(|skipped| glob_matches(rest, &name[skipped..]))
+                }
+                Some((c, rest)) => name.first() == Some(c) && glob_matches(rest, &name[1..]),
+            }
+        }
+        match *self {
+            NamePattern::Glob(ref glob) => glob_matches(glob.as_bytes(), name.as_bytes()),
+            NamePattern::Regex(ref regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// What calls are recorded as instead of trace lines
+pub(crate) enum Output {
+    /// Begin and end events in the Trace Event Format, written to the file at the given path
+    ChromeTrace(String),
+    /// Calls recorded by `trace_runtime::happens_before::record`
+    HappensBefore,
+    /// Which functions called which, recorded by `trace_runtime::call_graph`
+    CallGraph,
+}
+
+/// Where the code generated by `#[trace]` is printed while compiling
+pub(crate) enum Emit {
+    Stderr,
+}
+
+/// When `#[trace]` is applied relative to the other attributes of the item
+pub(crate) enum Apply {
+    /// After the attributes that follow it, so attribute macros among them are expanded first
+    Last,
+}
+
+/// What trace lines are written as instead of the usual human-readable lines
+pub(crate) enum Format {
+    /// One JSON object per line
+    Json,
+}
+
+/// What the enter line of a function without parameters shows between the parentheses
+pub(crate) enum ZeroArgs {
+    /// The path of the module the function is in
+    Module,
+    /// The name of the `Self` type
+    SelfType,
+    /// An expression, along with its value
+    Expr(Box<syn::Expr>),
+}
+
+/// What the names of functions are qualified with in trace lines
+pub(crate) enum Qualified {
+    /// The path of the module the function is in
+    Module,
+    /// The path of the module, followed by the file and line the function is expanded at
+    Location,
+}
+
+/// What time every trace line starts with
+pub(crate) enum Timestamp {
+    /// The wall-clock time in UTC, formatted as RFC 3339
+    Rfc3339,
+    /// The time since the first line printed with a timestamp, like `since_start`
+    Elapsed,
+}
+
+/// What calls are told apart by in the hash of the calls made with `fingerprint`
+pub(crate) enum Fingerprint {
+    /// The names of the functions
+    Names,
+    /// The names of the functions and the values of their arguments
+    Args,
+}
+
+/// What happens when a trace line can't be written
+pub(crate) enum OnError {
+    /// The line is dropped
+    Ignore,
+    /// The line is dropped and the error is printed to stderr
+    Stderr,
+    /// The traced program panics
+    Panic,
+}
+
+/// Which functions (or arguments) are traced, by name. Trait methods can also be given qualified
+/// by the name of the trait, e.g. `Display::fmt`, to only match them in `impl`s of that trait
 pub(crate) enum Filter {
     None,
-    Enable(HashSet<proc_macro2::Ident>),
-    Disable(HashSet<proc_macro2::Ident>),
+    Enable(FilterNames),
+    Disable(FilterNames),
 }
 
 const DEFAULT_PREFIX_ENTER: &str = "[+]";
 const DEFAULT_PREFIX_EXIT: &str = "[-]";
-const DEFAULT_PAUSE: bool = false;
+const DEFAULT_WORD_ENTER: &str = "Entering";
+const DEFAULT_WORD_EXIT: &str = "Exiting";
+const DEFAULT_DEPTH_VAR: &str = "DEPTH";
+const DEFAULT_BUFFER_SIZE: u64 = 8192;
 const DEFAULT_PRETTY: bool = false;
-const DEFAULT_LOGGING: bool = false;
+const DEFAULT_HIDE_RET: bool = false;
+const DEFAULT_QUIET_FMT: bool = false;
+const DEFAULT_CORRELATION_ID: bool = false;
+const DEFAULT_RC_COUNTS: bool = false;
+const DEFAULT_TIMING: bool = false;
+const DEFAULT_SINCE_START: bool = false;
+const DEFAULT_TRACING: bool = false;
+const DEFAULT_MAX_DEPTH_SUMMARY: bool = false;
+const DEFAULT_THREAD: bool = false;
+const DEFAULT_STATS: bool = false;
+const DEFAULT_ALLOC_PEAK: bool = false;
+const DEFAULT_ERRORS_ONLY: bool = false;
+const DEFAULT_SCOPED_SINK: bool = false;
+const DEFAULT_COMPACT: bool = false;
+const DEFAULT_RECEIVER: bool = false;
+const DEFAULT_RECORD_CALLS: bool = false;
+const DEFAULT_DEBUGGER_HOOK: bool = false;
+const DEFAULT_INSTANCE_IDS: bool = false;
+const DEFAULT_SKIP_CFG: bool = false;
+const DEFAULT_CALL_ID: bool = false;
+const DEFAULT_ASYNC_ONLY: bool = false;
+const DEFAULT_SYNC_ONLY: bool = false;
+const DEFAULT_NO_ARGS: bool = false;
+const DEFAULT_AUTO_BOUND: bool = false;
+const DEFAULT_LENIENT: bool = false;
+const DEFAULT_HEAT: bool = false;
+const DEFAULT_COUNT_ONLY: bool = false;
+const DEFAULT_POLL: bool = false;
+const DEFAULT_SKIP_TESTS: bool = false;
+const DEFAULT_OTEL: bool = false;
+const DEFAULT_COLLAPSE_REPEATS: bool = false;
+const DEFAULT_REGISTRY: bool = false;
+const DEFAULT_BREAKPOINT: bool = false;
+const DEFAULT_STUBS: bool = false;
+
+const DEFAULT_HIDE_UNIT_RETURN: bool = false;
+const DEFAULT_TYPES: bool = false;
+const DEFAULT_SILENT: bool = false;
+const DEFAULT_EXPAND_MACROS: bool = false;
+const DEFAULT_PANIC_MESSAGE: bool = false;
+const DEFAULT_BENCH: bool = false;
+const DEFAULT_EXIT_ARGS: bool = false;
 
 macro_rules! try_extract_str {
     ($lit:expr, $meta:expr, $arg_ty:ident) => {{
@@ -37,356 +460,2449 @@ macro_rules! try_extract_str {
     }};
 }
 impl Args {
+    /// The arguments for an item with a `#[trace(inner_args)]` of its own inside an item traced
+    /// with these arguments
+    ///
+    /// The inner arguments are added to the outer ones, replacing the ones of the same name. The
+    /// outer `enable` and `disable` are left out, since they choose among the items of the outer
+    /// item rather than among the arguments or items of the inner one.
+    pub(crate) fn merged_with(
+        &self,
+        inner_args: syn::AttributeArgs,
+    ) -> Result<Self, Vec<syn::Error>> {
+        let name = |nested_meta: &syn::NestedMeta| match *nested_meta {
+            syn::NestedMeta::Meta(ref meta) => meta
+                .path()
+                .segments
+                .first()
+                .map(|segment| segment.ident.to_string()),
+            syn::NestedMeta::Lit(_) => None,
+        };
+        let inner_names = inner_args.iter().filter_map(name).collect::<HashSet<_>>();
+        let merged_args = self
+            .raw_args
+            .iter()
+            .filter(|nested_meta| {
+                name(nested_meta).is_none_or(|name| {
+                    name != "enable" && name != "disable" && !inner_names.contains(&name)
+                })
+            })
+            .cloned()
+            .chain(inner_args)
+            .collect();
+        Self::from_raw_args(merged_args)
+    }
+
     pub(crate) fn from_raw_args(raw_args: syn::AttributeArgs) -> Result<Self, Vec<syn::Error>> {
-        // Different types of arguments accepted by `#[trace]`;
-        // spans are needed for friendly error reporting of duplicate arguments
-        enum Arg {
-            PrefixEnter(proc_macro2::Span, String),
-            PrefixExit(proc_macro2::Span, String),
-            Enable(proc_macro2::Span, HashSet<proc_macro2::Ident>),
-            Disable(proc_macro2::Span, HashSet<proc_macro2::Ident>),
-            Pause(proc_macro2::Span, bool),
-            Pretty(proc_macro2::Span, bool),
-            Logging(proc_macro2::Span, bool),
-            FormatEnter(proc_macro2::Span, String),
-            FormatExit(proc_macro2::Span, String),
-        }
-
-        // Parse arguments
-        let args_res = raw_args.into_iter().map(|nested_meta| match nested_meta {
-            syn::NestedMeta::Meta(ref meta) => {
-                enum ArgName {
-                    PrefixEnter,
-                    PrefixExit,
-                    FormatEnter,
-                    FormatExit,
-                    Enable,
-                    Disable,
-                    Pause,
-                    Pretty,
-                    Logging,
+        let options = raw_args
+            .iter()
+            .map(|nested_meta| nested_meta.to_token_stream().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // Group arguments of the same type and errors
+        let mut grouped_args = GroupedArgs::default();
+        let mut errors = vec![];
+        for arg_res in raw_args.iter().map(parse_arg) {
+            match arg_res {
+                Ok(arg) => grouped_args.push(arg),
+                Err(es) => errors.extend(es),
+            }
+        }
+        errors.extend(grouped_args.duplicate_errors());
+        errors.extend(grouped_args.conflict_errors(&raw_args));
+
+        if errors.is_empty() {
+            Ok(grouped_args.into_args(raw_args, options))
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+// Different types of arguments accepted by `#[trace]`;
+// spans are needed for friendly error reporting of duplicate arguments
+enum Arg {
+    PrefixEnter(proc_macro2::Span, String),
+    PrefixExit(proc_macro2::Span, String),
+    PrefixError(proc_macro2::Span, String),
+    PrefixPanic(proc_macro2::Span, String),
+    Enable(proc_macro2::Span, FilterNames),
+    Disable(proc_macro2::Span, FilterNames),
+    OnlyWhen(proc_macro2::Span, Vec<(proc_macro2::Ident, syn::Lit)>),
+    Pause(proc_macro2::Span, Pause),
+    Pretty(proc_macro2::Span, Option<HashSet<proc_macro2::Ident>>),
+    Logging(proc_macro2::Span, LogLevel),
+    HideRet(proc_macro2::Span, bool),
+    QuietFmt(proc_macro2::Span, bool),
+    CorrelationId(proc_macro2::Span, bool),
+    FormatEnter(proc_macro2::Span, syn::LitStr),
+    FormatExit(proc_macro2::Span, syn::LitStr),
+    RetFormat(proc_macro2::Span, String),
+    RetFmtWith(proc_macro2::Span, syn::Path),
+    Deferred(proc_macro2::Span, Option<u64>),
+    Color(proc_macro2::Span, Color),
+    Hex(proc_macro2::Span, HashSet<proc_macro2::Ident>),
+    Bin(proc_macro2::Span, HashSet<proc_macro2::Ident>),
+    Addr(proc_macro2::Span, HashSet<proc_macro2::Ident>),
+    AddrOnly(proc_macro2::Span, HashSet<proc_macro2::Ident>),
+    RcCounts(proc_macro2::Span, bool),
+    Deref(proc_macro2::Span, Option<HashSet<proc_macro2::Ident>>),
+    TryLock(proc_macro2::Span, HashSet<proc_macro2::Ident>),
+    Timing(proc_macro2::Span, bool),
+    TimingThreshold(proc_macro2::Span, std::time::Duration),
+    SinceStart(proc_macro2::Span, bool),
+    Tracing(proc_macro2::Span, bool),
+    Groups(
+        proc_macro2::Span,
+        Vec<(String, HashSet<proc_macro2::Ident>)>,
+    ),
+    Writer(proc_macro2::Span, Writer),
+    MaxDepth(proc_macro2::Span, u64),
+    MaxDepthSummary(proc_macro2::Span, bool),
+    RuntimeFilter(proc_macro2::Span, String),
+    ChunkMarkers(proc_macro2::Span, u64),
+    Thread(proc_macro2::Span, bool),
+    Stats(proc_macro2::Span, bool),
+    Transform(proc_macro2::Span, Vec<(proc_macro2::Ident, syn::Expr)>),
+    AllocPeak(proc_macro2::Span, bool),
+    OnError(proc_macro2::Span, OnError),
+    MaxLength(proc_macro2::Span, u64),
+    ErrorsOnly(proc_macro2::Span, bool),
+    ScopedSink(proc_macro2::Span, bool),
+    Marker(proc_macro2::Span, String),
+    Compact(proc_macro2::Span, bool),
+    Receiver(proc_macro2::Span, bool),
+    RecordCalls(proc_macro2::Span, bool),
+    DebuggerHook(proc_macro2::Span, bool),
+    LogTarget(proc_macro2::Span, String),
+    Output(proc_macro2::Span, Output),
+    Dedup(proc_macro2::Span, HashSet<proc_macro2::Ident>),
+    CollapseRepeats(proc_macro2::Span, bool),
+    Format(proc_macro2::Span, Format),
+    Predicate(proc_macro2::Span, syn::Expr),
+    WordEnter(proc_macro2::Span, String),
+    WordExit(proc_macro2::Span, String),
+    Sample(proc_macro2::Span, u64),
+    Cfg(proc_macro2::Span, syn::Meta),
+    InstanceIds(proc_macro2::Span, bool),
+    DepthVar(proc_macro2::Span, proc_macro2::Ident),
+    SkipCfg(proc_macro2::Span, bool),
+    Indent(proc_macro2::Span, String),
+    IndentWidth(proc_macro2::Span, u64),
+    Timestamp(proc_macro2::Span, Timestamp),
+    ZeroArgs(proc_macro2::Span, ZeroArgs),
+    CallId(proc_macro2::Span, bool),
+    AsyncOnly(proc_macro2::Span, bool),
+    SyncOnly(proc_macro2::Span, bool),
+    Display(proc_macro2::Span, HashSet<proc_macro2::Ident>),
+    Redact(proc_macro2::Span, HashSet<proc_macro2::Ident>),
+    NoArgs(proc_macro2::Span, bool),
+    AutoBound(proc_macro2::Span, bool),
+    Fingerprint(proc_macro2::Span, Fingerprint),
+    SampleFirst(proc_macro2::Span, u64),
+    Lenient(proc_macro2::Span, bool),
+    Heat(proc_macro2::Span, bool),
+    CountOnly(proc_macro2::Span, bool),
+    Poll(proc_macro2::Span, bool),
+    SkipTests(proc_macro2::Span, bool),
+    Otel(proc_macro2::Span, bool),
+    Qualified(proc_macro2::Span, Qualified),
+    Registry(proc_macro2::Span, bool),
+    PausePrompt(proc_macro2::Span, String),
+    PauseIf(proc_macro2::Span, Box<syn::Expr>),
+    Breakpoint(proc_macro2::Span, bool),
+    Stubs(proc_macro2::Span, bool),
+    DepthPath(proc_macro2::Span, syn::Path),
+    Printer(proc_macro2::Span, syn::Path),
+    Buffered(proc_macro2::Span, u64),
+    HideUnitReturn(proc_macro2::Span, bool),
+    Types(proc_macro2::Span, bool),
+    RateLimit(proc_macro2::Span, u64),
+    OnEnter(proc_macro2::Span, syn::Path),
+    OnExit(proc_macro2::Span, syn::Path),
+    Silent(proc_macro2::Span, bool),
+    SummarizeBelowDepth(proc_macro2::Span, u64),
+    Style(proc_macro2::Span, Style),
+    ExpandMacros(proc_macro2::Span, bool),
+    PanicMessage(proc_macro2::Span, bool),
+    Tag(proc_macro2::Span, String),
+    Bench(proc_macro2::Span, bool),
+    ExitArgs(proc_macro2::Span, bool),
+    Emit(proc_macro2::Span, Emit),
+    Apply(proc_macro2::Span, Apply),
+}
+
+// The names of the arguments accepted by `#[trace]`, to parse them by
+#[derive(Clone, Copy)]
+enum ArgName {
+    PrefixEnter,
+    PrefixExit,
+    PrefixError,
+    PrefixPanic,
+    FormatEnter,
+    FormatExit,
+    RetFormat,
+    RetFmtWith,
+    Enable,
+    Disable,
+    OnlyWhen,
+    Pause,
+    Pretty,
+    Logging,
+    HideRet,
+    QuietFmt,
+    CorrelationId,
+    Deferred,
+    Color,
+    Hex,
+    Bin,
+    Addr,
+    AddrOnly,
+    RcCounts,
+    Deref,
+    TryLock,
+    Timing,
+    TimingThreshold,
+    SinceStart,
+    Tracing,
+    Groups,
+    Writer,
+    MaxDepth,
+    MaxDepthSummary,
+    RuntimeFilter,
+    ChunkMarkers,
+    Thread,
+    Stats,
+    Transform,
+    AllocPeak,
+    OnError,
+    MaxLength,
+    ErrorsOnly,
+    ScopedSink,
+    Marker,
+    Compact,
+    Receiver,
+    RecordCalls,
+    DebuggerHook,
+    LogTarget,
+    Output,
+    Dedup,
+    CollapseRepeats,
+    Format,
+    Predicate,
+    WordEnter,
+    WordExit,
+    Sample,
+    Cfg,
+    InstanceIds,
+    DepthVar,
+    SkipCfg,
+    Indent,
+    IndentWidth,
+    Timestamp,
+    ZeroArgs,
+    CallId,
+    AsyncOnly,
+    SyncOnly,
+    Display,
+    Redact,
+    NoArgs,
+    AutoBound,
+    Fingerprint,
+    SampleFirst,
+    Lenient,
+    Heat,
+    CountOnly,
+    Poll,
+    SkipTests,
+    Otel,
+    Qualified,
+    Registry,
+    PausePrompt,
+    PauseIf,
+    Breakpoint,
+    Stubs,
+    DepthPath,
+    Printer,
+    Buffered,
+    HideUnitReturn,
+    Types,
+    RateLimit,
+    OnEnter,
+    OnExit,
+    Silent,
+    SummarizeBelowDepth,
+    Style,
+    ExpandMacros,
+    PanicMessage,
+    Tag,
+    Bench,
+    ExitArgs,
+    Emit,
+    Apply,
+}
+
+impl ArgName {
+    fn from_ident(ident: &proc_macro2::Ident) -> Option<Self> {
+        let arg_name = match ident.to_string().as_str() {
+            "prefix_enter" => ArgName::PrefixEnter,
+            "prefix_exit" => ArgName::PrefixExit,
+            "prefix_error" => ArgName::PrefixError,
+            "prefix_panic" => ArgName::PrefixPanic,
+            "format_enter" => ArgName::FormatEnter,
+            "format_exit" => ArgName::FormatExit,
+            "ret_format" => ArgName::RetFormat,
+            "ret_fmt_with" => ArgName::RetFmtWith,
+            "enable" => ArgName::Enable,
+            "disable" => ArgName::Disable,
+            "only_when" => ArgName::OnlyWhen,
+            "pause" => ArgName::Pause,
+            "pretty" => ArgName::Pretty,
+            "logging" => ArgName::Logging,
+            "hide_ret" => ArgName::HideRet,
+            "quiet_fmt" => ArgName::QuietFmt,
+            "correlation_id" => ArgName::CorrelationId,
+            "deferred" => ArgName::Deferred,
+            "color" => ArgName::Color,
+            "hex" => ArgName::Hex,
+            "bin" => ArgName::Bin,
+            "addr" => ArgName::Addr,
+            "addr_only" => ArgName::AddrOnly,
+            "rc_counts" => ArgName::RcCounts,
+            "deref" => ArgName::Deref,
+            "try_lock" => ArgName::TryLock,
+            "timing" => ArgName::Timing,
+            "timing_threshold" => ArgName::TimingThreshold,
+            "since_start" => ArgName::SinceStart,
+            "tracing" => ArgName::Tracing,
+            "groups" => ArgName::Groups,
+            "writer" => ArgName::Writer,
+            "max_depth" => ArgName::MaxDepth,
+            "max_depth_summary" => ArgName::MaxDepthSummary,
+            "runtime_filter" => ArgName::RuntimeFilter,
+            "chunk_markers" => ArgName::ChunkMarkers,
+            "thread" => ArgName::Thread,
+            "stats" => ArgName::Stats,
+            "transform" => ArgName::Transform,
+            "alloc_peak" => ArgName::AllocPeak,
+            "on_error" => ArgName::OnError,
+            "max_length" => ArgName::MaxLength,
+            "errors_only" => ArgName::ErrorsOnly,
+            "scoped_sink" => ArgName::ScopedSink,
+            "marker" => ArgName::Marker,
+            "compact" => ArgName::Compact,
+            "receiver" => ArgName::Receiver,
+            "record_calls" => ArgName::RecordCalls,
+            "debugger_hook" => ArgName::DebuggerHook,
+            "log_target" => ArgName::LogTarget,
+            "output" => ArgName::Output,
+            "dedup" => ArgName::Dedup,
+            "collapse_repeats" => ArgName::CollapseRepeats,
+            "format" => ArgName::Format,
+            "if" => ArgName::Predicate,
+            "word_enter" => ArgName::WordEnter,
+            "word_exit" => ArgName::WordExit,
+            "sample" => ArgName::Sample,
+            "cfg" => ArgName::Cfg,
+            "instance_ids" => ArgName::InstanceIds,
+            "depth_var" => ArgName::DepthVar,
+            "skip_cfg" => ArgName::SkipCfg,
+            "indent" => ArgName::Indent,
+            "indent_width" => ArgName::IndentWidth,
+            "timestamp" => ArgName::Timestamp,
+            "zero_args" => ArgName::ZeroArgs,
+            "call_id" => ArgName::CallId,
+            "async_only" => ArgName::AsyncOnly,
+            "sync_only" => ArgName::SyncOnly,
+            "display" => ArgName::Display,
+            "redact" => ArgName::Redact,
+            "no_args" => ArgName::NoArgs,
+            "auto_bound" => ArgName::AutoBound,
+            "fingerprint" => ArgName::Fingerprint,
+            "sample_first" => ArgName::SampleFirst,
+            "lenient" => ArgName::Lenient,
+            "heat" => ArgName::Heat,
+            "count_only" => ArgName::CountOnly,
+            "poll" => ArgName::Poll,
+            "skip_tests" => ArgName::SkipTests,
+            "otel" => ArgName::Otel,
+            "qualified" => ArgName::Qualified,
+            "registry" => ArgName::Registry,
+            "pause_prompt" => ArgName::PausePrompt,
+            "pause_if" => ArgName::PauseIf,
+            "breakpoint" => ArgName::Breakpoint,
+            "stubs" => ArgName::Stubs,
+            "depth_path" => ArgName::DepthPath,
+            "printer" => ArgName::Printer,
+            "buffered" => ArgName::Buffered,
+            "hide_unit_return" => ArgName::HideUnitReturn,
+            "types" => ArgName::Types,
+            "rate_limit" => ArgName::RateLimit,
+            "on_enter" => ArgName::OnEnter,
+            "on_exit" => ArgName::OnExit,
+            "silent" => ArgName::Silent,
+            "summarize_below_depth" => ArgName::SummarizeBelowDepth,
+            "style" => ArgName::Style,
+            "expand_macros" => ArgName::ExpandMacros,
+            "panic_message" => ArgName::PanicMessage,
+            "tag" => ArgName::Tag,
+            "bench" => ArgName::Bench,
+            "exit_args" => ArgName::ExitArgs,
+            "emit" => ArgName::Emit,
+            "apply" => ArgName::Apply,
+            _ => return None,
+        };
+        Some(arg_name)
+    }
+
+    // the error for an argument given a value of the wrong kind, e.g. a list where a string is
+    // expected
+    fn type_error(self, ident: &proc_macro2::Ident) -> Vec<syn::Error> {
+        let message = match self {
+            ArgName::PrefixEnter => "`prefix_enter` requires a string value",
+            ArgName::PrefixExit => "`prefix_exit` requires a string value",
+            ArgName::PrefixError => "`prefix_error` requires a string value",
+            ArgName::PrefixPanic => "`prefix_panic` requires a string value",
+            ArgName::FormatEnter => "`format_enter` requires a string value",
+            ArgName::FormatExit => "`format_exit` requires a string value",
+            ArgName::RetFormat => "`ret_format` requires a string value",
+            ArgName::RetFmtWith => "`ret_fmt_with` requires a string value",
+            ArgName::Enable => "`enable` requires a list of meta words",
+            ArgName::Disable => "`disable` requires a list of meta words",
+            ArgName::OnlyWhen => "`only_when` requires a list of `name = value` pairs",
+            ArgName::Pause => "`pause` must be a meta word, `\"enter\"` or `\"exit\"`, or a list of `min_depth = ..`, `max_depth = ..`, `on = ..`, and `sleep_ms = ..`",
+            ArgName::Pretty => "`pretty` must be a meta word or a list of `args`, `return`, and argument names",
+            ArgName::Logging => "`logging` must be a meta word or a log level, e.g. `logging = \"debug\"`",
+            ArgName::HideRet => "`hide_ret` must be a meta word",
+            ArgName::QuietFmt => "`quiet_fmt` must be a meta word",
+            ArgName::CorrelationId => "`correlation_id` must be a meta word",
+            ArgName::Deferred => "`deferred` must be a meta word or have an integer value",
+            ArgName::Color => "`color` requires a string value",
+            ArgName::Hex => "`hex` requires a list of meta words",
+            ArgName::Bin => "`bin` requires a list of meta words",
+            ArgName::Addr => "`addr` requires a list of meta words",
+            ArgName::AddrOnly => "`addr_only` requires a list of meta words",
+            ArgName::RcCounts => "`rc_counts` must be a meta word",
+            ArgName::Deref => "`deref` must be a meta word or have a list of meta words",
+            ArgName::TryLock => "`try_lock` requires a list of meta words",
+            ArgName::Timing => "`timing` must be a meta word",
+            ArgName::TimingThreshold => "`timing_threshold` requires a string value",
+            ArgName::SinceStart => "`since_start` must be a meta word",
+            ArgName::Tracing => "`tracing` must be a meta word",
+            ArgName::Groups => "`groups` requires a list of named lists of function names",
+            ArgName::Writer => "`writer` requires a string value",
+            ArgName::MaxDepth => "`max_depth` requires an integer value",
+            ArgName::MaxDepthSummary => "`max_depth_summary` must be a meta word",
+            ArgName::RuntimeFilter => "`runtime_filter` requires a string value",
+            ArgName::ChunkMarkers => "`chunk_markers` requires an integer value",
+            ArgName::Thread => "`thread` must be a meta word",
+            ArgName::Stats => "`stats` must be a meta word",
+            ArgName::Transform => "`transform` requires a list of `name = \"expression\"` pairs",
+            ArgName::AllocPeak => "`alloc_peak` must be a meta word",
+            ArgName::OnError => "`on_error` requires a string value",
+            ArgName::MaxLength => "`max_length` requires an integer value",
+            ArgName::ErrorsOnly => "`errors_only` must be a meta word",
+            ArgName::ScopedSink => "`scoped_sink` must be a meta word",
+            ArgName::Marker => "`marker` requires a string value",
+            ArgName::Compact => "`compact` must be a meta word",
+            ArgName::Receiver => "`receiver` must be a meta word",
+            ArgName::RecordCalls => "`record_calls` must be a meta word",
+            ArgName::DebuggerHook => "`debugger_hook` must be a meta word",
+            ArgName::LogTarget => "`log_target` requires a string value",
+            ArgName::Output => "`output` requires a string value",
+            ArgName::Dedup => "`dedup` requires a list of meta words",
+            ArgName::CollapseRepeats => "`collapse_repeats` must be a meta word",
+            ArgName::Format => "`format` requires a string value",
+            ArgName::Predicate => "`if` requires a string value",
+            ArgName::WordEnter => "`word_enter` requires a string value",
+            ArgName::WordExit => "`word_exit` requires a string value",
+            ArgName::Sample => "`sample` requires an integer value",
+            ArgName::Cfg => "`cfg` requires a string value",
+            ArgName::InstanceIds => "`instance_ids` must be a meta word",
+            ArgName::DepthVar => "`depth_var` requires a string value",
+            ArgName::SkipCfg => "`skip_cfg` must be a meta word",
+            ArgName::Indent => "`indent` requires a string value",
+            ArgName::IndentWidth => "`indent_width` requires an integer value",
+            ArgName::Timestamp => "`timestamp` must be a meta word or have a string value",
+            ArgName::ZeroArgs => "`zero_args` requires a string value",
+            ArgName::CallId => "`call_id` must be a meta word",
+            ArgName::AsyncOnly => "`async_only` must be a meta word",
+            ArgName::SyncOnly => "`sync_only` must be a meta word",
+            ArgName::Display => "`display` requires a list of meta words",
+            ArgName::Redact => "`redact` requires a list of meta words",
+            ArgName::NoArgs => "`no_args` must be a meta word",
+            ArgName::AutoBound => "`auto_bound` must be a meta word",
+            ArgName::Fingerprint => "`fingerprint` must be a meta word or have a string value",
+            ArgName::SampleFirst => "`sample_first` requires an integer value",
+            ArgName::Lenient => "`lenient` must be a meta word",
+            ArgName::Heat => "`heat` must be a meta word",
+            ArgName::CountOnly => "`count_only` must be a meta word",
+            ArgName::Poll => "`poll` must be a meta word",
+            ArgName::SkipTests => "`skip_tests` must be a meta word",
+            ArgName::Otel => "`otel` must be a meta word",
+            ArgName::Qualified => "`qualified` must be a meta word or have a string value",
+            ArgName::Registry => "`registry` must be a meta word",
+            ArgName::PausePrompt => "`pause_prompt` requires a string value",
+            ArgName::PauseIf => "`pause_if` requires a string value",
+            ArgName::Breakpoint => "`breakpoint` must be a meta word",
+            ArgName::Stubs => "`stubs` must be a meta word",
+            ArgName::DepthPath => "`depth_path` requires a string value",
+            ArgName::Printer => "`printer` requires a string value",
+            ArgName::Buffered => "`buffered` must be a meta word or have an integer value",
+            ArgName::HideUnitReturn => "`hide_unit_return` must be a meta word",
+            ArgName::Types => "`types` must be a meta word",
+            ArgName::RateLimit => "`rate_limit` must be a string literal like `\"100/s\"`",
+            ArgName::OnEnter => "`on_enter` requires a string value",
+            ArgName::OnExit => "`on_exit` requires a string value",
+            ArgName::Silent => "`silent` must be a meta word",
+            ArgName::SummarizeBelowDepth => "`summarize_below_depth` requires an integer value",
+            ArgName::Style => "`style` requires a string value",
+            ArgName::ExpandMacros => "`expand_macros` must be a meta word",
+            ArgName::PanicMessage => "`panic_message` must be a meta word",
+            ArgName::Tag => "`tag` requires a string value",
+            ArgName::Bench => "`bench` must be a meta word",
+            ArgName::ExitArgs => "`exit_args` must be a meta word",
+            ArgName::Emit => "`emit` requires a string value",
+            ArgName::Apply => "`apply` requires a string value",
+        };
+        vec![syn::Error::new_spanned(ident.clone(), message)]
+    }
+}
+
+// Parses a single argument of `#[trace]`, with a helper for each of the forms it can take
+fn parse_arg(nested_meta: &syn::NestedMeta) -> Result<Arg, Vec<syn::Error>> {
+    let meta = match *nested_meta {
+        syn::NestedMeta::Meta(ref meta) => meta,
+        syn::NestedMeta::Lit(_) => {
+            return Err(vec![syn::Error::new_spanned(
+                nested_meta,
+                "literal attribute not allowed",
+            )])
+        }
+    };
+    let ident = &meta.path().segments.first().unwrap().ident;
+    let Some(arg_name) = ArgName::from_ident(ident) else {
+        return Err(vec![syn::Error::new_spanned(
+            ident.clone(),
+            format_args!("unknown attribute argument `{}`", ident),
+        )]);
+    };
+    match *meta {
+        syn::Meta::Path(_) => parse_word_arg(arg_name, ident, meta),
+        syn::Meta::List(syn::MetaList { ref nested, .. }) => {
+            parse_list_arg(arg_name, ident, meta, nested)
+        }
+        syn::Meta::NameValue(syn::MetaNameValue { ref lit, .. }) => {
+            parse_name_value_arg(arg_name, ident, meta, lit)
+        }
+    }
+}
+
+// an argument given as a meta word, like `pretty`
+fn parse_word_arg(
+    arg_name: ArgName,
+    ident: &proc_macro2::Ident,
+    meta: &syn::Meta,
+) -> Result<Arg, Vec<syn::Error>> {
+    match arg_name {
+        ArgName::Pause => Ok(Arg::Pause(
+            meta.span(),
+            Pause {
+                min_depth: None,
+                max_depth: None,
+                on: None,
+                sleep_ms: 0,
+            },
+        )),
+        ArgName::Pretty => Ok(Arg::Pretty(meta.span(), None)),
+        ArgName::Logging => Ok(Arg::Logging(meta.span(), LogLevel::Trace)),
+        ArgName::HideRet => Ok(Arg::HideRet(meta.span(), true)),
+        ArgName::QuietFmt => Ok(Arg::QuietFmt(meta.span(), true)),
+        ArgName::CorrelationId => Ok(Arg::CorrelationId(meta.span(), true)),
+        ArgName::Deferred => Ok(Arg::Deferred(meta.span(), None)),
+        ArgName::RcCounts => Ok(Arg::RcCounts(meta.span(), true)),
+        ArgName::Deref => Ok(Arg::Deref(meta.span(), None)),
+        ArgName::Timing => Ok(Arg::Timing(meta.span(), true)),
+        ArgName::SinceStart => Ok(Arg::SinceStart(meta.span(), true)),
+        ArgName::Tracing => Ok(Arg::Tracing(meta.span(), true)),
+        ArgName::MaxDepthSummary => Ok(Arg::MaxDepthSummary(meta.span(), true)),
+        ArgName::Thread => Ok(Arg::Thread(meta.span(), true)),
+        ArgName::Stats => Ok(Arg::Stats(meta.span(), true)),
+        ArgName::AllocPeak => Ok(Arg::AllocPeak(meta.span(), true)),
+        ArgName::ErrorsOnly => Ok(Arg::ErrorsOnly(meta.span(), true)),
+        ArgName::ScopedSink => Ok(Arg::ScopedSink(meta.span(), true)),
+        ArgName::Compact => Ok(Arg::Compact(meta.span(), true)),
+        ArgName::Receiver => Ok(Arg::Receiver(meta.span(), true)),
+        ArgName::RecordCalls => Ok(Arg::RecordCalls(meta.span(), true)),
+        ArgName::DebuggerHook => Ok(Arg::DebuggerHook(meta.span(), true)),
+        ArgName::CollapseRepeats => Ok(Arg::CollapseRepeats(meta.span(), true)),
+        ArgName::InstanceIds => Ok(Arg::InstanceIds(meta.span(), true)),
+        ArgName::SkipCfg => Ok(Arg::SkipCfg(meta.span(), true)),
+        ArgName::Timestamp => Ok(Arg::Timestamp(meta.span(), Timestamp::Rfc3339)),
+        ArgName::CallId => Ok(Arg::CallId(meta.span(), true)),
+        ArgName::AsyncOnly => Ok(Arg::AsyncOnly(meta.span(), true)),
+        ArgName::SyncOnly => Ok(Arg::SyncOnly(meta.span(), true)),
+        ArgName::NoArgs => Ok(Arg::NoArgs(meta.span(), true)),
+        ArgName::AutoBound => Ok(Arg::AutoBound(meta.span(), true)),
+        ArgName::Fingerprint => Ok(Arg::Fingerprint(meta.span(), Fingerprint::Names)),
+        ArgName::Lenient => Ok(Arg::Lenient(meta.span(), true)),
+        ArgName::Heat => Ok(Arg::Heat(meta.span(), true)),
+        ArgName::CountOnly => Ok(Arg::CountOnly(meta.span(), true)),
+        ArgName::Poll => Ok(Arg::Poll(meta.span(), true)),
+        ArgName::SkipTests => Ok(Arg::SkipTests(meta.span(), true)),
+        ArgName::Otel => Ok(Arg::Otel(meta.span(), true)),
+        ArgName::Qualified => Ok(Arg::Qualified(meta.span(), Qualified::Module)),
+        ArgName::Registry => Ok(Arg::Registry(meta.span(), true)),
+        ArgName::Breakpoint => Ok(Arg::Breakpoint(meta.span(), true)),
+        ArgName::Stubs => Ok(Arg::Stubs(meta.span(), true)),
+        ArgName::Buffered => Ok(Arg::Buffered(meta.span(), DEFAULT_BUFFER_SIZE)),
+        ArgName::HideUnitReturn => Ok(Arg::HideUnitReturn(meta.span(), true)),
+        ArgName::Types => Ok(Arg::Types(meta.span(), true)),
+        ArgName::Silent => Ok(Arg::Silent(meta.span(), true)),
+        ArgName::ExpandMacros => Ok(Arg::ExpandMacros(meta.span(), true)),
+        ArgName::PanicMessage => Ok(Arg::PanicMessage(meta.span(), true)),
+        ArgName::Bench => Ok(Arg::Bench(meta.span(), true)),
+        ArgName::ExitArgs => Ok(Arg::ExitArgs(meta.span(), true)),
+        _ => Err(arg_name.type_error(ident)),
+    }
+}
+
+// an argument given as a list, like `enable(foo, bar)`
+fn parse_list_arg(
+    arg_name: ArgName,
+    ident: &proc_macro2::Ident,
+    meta: &syn::Meta,
+    nested: &syn::punctuated::Punctuated<syn::NestedMeta, syn::Token![,]>,
+) -> Result<Arg, Vec<syn::Error>> {
+    match arg_name {
+        ArgName::Enable => {
+            parse_filter_names("enable", nested).map(|names| Arg::Enable(meta.span(), names))
+        }
+        ArgName::Disable => {
+            parse_filter_names("disable", nested).map(|names| Arg::Disable(meta.span(), names))
+        }
+        ArgName::OnlyWhen => {
+            let mut matchers = Vec::new();
+            let mut other_nested_meta_errors = Vec::new();
+
+            nested.iter().for_each(|nested_meta| match *nested_meta {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    ref path,
+                    ref lit,
+                    ..
+                })) if path.segments.len() == 1 => {
+                    matchers.push((path.segments.first().unwrap().ident.clone(), lit.clone()));
                 }
+                _ => other_nested_meta_errors.push(syn::Error::new_spanned(
+                    nested_meta,
+                    "`only_when` must contain `name = value` pairs only",
+                )),
+            });
+
+            if other_nested_meta_errors.is_empty() {
+                Ok(Arg::OnlyWhen(meta.span(), matchers))
+            } else {
+                Err(other_nested_meta_errors)
+            }
+        }
+
+        ArgName::Pause => parse_pause(meta, nested).map(|pause| Arg::Pause(meta.span(), pause)),
+        ArgName::Pretty => parse_idents(
+            nested,
+            "`pretty` must contain `args`, `return`, and argument names only",
+        )
+        .map(|idents| Arg::Pretty(meta.span(), Some(idents))),
+        ArgName::Hex => parse_idents(nested, "`hex` must contain single ident paths only")
+            .map(|idents| Arg::Hex(meta.span(), idents)),
+        ArgName::Bin => parse_idents(nested, "`bin` must contain single ident paths only")
+            .map(|idents| Arg::Bin(meta.span(), idents)),
+        ArgName::Addr => parse_idents(nested, "`addr` must contain single ident paths only")
+            .map(|idents| Arg::Addr(meta.span(), idents)),
+        ArgName::AddrOnly => {
+            parse_idents(nested, "`addr_only` must contain single ident paths only")
+                .map(|idents| Arg::AddrOnly(meta.span(), idents))
+        }
+        ArgName::Deref => parse_idents(nested, "`deref` must contain single ident paths only")
+            .map(|idents| Arg::Deref(meta.span(), Some(idents))),
+        ArgName::TryLock => parse_idents(nested, "`try_lock` must contain single ident paths only")
+            .map(|idents| Arg::TryLock(meta.span(), idents)),
+        ArgName::Groups => parse_groups(nested).map(|groups| Arg::Groups(meta.span(), groups)),
+        ArgName::Transform => {
+            parse_transforms(nested).map(|transforms| Arg::Transform(meta.span(), transforms))
+        }
+        ArgName::Dedup => parse_idents(nested, "`dedup` must contain single ident paths only")
+            .map(|idents| Arg::Dedup(meta.span(), idents)),
+        ArgName::Display => parse_idents(nested, "`display` must contain single ident paths only")
+            .map(|idents| Arg::Display(meta.span(), idents)),
+        ArgName::Redact => parse_idents(nested, "`redact` must contain single ident paths only")
+            .map(|idents| Arg::Redact(meta.span(), idents)),
+        _ => Err(arg_name.type_error(ident)),
+    }
+}
 
-                let ident = &meta.path().segments.first().unwrap().ident;
-                let arg_name = match ident.to_string().as_str() {
-                    "prefix_enter" => ArgName::PrefixEnter,
-                    "prefix_exit" => ArgName::PrefixExit,
-                    "format_enter" => ArgName::FormatEnter,
-                    "format_exit" => ArgName::FormatExit,
-                    "enable" => ArgName::Enable,
-                    "disable" => ArgName::Disable,
-                    "pause" => ArgName::Pause,
-                    "pretty" => ArgName::Pretty,
-                    "logging" => ArgName::Logging,
+// an argument given a value, like `prefix_enter = "[in]"`
+fn parse_name_value_arg(
+    arg_name: ArgName,
+    ident: &proc_macro2::Ident,
+    meta: &syn::Meta,
+    lit: &syn::Lit,
+) -> Result<Arg, Vec<syn::Error>> {
+    match arg_name {
+        ArgName::PrefixEnter => try_extract_str!(lit, meta, PrefixEnter),
+        ArgName::PrefixExit => try_extract_str!(lit, meta, PrefixExit),
+        ArgName::PrefixError => try_extract_str!(lit, meta, PrefixError),
+        ArgName::PrefixPanic => try_extract_str!(lit, meta, PrefixPanic),
+        ArgName::FormatEnter => match *lit {
+            syn::Lit::Str(ref lit_str) => Ok(Arg::FormatEnter(meta.span(), lit_str.clone())),
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::FormatExit => match *lit {
+            syn::Lit::Str(ref lit_str) => Ok(Arg::FormatExit(meta.span(), lit_str.clone())),
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::RetFormat => try_extract_str!(lit, meta, RetFormat),
+        ArgName::RetFmtWith => match *lit {
+            syn::Lit::Str(ref lit_str) => lit_str
+                .parse()
+                .map(|path| Arg::RetFmtWith(meta.span(), path))
+                .map_err(|e| vec![e]),
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Deferred => match *lit {
+            syn::Lit::Int(ref lit_int) => lit_int
+                .base10_parse()
+                .map(|ms| Arg::Deferred(meta.span(), Some(ms)))
+                .map_err(|e| vec![e]),
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Color => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.value().as_str() {
+                "thread" => Ok(Arg::Color(meta.span(), Color::Thread)),
+                "depth" => Ok(Arg::Color(meta.span(), Color::Depth)),
+                "fn" => Ok(Arg::Color(meta.span(), Color::Fn)),
+                _ => Err(vec![syn::Error::new_spanned(
+                    lit,
+                    "unknown `color` mode, expected one of: `\"thread\"`, `\"depth\"`, `\"fn\"`",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Pause => match *lit {
+            syn::Lit::Str(ref lit_str) => PauseOn::parse(lit_str)
+                .map(|on| {
+                    Arg::Pause(
+                        meta.span(),
+                        Pause {
+                            min_depth: None,
+                            max_depth: None,
+                            on: Some(on),
+                            sleep_ms: 0,
+                        },
+                    )
+                })
+                .map_err(|e| vec![e]),
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Logging => match *lit {
+            syn::Lit::Str(ref lit_str) => {
+                let level = match lit_str.value().as_str() {
+                    "error" => LogLevel::Error,
+                    "warn" => LogLevel::Warn,
+                    "info" => LogLevel::Info,
+                    "debug" => LogLevel::Debug,
+                    "trace" => LogLevel::Trace,
                     _ => {
                         return Err(vec![syn::Error::new_spanned(
-                            ident.clone(),
-                            format_args!("unknown attribute argument `{}`", ident),
+                            lit,
+                            "unknown log level, expected one of: `\"error\"`, `\"warn\"`, `\"info\"`, `\"debug\"`, `\"trace\"`",
                         )])
                     }
                 };
-
-                let prefix_enter_type_error = || {
-                    vec![syn::Error::new_spanned(
-                        ident.clone(),
-                        "`prefix_enter` requires a string value",
-                    )]
-                };
-                let prefix_exit_type_error = || {
-                    vec![syn::Error::new_spanned(
-                        ident.clone(),
-                        "`prefix_exit` requires a string value",
-                    )]
-                };
-                let format_enter_type_error = || {
-                    vec![syn::Error::new_spanned(
-                        ident.clone(),
-                        "`format_enter` requires a string value",
-                    )]
-                };
-                let format_exit_type_error = || {
-                    vec![syn::Error::new_spanned(
-                        ident.clone(),
-                        "`format_exit` requires a string value",
-                    )]
-                };
-                let enable_type_error = || {
-                    vec![syn::Error::new_spanned(
-                        ident.clone(),
-                        "`enable` requires a list of meta words",
-                    )]
-                };
-                let disable_type_error = || {
-                    vec![syn::Error::new_spanned(
-                        ident.clone(),
-                        "`disable` requires a list of meta words",
-                    )]
-                };
-                let pause_type_error = || {
-                    vec![syn::Error::new_spanned(
-                        ident.clone(),
-                        "`pause` must be a meta word",
-                    )]
-                };
-                let pretty_type_error = || {
-                    vec![syn::Error::new_spanned(
-                        ident.clone(),
-                        "`pretty` must be a meta word",
-                    )]
-                };
-                let logging_type_error = || {
+                Ok(Arg::Logging(meta.span(), level))
+            }
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::TimingThreshold => match *lit {
+            syn::Lit::Str(ref lit_str) => parse_duration(&lit_str.value())
+                .map(|threshold| Arg::TimingThreshold(meta.span(), threshold))
+                .ok_or_else(|| {
                     vec![syn::Error::new_spanned(
-                        ident.clone(),
-                        "`logging` must be a meta word",
+                        lit_str,
+                        "`timing_threshold` must be a duration with a unit of `ns`, `us`, `ms`, or `s`, e.g. \"10ms\"",
                     )]
-                };
-
-                match *meta {
-                    syn::Meta::Path(_) => match arg_name {
-                        ArgName::Pause => Ok(Arg::Pause(meta.span(), true)),
-                        ArgName::Pretty => Ok(Arg::Pretty(meta.span(), true)),
-                        ArgName::Logging => Ok(Arg::Logging(meta.span(), true)),
-                        ArgName::PrefixEnter => Err(prefix_enter_type_error()),
-                        ArgName::PrefixExit => Err(prefix_exit_type_error()),
-                        ArgName::Enable => Err(enable_type_error()),
-                        ArgName::Disable => Err(disable_type_error()),
-                        ArgName::FormatEnter => Err(format_enter_type_error()),
-                        ArgName::FormatExit => Err(format_exit_type_error()),
-                    },
-                    syn::Meta::List(syn::MetaList { ref nested, .. }) => match arg_name {
-                        ArgName::Enable => {
-                            let mut idents = HashSet::new();
-                            let mut other_nested_meta_errors = Vec::new();
-
-                            nested.iter().for_each(|nested_meta| match *nested_meta {
-                                syn::NestedMeta::Meta(syn::Meta::Path(ref path))
-                                    if path.segments.len() == 1 =>
-                                {
-                                    idents.insert(path.segments.first().unwrap().ident.clone());
-                                }
-                                _ => other_nested_meta_errors.push(syn::Error::new_spanned(
-                                    nested_meta,
-                                    "`enable` must contain single ident paths only",
-                                )),
-                            });
-
-                            if other_nested_meta_errors.is_empty() {
-                                Ok(Arg::Enable(meta.span(), idents))
-                            } else {
-                                Err(other_nested_meta_errors)
-                            }
+                }),
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Writer => match *lit {
+            syn::Lit::Str(ref lit_str) => {
+                let writer = lit_str.value();
+                let writer = match writer.as_str() {
+                    "stderr" => Ok(Writer::Stderr),
+                    "debugger" => Ok(Writer::Debugger),
+                    "oslog" => Ok(Writer::OsLog),
+                    _ => match writer
+                        .strip_prefix("file(")
+                        .and_then(|path| path.strip_suffix(')'))
+                    {
+                        Some(path) => Ok(Writer::File(path.to_string())),
+                        None => match writer.strip_suffix('!') {
+                            Some(path) => syn::parse_str(path).map(Writer::Macro),
+                            None => syn::parse_str(&writer).map(Writer::Function),
                         }
-                        ArgName::Disable => {
-                            let mut idents = HashSet::new();
-                            let mut other_nested_meta_errors = Vec::new();
-
-                            nested.iter().for_each(|nested_meta| match *nested_meta {
-                                syn::NestedMeta::Meta(syn::Meta::Path(ref path))
-                                    if path.segments.len() == 1 =>
-                                {
-                                    idents.insert(path.segments.first().unwrap().ident.clone());
-                                }
-                                _ => other_nested_meta_errors.push(syn::Error::new_spanned(
-                                    nested_meta,
-                                    "`disable` must contain single ident paths only",
-                                )),
-                            });
-
-                            if other_nested_meta_errors.is_empty() {
-                                Ok(Arg::Disable(meta.span(), idents))
-                            } else {
-                                Err(other_nested_meta_errors)
-                            }
-                        }
-
-                        ArgName::PrefixEnter => Err(prefix_enter_type_error()),
-                        ArgName::PrefixExit => Err(prefix_exit_type_error()),
-                        ArgName::Pause => Err(pause_type_error()),
-                        ArgName::Pretty => Err(pretty_type_error()),
-                        ArgName::Logging => Err(logging_type_error()),
-                        ArgName::FormatEnter => Err(format_enter_type_error()),
-                        ArgName::FormatExit => Err(format_exit_type_error()),
-                    },
-                    syn::Meta::NameValue(syn::MetaNameValue { ref lit, .. }) => match arg_name {
-                        ArgName::PrefixEnter => try_extract_str!(lit, meta, PrefixEnter),
-                        ArgName::PrefixExit => try_extract_str!(lit, meta, PrefixExit),
-                        ArgName::FormatEnter => try_extract_str!(lit, meta, FormatEnter),
-                        ArgName::FormatExit => try_extract_str!(lit, meta, FormatExit),
-                        ArgName::Enable => Err(enable_type_error()),
-                        ArgName::Disable => Err(disable_type_error()),
-                        ArgName::Pause => Err(pause_type_error()),
-                        ArgName::Pretty => Err(pretty_type_error()),
-                        ArgName::Logging => Err(logging_type_error()),
+                        .map_err(|_| {
+                            vec![syn::Error::new_spanned(
+                                lit_str,
+                                "`writer` must be `\"stderr\"`, `\"debugger\"`, `\"oslog\"`, `\"file(path)\"`, or the path to a function or macro (ending in `!`)",
+                            )]
+                        }),
                     },
+                };
+                writer.map(|writer| Arg::Writer(meta.span(), writer))
+            }
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::MaxDepth => {
+            parse_int(arg_name, ident, lit).map(|n| Arg::MaxDepth(meta.span(), n))
+        }
+        ArgName::RuntimeFilter => try_extract_str!(lit, meta, RuntimeFilter),
+        ArgName::ChunkMarkers => {
+            parse_int(arg_name, ident, lit).map(|n| Arg::ChunkMarkers(meta.span(), n))
+        }
+        ArgName::OnError => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.value().as_str() {
+                "ignore" => Ok(Arg::OnError(meta.span(), OnError::Ignore)),
+                "stderr" => Ok(Arg::OnError(meta.span(), OnError::Stderr)),
+                "panic" => Ok(Arg::OnError(meta.span(), OnError::Panic)),
+                _ => Err(vec![syn::Error::new_spanned(
+                    lit,
+                    "unknown `on_error` policy, expected one of: `\"ignore\"`, `\"stderr\"`, `\"panic\"`",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::MaxLength => {
+            parse_int(arg_name, ident, lit).map(|n| Arg::MaxLength(meta.span(), n))
+        }
+        ArgName::Marker => try_extract_str!(lit, meta, Marker),
+        ArgName::LogTarget => try_extract_str!(lit, meta, LogTarget),
+        ArgName::Output => match *lit {
+            syn::Lit::Str(ref lit_str) => {
+                let output = lit_str.value();
+                let path = match output.as_str() {
+                    "chrome_trace" => Some("trace.json"),
+                    _ => output
+                        .strip_prefix("chrome_trace(")
+                        .and_then(|path| path.strip_suffix(')')),
+                };
+                match path {
+                    Some(path) => Ok(Arg::Output(
+                        meta.span(),
+                        Output::ChromeTrace(path.to_string()),
+                    )),
+                    None if output == "happens_before" => {
+                        Ok(Arg::Output(meta.span(), Output::HappensBefore))
+                    }
+                    None if output == "call_graph" => {
+                        Ok(Arg::Output(meta.span(), Output::CallGraph))
+                    }
+                    None => Err(vec![syn::Error::new_spanned(
+                        lit_str,
+                        "`output` must be `\"chrome_trace\"`, `\"chrome_trace(path)\"`, `\"happens_before\"`, or `\"call_graph\"`",
+                    )]),
                 }
             }
-            syn::NestedMeta::Lit(_) => Err(vec![syn::Error::new_spanned(
-                nested_meta,
-                "literal attribute not allowed",
-            )]),
-        });
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Format => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.value().as_str() {
+                "json" => Ok(Arg::Format(meta.span(), Format::Json)),
+                _ => Err(vec![syn::Error::new_spanned(
+                    lit_str,
+                    "`format` must be `\"json\"`",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Predicate => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.parse::<syn::Expr>() {
+                Ok(expr) => Ok(Arg::Predicate(meta.span(), expr)),
+                Err(e) => Err(vec![syn::Error::new(
+                    lit_str.span(),
+                    format_args!("invalid `if` expression: {}", e),
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::WordEnter => try_extract_str!(lit, meta, WordEnter),
+        ArgName::WordExit => try_extract_str!(lit, meta, WordExit),
+        ArgName::Sample => {
+            parse_int(arg_name, ident, lit).map(|n| Arg::Sample(meta.span(), n))
+        }
+        ArgName::Cfg => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.parse::<syn::Meta>() {
+                Ok(predicate) => Ok(Arg::Cfg(meta.span(), predicate)),
+                Err(e) => Err(vec![syn::Error::new(
+                    lit_str.span(),
+                    format_args!("invalid `cfg` predicate: {}", e),
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::DepthVar => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.parse::<proc_macro2::Ident>() {
+                Ok(ident) => Ok(Arg::DepthVar(meta.span(), ident)),
+                Err(_) => Err(vec![syn::Error::new_spanned(
+                    lit_str,
+                    "`depth_var` must be the name of a variable",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Indent => try_extract_str!(lit, meta, Indent),
+        ArgName::IndentWidth => {
+            parse_int(arg_name, ident, lit).map(|n| Arg::IndentWidth(meta.span(), n))
+        }
+        ArgName::Timestamp => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.value().as_str() {
+                "rfc3339" => Ok(Arg::Timestamp(meta.span(), Timestamp::Rfc3339)),
+                "elapsed" => Ok(Arg::Timestamp(meta.span(), Timestamp::Elapsed)),
+                _ => Err(vec![syn::Error::new_spanned(
+                    lit_str,
+                    "`timestamp` must be `\"rfc3339\"` or `\"elapsed\"`",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::ZeroArgs => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.value().as_str() {
+                "module" => Ok(Arg::ZeroArgs(meta.span(), ZeroArgs::Module)),
+                "self_type" => Ok(Arg::ZeroArgs(meta.span(), ZeroArgs::SelfType)),
+                _ => match lit_str.parse::<syn::Expr>() {
+                    Ok(expr) => {
+                        Ok(Arg::ZeroArgs(meta.span(), ZeroArgs::Expr(Box::new(expr))))
+                    }
+                    Err(e) => Err(vec![syn::Error::new(
+                        lit_str.span(),
+                        format_args!(
+                            "`zero_args` must be `\"module\"`, `\"self_type\"`, or an expression: {}",
+                            e
+                        ),
+                    )]),
+                },
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Fingerprint => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.value().as_str() {
+                "names" => Ok(Arg::Fingerprint(meta.span(), Fingerprint::Names)),
+                "args" => Ok(Arg::Fingerprint(meta.span(), Fingerprint::Args)),
+                _ => Err(vec![syn::Error::new_spanned(
+                    lit_str,
+                    "`fingerprint` must be `\"names\"` or `\"args\"`",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::SampleFirst => {
+            parse_int(arg_name, ident, lit).map(|n| Arg::SampleFirst(meta.span(), n))
+        }
+        ArgName::Qualified => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.value().as_str() {
+                "module" => Ok(Arg::Qualified(meta.span(), Qualified::Module)),
+                "location" => Ok(Arg::Qualified(meta.span(), Qualified::Location)),
+                _ => Err(vec![syn::Error::new_spanned(
+                    lit_str,
+                    "`qualified` must be `\"module\"` or `\"location\"`",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::PausePrompt => try_extract_str!(lit, meta, PausePrompt),
+        ArgName::PauseIf => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.parse::<syn::Expr>() {
+                Ok(expr) => Ok(Arg::PauseIf(meta.span(), Box::new(expr))),
+                Err(e) => Err(vec![syn::Error::new(
+                    lit_str.span(),
+                    format_args!("`pause_if` must be an expression: {}", e),
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::DepthPath => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.parse::<syn::Path>() {
+                Ok(path) => Ok(Arg::DepthPath(meta.span(), path)),
+                Err(_) => Err(vec![syn::Error::new_spanned(
+                    lit_str,
+                    "`depth_path` must be the path of a variable",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Printer => match *lit {
+            // the `!` of the macro is optional
+            syn::Lit::Str(ref lit_str) => match syn::parse_str::<syn::Path>(
+                lit_str.value().trim_end().trim_end_matches('!'),
+            ) {
+                Ok(path) => Ok(Arg::Printer(meta.span(), path)),
+                Err(_) => Err(vec![syn::Error::new_spanned(
+                    lit_str,
+                    "`printer` must be the path of a macro, e.g. `\"defmt::trace\"`",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Buffered => {
+            parse_int(arg_name, ident, lit).map(|n| Arg::Buffered(meta.span(), n))
+        }
+        ArgName::RateLimit => match *lit {
+            syn::Lit::Str(ref lit_str) => {
+                let rate_limit = lit_str.value();
+                match rate_limit
+                    .strip_suffix("/s")
+                    .and_then(|calls| calls.trim().parse().ok())
+                {
+                    Some(calls) => Ok(Arg::RateLimit(meta.span(), calls)),
+                    None => Err(vec![syn::Error::new_spanned(
+                        lit_str,
+                        "`rate_limit` must be a number of calls per second, like `\"100/s\"`",
+                    )]),
+                }
+            }
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::OnEnter => match *lit {
+            syn::Lit::Str(ref lit_str) => lit_str
+                .parse()
+                .map(|path| Arg::OnEnter(meta.span(), path))
+                .map_err(|e| vec![e]),
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::OnExit => match *lit {
+            syn::Lit::Str(ref lit_str) => lit_str
+                .parse()
+                .map(|path| Arg::OnExit(meta.span(), path))
+                .map_err(|e| vec![e]),
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::SummarizeBelowDepth => {
+            parse_int(arg_name, ident, lit).map(|n| Arg::SummarizeBelowDepth(meta.span(), n))
+        }
+        ArgName::Style => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.value().as_str() {
+                "plain" => Ok(Arg::Style(meta.span(), Style::Plain)),
+                "tree" => Ok(Arg::Style(meta.span(), Style::Tree)),
+                _ => Err(vec![syn::Error::new_spanned(
+                    lit,
+                    "unknown `style`, expected one of: `\"plain\"`, `\"tree\"`",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Tag => try_extract_str!(lit, meta, Tag),
+        ArgName::Emit => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.value().as_str() {
+                "stderr" => Ok(Arg::Emit(meta.span(), Emit::Stderr)),
+                _ => Err(vec![syn::Error::new_spanned(
+                    lit_str,
+                    "`emit` must be `\"stderr\"`",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        ArgName::Apply => match *lit {
+            syn::Lit::Str(ref lit_str) => match lit_str.value().as_str() {
+                "last" => Ok(Arg::Apply(meta.span(), Apply::Last)),
+                _ => Err(vec![syn::Error::new_spanned(
+                    lit_str,
+                    "`apply` must be `\"last\"`",
+                )]),
+            },
+            _ => Err(arg_name.type_error(ident)),
+        },
+        _ => Err(arg_name.type_error(ident)),
+    }
+}
 
-        let mut prefix_enter_args = vec![];
-        let mut prefix_exit_args = vec![];
-        let mut format_enter_args = vec![];
-        let mut format_exit_args = vec![];
-        let mut enable_args = vec![];
-        let mut disable_args = vec![];
-        let mut pause_args = vec![];
-        let mut pretty_args = vec![];
-        let mut logging_args = vec![];
-        let mut errors = vec![];
+// the list form of `pause`, like `pause(min_depth = 2, on = "exit")`
+fn parse_pause(
+    meta: &syn::Meta,
+    nested: &syn::punctuated::Punctuated<syn::NestedMeta, syn::Token![,]>,
+) -> Result<Pause, Vec<syn::Error>> {
+    let mut pause = Pause {
+        min_depth: None,
+        max_depth: None,
+        on: None,
+        sleep_ms: 0,
+    };
+    let mut sleep_ms = None;
+    let mut errors = Vec::new();
 
-        // Group arguments of the same type and errors
-        for arg_res in args_res {
-            match arg_res {
-                Ok(arg) => match arg {
-                    Arg::PrefixEnter(span, s) => prefix_enter_args.push((span, s)),
-                    Arg::PrefixExit(span, s) => prefix_exit_args.push((span, s)),
-                    Arg::Enable(span, idents) => enable_args.push((span, idents)),
-                    Arg::Disable(span, idents) => disable_args.push((span, idents)),
-                    Arg::Pause(span, b) => pause_args.push((span, b)),
-                    Arg::Pretty(span, b) => pretty_args.push((span, b)),
-                    Arg::Logging(span, b) => logging_args.push((span, b)),
-                    Arg::FormatEnter(span, s) => format_enter_args.push((span, s)),
-                    Arg::FormatExit(span, s) => format_exit_args.push((span, s)),
-                },
-                Err(es) => errors.extend(es),
+    nested.iter().for_each(|nested_meta| match *nested_meta {
+        syn::NestedMeta::Meta(syn::Meta::NameValue(
+            syn::MetaNameValue {
+                ref path,
+                lit: syn::Lit::Int(ref lit_int),
+                ..
+            },
+        )) if path.is_ident("min_depth") || path.is_ident("max_depth") => {
+            let bound = if path.is_ident("min_depth") {
+                &mut pause.min_depth
+            } else {
+                &mut pause.max_depth
+            };
+            if bound.is_some() {
+                errors.push(syn::Error::new_spanned(
+                    path,
+                    "duplicate depth in `pause`",
+                ));
+            }
+            match lit_int.base10_parse() {
+                Ok(depth) => *bound = Some(depth),
+                Err(e) => errors.push(e),
+            }
+        }
+        syn::NestedMeta::Meta(syn::Meta::NameValue(
+            syn::MetaNameValue {
+                ref path,
+                lit: syn::Lit::Int(ref lit_int),
+                ..
+            },
+        )) if path.is_ident("sleep_ms") => {
+            if sleep_ms.is_some() {
+                errors.push(syn::Error::new_spanned(
+                    path,
+                    "duplicate `sleep_ms` in `pause`",
+                ));
+            }
+            match lit_int.base10_parse() {
+                Ok(ms) => sleep_ms = Some(ms),
+                Err(e) => errors.push(e),
             }
         }
+        syn::NestedMeta::Meta(syn::Meta::NameValue(
+            syn::MetaNameValue {
+                ref path,
+                lit: syn::Lit::Str(ref lit_str),
+                ..
+            },
+        )) if path.is_ident("on") => {
+            if pause.on.is_some() {
+                errors.push(syn::Error::new_spanned(
+                    path,
+                    "duplicate `on` in `pause`",
+                ));
+            }
+            match PauseOn::parse(lit_str) {
+                Ok(on) => pause.on = Some(on),
+                Err(e) => errors.push(e),
+            }
+        }
+        _ => errors.push(syn::Error::new_spanned(
+            nested_meta,
+            "`pause` must contain `min_depth = ..`, `max_depth = ..`, `on = ..`, and `sleep_ms = ..` only",
+        )),
+    });
+    pause.sleep_ms = sleep_ms.unwrap_or(0);
+    if let (Some(min_depth), Some(max_depth)) = (pause.min_depth, pause.max_depth) {
+        if min_depth > max_depth {
+            errors.push(syn::Error::new_spanned(
+                meta,
+                "`min_depth` of `pause` is greater than its `max_depth`",
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(pause)
+    } else {
+        Err(errors)
+    }
+}
+
+// the groups of `groups(name(a, b), ..)`, named for the group and listing its arguments
+fn parse_groups(
+    nested: &syn::punctuated::Punctuated<syn::NestedMeta, syn::Token![,]>,
+) -> Result<Vec<(String, HashSet<proc_macro2::Ident>)>, Vec<syn::Error>> {
+    let mut groups = Vec::new();
+    let mut other_nested_meta_errors = Vec::new();
 
-        // Report duplicates
-        if prefix_enter_args.len() >= 2 {
-            errors.extend(
-                prefix_enter_args
+    nested.iter().for_each(|nested_meta| match *nested_meta {
+        syn::NestedMeta::Meta(syn::Meta::List(ref group))
+            if group.path.segments.len() == 1 =>
+        {
+            let mut fn_idents = HashSet::new();
+            group.nested.iter().for_each(|nested_meta| match *nested_meta {
+                syn::NestedMeta::Meta(syn::Meta::Path(ref path))
+                    if path.segments.len() == 1 =>
+                {
+                    fn_idents
+                        .insert(path.segments.first().unwrap().ident.clone());
+                }
+                _ => other_nested_meta_errors.push(syn::Error::new_spanned(
+                    nested_meta,
+                    "groups must contain single ident paths only",
+                )),
+            });
+            let group_name =
+                group.path.segments.first().unwrap().ident.to_string();
+            groups.push((group_name, fn_idents));
+        }
+        _ => other_nested_meta_errors.push(syn::Error::new_spanned(
+            nested_meta,
+            "`groups` must contain named lists of function names only, e.g. `groups(io(read, write))`",
+        )),
+    });
+
+    if other_nested_meta_errors.is_empty() {
+        Ok(groups)
+    } else {
+        Err(other_nested_meta_errors)
+    }
+}
+
+// the `name = "expr"` pairs of `transform(..)`
+fn parse_transforms(
+    nested: &syn::punctuated::Punctuated<syn::NestedMeta, syn::Token![,]>,
+) -> Result<Vec<(proc_macro2::Ident, syn::Expr)>, Vec<syn::Error>> {
+    let mut transforms = Vec::new();
+    let mut other_nested_meta_errors = Vec::new();
+
+    nested.iter().for_each(|nested_meta| match *nested_meta {
+        syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+            ref path,
+            lit: syn::Lit::Str(ref lit_str),
+            ..
+        })) if path.segments.len() == 1 => match lit_str.parse::<syn::Expr>() {
+            Ok(expr) => transforms.push((path.segments.first().unwrap().ident.clone(), expr)),
+            Err(e) => other_nested_meta_errors.push(syn::Error::new(
+                lit_str.span(),
+                format_args!("invalid `transform` expression: {}", e),
+            )),
+        },
+        _ => other_nested_meta_errors.push(syn::Error::new_spanned(
+            nested_meta,
+            "`transform` must contain `name = \"expression\"` pairs only",
+        )),
+    });
+
+    if other_nested_meta_errors.is_empty() {
+        Ok(transforms)
+    } else {
+        Err(other_nested_meta_errors)
+    }
+}
+
+// the value of an argument that takes an integer, like `max_depth = 3`
+fn parse_int(
+    arg_name: ArgName,
+    ident: &proc_macro2::Ident,
+    lit: &syn::Lit,
+) -> Result<u64, Vec<syn::Error>> {
+    match *lit {
+        syn::Lit::Int(ref lit_int) => lit_int.base10_parse().map_err(|e| vec![e]),
+        _ => Err(arg_name.type_error(ident)),
+    }
+}
+
+// a list of single idents, like `hex(a, b)`, with `message` for anything else in it
+fn parse_idents(
+    nested: &syn::punctuated::Punctuated<syn::NestedMeta, syn::Token![,]>,
+    message: &str,
+) -> Result<HashSet<proc_macro2::Ident>, Vec<syn::Error>> {
+    let mut idents = HashSet::new();
+    let mut other_nested_meta_errors = Vec::new();
+
+    nested.iter().for_each(|nested_meta| match *nested_meta {
+        syn::NestedMeta::Meta(syn::Meta::Path(ref path)) if path.segments.len() == 1 => {
+            idents.insert(path.segments.first().unwrap().ident.clone());
+        }
+        _ => other_nested_meta_errors.push(syn::Error::new_spanned(nested_meta, message)),
+    });
+
+    if other_nested_meta_errors.is_empty() {
+        Ok(idents)
+    } else {
+        Err(other_nested_meta_errors)
+    }
+}
+
+// the names `enable` or `disable` (`name`) choose items by
+fn parse_filter_names(
+    name: &str,
+    nested: &syn::punctuated::Punctuated<syn::NestedMeta, syn::Token![,]>,
+) -> Result<FilterNames, Vec<syn::Error>> {
+    let mut names = FilterNames::default();
+    let mut other_nested_meta_errors = Vec::new();
+
+    nested.iter().for_each(|nested_meta| match *nested_meta {
+        syn::NestedMeta::Meta(syn::Meta::Path(ref path)) if path.segments.len() == 1 => {
+            names
+                .idents
+                .insert(path.segments.first().unwrap().ident.clone());
+        }
+        syn::NestedMeta::Meta(syn::Meta::Path(ref path))
+            if path.leading_colon.is_none()
+                && path
+                    .segments
+                    .iter()
+                    .all(|segment| segment.arguments.is_empty()) =>
+        {
+            names.paths.insert(
+                path.segments
                     .iter()
-                    .map(|(span, _)| syn::Error::new(*span, "duplicate `prefix_enter`")),
+                    .map(|segment| segment.ident.clone())
+                    .collect(),
             );
         }
-        if prefix_exit_args.len() >= 2 {
-            errors.extend(
-                prefix_exit_args
-                    .iter()
-                    .map(|(span, _)| syn::Error::new(*span, "duplicate `prefix_exit`")),
+        syn::NestedMeta::Lit(syn::Lit::Str(ref lit_str)) => match NamePattern::parse(lit_str) {
+            Ok(pattern) => names.patterns.push(pattern),
+            Err(e) => other_nested_meta_errors.push(e),
+        },
+        _ => other_nested_meta_errors.push(syn::Error::new_spanned(
+            nested_meta,
+            format!(
+                "`{}` must contain single ident paths, paths like `Display::fmt` or `inner::helper`, or patterns like `\"parse_*\"` only",
+                name
+            ),
+        )),
+    });
+
+    if other_nested_meta_errors.is_empty() {
+        Ok(names)
+    } else {
+        Err(other_nested_meta_errors)
+    }
+}
+
+// The arguments given to `#[trace]`, grouped by name, so that the ones given more than once
+// and the ones that can't go together can be reported
+#[derive(Default)]
+struct GroupedArgs {
+    prefix_enter: Vec<(proc_macro2::Span, String)>,
+    prefix_exit: Vec<(proc_macro2::Span, String)>,
+    prefix_error: Vec<(proc_macro2::Span, String)>,
+    prefix_panic: Vec<(proc_macro2::Span, String)>,
+    enable: Vec<(proc_macro2::Span, FilterNames)>,
+    disable: Vec<(proc_macro2::Span, FilterNames)>,
+    only_when: Vec<(proc_macro2::Span, Vec<(proc_macro2::Ident, syn::Lit)>)>,
+    pause: Vec<(proc_macro2::Span, Pause)>,
+    pretty: Vec<(proc_macro2::Span, Option<HashSet<proc_macro2::Ident>>)>,
+    logging: Vec<(proc_macro2::Span, LogLevel)>,
+    hide_ret: Vec<(proc_macro2::Span, bool)>,
+    quiet_fmt: Vec<(proc_macro2::Span, bool)>,
+    correlation_id: Vec<(proc_macro2::Span, bool)>,
+    format_enter: Vec<(proc_macro2::Span, syn::LitStr)>,
+    format_exit: Vec<(proc_macro2::Span, syn::LitStr)>,
+    ret_format: Vec<(proc_macro2::Span, String)>,
+    ret_fmt_with: Vec<(proc_macro2::Span, syn::Path)>,
+    deferred: Vec<(proc_macro2::Span, Option<u64>)>,
+    color: Vec<(proc_macro2::Span, Color)>,
+    hex: Vec<(proc_macro2::Span, HashSet<proc_macro2::Ident>)>,
+    bin: Vec<(proc_macro2::Span, HashSet<proc_macro2::Ident>)>,
+    addr: Vec<(proc_macro2::Span, HashSet<proc_macro2::Ident>)>,
+    addr_only: Vec<(proc_macro2::Span, HashSet<proc_macro2::Ident>)>,
+    rc_counts: Vec<(proc_macro2::Span, bool)>,
+    deref: Vec<(proc_macro2::Span, Option<HashSet<proc_macro2::Ident>>)>,
+    try_lock: Vec<(proc_macro2::Span, HashSet<proc_macro2::Ident>)>,
+    timing: Vec<(proc_macro2::Span, bool)>,
+    timing_threshold: Vec<(proc_macro2::Span, std::time::Duration)>,
+    since_start: Vec<(proc_macro2::Span, bool)>,
+    tracing: Vec<(proc_macro2::Span, bool)>,
+    #[allow(clippy::type_complexity)]
+    groups: Vec<(
+        proc_macro2::Span,
+        Vec<(String, HashSet<proc_macro2::Ident>)>,
+    )>,
+    writer: Vec<(proc_macro2::Span, Writer)>,
+    max_depth: Vec<(proc_macro2::Span, u64)>,
+    max_depth_summary: Vec<(proc_macro2::Span, bool)>,
+    runtime_filter: Vec<(proc_macro2::Span, String)>,
+    chunk_markers: Vec<(proc_macro2::Span, u64)>,
+    thread: Vec<(proc_macro2::Span, bool)>,
+    stats: Vec<(proc_macro2::Span, bool)>,
+    transform: Vec<(proc_macro2::Span, Vec<(proc_macro2::Ident, syn::Expr)>)>,
+    alloc_peak: Vec<(proc_macro2::Span, bool)>,
+    on_error: Vec<(proc_macro2::Span, OnError)>,
+    max_length: Vec<(proc_macro2::Span, u64)>,
+    errors_only: Vec<(proc_macro2::Span, bool)>,
+    scoped_sink: Vec<(proc_macro2::Span, bool)>,
+    marker: Vec<(proc_macro2::Span, String)>,
+    compact: Vec<(proc_macro2::Span, bool)>,
+    receiver: Vec<(proc_macro2::Span, bool)>,
+    record_calls: Vec<(proc_macro2::Span, bool)>,
+    debugger_hook: Vec<(proc_macro2::Span, bool)>,
+    log_target: Vec<(proc_macro2::Span, String)>,
+    output: Vec<(proc_macro2::Span, Output)>,
+    dedup: Vec<(proc_macro2::Span, HashSet<proc_macro2::Ident>)>,
+    collapse_repeats: Vec<(proc_macro2::Span, bool)>,
+    format: Vec<(proc_macro2::Span, Format)>,
+    predicate: Vec<(proc_macro2::Span, syn::Expr)>,
+    word_enter: Vec<(proc_macro2::Span, String)>,
+    word_exit: Vec<(proc_macro2::Span, String)>,
+    sample: Vec<(proc_macro2::Span, u64)>,
+    cfg: Vec<(proc_macro2::Span, syn::Meta)>,
+    instance_ids: Vec<(proc_macro2::Span, bool)>,
+    depth_var: Vec<(proc_macro2::Span, proc_macro2::Ident)>,
+    skip_cfg: Vec<(proc_macro2::Span, bool)>,
+    indent: Vec<(proc_macro2::Span, String)>,
+    indent_width: Vec<(proc_macro2::Span, u64)>,
+    timestamp: Vec<(proc_macro2::Span, Timestamp)>,
+    zero_args: Vec<(proc_macro2::Span, ZeroArgs)>,
+    call_id: Vec<(proc_macro2::Span, bool)>,
+    async_only: Vec<(proc_macro2::Span, bool)>,
+    sync_only: Vec<(proc_macro2::Span, bool)>,
+    display: Vec<(proc_macro2::Span, HashSet<proc_macro2::Ident>)>,
+    redact: Vec<(proc_macro2::Span, HashSet<proc_macro2::Ident>)>,
+    no_args: Vec<(proc_macro2::Span, bool)>,
+    auto_bound: Vec<(proc_macro2::Span, bool)>,
+    fingerprint: Vec<(proc_macro2::Span, Fingerprint)>,
+    sample_first: Vec<(proc_macro2::Span, u64)>,
+    lenient: Vec<(proc_macro2::Span, bool)>,
+    heat: Vec<(proc_macro2::Span, bool)>,
+    count_only: Vec<(proc_macro2::Span, bool)>,
+    poll: Vec<(proc_macro2::Span, bool)>,
+    skip_tests: Vec<(proc_macro2::Span, bool)>,
+    otel: Vec<(proc_macro2::Span, bool)>,
+    qualified: Vec<(proc_macro2::Span, Qualified)>,
+    registry: Vec<(proc_macro2::Span, bool)>,
+    pause_prompt: Vec<(proc_macro2::Span, String)>,
+    pause_if: Vec<(proc_macro2::Span, Box<syn::Expr>)>,
+    breakpoint: Vec<(proc_macro2::Span, bool)>,
+    stubs: Vec<(proc_macro2::Span, bool)>,
+    depth_path: Vec<(proc_macro2::Span, syn::Path)>,
+    printer: Vec<(proc_macro2::Span, syn::Path)>,
+    buffered: Vec<(proc_macro2::Span, u64)>,
+    hide_unit_return: Vec<(proc_macro2::Span, bool)>,
+    types: Vec<(proc_macro2::Span, bool)>,
+    rate_limit: Vec<(proc_macro2::Span, u64)>,
+    on_enter: Vec<(proc_macro2::Span, syn::Path)>,
+    on_exit: Vec<(proc_macro2::Span, syn::Path)>,
+    silent: Vec<(proc_macro2::Span, bool)>,
+    summarize_below_depth: Vec<(proc_macro2::Span, u64)>,
+    style: Vec<(proc_macro2::Span, Style)>,
+    expand_macros: Vec<(proc_macro2::Span, bool)>,
+    panic_message: Vec<(proc_macro2::Span, bool)>,
+    tag: Vec<(proc_macro2::Span, String)>,
+    bench: Vec<(proc_macro2::Span, bool)>,
+    exit_args: Vec<(proc_macro2::Span, bool)>,
+    emit: Vec<(proc_macro2::Span, Emit)>,
+    apply: Vec<(proc_macro2::Span, Apply)>,
+}
+
+impl GroupedArgs {
+    fn push(&mut self, arg: Arg) {
+        match arg {
+            Arg::PrefixEnter(span, value) => self.prefix_enter.push((span, value)),
+            Arg::PrefixExit(span, value) => self.prefix_exit.push((span, value)),
+            Arg::PrefixError(span, value) => self.prefix_error.push((span, value)),
+            Arg::PrefixPanic(span, value) => self.prefix_panic.push((span, value)),
+            Arg::Enable(span, value) => self.enable.push((span, value)),
+            Arg::Disable(span, value) => self.disable.push((span, value)),
+            Arg::OnlyWhen(span, value) => self.only_when.push((span, value)),
+            Arg::Pause(span, value) => self.pause.push((span, value)),
+            Arg::Pretty(span, value) => self.pretty.push((span, value)),
+            Arg::Logging(span, value) => self.logging.push((span, value)),
+            Arg::HideRet(span, value) => self.hide_ret.push((span, value)),
+            Arg::QuietFmt(span, value) => self.quiet_fmt.push((span, value)),
+            Arg::CorrelationId(span, value) => self.correlation_id.push((span, value)),
+            Arg::FormatEnter(span, value) => self.format_enter.push((span, value)),
+            Arg::FormatExit(span, value) => self.format_exit.push((span, value)),
+            Arg::RetFormat(span, value) => self.ret_format.push((span, value)),
+            Arg::RetFmtWith(span, value) => self.ret_fmt_with.push((span, value)),
+            Arg::Deferred(span, value) => self.deferred.push((span, value)),
+            Arg::Color(span, value) => self.color.push((span, value)),
+            Arg::Hex(span, value) => self.hex.push((span, value)),
+            Arg::Bin(span, value) => self.bin.push((span, value)),
+            Arg::Addr(span, value) => self.addr.push((span, value)),
+            Arg::AddrOnly(span, value) => self.addr_only.push((span, value)),
+            Arg::RcCounts(span, value) => self.rc_counts.push((span, value)),
+            Arg::Deref(span, value) => self.deref.push((span, value)),
+            Arg::TryLock(span, value) => self.try_lock.push((span, value)),
+            Arg::Timing(span, value) => self.timing.push((span, value)),
+            Arg::TimingThreshold(span, value) => self.timing_threshold.push((span, value)),
+            Arg::SinceStart(span, value) => self.since_start.push((span, value)),
+            Arg::Tracing(span, value) => self.tracing.push((span, value)),
+            Arg::Groups(span, value) => self.groups.push((span, value)),
+            Arg::Writer(span, value) => self.writer.push((span, value)),
+            Arg::MaxDepth(span, value) => self.max_depth.push((span, value)),
+            Arg::MaxDepthSummary(span, value) => self.max_depth_summary.push((span, value)),
+            Arg::RuntimeFilter(span, value) => self.runtime_filter.push((span, value)),
+            Arg::ChunkMarkers(span, value) => self.chunk_markers.push((span, value)),
+            Arg::Thread(span, value) => self.thread.push((span, value)),
+            Arg::Stats(span, value) => self.stats.push((span, value)),
+            Arg::Transform(span, value) => self.transform.push((span, value)),
+            Arg::AllocPeak(span, value) => self.alloc_peak.push((span, value)),
+            Arg::OnError(span, value) => self.on_error.push((span, value)),
+            Arg::MaxLength(span, value) => self.max_length.push((span, value)),
+            Arg::ErrorsOnly(span, value) => self.errors_only.push((span, value)),
+            Arg::ScopedSink(span, value) => self.scoped_sink.push((span, value)),
+            Arg::Marker(span, value) => self.marker.push((span, value)),
+            Arg::Compact(span, value) => self.compact.push((span, value)),
+            Arg::Receiver(span, value) => self.receiver.push((span, value)),
+            Arg::RecordCalls(span, value) => self.record_calls.push((span, value)),
+            Arg::DebuggerHook(span, value) => self.debugger_hook.push((span, value)),
+            Arg::LogTarget(span, value) => self.log_target.push((span, value)),
+            Arg::Output(span, value) => self.output.push((span, value)),
+            Arg::Dedup(span, value) => self.dedup.push((span, value)),
+            Arg::CollapseRepeats(span, value) => self.collapse_repeats.push((span, value)),
+            Arg::Format(span, value) => self.format.push((span, value)),
+            Arg::Predicate(span, value) => self.predicate.push((span, value)),
+            Arg::WordEnter(span, value) => self.word_enter.push((span, value)),
+            Arg::WordExit(span, value) => self.word_exit.push((span, value)),
+            Arg::Sample(span, value) => self.sample.push((span, value)),
+            Arg::Cfg(span, value) => self.cfg.push((span, value)),
+            Arg::InstanceIds(span, value) => self.instance_ids.push((span, value)),
+            Arg::DepthVar(span, value) => self.depth_var.push((span, value)),
+            Arg::SkipCfg(span, value) => self.skip_cfg.push((span, value)),
+            Arg::Indent(span, value) => self.indent.push((span, value)),
+            Arg::IndentWidth(span, value) => self.indent_width.push((span, value)),
+            Arg::Timestamp(span, value) => self.timestamp.push((span, value)),
+            Arg::ZeroArgs(span, value) => self.zero_args.push((span, value)),
+            Arg::CallId(span, value) => self.call_id.push((span, value)),
+            Arg::AsyncOnly(span, value) => self.async_only.push((span, value)),
+            Arg::SyncOnly(span, value) => self.sync_only.push((span, value)),
+            Arg::Display(span, value) => self.display.push((span, value)),
+            Arg::Redact(span, value) => self.redact.push((span, value)),
+            Arg::NoArgs(span, value) => self.no_args.push((span, value)),
+            Arg::AutoBound(span, value) => self.auto_bound.push((span, value)),
+            Arg::Fingerprint(span, value) => self.fingerprint.push((span, value)),
+            Arg::SampleFirst(span, value) => self.sample_first.push((span, value)),
+            Arg::Lenient(span, value) => self.lenient.push((span, value)),
+            Arg::Heat(span, value) => self.heat.push((span, value)),
+            Arg::CountOnly(span, value) => self.count_only.push((span, value)),
+            Arg::Poll(span, value) => self.poll.push((span, value)),
+            Arg::SkipTests(span, value) => self.skip_tests.push((span, value)),
+            Arg::Otel(span, value) => self.otel.push((span, value)),
+            Arg::Qualified(span, value) => self.qualified.push((span, value)),
+            Arg::Registry(span, value) => self.registry.push((span, value)),
+            Arg::PausePrompt(span, value) => self.pause_prompt.push((span, value)),
+            Arg::PauseIf(span, value) => self.pause_if.push((span, value)),
+            Arg::Breakpoint(span, value) => self.breakpoint.push((span, value)),
+            Arg::Stubs(span, value) => self.stubs.push((span, value)),
+            Arg::DepthPath(span, value) => self.depth_path.push((span, value)),
+            Arg::Printer(span, value) => self.printer.push((span, value)),
+            Arg::Buffered(span, value) => self.buffered.push((span, value)),
+            Arg::HideUnitReturn(span, value) => self.hide_unit_return.push((span, value)),
+            Arg::Types(span, value) => self.types.push((span, value)),
+            Arg::RateLimit(span, value) => self.rate_limit.push((span, value)),
+            Arg::OnEnter(span, value) => self.on_enter.push((span, value)),
+            Arg::OnExit(span, value) => self.on_exit.push((span, value)),
+            Arg::Silent(span, value) => self.silent.push((span, value)),
+            Arg::SummarizeBelowDepth(span, value) => self.summarize_below_depth.push((span, value)),
+            Arg::Style(span, value) => self.style.push((span, value)),
+            Arg::ExpandMacros(span, value) => self.expand_macros.push((span, value)),
+            Arg::PanicMessage(span, value) => self.panic_message.push((span, value)),
+            Arg::Tag(span, value) => self.tag.push((span, value)),
+            Arg::Bench(span, value) => self.bench.push((span, value)),
+            Arg::ExitArgs(span, value) => self.exit_args.push((span, value)),
+            Arg::Emit(span, value) => self.emit.push((span, value)),
+            Arg::Apply(span, value) => self.apply.push((span, value)),
+        }
+    }
+
+    // arguments that can only be given once
+    fn duplicate_errors(&self) -> Vec<syn::Error> {
+        let mut errors = vec![];
+        report_duplicates("prefix_enter", &self.prefix_enter, &mut errors);
+        report_duplicates("prefix_exit", &self.prefix_exit, &mut errors);
+        report_duplicates("prefix_error", &self.prefix_error, &mut errors);
+        report_duplicates("prefix_panic", &self.prefix_panic, &mut errors);
+        report_duplicates("format_enter", &self.format_enter, &mut errors);
+        report_duplicates("format_exit", &self.format_exit, &mut errors);
+        report_duplicates("ret_format", &self.ret_format, &mut errors);
+        report_duplicates("ret_fmt_with", &self.ret_fmt_with, &mut errors);
+        report_duplicates("enable", &self.enable, &mut errors);
+        report_duplicates("disable", &self.disable, &mut errors);
+        report_duplicates("only_when", &self.only_when, &mut errors);
+        report_duplicates("pause", &self.pause, &mut errors);
+        report_duplicates("pretty", &self.pretty, &mut errors);
+        report_duplicates("logging", &self.logging, &mut errors);
+        report_duplicates("hide_ret", &self.hide_ret, &mut errors);
+        report_duplicates("quiet_fmt", &self.quiet_fmt, &mut errors);
+        report_duplicates("correlation_id", &self.correlation_id, &mut errors);
+        report_duplicates("deferred", &self.deferred, &mut errors);
+        report_duplicates("color", &self.color, &mut errors);
+        report_duplicates("hex", &self.hex, &mut errors);
+        report_duplicates("bin", &self.bin, &mut errors);
+        report_duplicates("addr", &self.addr, &mut errors);
+        report_duplicates("addr_only", &self.addr_only, &mut errors);
+        report_duplicates("rc_counts", &self.rc_counts, &mut errors);
+        report_duplicates("deref", &self.deref, &mut errors);
+        report_duplicates("try_lock", &self.try_lock, &mut errors);
+        report_duplicates("timing", &self.timing, &mut errors);
+        report_duplicates("timing_threshold", &self.timing_threshold, &mut errors);
+        report_duplicates("since_start", &self.since_start, &mut errors);
+        report_duplicates("tracing", &self.tracing, &mut errors);
+        report_duplicates("groups", &self.groups, &mut errors);
+        report_duplicates("writer", &self.writer, &mut errors);
+        report_duplicates("max_depth", &self.max_depth, &mut errors);
+        report_duplicates("max_depth_summary", &self.max_depth_summary, &mut errors);
+        report_duplicates("runtime_filter", &self.runtime_filter, &mut errors);
+        report_duplicates("chunk_markers", &self.chunk_markers, &mut errors);
+        report_duplicates("thread", &self.thread, &mut errors);
+        report_duplicates("stats", &self.stats, &mut errors);
+        report_duplicates("transform", &self.transform, &mut errors);
+        report_duplicates("alloc_peak", &self.alloc_peak, &mut errors);
+        report_duplicates("on_error", &self.on_error, &mut errors);
+        report_duplicates("max_length", &self.max_length, &mut errors);
+        report_duplicates("errors_only", &self.errors_only, &mut errors);
+        report_duplicates("scoped_sink", &self.scoped_sink, &mut errors);
+        report_duplicates("marker", &self.marker, &mut errors);
+        report_duplicates("compact", &self.compact, &mut errors);
+        report_duplicates("receiver", &self.receiver, &mut errors);
+        report_duplicates("record_calls", &self.record_calls, &mut errors);
+        report_duplicates("debugger_hook", &self.debugger_hook, &mut errors);
+        report_duplicates("log_target", &self.log_target, &mut errors);
+        report_duplicates("output", &self.output, &mut errors);
+        report_duplicates("dedup", &self.dedup, &mut errors);
+        report_duplicates("collapse_repeats", &self.collapse_repeats, &mut errors);
+        report_duplicates("format", &self.format, &mut errors);
+        report_duplicates("if", &self.predicate, &mut errors);
+        report_duplicates("word_enter", &self.word_enter, &mut errors);
+        report_duplicates("word_exit", &self.word_exit, &mut errors);
+        report_duplicates("sample", &self.sample, &mut errors);
+        report_duplicates("cfg", &self.cfg, &mut errors);
+        report_duplicates("instance_ids", &self.instance_ids, &mut errors);
+        report_duplicates("depth_var", &self.depth_var, &mut errors);
+        report_duplicates("skip_cfg", &self.skip_cfg, &mut errors);
+        report_duplicates("indent", &self.indent, &mut errors);
+        report_duplicates("indent_width", &self.indent_width, &mut errors);
+        report_duplicates("timestamp", &self.timestamp, &mut errors);
+        report_duplicates("zero_args", &self.zero_args, &mut errors);
+        report_duplicates("call_id", &self.call_id, &mut errors);
+        report_duplicates("async_only", &self.async_only, &mut errors);
+        report_duplicates("sync_only", &self.sync_only, &mut errors);
+        report_duplicates("display", &self.display, &mut errors);
+        report_duplicates("redact", &self.redact, &mut errors);
+        report_duplicates("no_args", &self.no_args, &mut errors);
+        report_duplicates("auto_bound", &self.auto_bound, &mut errors);
+        report_duplicates("fingerprint", &self.fingerprint, &mut errors);
+        report_duplicates("sample_first", &self.sample_first, &mut errors);
+        report_duplicates("lenient", &self.lenient, &mut errors);
+        report_duplicates("heat", &self.heat, &mut errors);
+        report_duplicates("count_only", &self.count_only, &mut errors);
+        report_duplicates("poll", &self.poll, &mut errors);
+        report_duplicates("skip_tests", &self.skip_tests, &mut errors);
+        report_duplicates("otel", &self.otel, &mut errors);
+        report_duplicates("qualified", &self.qualified, &mut errors);
+        report_duplicates("registry", &self.registry, &mut errors);
+        report_duplicates("pause_prompt", &self.pause_prompt, &mut errors);
+        report_duplicates("pause_if", &self.pause_if, &mut errors);
+        report_duplicates("breakpoint", &self.breakpoint, &mut errors);
+        report_duplicates("stubs", &self.stubs, &mut errors);
+        report_duplicates("depth_path", &self.depth_path, &mut errors);
+        report_duplicates("printer", &self.printer, &mut errors);
+        report_duplicates("buffered", &self.buffered, &mut errors);
+        report_duplicates("hide_unit_return", &self.hide_unit_return, &mut errors);
+        report_duplicates("types", &self.types, &mut errors);
+        report_duplicates("rate_limit", &self.rate_limit, &mut errors);
+        report_duplicates("on_enter", &self.on_enter, &mut errors);
+        report_duplicates("on_exit", &self.on_exit, &mut errors);
+        report_duplicates("silent", &self.silent, &mut errors);
+        report_duplicates(
+            "summarize_below_depth",
+            &self.summarize_below_depth,
+            &mut errors,
+        );
+        report_duplicates("style", &self.style, &mut errors);
+        report_duplicates("expand_macros", &self.expand_macros, &mut errors);
+        report_duplicates("panic_message", &self.panic_message, &mut errors);
+        report_duplicates("tag", &self.tag, &mut errors);
+        report_duplicates("bench", &self.bench, &mut errors);
+        report_duplicates("exit_args", &self.exit_args, &mut errors);
+        report_duplicates("emit", &self.emit, &mut errors);
+        report_duplicates("apply", &self.apply, &mut errors);
+        errors
+    }
+
+    // arguments that can't be used together, or with the values they're given
+    fn conflict_errors(&self, raw_args: &[syn::NestedMeta]) -> Vec<syn::Error> {
+        let mut errors = vec![];
+        self.printer_conflicts(raw_args, &mut errors);
+        self.value_errors(&mut errors);
+        self.exclusive_errors(&mut errors);
+        self.format_conflicts(&mut errors);
+        self.output_conflicts(&mut errors);
+        self.overlap_errors(&mut errors);
+        errors
+    }
+
+    // lines are printed by a macro that may not be able to do more than format its arguments,
+    // and the depth is kept in a counter that doesn't need `std`, so only the arguments that
+    // decide which items are traced and what their lines say apply
+    fn printer_conflicts(&self, raw_args: &[syn::NestedMeta], errors: &mut Vec<syn::Error>) {
+        /// The arguments that can be used along with `printer`
+        const PRINTER_ARGS: [&str; 19] = [
+            "printer",
+            "prefix_enter",
+            "prefix_exit",
+            "word_enter",
+            "word_exit",
+            "enable",
+            "disable",
+            "hide_ret",
+            "hide_unit_return",
+            "no_args",
+            "redact",
+            "receiver",
+            "depth_var",
+            "depth_path",
+            "skip_cfg",
+            "skip_tests",
+            "async_only",
+            "sync_only",
+            "cfg",
+        ];
+
+        let Some(printer_span) = first_span(&self.printer) else {
+            return;
+        };
+        for nested_meta in raw_args {
+            let syn::NestedMeta::Meta(ref meta) = *nested_meta else {
+                continue;
+            };
+            let Some(segment) = meta.path().segments.first() else {
+                continue;
+            };
+            let name = segment.ident.to_string();
+            if !PRINTER_ARGS.contains(&name.as_str()) {
+                let message = format!("cannot have both `printer` and `{}`", name);
+                errors.push(syn::Error::new(printer_span, &message));
+                errors.push(syn::Error::new(meta.span(), message));
+            }
+        }
+    }
+
+    // values that are out of range, and arguments that only apply along with another one
+    fn value_errors(&self, errors: &mut Vec<syn::Error>) {
+        if self.max_depth_summary.len() == 1 && self.max_depth.is_empty() {
+            errors.push(syn::Error::new(
+                self.max_depth_summary[0].0,
+                "`max_depth_summary` requires `max_depth`",
+            ));
+        }
+        for (name, span) in [
+            ("pause_prompt", first_span(&self.pause_prompt)),
+            ("pause_if", first_span(&self.pause_if)),
+        ] {
+            if let (Some(span), true) = (span, self.pause.is_empty()) {
+                errors.push(syn::Error::new(
+                    span,
+                    format_args!("`{}` requires `pause`", name),
+                ));
+            }
+        }
+        for (name, args) in [
+            ("chunk_markers", &self.chunk_markers),
+            ("sample", &self.sample),
+            ("sample_first", &self.sample_first),
+            ("indent_width", &self.indent_width),
+        ] {
+            if let Some((span, 0)) = args.first() {
+                errors.push(syn::Error::new(
+                    *span,
+                    format_args!("`{}` must be at least 1", name),
+                ));
+            }
+        }
+        if let Some((span, 0)) = self.rate_limit.first() {
+            errors.push(syn::Error::new(*span, "`rate_limit` must be at least 1/s"));
+        }
+        if let Some((span, indent)) = self.indent.first() {
+            if indent.is_empty() {
+                errors.push(syn::Error::new(*span, "`indent` can't be empty"));
+            }
+        }
+        // tags are listed in `TRACE_TAGS` separated by commas
+        if let Some((span, tag)) = self.tag.first() {
+            if tag.trim().is_empty() || tag.contains(',') {
+                errors.push(syn::Error::new(
+                    *span,
+                    "`tag` can't be empty or contain commas",
+                ));
+            }
+        }
+    }
+
+    // pairs of arguments that do the same thing in different ways, or that can't both apply
+    fn exclusive_errors(&self, errors: &mut Vec<syn::Error>) {
+        // `pretty` only conflicts with the return value formats if it applies to the return value
+        let pretty_ret_args = self
+            .pretty
+            .iter()
+            .filter(|(_, idents)| {
+                idents
+                    .as_ref()
+                    .is_none_or(|idents| idents.iter().any(|ident| ident == "return"))
+            })
+            .map(|&(span, _)| (span, ()))
+            .collect::<Vec<_>>();
+        let pairs = [
+            (
+                ("depth_var", once(&self.depth_var)),
+                ("depth_path", once(&self.depth_path)),
+            ),
+            (
+                ("enable", once(&self.enable)),
+                ("disable", once(&self.disable)),
+            ),
+            (
+                ("timestamp", once(&self.timestamp)),
+                ("since_start", once(&self.since_start)),
+            ),
+            (
+                ("async_only", once(&self.async_only)),
+                ("sync_only", once(&self.sync_only)),
+            ),
+            (
+                ("indent", once(&self.indent)),
+                ("indent_width", once(&self.indent_width)),
+            ),
+            (
+                ("logging", once(&self.logging)),
+                ("writer", once(&self.writer)),
+            ),
+            (
+                ("logging", once(&self.logging)),
+                ("tracing", once(&self.tracing)),
+            ),
+            (
+                ("ret_format", once(&self.ret_format)),
+                ("format_exit", once(&self.format_exit)),
+            ),
+            (
+                ("ret_format", once(&self.ret_format)),
+                ("pretty", once(&pretty_ret_args)),
+            ),
+            (
+                ("ret_fmt_with", once(&self.ret_fmt_with)),
+                ("pretty", once(&pretty_ret_args)),
+            ),
+            (
+                ("ret_fmt_with", once(&self.ret_fmt_with)),
+                ("hide_ret", once(&self.hide_ret)),
+            ),
+            (
+                ("deferred", once(&self.deferred)),
+                ("pause", once(&self.pause)),
+            ),
+            (
+                ("deferred", once(&self.deferred)),
+                ("breakpoint", once(&self.breakpoint)),
+            ),
+            (
+                ("deferred", once(&self.deferred)),
+                ("timing_threshold", once(&self.timing_threshold)),
+            ),
+            (
+                ("timing_threshold", once(&self.timing_threshold)),
+                ("pause", once(&self.pause)),
+            ),
+        ];
+        for ((name, span), other) in pairs {
+            if let Some(span) = span {
+                report_conflicts((name, span), &[other], errors);
+            }
+        }
+        if let Some(errors_only_span) = once(&self.errors_only) {
+            for (name, span) in [
+                ("deferred", once(&self.deferred)),
+                ("timing_threshold", once(&self.timing_threshold)),
+            ] {
+                if let Some(span) = span {
+                    let message = format!("`errors_only` and `{}` are mutually exclusive", name);
+                    errors.push(syn::Error::new(errors_only_span, &message));
+                    errors.push(syn::Error::new(span, message));
+                }
+            }
+        }
+    }
+
+    // arguments that decide how lines look, along with the ones that change what's in them
+    fn format_conflicts(&self, errors: &mut Vec<syn::Error>) {
+        // the tree style decides what lines are indented by
+        if let Some(style_span) = first_span(&self.style) {
+            report_conflicts(
+                ("style", style_span),
+                &[
+                    ("indent", first_span(&self.indent)),
+                    ("indent_width", first_span(&self.indent_width)),
+                ],
+                errors,
             );
         }
-        if format_enter_args.len() >= 2 {
-            errors.extend(
-                format_enter_args
-                    .iter()
-                    .map(|(span, _)| syn::Error::new(*span, "duplicate `format_enter`")),
+        // the compact format is fixed, so nothing that changes how lines look can go with it
+        if let Some(compact_span) = first_span(&self.compact) {
+            report_conflicts(
+                ("compact", compact_span),
+                &[
+                    ("pretty", first_span(&self.pretty)),
+                    ("prefix_enter", first_span(&self.prefix_enter)),
+                    ("prefix_exit", first_span(&self.prefix_exit)),
+                    ("prefix_error", first_span(&self.prefix_error)),
+                    ("prefix_panic", first_span(&self.prefix_panic)),
+                    ("format_enter", first_span(&self.format_enter)),
+                    ("format_exit", first_span(&self.format_exit)),
+                    ("word_enter", first_span(&self.word_enter)),
+                    ("word_exit", first_span(&self.word_exit)),
+                    ("call_id", first_span(&self.call_id)),
+                    ("types", first_span(&self.types)),
+                    ("style", first_span(&self.style)),
+                    ("exit_args", first_span(&self.exit_args)),
+                ],
+                errors,
             );
         }
-        if format_exit_args.len() >= 2 {
-            errors.extend(
-                format_exit_args
-                    .iter()
-                    .map(|(span, _)| syn::Error::new(*span, "duplicate `format_exit`")),
+        // JSON lines have a fixed set of fields, so nothing that adds to lines or changes how they
+        // look can go with it, and neither can anything that writes lines that aren't events
+        if let Some(json_span) = first_span(&self.format) {
+            report_conflicts(
+                ("format", json_span),
+                &[
+                    ("pretty", first_span(&self.pretty)),
+                    ("compact", first_span(&self.compact)),
+                    ("stubs", first_span(&self.stubs)),
+                    ("prefix_enter", first_span(&self.prefix_enter)),
+                    ("prefix_exit", first_span(&self.prefix_exit)),
+                    ("prefix_error", first_span(&self.prefix_error)),
+                    ("prefix_panic", first_span(&self.prefix_panic)),
+                    ("format_enter", first_span(&self.format_enter)),
+                    ("format_exit", first_span(&self.format_exit)),
+                    ("word_enter", first_span(&self.word_enter)),
+                    ("word_exit", first_span(&self.word_exit)),
+                    ("color", first_span(&self.color)),
+                    ("marker", first_span(&self.marker)),
+                    ("thread", first_span(&self.thread)),
+                    ("since_start", first_span(&self.since_start)),
+                    ("correlation_id", first_span(&self.correlation_id)),
+                    ("chunk_markers", first_span(&self.chunk_markers)),
+                    ("max_depth_summary", first_span(&self.max_depth_summary)),
+                    ("tracing", first_span(&self.tracing)),
+                    ("output", first_span(&self.output)),
+                    ("indent", first_span(&self.indent)),
+                    ("timestamp", first_span(&self.timestamp)),
+                    ("qualified", first_span(&self.qualified)),
+                    ("zero_args", first_span(&self.zero_args)),
+                    ("call_id", first_span(&self.call_id)),
+                    ("heat", first_span(&self.heat)),
+                    ("indent_width", first_span(&self.indent_width)),
+                    ("types", first_span(&self.types)),
+                    (
+                        "summarize_below_depth",
+                        first_span(&self.summarize_below_depth),
+                    ),
+                    ("style", first_span(&self.style)),
+                    ("exit_args", first_span(&self.exit_args)),
+                ],
+                errors,
             );
         }
-        if enable_args.len() >= 2 {
-            errors.extend(
-                enable_args
-                    .iter()
-                    .map(|(span, _)| syn::Error::new(*span, "duplicate `enable`")),
+    }
+
+    // arguments that decide where lines go (or that calls are recorded some other way), along with
+    // the ones that only apply to some of those places
+    fn output_conflicts(&self, errors: &mut Vec<syn::Error>) {
+        if let Some(span) = first_span(&self.on_error) {
+            let writer_can_fail = match self.writer.first() {
+                Some((
+                    _,
+                    Writer::Debugger | Writer::OsLog | Writer::Function(_) | Writer::Macro(_),
+                )) => false,
+                Some((_, Writer::Stderr | Writer::File(_))) | None => true,
+            };
+            if !writer_can_fail || !self.logging.is_empty() || !self.tracing.is_empty() {
+                errors.push(syn::Error::new(
+                    span,
+                    "`on_error` only applies to lines written to stdout, stderr, or a file",
+                ));
+            }
+        }
+        if let Some(span) = first_span(&self.log_target) {
+            let uses_oslog = matches!(self.writer.first(), Some((_, Writer::OsLog)));
+            if self.logging.is_empty() && !uses_oslog {
+                errors.push(syn::Error::new(
+                    span,
+                    "`log_target` requires `logging` or `writer = \"oslog\"`",
+                ));
+            }
+        }
+        // registered functions are toggled where their lines are printed
+        if let Some(registry_span) = first_span(&self.registry) {
+            report_conflicts(
+                ("registry", registry_span),
+                &[
+                    ("tracing", first_span(&self.tracing)),
+                    ("output", first_span(&self.output)),
+                    ("otel", first_span(&self.otel)),
+                    ("poll", first_span(&self.poll)),
+                    ("count_only", first_span(&self.count_only)),
+                ],
+                errors,
             );
         }
-        if disable_args.len() >= 2 {
-            errors.extend(
-                disable_args
-                    .iter()
-                    .map(|(span, _)| syn::Error::new(*span, "duplicate `disable`")),
+        if let Some(poll_span) = first_span(&self.poll) {
+            report_conflicts(
+                ("poll", poll_span),
+                &[
+                    ("tracing", first_span(&self.tracing)),
+                    ("output", first_span(&self.output)),
+                    ("format", first_span(&self.format)),
+                    ("count_only", first_span(&self.count_only)),
+                ],
+                errors,
             );
         }
-        if pause_args.len() >= 2 {
-            errors.extend(
-                pause_args
-                    .iter()
-                    .map(|(span, _)| syn::Error::new(*span, "duplicate `pause`")),
+        // calls are exported as spans instead of printed, so nothing that decides where lines go or
+        // how calls are recorded applies
+        if let Some(otel_span) = first_span(&self.otel) {
+            report_conflicts(
+                ("otel", otel_span),
+                &[
+                    ("logging", first_span(&self.logging)),
+                    ("writer", first_span(&self.writer)),
+                    ("tracing", first_span(&self.tracing)),
+                    ("output", first_span(&self.output)),
+                    ("format", first_span(&self.format)),
+                    ("poll", first_span(&self.poll)),
+                    ("count_only", first_span(&self.count_only)),
+                ],
+                errors,
             );
         }
-        if pretty_args.len() >= 2 {
-            errors.extend(
-                pretty_args
-                    .iter()
-                    .map(|(span, _)| syn::Error::new(*span, "duplicate `pretty`")),
+        // calls are recorded instead of printed, so nothing else that decides where lines go applies
+        if let Some(output_span) = first_span(&self.output) {
+            report_conflicts(
+                ("output", output_span),
+                &[
+                    ("logging", first_span(&self.logging)),
+                    ("writer", first_span(&self.writer)),
+                    ("tracing", first_span(&self.tracing)),
+                ],
+                errors,
             );
         }
-        if logging_args.len() >= 2 {
-            errors.extend(
-                logging_args
-                    .iter()
-                    .map(|(span, _)| syn::Error::new(*span, "duplicate `logging`")),
+        // lines are held back on their way to stdout, and written many at a time
+        if let Some(buffered_span) = first_span(&self.buffered) {
+            report_conflicts(
+                ("buffered", buffered_span),
+                &[
+                    ("logging", first_span(&self.logging)),
+                    ("writer", first_span(&self.writer)),
+                    ("tracing", first_span(&self.tracing)),
+                    ("otel", first_span(&self.otel)),
+                    ("output", first_span(&self.output)),
+                    ("on_error", first_span(&self.on_error)),
+                    ("poll", first_span(&self.poll)),
+                    ("count_only", first_span(&self.count_only)),
+                ],
+                errors,
             );
         }
+        // hooks are called (lines left out with `silent`, and panic messages added with
+        // `panic_message`) where lines are printed
+        for (hook_name, hook_span) in [
+            ("on_enter", first_span(&self.on_enter)),
+            ("on_exit", first_span(&self.on_exit)),
+            ("silent", first_span(&self.silent)),
+            ("bench", first_span(&self.bench)),
+            ("panic_message", first_span(&self.panic_message)),
+        ] {
+            if let Some(hook_span) = hook_span {
+                report_conflicts(
+                    (hook_name, hook_span),
+                    &[
+                        ("tracing", first_span(&self.tracing)),
+                        ("otel", first_span(&self.otel)),
+                        ("output", first_span(&self.output)),
+                        ("poll", first_span(&self.poll)),
+                        ("count_only", first_span(&self.count_only)),
+                    ],
+                    errors,
+                );
+            }
+        }
+        // nothing is printed with `silent` (or `bench`), so there's nothing to hold back
+        for (quiet_name, quiet_span) in [
+            ("silent", first_span(&self.silent)),
+            ("bench", first_span(&self.bench)),
+        ] {
+            if let Some(quiet_span) = quiet_span {
+                report_conflicts(
+                    (quiet_name, quiet_span),
+                    &[
+                        ("deferred", first_span(&self.deferred)),
+                        ("timing_threshold", first_span(&self.timing_threshold)),
+                    ],
+                    errors,
+                );
+            }
+        }
+    }
 
-        // Report the presence of mutually exclusive arguments
-        if enable_args.len() == 1 && disable_args.len() == 1 {
-            errors.push(syn::Error::new(
-                enable_args[0].0,
-                "cannot have both `enable` and `disable`",
-            ));
-            errors.push(syn::Error::new(
-                disable_args[0].0,
-                "cannot have both `enable` and `disable`",
-            ));
+    // arguments that are listed in more than one of the arguments that choose how they're printed
+    fn overlap_errors(&self, errors: &mut Vec<syn::Error>) {
+        if let (Some((_, hex_idents)), Some((bin_span, bin_idents))) =
+            (self.hex.first(), self.bin.first())
+        {
+            errors.extend(hex_idents.intersection(bin_idents).map(|ident| {
+                syn::Error::new(
+                    *bin_span,
+                    format_args!("`{}` cannot be in both `hex` and `bin`", ident),
+                )
+            }));
         }
-        if pretty_args.len() == 1 && format_enter_args.len() == 1 {
-            errors.push(syn::Error::new(
-                pretty_args[0].0,
-                "cannot have both `pretty` and `format_enter`",
-            ));
-            errors.push(syn::Error::new(
-                format_enter_args[0].0,
-                "cannot have both `pretty` and `format_enter`",
-            ));
+        if let Some((display_span, display_idents)) = self.display.first() {
+            for (name, other_args) in [("hex", &self.hex), ("bin", &self.bin)] {
+                if let Some((_, other_idents)) = other_args.first() {
+                    errors.extend(display_idents.intersection(other_idents).map(|ident| {
+                        syn::Error::new(
+                            *display_span,
+                            format_args!("`{}` cannot be in both `{}` and `display`", ident, name),
+                        )
+                    }));
+                }
+            }
         }
-        if pretty_args.len() == 1 && format_exit_args.len() == 1 {
-            errors.push(syn::Error::new(
-                pretty_args[0].0,
-                "cannot have both `pretty` and `format_exit`",
-            ));
-            errors.push(syn::Error::new(
-                format_exit_args[0].0,
-                "cannot have both `pretty` and `format_exit`",
-            ));
+    }
+
+    // the `Args` these stand for, once they've been checked for errors
+    fn into_args(self, raw_args: syn::AttributeArgs, options: String) -> Args {
+        macro_rules! first_no_span {
+            ($iterable:expr) => {
+                $iterable.into_iter().next().map(|(_, elem)| elem)
+            };
         }
 
-        if errors.is_empty() {
-            macro_rules! first_no_span {
-                ($iterable:expr) => {
-                    $iterable.into_iter().next().map(|(_, elem)| elem)
+        let mut prefix_enter =
+            first_no_span!(self.prefix_enter).unwrap_or_else(|| DEFAULT_PREFIX_ENTER.to_owned());
+        let mut prefix_exit =
+            first_no_span!(self.prefix_exit).unwrap_or_else(|| DEFAULT_PREFIX_EXIT.to_owned());
+        let mut prefix_error = first_no_span!(self.prefix_error);
+        let mut prefix_panic = first_no_span!(self.prefix_panic);
+        // `call_id` is the `{call_id}` placeholder after every prefix
+        if first_no_span!(self.call_id).unwrap_or(DEFAULT_CALL_ID) {
+            for prefix in [&mut prefix_enter, &mut prefix_exit]
+                .into_iter()
+                .chain(prefix_error.as_mut())
+                .chain(prefix_panic.as_mut())
+            {
+                prefix.push_str(" #{call_id}");
+            }
+        }
+        // with `style = "tree"`, every level is a line down from the enter line of the call
+        // it's in, which the enter and exit lines of calls branch off from
+        let style = first_no_span!(self.style).unwrap_or(Style::Plain);
+        if style == Style::Tree {
+            let branch = |prefix: &mut String, branch: &str| {
+                *prefix = if prefix.is_empty() {
+                    branch.to_string()
+                } else {
+                    format!("{} {}", branch, prefix)
+                };
+            };
+            branch(&mut prefix_enter, "├─");
+            for prefix in [&mut prefix_exit]
+                .into_iter()
+                .chain(prefix_error.as_mut())
+                .chain(prefix_panic.as_mut())
+            {
+                branch(prefix, "└─");
+            }
+        }
+        let format_enter = first_no_span!(self.format_enter);
+        let format_exit = first_no_span!(self.format_exit);
+        let ret_format = first_no_span!(self.ret_format);
+        let ret_fmt_with = first_no_span!(self.ret_fmt_with);
+        let filter = match (first_no_span!(self.enable), first_no_span!(self.disable)) {
+            (None, None) => Filter::None,
+            (Some(names), None) => Filter::Enable(names),
+            (None, Some(names)) => Filter::Disable(names),
+            (Some(_), Some(_)) => unreachable!(),
+        };
+        let only_when = first_no_span!(self.only_when).unwrap_or_default();
+        let pause = first_no_span!(self.pause);
+        // bare `pretty` applies to the return value and to everything interpolated in
+        // `format_enter`, while a list only applies to what it names
+        let (pretty, pretty_inputs) = match first_no_span!(self.pretty) {
+            None => (DEFAULT_PRETTY, None),
+            Some(None) => (true, None),
+            Some(Some(mut idents)) => {
+                let pretty = idents.iter().any(|ident| ident == "return");
+                idents.retain(|ident| ident != "return");
+                let pretty_inputs = if idents.iter().any(|ident| ident == "args") {
+                    PrettyArgs::All
+                } else {
+                    PrettyArgs::Only(idents)
                 };
+                (pretty, Some(pretty_inputs))
             }
+        };
+        let logging = first_no_span!(self.logging);
+        let hide_ret = first_no_span!(self.hide_ret).unwrap_or(DEFAULT_HIDE_RET);
+        let quiet_fmt = first_no_span!(self.quiet_fmt).unwrap_or(DEFAULT_QUIET_FMT);
+        let correlation_id = first_no_span!(self.correlation_id).unwrap_or(DEFAULT_CORRELATION_ID);
+        let deferred = first_no_span!(self.deferred).map(|threshold_ms| Deferred { threshold_ms });
+        let color = first_no_span!(self.color);
+        let hex = first_no_span!(self.hex).unwrap_or_default();
+        let bin = first_no_span!(self.bin).unwrap_or_default();
+        let addr = first_no_span!(self.addr).unwrap_or_default();
+        let addr_only = first_no_span!(self.addr_only).unwrap_or_default();
+        let rc_counts = first_no_span!(self.rc_counts).unwrap_or(DEFAULT_RC_COUNTS);
+        let deref = first_no_span!(self.deref).map(|idents| match idents {
+            Some(idents) => Deref::Only(idents),
+            None => Deref::All,
+        });
+        let try_lock = first_no_span!(self.try_lock).unwrap_or_default();
+        let timing = first_no_span!(self.timing).unwrap_or(DEFAULT_TIMING);
+        let timing_threshold = first_no_span!(self.timing_threshold);
+        let since_start = first_no_span!(self.since_start).unwrap_or(DEFAULT_SINCE_START);
+        let tracing = first_no_span!(self.tracing).unwrap_or(DEFAULT_TRACING);
+        let groups = first_no_span!(self.groups).unwrap_or_default();
+        let writer = first_no_span!(self.writer);
+        let max_depth = first_no_span!(self.max_depth);
+        let max_depth_summary =
+            first_no_span!(self.max_depth_summary).unwrap_or(DEFAULT_MAX_DEPTH_SUMMARY);
+        let runtime_filter = first_no_span!(self.runtime_filter);
+        let chunk_markers = first_no_span!(self.chunk_markers);
+        let thread = first_no_span!(self.thread).unwrap_or(DEFAULT_THREAD);
+        let stats = first_no_span!(self.stats).unwrap_or(DEFAULT_STATS);
+        let transform = first_no_span!(self.transform).unwrap_or_default();
+        let alloc_peak = first_no_span!(self.alloc_peak).unwrap_or(DEFAULT_ALLOC_PEAK);
+        let on_error = first_no_span!(self.on_error);
+        let max_length = first_no_span!(self.max_length);
+        let errors_only = first_no_span!(self.errors_only).unwrap_or(DEFAULT_ERRORS_ONLY);
+        let scoped_sink = first_no_span!(self.scoped_sink).unwrap_or(DEFAULT_SCOPED_SINK);
+        let marker = first_no_span!(self.marker);
+        let compact = first_no_span!(self.compact).unwrap_or(DEFAULT_COMPACT);
+        let receiver = first_no_span!(self.receiver).unwrap_or(DEFAULT_RECEIVER);
+        let record_calls = first_no_span!(self.record_calls).unwrap_or(DEFAULT_RECORD_CALLS);
+        let debugger_hook = first_no_span!(self.debugger_hook).unwrap_or(DEFAULT_DEBUGGER_HOOK);
+        let log_target = first_no_span!(self.log_target);
+        let output = first_no_span!(self.output);
+        let dedup = first_no_span!(self.dedup).unwrap_or_default();
+        let collapse_repeats =
+            first_no_span!(self.collapse_repeats).unwrap_or(DEFAULT_COLLAPSE_REPEATS);
+        let format = first_no_span!(self.format);
+        let predicate = first_no_span!(self.predicate);
+        let word_enter =
+            first_no_span!(self.word_enter).unwrap_or_else(|| DEFAULT_WORD_ENTER.to_owned());
+        let word_exit =
+            first_no_span!(self.word_exit).unwrap_or_else(|| DEFAULT_WORD_EXIT.to_owned());
+        let sample = first_no_span!(self.sample);
+        let cfg = first_no_span!(self.cfg);
+        let instance_ids = first_no_span!(self.instance_ids).unwrap_or(DEFAULT_INSTANCE_IDS);
+        let depth_var = first_no_span!(self.depth_var).unwrap_or_else(|| {
+            proc_macro2::Ident::new(DEFAULT_DEPTH_VAR, proc_macro2::Span::call_site())
+        });
+        let skip_cfg = first_no_span!(self.skip_cfg).unwrap_or(DEFAULT_SKIP_CFG);
+        // `indent_width` is a shorthand for an `indent` of that many spaces
+        let indent = first_no_span!(self.indent)
+            .or_else(|| first_no_span!(self.indent_width).map(|width| " ".repeat(width as usize)))
+            .or_else(|| (style == Style::Tree).then(|| "│  ".to_string()));
+        let timestamp = first_no_span!(self.timestamp);
+        let zero_args = first_no_span!(self.zero_args);
+        let async_only = first_no_span!(self.async_only).unwrap_or(DEFAULT_ASYNC_ONLY);
+        let sync_only = first_no_span!(self.sync_only).unwrap_or(DEFAULT_SYNC_ONLY);
+        let display = first_no_span!(self.display).unwrap_or_default();
+        let redact = first_no_span!(self.redact).unwrap_or_default();
+        let no_args = first_no_span!(self.no_args).unwrap_or(DEFAULT_NO_ARGS);
+        let auto_bound = first_no_span!(self.auto_bound).unwrap_or(DEFAULT_AUTO_BOUND);
+        let fingerprint = first_no_span!(self.fingerprint);
+        let sample_first = first_no_span!(self.sample_first);
+        let lenient = first_no_span!(self.lenient).unwrap_or(DEFAULT_LENIENT);
+        let heat = first_no_span!(self.heat).unwrap_or(DEFAULT_HEAT);
+        let count_only = first_no_span!(self.count_only).unwrap_or(DEFAULT_COUNT_ONLY);
+        let poll = first_no_span!(self.poll).unwrap_or(DEFAULT_POLL);
+        let skip_tests = first_no_span!(self.skip_tests).unwrap_or(DEFAULT_SKIP_TESTS);
+        let otel = first_no_span!(self.otel).unwrap_or(DEFAULT_OTEL);
+        let qualified = first_no_span!(self.qualified);
+        let registry = first_no_span!(self.registry).unwrap_or(DEFAULT_REGISTRY);
+        let pause_prompt = first_no_span!(self.pause_prompt);
+        let pause_if = first_no_span!(self.pause_if).map(|expr| *expr);
+        let breakpoint = first_no_span!(self.breakpoint).unwrap_or(DEFAULT_BREAKPOINT);
+        let stubs = first_no_span!(self.stubs).unwrap_or(DEFAULT_STUBS);
+        let depth_path = first_no_span!(self.depth_path);
+        let printer = first_no_span!(self.printer);
+        let buffered = first_no_span!(self.buffered);
+        let hide_unit_return =
+            first_no_span!(self.hide_unit_return).unwrap_or(DEFAULT_HIDE_UNIT_RETURN);
+        let types = first_no_span!(self.types).unwrap_or(DEFAULT_TYPES);
+        let rate_limit = first_no_span!(self.rate_limit);
+        let on_enter = first_no_span!(self.on_enter);
+        let on_exit = first_no_span!(self.on_exit);
+        let silent = first_no_span!(self.silent).unwrap_or(DEFAULT_SILENT);
+        let summarize_below_depth = first_no_span!(self.summarize_below_depth);
+        let expand_macros = first_no_span!(self.expand_macros).unwrap_or(DEFAULT_EXPAND_MACROS);
+        let panic_message = first_no_span!(self.panic_message).unwrap_or(DEFAULT_PANIC_MESSAGE);
+        let tag = first_no_span!(self.tag);
+        let bench = first_no_span!(self.bench).unwrap_or(DEFAULT_BENCH);
+        let exit_args = first_no_span!(self.exit_args).unwrap_or(DEFAULT_EXIT_ARGS);
+        let emit = first_no_span!(self.emit);
+        let apply = first_no_span!(self.apply);
 
-            let prefix_enter = first_no_span!(prefix_enter_args)
-                .unwrap_or_else(|| DEFAULT_PREFIX_ENTER.to_owned());
-            let prefix_exit =
-                first_no_span!(prefix_exit_args).unwrap_or_else(|| DEFAULT_PREFIX_EXIT.to_owned());
-            let format_enter = first_no_span!(format_enter_args);
-            let format_exit = first_no_span!(format_exit_args);
-            let filter = match (first_no_span!(enable_args), first_no_span!(disable_args)) {
-                (None, None) => Filter::None,
-                (Some(idents), None) => Filter::Enable(idents),
-                (None, Some(idents)) => Filter::Disable(idents),
-                (Some(_), Some(_)) => unreachable!(),
-            };
-            let pause = first_no_span!(pause_args).unwrap_or(DEFAULT_PAUSE);
-            let pretty = first_no_span!(pretty_args).unwrap_or(DEFAULT_PRETTY);
-            let logging = first_no_span!(logging_args).unwrap_or(DEFAULT_LOGGING);
-
-            Ok(Self {
-                prefix_enter,
-                prefix_exit,
-                filter,
-                pause,
-                pretty,
-                logging,
-                format_enter,
-                format_exit,
-            })
-        } else {
-            Err(errors)
+        Args {
+            prefix_enter,
+            prefix_exit,
+            prefix_error,
+            prefix_panic,
+            filter,
+            only_when,
+            pause,
+            pretty,
+            pretty_inputs,
+            logging,
+            hide_ret,
+            quiet_fmt,
+            correlation_id,
+            format_enter,
+            format_exit,
+            ret_format,
+            ret_fmt_with,
+            deferred,
+            color,
+            hex,
+            bin,
+            addr,
+            addr_only,
+            rc_counts,
+            deref,
+            try_lock,
+            timing,
+            timing_threshold,
+            since_start,
+            tracing,
+            groups,
+            writer,
+            max_depth,
+            max_depth_summary,
+            runtime_filter,
+            chunk_markers,
+            thread,
+            stats,
+            transform,
+            alloc_peak,
+            on_error,
+            max_length,
+            errors_only,
+            scoped_sink,
+            marker,
+            compact,
+            receiver,
+            record_calls,
+            debugger_hook,
+            log_target,
+            output,
+            dedup,
+            collapse_repeats,
+            format,
+            predicate,
+            word_enter,
+            word_exit,
+            sample,
+            cfg,
+            instance_ids,
+            depth_var,
+            skip_cfg,
+            indent,
+            timestamp,
+            zero_args,
+            async_only,
+            sync_only,
+            display,
+            redact,
+            no_args,
+            auto_bound,
+            fingerprint,
+            sample_first,
+            lenient,
+            heat,
+            count_only,
+            poll,
+            skip_tests,
+            otel,
+            qualified,
+            registry,
+            options,
+            raw_args,
+            pause_prompt,
+            pause_if,
+            breakpoint,
+            stubs,
+            depth_path,
+            printer,
+            buffered,
+            hide_unit_return,
+            types,
+            rate_limit,
+            on_enter,
+            on_exit,
+            silent,
+            summarize_below_depth,
+            expand_macros,
+            panic_message,
+            tag,
+            bench,
+            exit_args,
+            emit,
+            apply,
+            closure: false,
+            track_caller: false,
+        }
+    }
+}
+
+// reports every time `name` was given, if it was given more than once
+fn report_duplicates<T>(name: &str, args: &[(proc_macro2::Span, T)], errors: &mut Vec<syn::Error>) {
+    if args.len() >= 2 {
+        errors.extend(
+            args.iter()
+                .map(|(span, _)| syn::Error::new(*span, format!("duplicate `{}`", name))),
+        );
+    }
+}
+
+// where an argument was first given, if it was
+fn first_span<T>(args: &[(proc_macro2::Span, T)]) -> Option<proc_macro2::Span> {
+    args.first().map(|(span, _)| *span)
+}
+
+// where an argument was given, if it was given once (given more than once, it's reported as a
+// duplicate instead)
+fn once<T>(args: &[(proc_macro2::Span, T)]) -> Option<proc_macro2::Span> {
+    match *args {
+        [(span, _)] => Some(span),
+        _ => None,
+    }
+}
+
+// reports `name` (given at `span`) along with each of `others` that's given too, since they can't
+// be used together
+fn report_conflicts(
+    (name, span): (&str, proc_macro2::Span),
+    others: &[(&str, Option<proc_macro2::Span>)],
+    errors: &mut Vec<syn::Error>,
+) {
+    for (other_name, other_span) in others {
+        if let Some(other_span) = *other_span {
+            let message = format!("cannot have both `{}` and `{}`", name, other_name);
+            errors.push(syn::Error::new(span, &message));
+            errors.push(syn::Error::new(other_span, message));
         }
     }
 }
+
+// parses a duration like `10ms` or `1.5s`
+fn parse_duration(duration: &str) -> Option<std::time::Duration> {
+    let unit_start = duration.find(|c: char| c.is_ascii_alphabetic())?;
+    let (amount, unit) = duration.split_at(unit_start);
+    let amount = amount.trim().parse::<f64>().ok()?;
+    let secs_per_unit = match unit {
+        "ns" => 1e-9,
+        "us" => 1e-6,
+        "ms" => 1e-3,
+        "s" => 1.0,
+        _ => return None,
+    };
+    std::time::Duration::try_from_secs_f64(amount * secs_per_unit).ok()
+}
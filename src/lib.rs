@@ -6,11 +6,32 @@
 //! that are supposed to be called are actually called without manually inserting print statements.
 //!
 //! Note that this macro requires all arguments to the function and the return value to have types
-//! that implement `Debug`. You can disable the printing of certain arguments if necessary.
+//! that implement `Debug`. You can disable the printing of certain arguments if necessary, and
+//! return values can be replaced by their type name with `hide_ret`. The exception are values whose
+//! type depends on a type parameter of the function (or of the `impl` or `trait` `#[trace]` is
+//! applied to, including `Self`) that isn't bounded by `Debug`, which are printed as the name of
+//! their type instead, e.g. `config = <my_crate::Secret>`, unless `auto_bound` is given. The same
+//! goes for values whose type mentions an associated type in a traced `impl` of a trait, like the
+//! `Option<Self::Item>` returned by `Iterator::next`, since the method can't require it to
+//! implement `Debug`. With `lenient`, that goes for all values whose type doesn't implement
+//! `Debug`.
+//!
+//! Only the body of a function is changed, so its signature and attributes stay as they were
+//! written: `extern "C"` and `unsafe` functions, and ones exported with `#[no_mangle]` or
+//! `#[export_name = "..."]` (or their `#[unsafe(...)]` forms), keep their ABI and symbol. A
+//! `const fn` or a naked function can't be traced, which is an error when `#[trace]` is applied to
+//! it directly, and leaves it untraced in a traced `mod` or `impl`.
 //!
 //! You can also add `#[trace]` to `impl`s and `mod`s to enable tracing for all functions in the
-//! `impl` or `mod`. If you use `#[trace]` on a `mod` or `impl` as well as on a method or function
-//! inside one of those elements, then only the outermost `#[trace]` is used.
+//! `impl` or `mod`, and to `trait`s to enable tracing for their default methods. If you use
+//! `#[trace]` on a `mod` or `impl` as well as on a method or function inside one of those
+//! elements, the inner one is traced once with the arguments of both, the inner arguments
+//! replacing the outer ones of the same name, e.g. a function can add `pretty` to the `prefix_enter`
+//! of its `mod`. Only the outer `enable` and `disable` aren't carried over, and an item with a
+//! `#[trace]` of its own is traced even if they leave it out. Items declared inside the bodies of
+//! functions in a traced `impl` or `mod` (local functions, `impl`s, and `mod`s) are traced too,
+//! and `#[trace]` can be used on local items directly as long as a `DEPTH` variable is in scope,
+//! e.g. by calling `trace::init_depth_var!()` in the same function body.
 //!
 //! `#[trace]` takes a few optional arguments that configure things like the prefixes to use,
 //! enabling/disabling particular arguments or functions, and more. See the
@@ -68,45 +89,176 @@
 //! ```
 //!
 //! The only time it can be omitted is when `#[trace]` is applied to `mod`s as it's defined for you
-//! automatically (see `examples/example_mod.rs`). Note that the `DEPTH` variable isn't shared
-//! between `mod`s, so indentation won't be perfect when tracing functions in multiple `mod`s. Also
-//! note that using trace as an inner attribute (`#![trace]`) is not supported at this time.
+//! automatically (see `examples/example_mod.rs`). `mod`s nested in a traced `mod` share its `DEPTH`
+//! (see `examples/example_nested_mod.rs`), but separately traced `mod`s don't, so indentation won't
+//! be perfect when tracing functions in multiple `mod`s, unless the `runtime` feature is enabled,
+//! in which case all of them use the `DEPTH` of the `trace-runtime` crate, which then has to be
+//! added as a dependency too (see `examples/example_runtime.rs`). It also lets tracing be disabled
+//! and its output be redirected for the whole program at runtime, and sent to several writers that
+//! each get enter and exit lines with their own tier of detail (see `trace_runtime::add_writer`),
+//! printed in full detail while a closure runs (see `examples/example_verbose_scope.rs`), or
+//! collected while a closure runs for a test to check (see `examples/example_capture.rs`). For
+//! `async fn`s, the depth is tracked per future rather than per thread, so futures running
+//! concurrently on the same thread don't affect each other's indentation. `#[trace]` can be put
+//! before or after `#[tokio::main]`, `#[tokio::test]`, `#[async_std::main]`, and
+//! `#[async_std::test]`, and either way traces the `async fn` itself rather than the function
+//! generated to run it (see `examples/example_entry_point.rs`). Likewise, `#[trace]` can be put
+//! before or after `#[async_trait]` (on the `impl` or `trait`, or on a method in it), and either
+//! way traces the body of the method rather than the method that `#[async_trait]` makes return it
+//! as a boxed future (see `examples/example_attribute_order.rs`).
+//!
+//! To trace a `mod` declared in another file (`mod foo;`), wrap the contents of that file in
+//! [`trace::trace_items!`](macro@trace_items) (see `examples/example_mod_file.rs`). On nightly,
+//! trace can be used as an inner attribute instead, by starting the file with `#![trace::trace]`
+//! (or `#![trace::trace(..)]` with a configuration), which traces it like a traced `mod`. The same
+//! goes for the root of a crate, in `lib.rs` or `main.rs`, which then declares `DEPTH` as well, but
+//! the files of its `mod`s need their own `#![trace::trace]` to be traced (see
+//! `examples/example_mod_inner_attribute_nightly.rs`). Inner attributes from other crates are
+//! unstable, so the crate needs `#![feature(custom_inner_attributes, proc_macro_hygiene)]`, and at
+//! the root of a crate `prelude_import` as well, which keeps the standard prelude in every module:
+//! ```ignore
+//! // src/parser.rs
+//! #![trace::trace(prefix_enter = "[parser]")]
+//!
+//! pub fn parse(input: &str) -> Vec<u32> {
+//!     input.split(',').map(|n| n.parse().unwrap()).collect()
+//! }
+//! ```
+//!
+//! Closures can't take attributes, so they're traced with
+//! [`trace::trace_closure!`](macro@trace_closure) (see `examples/example_trace_closure.rs`).
 
 mod args;
 
-use std::{iter::Peekable, str::Chars};
+use std::{
+    collections::{HashMap, HashSet},
+    iter::Peekable,
+    ops::Range,
+    str::CharIndices,
+};
 
 use proc_macro2::{Span, TokenStream};
-use quote::{quote, ToTokens};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::{
     parse::{Parse, Parser},
     parse_quote,
 };
 
+/// ANSI color codes that threads, levels of nesting, or functions are assigned from with `color`
+const LINE_COLORS: [u8; 12] = [31, 32, 33, 34, 35, 36, 91, 92, 93, 94, 95, 96];
+
+/// The prefix of the lines printed by `section!`
+const SECTION_PREFIX: &str = "[*]";
+
+/// The prefix of the lines printed by `print_stats!`
+const STATS_PREFIX: &str = "[stats]";
+
+/// The prefix of the lines of the report returned by `bench_report!`
+const BENCH_PREFIX: &str = "[bench]";
+
+/// The prefix of the lines printed by functions traced with `poll` when their future is first
+/// polled
+const POLL_PREFIX: &str = "[~]";
+
+/// The prefix of the lines printed by functions traced with `poll` when their future is woken
+const WAKE_PREFIX: &str = "[!]";
+
+/// How many levels of nesting the lines printed with `printer` are indented by at most, since their
+/// indentation is cut from a string of spaces rather than allocated
+const PRINTER_MAX_INDENT: usize = 64;
+
+/// Marks the continuation lines of multi-line values, like the ones printed with `pretty`
+const PRETTY_CONTINUATION: &str = "|";
+
 /// A convenience macro for declaring the `DEPTH` variable used for indenting the output
 ///
 /// Calling this macro is equivalent to:
 /// ```
-/// use std::cell::Cell;
+/// use std::{cell::{Cell, RefCell}, collections::HashMap};
 ///
 /// thread_local! {
 ///     static DEPTH: Cell<usize> = Cell::new(0);
+///     static TRACE_METRICS: RefCell<HashMap<&'static str, i64>> = RefCell::new(HashMap::new());
+///     static TRACE_CORRELATION_ID: RefCell<Option<String>> = RefCell::new(None);
 /// }
 /// ```
 ///
+/// Except that the `DEPTH` it declares derefs to a `Cell<usize>` rather than being one, so it can
+/// also write the lines held back by functions traced with `buffered` before other functions print
+/// theirs, and the ones that are pending until the outermost traced call returns.
+///
 /// It is required to declare a `DEPTH` variable unless using `#[trace]` on a `mod`, in which case
-/// the variable is declared for you.
+/// the variable is declared for you. `TRACE_METRICS` is only needed when using
+/// [`counter!`](macro@counter) or [`gauge!`](macro@gauge), and `TRACE_CORRELATION_ID` when using
+/// [`with_correlation_id!`](macro@with_correlation_id).
+///
+/// `init_depth_var!(MY_DEPTH)` only declares a depth variable with the given name, which functions
+/// traced with `#[trace(depth_var = "MY_DEPTH")]` use instead of `DEPTH`, e.g. when `DEPTH` is
+/// already taken or to indent some functions independently of the others (even with the `runtime`
+/// feature).
+///
+/// The variables are private to the module the macro is called in (which, at the root of the
+/// crate, still makes them visible to the whole crate), unless it's given a visibility, e.g.
+/// `init_depth_var!(pub)` or `init_depth_var!(pub(crate) MY_DEPTH)`. That way a library can share
+/// its depth variable with other crates, whose traced functions then find it with
+/// `#[trace(depth_path = "my_lib::DEPTH")]`, so calls across both crates are indented together.
+/// The other variables are found the same way, by name or next to the variable `depth_path` gives.
 #[proc_macro]
 pub fn init_depth_var(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let output = if input.is_empty() {
-        quote! {
-            ::std::thread_local! {
-                static DEPTH: ::std::cell::Cell<usize> = ::std::cell::Cell::new(0);
-            }
+    let parser = |input: syn::parse::ParseStream| {
+        let vis = input.parse::<syn::Visibility>()?;
+        let depth_var = if input.is_empty() {
+            None
+        } else {
+            Some(input.parse::<syn::Ident>()?)
+        };
+        Ok((vis, depth_var))
+    };
+    let output = match parser.parse(input) {
+        Ok((vis, None)) => {
+            depth_var_decl(&vis, Some(&syn::Ident::new("DEPTH", Span::call_site())))
         }
-    } else {
-        let input2 = proc_macro2::TokenStream::from(input);
-        syn::Error::new_spanned(input2, "`init_depth_var` takes no arguments").to_compile_error()
+        Ok((vis, Some(depth_var))) => depth_decl(&vis, &depth_var),
+        Err(e) => syn::Error::new(
+            e.span(),
+            "`init_depth_var` takes no arguments, or a visibility and/or the name of a depth variable",
+        )
+        .to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Declares the depth variable used by functions traced with `printer`, which don't need `std`
+///
+/// Calling this macro is equivalent to:
+/// ```
+/// use core::sync::atomic::AtomicUsize;
+///
+/// static DEPTH: AtomicUsize = AtomicUsize::new(0);
+/// ```
+///
+/// Like [`init_depth_var!`](macro@init_depth_var), it can be given a visibility and/or another name
+/// for the variable, e.g. `init_atomic_depth_var!(pub(crate) MY_DEPTH)`. It isn't needed when using
+/// `#[trace(printer = "...")]` on a `mod`, which declares the variable for you.
+#[proc_macro]
+pub fn init_atomic_depth_var(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let vis = input.parse::<syn::Visibility>()?;
+        let depth_var = if input.is_empty() {
+            syn::Ident::new("DEPTH", Span::call_site())
+        } else {
+            input.parse::<syn::Ident>()?
+        };
+        Ok((vis, depth_var))
+    };
+    let output = match parser.parse(input) {
+        Ok((vis, depth_var)) => atomic_depth_decl(&vis, &depth_var),
+        Err(e) => syn::Error::new(
+            e.span(),
+            "`init_atomic_depth_var` takes no arguments, or a visibility and/or the name of a depth variable",
+        )
+        .to_compile_error(),
     };
 
     output.into()
@@ -118,61 +270,707 @@ pub fn init_depth_var(input: proc_macro::TokenStream) -> proc_macro::TokenStream
 /// each of these):
 ///
 /// - `prefix_enter` - The prefix of the `println!` statement when a function is entered. Defaults
-/// to `[+]`.
+///   to `[+]`.
 ///
 /// - `prefix_exit` - The prefix of the `println!` statement when a function is exited. Defaults to
-/// `[-]`.
+///   `[-]`.
+///
+/// - `prefix_error` - The prefix used instead of `prefix_exit` when a function that returns a
+///   `Result` (or an alias of it) returns an `Err`. Defaults to `prefix_exit`.
+///
+/// - `prefix_panic` - The prefix used instead of `prefix_exit` when a function is exited by a panic,
+///   e.g. `[-] Exiting foo (panicked)`. Defaults to `prefix_exit`.
+///
+///   All prefixes can contain the placeholders `{fn}` (the name of the function), `{depth}` (the
+///   current depth), `{thread}` (the name of the current thread), `{caller_fn}` (the name of
+///   the closest calling function that also uses `{caller_fn}` or `{call_id}`, or `<none>`), and
+///   `{call_id}` (a number identifying the call, which user code can get with
+///   [`current_call_id!()`](macro@current_call_id)), e.g. `prefix_enter = "[{thread}:{fn}]"`.
+///   Use `{{` and `}}` for literal braces. Since only functions using `{caller_fn}` or `{call_id}`
+///   keep track of the current call, they're best used on a whole `mod` or `impl`. With `tracing`,
+///   the caller is the span's parent instead.
+///
+/// - `call_id` - Number every call and print its number after the prefixes, so the exit line of
+///   a call can be matched up with its enter line in recursive or multi-threaded traces, e.g.
+///   `[+] #42 Entering foo(a = 1)` and `[-] #42 Exiting foo = 2`. Short for adding `#{call_id}`
+///   to every prefix. Disabled by default.
+///
+/// - `enable` - When applied to a `mod`, `impl`, or `trait`, `enable` takes a list of function
+///   names to print, not printing any functions that are not part of this list. All functions are
+///   enabled by default. When applied to an `impl` method or a function, `enable` takes a list of
+///   arguments to print, not printing any arguments that are not part of the list. All arguments
+///   are enabled by default.
+///
+/// - `disable` - When applied to a `mod`, `impl`, or `trait`, `disable` takes a list of function
+///   names to not print, printing all other functions in the `mod`, `impl`, or `trait`. No
+///   functions are disabled by default. When applied to an `impl` method or a function, `disable`
+///   takes a list of arguments to not print, printing all other arguments. No arguments are
+///   disabled by default.
+///
+///   `disable(return)` leaves the return value out of the exiting line (`[-] Exiting foo`), so
+///   functions whose return type doesn't implement `Debug` can be traced. It can be given
+///   anywhere `disable` can, and applies to every traced function.
+///
+///   Besides names, both take patterns as strings: globs where `*` stands for anything, e.g.
+///   `enable("parse_*")`, and regular expressions between slashes, e.g. `disable("/^test_/")`,
+///   so large `mod`s can be filtered without listing every function.
+///
+///   In an `impl` of a trait, the methods given to `enable` and `disable` can be qualified by the
+///   name of the trait, e.g. `enable(Display::fmt)`, so they only match the method of that trait.
+///   In a `mod`, the functions of the `mod`s nested in it can be given by their path, e.g.
+///   `disable(inner::helper)`, which traces all of `inner` but `helper`, or
+///   `enable(inner::helper)`, which traces just `helper` of everything in `inner`.
+///   Associated types, consts, and macro invocations in an `impl` are left as they are.
+///
+/// - `no_args` - Don't print any arguments, so the entering line is just `[+] Entering foo()`.
+///   Only applies when `format_enter` isn't given. Disabled by default.
+///
+/// - `transform` - Takes a list of arguments to print the value of an expression for instead, e.g.
+///   `transform(buf = "buf.len()")` to print the length of a large buffer instead of its contents
+///   (`buf = 4096`). The expression only has to implement `Debug` and can use any of the
+///   arguments, which aren't changed by it. Only applies when `format_enter` isn't given.
+///
+/// - `hex` - Takes a list of arguments to print in hexadecimal (with `{:#x}`) instead of with
+///   `Debug`, e.g. `hex(addr, flags)`. Only applies when `format_enter` isn't given.
+///
+/// - `bin` - Like `hex`, but prints the arguments in binary (with `{:#b}`).
+///
+/// - `display` - Like `hex`, but prints the arguments with `Display` (with `{}`), e.g. for types
+///   that don't implement `Debug`. `r` stands for the return value, e.g. `display(path, r)`, unless
+///   `format_exit` or `ret_format` is given.
+///
+/// - `redact` - Takes a list of arguments that hold secrets, e.g. `redact(password, token)`, which
+///   are printed as `password = <redacted>`. Unlike with `disable`, the argument still shows up on
+///   the line, but its value is never formatted (or recorded with `record_calls`). Only applies
+///   when `format_enter` isn't given.
+///
+/// - `dedup` - Takes a list of arguments whose values are often repeated between calls, e.g.
+///   `dedup(cfg)`. Calls are numbered, and a value that was already printed is replaced by a
+///   reference to the first call it was printed in (`cfg = <same as call #3>`). Only a hash of
+///   each value is kept. Only applies when `format_enter` isn't given.
+///
+/// - `collapse_repeats` - Collapse calls whose lines are the same as those of the call just before
+///   them: the first one is printed, and the others are only counted, as in `… repeated 4 times`.
+///   The count is printed before the next line of a function traced with `collapse_repeats` on
+///   that thread, so it's best given to a whole `mod` (see `examples/example_collapse_repeats.rs`),
+///   or when the outermost traced call returns. The repeats of calls that aren't in another traced
+///   call are only reported before the next line. Disabled by default.
+///
+/// - `addr` - Takes a list of arguments to print the address of after their value, e.g.
+///   `addr(buf)` prints `buf = [1, 2] @ 0x7ffd5a3c`. The address is that of the value the argument
+///   points to, so the arguments have to implement `Deref` (like references, `Box`, `Rc`, `Arc`,
+///   `Vec`, and `String` do). Only applies when `format_enter` isn't given.
+///
+/// - `addr_only` - Like `addr`, but only prints the address (`buf = @ 0x7ffd5a3c`), so the
+///   arguments don't have to implement `Debug`.
+///
+/// - `deref` - Print the value that smart pointer arguments (`Box`, `Rc`, `Arc`, `Ref`, `RefMut`,
+///   `MutexGuard`, `RwLockReadGuard`, and `RwLockWriteGuard`, or references to them) point to,
+///   e.g. so a `Box<u8>` can be printed with `hex`. Takes a list of arguments to only dereference
+///   those, e.g. `deref(guard)`, in which case they can be of any type that implements `Deref`.
+///   Only applies when `format_enter` isn't given. Disabled by default.
+///
+/// - `try_lock` - Takes a list of `Mutex` and `RwLock` arguments (or references or smart pointers
+///   to them) to print the contents of, e.g. `try_lock(cache)`. The lock is only tried, so
+///   `<locked>` is printed instead if it's held (including by the current thread), and
+///   `<poisoned>` if it's poisoned. Other arguments in the list are printed as usual. Only applies
+///   when `format_enter` isn't given.
+///
+/// - `rc_counts` - Print the strong and weak reference counts after the values of arguments and
+///   return values that are `Rc`s or `Arc`s (or references to them), e.g.
+///   `state = 5 Arc(strong=3, weak=0)`. Types are recognized by name, so type aliases of `Rc` and
+///   `Arc` aren't. Only applies to arguments when `format_enter` isn't given, and to return values
+///   when they're printed with `Debug`. Disabled by default.
+///
+/// - `groups` - Sorts functions into named groups, e.g. `groups(io(read, write), math(add, mul))`,
+///   which can be disabled and enabled again at runtime with
+///   [`disable_group!`](macro@disable_group) and [`enable_group!`](macro@enable_group). Usually
+///   applied to a `mod` or `impl`, and requires `init_depth_var!()`.
+///
+/// - `max_depth` - Don't print calls nested `max_depth` or more levels deep, e.g. with
+///   `max_depth = 2` only the outermost traced calls and the traced calls made by them are printed.
+///   Disabled by default.
 ///
-/// - `enable` - When applied to a `mod` or `impl`, `enable` takes a list of function names to
-/// print, not printing any functions that are not part of this list. All functions are enabled by
-/// default. When applied to an `impl` method or a function, `enable` takes a list of arguments to
-/// print, not printing any arguments that are not part of the list. All arguments are enabled by
-/// default.
+/// - `max_depth_summary` - With `max_depth`, print a line saying how many calls were hidden below
+///   each call at the deepest printed level, e.g. `… 37 deeper traced calls hidden (max 12
+///   levels)`. Disabled by default.
 ///
-/// - `disable` - When applied to a `mod` or `impl`, `disable` takes a list of function names to not
-/// print, printing all other functions in the `mod` or `impl`. No functions are disabled by
-/// default. When applied to an `impl` method or a function, `disable` takes a list of arguments to
-/// not print, printing all other arguments. No arguments are disabled by default.
+/// - `summarize_below_depth` - Print the arguments and return values of calls nested
+///   `summarize_below_depth` or more levels deep as `…`, e.g. `[+] Entering parse_list(…)` and
+///   `[-] Exiting parse_list = …`, so deep recursion still shows every call without the details
+///   of each. Unlike with `max_depth`, the lines of those calls are still printed. Can't be
+///   combined with `format`. Disabled by default.
+///
+/// - `sample` - Only print every `sample`th call, e.g. with `sample = 1000` the first call and
+///   then every 1000th one after it are printed, for functions called so often that printing
+///   every call would be too much. The calls that aren't printed still count towards `stats`.
+///   Disabled by default.
+///
+/// - `sample_first` - Print the first `sample_first` calls in full, e.g. to see how a hot function
+///   is used before its lines are thinned out by `sample` (the calls printed by either are
+///   printed). Without `sample`, only the first calls are printed. Combined with `stats`, every
+///   call is still counted, so cheap totals for all calls come with the details of a few.
+///   Disabled by default.
+///
+/// - `rate_limit` - Print at most this many calls of the function per second, e.g.
+///   `rate_limit = "100/s"` for an event loop callback that fires thousands of times a second.
+///   Calls beyond that are dropped (a burst of up to a second's worth of calls is still printed),
+///   and the next call that's printed is preceded by a line like
+///   `… dropped 950 calls of on_event (over 100/s)`, or an event like
+///   `{"event":"dropped","fn":"on_event",...,"calls":950}` with `format = "json"`. Calls dropped
+///   in another traced call are also reported when the outermost one returns. A call counts as
+///   one, with both its enter and exit lines. Disabled by default.
+///
+/// - `chunk_markers` - Print a marker line after every `chunk_markers` lines, with the number of
+///   lines printed so far and the time, e.g. `chunk_markers = 1000` prints
+///   `--- trace chunk 3 ended after 3000 events at 1697461234.123456s since the Unix epoch ---`.
+///   Lines are counted across all functions using it, so huge traces that are split or sampled can
+///   still be put in order. Requires `init_depth_var!()`.
+///
+/// - `alloc_peak` - Print the most memory the call had allocated at once, on top of what was
+///   already allocated when it started, at the end of the exit line, e.g.
+///   `[-] Exiting parse = 3 (peak alloc 4096 bytes)`. Requires `trace-runtime` with its
+///   `tracking-allocator` feature as a dependency, and its `TrackingAllocator` as the global
+///   allocator. Allocations made by other threads in the meantime are counted too. Disabled by
+///   default.
+///
+/// - `debugger_hook` - Call `trace_runtime::debugger_hook` with the name of the function whenever
+///   it's entered or exited (printed or not), so setting a breakpoint on it in a debugger stops at
+///   every traced call. Requires the `trace-runtime` crate as a dependency. Disabled by default.
+///
+/// - `on_enter` and `on_exit` - A path to a function to call whenever the function is entered or
+///   exited, along with printing the line, e.g. `on_enter = "telemetry::call_started"`. It's
+///   called with the name of the function, the depth of the call, and the arguments (for
+///   `on_enter`) or the return value (for `on_exit`) formatted like in the line, so it has to be
+///   a `fn(&str, usize, &str)`:
+///   ```rust
+///   # use trace::trace;
+///   # trace::init_depth_var!();
+///   fn count_parses(name: &str, depth: usize, args: &str) {
+///       // ...
+///   }
+///
+///   #[trace(on_enter = "count_parses", silent)]
+///   fn parse(input: &str) -> usize {
+///       input.len()
+///   }
+///   ```
+///   Only the calls that are printed are passed to the hooks, so they're filtered like the lines
+///   are (e.g. by `max_depth` or `sample`). Can't be combined with `tracing`, `otel`, `output`,
+///   `poll`, or `count_only`. Disabled by default.
+///
+/// - `silent` - Don't print any lines, not even for calls that panic, e.g. to only call the hooks
+///   given with `on_enter` and `on_exit`. Can't be combined with `deferred` or `timing_threshold`,
+///   besides what the hooks can't be combined with. Disabled by default.
+///
+/// - `breakpoint` - Stop in the attached debugger whenever the function is entered, right after
+///   its enter line is printed, with `trace_runtime::breakpoint`. Nothing happens without a
+///   debugger attached, which is checked on Linux, Windows, and Apple's platforms, while elsewhere
+///   it's assumed that none is.
+///   Requires the `trace-runtime` crate as a dependency, and can't be used together with
+///   `deferred`. Disabled by default.
+///
+/// - `stubs` - Report a call that a `todo!()` or `unimplemented!()` panics out of as returning
+///   `<unimplemented>`, with the calls traced with `stubs` that lead to it, e.g.
+///   `[-] Exiting parse_header = <unimplemented: checksums> (load > parse > parse_header)`,
+///   instead of as having panicked, so unfinished parts of a program stand out in its trace. The
+///   calls it keeps panicking out of are still reported as panicking. Doesn't apply to
+///   `async fn`s, and can't be used together with `format = "json"`. Disabled by default.
+///
+/// - `panic_message` - Print the message of the panic a call is exited by in place of its return
+///   value, e.g. `[-] Exiting lookup = panicked: "index out of bounds: the len is 2 but the index
+///   is 3"`, so the deepest traced call a crash started in is the first one with that message. The
+///   panic is caught with `catch_unwind` and resumed once the message is taken. With
+///   `format = "json"`, exit lines get a `"panic"` field. Doesn't apply to `async fn`s, and can't
+///   be used together with `tracing`, `otel`, `output`, `poll`, or `count_only`. Disabled by
+///   default.
+///
+/// - `heat` - End enter lines with how many times the function has been called and how many
+///   calls per second that makes since its first call, e.g.
+///   `[+] Entering foo(a = 1) [call #12000, ~3k/s]`, so hot functions stand out while reading a
+///   trace. Every call is counted, printed or not. Disabled by default.
+///
+/// - `stats` - Count the calls of the function and how long they took, to be printed with
+///   [`print_stats!`](macro@print_stats). Methods of generic `impl`s are counted separately for each
+///   type they're called on. Requires `init_depth_var!()`. Disabled by default.
+///
+/// - `bench` - Like `stats`, but without printing any lines, so the functions can be timed without
+///   the cost of printing: their calls and their shortest, average, longest, and total time are
+///   returned by [`bench_report!()`](macro@bench_report), which is printed when `main` returns if
+///   it's traced with `bench` too (see `examples/example_bench.rs`). Requires `init_depth_var!()`.
+///   Disabled by default.
+///
+/// - `count_only` - Only count the calls of the function, without printing, formatting, or
+///   tracking the depth, so functions that are too hot to trace can still show up in
+///   [`print_stats!`](macro@print_stats) at the cost of an atomic increment per call. Calls are
+///   counted by the name of the function alone, and every other option is ignored. Requires
+///   `init_depth_var!()`. Disabled by default.
+///
+/// - `poll` - For functions returning `impl Future` that aren't `async fn`s, print the exit line
+///   when the returned future completes, with the value it resolved to, instead of when the
+///   future is created. `[~] Polling foo` is printed when it's first polled and `[!] Woke foo`
+///   whenever it's woken, and traced calls made while it's polled are nested in it. Only the
+///   prefixes, `pretty`, `lenient`, `receiver`, `no_args`, `redact`, and `enable` or `disable`
+///   (including `disable(return)`) apply to these lines (see `examples/example_poll.rs`).
+///   Disabled by default.
+///
+/// - `registry` - Register the function with `trace_runtime::registry()` when the program starts,
+///   along with its module, file, line, and the arguments given to `#[trace]`, so everything that
+///   can be traced in the binary can be listed, and each function enabled or disabled with
+///   `TracedFn::set_enabled`, or over HTTP with the handler of its `admin` feature (see
+///   `examples/example_admin.rs`). Requires the `trace-runtime` crate with its `registry` feature
+///   as a dependency (see `examples/example_registry.rs`). Disabled by default.
+///
+/// - `runtime_filter` - Only print the functions matched by the environment variable with the
+///   given name, e.g. with `runtime_filter = "RUST_TRACE"` and `RUST_TRACE=foo,parser::*`, only
+///   functions named `foo` and the functions in a `parser` module are printed, and nothing is
+///   printed when the variable isn't set. Patterns are separated by commas, can use `*` to match
+///   any part of a name, and are matched against the function's name and its path, with or
+///   without the crate name. The variable is read once per function, the first time it's called.
+///
+/// - `tag` - Put the function (or every function in a `mod` or `impl`) in a subsystem with the
+///   given name, e.g. `tag = "parser"`, which is only printed while the tag is enabled. Every tag is
+///   enabled by default, unless the `TRACE_TAGS` environment variable is set, in which case only
+///   the tags it lists (separated by commas) are, e.g. `TRACE_TAGS=parser,io`. Tags can be enabled
+///   and disabled while the program runs with `trace_runtime::enable_tag` and
+///   `trace_runtime::disable_tag`. Requires the `trace-runtime` crate as a dependency.
+///
+/// - `only_when` - Only trace calls whose arguments match all of the given values, e.g.
+///   `only_when(user_id = 42, status = "Pending")`. Arguments are compared by their `Debug` output,
+///   so a string value matches both a string argument with the same contents and e.g. an enum
+///   variant that's printed as `Pending`. When applied to a `mod` or `impl`, values are only
+///   checked for the functions that have an argument of that name. Disabled by default.
+///
+/// - `if` - Only trace calls for which the given expression is true, e.g. `if = "n > 100"`. The
+///   expression can use any of the arguments, and is evaluated when the function is entered.
+///   Calls that aren't traced still count towards the depth of the calls they make. When applied
+///   to a `mod` or `impl`, the expression is checked for every function in it. Disabled by
+///   default.
+///
+/// - `scoped_sink` - Let the lines be captured with [`with_sink!`](macro@with_sink) instead of
+///   printed where they'd usually go. Disabled by default.
+///
+/// - `marker` - A fixed string to start every line with, before the indentation and anything else,
+///   e.g. `marker = "TRACE|"`, so the trace lines can be separated from the program's own output
+///   with `grep '^TRACE|'`. Disabled by default.
+///
+/// - `thread` - Start every line with the name of the thread it was printed on, or its
+///   `ThreadId` if it doesn't have a name, e.g. `[worker-1] [+] Entering foo(a = 1)`. Since
+///   `DEPTH` is thread local, every thread is indented separately (see
+///   `examples/example_thread_names.rs`). Disabled by default.
 ///
 /// - `pause` - When given as an argument to `#[trace]`, execution is paused after each line of
-/// tracing output until enter is pressed. This allows you to trace through a program step by
-/// step. Disabled by default.
+///   tracing output until enter is pressed. This allows you to trace through a program step by
+///   step. `pause(min_depth = 2, max_depth = 4)` only pauses for calls whose `{depth}` is in that
+///   range (either bound can be left out), so the calls outside of it run without stopping.
+///   `pause = "enter"` and `pause = "exit"` (or `on = ..` in the list) only pause after the enter
+///   or the exit lines. Once there's nothing left to read from stdin, e.g. when it's a pipe that
+///   was closed, `sleep_ms = ..` in the list waits that long instead. Disabled by default.
+///
+/// - `pause_prompt` - What `pause` prints to stderr while it waits for enter, e.g.
+///   `pause_prompt = "press enter to continue"`. Only printed when stdin is a terminal.
+///
+/// - `pause_if` - An expression that decides whether `pause` waits after a line, in which `depth`
+///   is the `{depth}` of the call, e.g. `pause_if = "depth > 3"`.
+///
+/// - `max_length` - Cut off each printed argument and the return value after this many
+///   characters, followed by `…`, e.g. `max_length = 10` prints `[1, 2, 3, …` instead of the full
+///   contents of a long `Vec`. Formatting a value stops as soon as it's that long, so even a
+///   `Debug` impl that would take ages to print everything (e.g. by walking a huge graph) is cut
+///   short, as long as it passes on the errors of the `Formatter` it writes to. Only applies to
+///   values that aren't printed with `format_enter`, `format_exit`, or `ret_format`. Disabled by
+///   default.
+///
+/// - `pretty` - Pretty print the output (use `{:#?}` instead of `{:?}`). Lines after the first are
+///   indented to the current depth and prefixed with `|`. Also applies to what's interpolated in
+///   `format_enter` and `format_exit`. `pretty(..)` only pretty prints what it lists: `return` for
+///   the return value, `args` for every argument, or the names of arguments, e.g.
+///   `pretty(config, return)`. Disabled by default. Values that span several lines without it
+///   (like `{config:#?}` in `format_enter`, or a multi-line string printed with `display`) are
+///   indented the same way.
+///
+/// - `zero_args` - What to show between the parentheses of the enter line of a function without
+///   parameters, which would otherwise be empty: `zero_args = "module"` shows the path of the
+///   module it's in (`tick(module = my_crate::timer)`), `zero_args = "self_type"` the name of the
+///   `Self` type of a method (`tick(Self = my_crate::Timer)`), and anything else is an expression
+///   that's printed along with its value, e.g. `zero_args = "self.queue.len()"`
+///   (`pop(self.queue.len() = 3)`). Doesn't apply with `format_enter`. Disabled by default.
+///
+/// - `word_enter` - The word printed after `prefix_enter`, e.g. `word_enter = "Betrete"`. Defaults
+///   to `"Entering"`.
+///
+/// - `word_exit` - The word printed after `prefix_exit` (and `prefix_error` and `prefix_panic`).
+///   Defaults to `"Exiting"`.
+///
+/// - `indent` - What lines are indented by for every level of depth, e.g. `indent = "\t"` or
+///   `indent = "│ "` for guides. `indent_width = 2` is short for `indent = "  "`. Defaults to a
+///   single space.
+///
+/// - `style` - How nesting is drawn, either `"plain"` (the default) or `"tree"`, which draws it
+///   with box-drawing characters instead of `indent`:
+///
+///   ```text
+///   ├─ [+] Entering eval(Add(Num(1), Num(2)))
+///   │  ├─ [+] Entering eval(Num(1))
+///   │  └─ [-] Exiting eval = 1
+///   │  ├─ [+] Entering eval(Num(2))
+///   │  └─ [-] Exiting eval = 2
+///   └─ [-] Exiting eval = 3
+///   ```
+///
+///   Can't be used with `indent`, `indent_width`, `compact`, or `format`.
+///
+/// - `compact` - Print every line in a compact format meant to be parsed by scripts, which (unlike
+///   the default format) won't change between versions: `> foo a=1 b=2` when `foo` is entered and
+///   `< foo =3` when it exits (or `< foo !panicked`), indented to the current depth. Options that
+///   add to a line, like `timing` (`< foo =3 1.2ms`), still apply, but the ones that change its
+///   format (`pretty`, the prefixes, the words, `format_enter` and `format_exit`) can't be used
+///   with it. Disabled by default.
+///
+/// - `format` - With `format = "json"`, print every event as a JSON object on its own line, for
+///   piping into `jq` or a log aggregator, e.g.
+///   `{"event":"enter","fn":"foo","depth":0,"thread":"main","args":{"a":"1"}}` and
+///   `{"event":"exit","fn":"foo","depth":0,"thread":"main","return":"2"}` (or `"panicked":true`
+///   instead of `"return"`). Arguments and return values are the strings they'd otherwise be
+///   printed as, and `timing` and `alloc_peak` add `"elapsed"` and `"peak_alloc"` fields. Options
+///   that change how lines look (`pretty`, `compact`, the prefixes and words, `format_enter`,
+///   `format_exit`, `color`, `marker`, `thread`, `since_start`, `timestamp`, `qualified`,
+///   `correlation_id`, `heat`, and the indentation) can't be used with it, and neither can
+///   `chunk_markers` or `max_depth_summary`. The recordings of two runs can be compared with
+///   `trace_runtime::diff::diff`, which reports the calls that were added, removed, moved, or
+///   returned something else. Disabled by default.
+///
+/// - `logging` - Use `log::trace!` from the `log` crate instead of `println`. Another level can be
+///   chosen with `logging = "debug"` (or `"error"`, `"warn"`, `"info"`, `"trace"`). Disabled by
+///   default.
+///
+/// - `log_target` - The target of the `log` records with `logging`, e.g.
+///   `log_target = "my_crate::calls"`, so they can be filtered separately from the rest of the
+///   crate's logs. Also the category with `writer = "oslog"`. Defaults to the module path.
+///
+/// - `writer` - Where to write the lines to instead of stdout: `"stderr"`, `"file(trace.log)"` to
+///   append them to a file, the path to a function taking a `std::fmt::Arguments` (e.g.
+///   `"my_crate::trace_sink"`), or the path to a macro taking the same arguments as `println!`
+///   (e.g. `"my_crate::trace_sink!"`). With `"debugger"`, they're passed to `OutputDebugStringW`
+///   on Windows, so they show up in the debugger (or DebugView) of GUI applications without a
+///   console, and are written to stderr on other platforms. With `"oslog"`, they're logged at the
+///   debug level of Apple's unified logging system with the `oslog` crate (which then has to be a
+///   dependency on Apple platforms), with the package name as the subsystem and `log_target` (or
+///   the module path) as the category, and written to stderr on other platforms. Lines that can't
+///   be written to a file are dropped. Can't be used together with `logging`.
+///
+/// - `output` - Record calls instead of printing them. With `output = "chrome_trace"`, every call is
+///   a pair of begin and end events in the Trace Event Format, written to `trace.json` (or another
+///   file with `"chrome_trace(path)"`) when the program exits, which can be opened in
+///   `chrome://tracing` or Perfetto. With `output = "happens_before"`, calls made while
+///   `trace_runtime::happens_before::record` runs are recorded along with which of them are known
+///   to happen before which, to check e.g. that two functions never run concurrently. With
+///   `output = "call_graph"`, every call is recorded as an edge from the traced function running on
+///   the same thread when it was made, and `trace_runtime::call_graph::dot()` (or `write_dot`)
+///   gives the graph of which functions called which, and how many times, for Graphviz. Requires the
+///   `trace-runtime` crate as a dependency, and can't be used together with `logging`, `writer`,
+///   or `tracing`. Calls of `async fn`s are recorded on the threads they were first polled and
+///   finished on.
+///
+/// - `otel` - Export calls as OpenTelemetry spans instead of printing them, to be sent to Jaeger or
+///   an OTLP collector by the tracer provider set with `opentelemetry::global::set_tracer_provider`.
+///   Each span is named after the function, with the printed arguments as attributes and the
+///   return value as a `return` event, and calls made during it are its children. Requires the
+///   `trace-runtime` crate with its `otel` feature as a dependency (see
+///   `examples/example_otel.rs`), and can't be used together with `logging`, `writer`, `tracing`,
+///   `output`, or `format`.
+///
+/// - `on_error` - What to do when a line can't be written to stdout, stderr, or the file given to
+///   `writer`: `"ignore"` drops it, `"stderr"` drops it and prints the error to stderr, and
+///   `"panic"` panics. Either way the line is counted by
+///   [`dropped_events!()`](macro@dropped_events). Without it, lines that can't be written to a file
+///   are dropped without being counted, and `println!` and `eprintln!` panic. Requires
+///   `init_depth_var!()`.
+///
+/// - `buffered` - Hold lines back in a buffer of the thread instead of writing each one to stdout
+///   as it's printed, and write them all at once when the buffer holds 8 KiB of lines (or as many
+///   bytes as given, e.g. `buffered = 65536`) or when the outermost traced call of the thread
+///   returns or panics, so tracing hot code doesn't lock stdout for every line. They're also
+///   written before a function traced without it prints a line to stdout, so the lines stay in
+///   order (see `examples/example_buffered_mixed.rs`), as long as its `DEPTH` is the one declared
+///   by `init_depth_var!()` or a traced `mod`. Lines still in the buffer when the program exits in
+///   the middle of a call are lost. With the `runtime` feature,
+///   the buffered lines are passed to the writers together, as one line. Can't be used with
+///   `logging`, `writer`, `tracing`, `otel`, `output`, `on_error`, `poll`, or `count_only`.
+///   Disabled by default.
+///
+/// - `tracing` - Instead of printing lines, make every call a `TRACE` level span of the `tracing`
+///   crate named after the function, with the arguments as fields (recorded with `Debug`) and the
+///   return value recorded in the `return` field once the call returns. This lets the output be
+///   handled by any `tracing` subscriber. Functions traced this way don't need `init_depth_var!()`,
+///   and options that only change how lines are printed don't apply. Disabled by default.
+///
+/// - `since_start` - Start every line with the time since the first line printed with
+///   `since_start`, e.g. `+12.345s`. The clock is shared between threads, but not between `mod`s
+///   that `DEPTH` isn't shared between either. Disabled by default.
+///
+/// - `timestamp` - Start every line with the wall-clock time in UTC, formatted as RFC 3339, e.g.
+///   `2024-05-01T12:34:56.789Z`, so the trace can be lined up with other logs. With
+///   `timestamp = "elapsed"`, the time since the first line is printed instead, like with
+///   `since_start` (which can't be used together with it). Disabled by default.
+///
+/// - `qualified` - Print the name of the function with the path of its module, e.g.
+///   `[+] Entering my_crate::parser::foo(a = 1)`, so functions of the same name in different
+///   modules can be told apart. With `qualified = "location"`, it's preceded by the file and line
+///   the function is in, as in `src/parser.rs:12: my_crate::parser::foo`. Disabled by default.
+///
+/// - `correlation_id` - Start every line with the correlation id set by
+///   [`with_correlation_id!`](macro@with_correlation_id), if there is one. Disabled by default.
+///
+/// - `color` - Color the tracing output with ANSI escape codes. With `color = "thread"`, every thread
+///   is assigned a color that all of its lines are printed in, so interleaved output from multiple
+///   threads can be told apart. With `color = "depth"`, every level of nesting gets its own
+///   color, and with `color = "fn"` every function does, so deeply nested traces are easier to
+///   follow. These two are only applied when stdout is a terminal, or when the `CLICOLOR_FORCE`
+///   environment variable is set to anything but `0`. Disabled by default.
+///
+/// - `hide_ret` - Print the return type written in the signature (e.g. `<Handle>`) instead of the
+///   return value, for return types that don't implement `Debug`. Functions returning
+///   `impl Trait` are always printed this way. Disabled by default.
+///
+/// - `hide_unit_return` - Leave the return value out of the exit line of functions returning `()`,
+///   printing just `[-] Exiting foo` instead of `[-] Exiting foo = ()`. Functions returning `!`
+///   never print an exit line unless they panic, whether or not it's given. Disabled by default.
+///
+/// - `exit_args` - Repeat the arguments in the exit line, as they were when the call was entered,
+///   e.g. `[-] Exiting foo(a = 1, b = 2) = 3`, so it's clear which call a line belongs to when
+///   many others were printed in between. Can't be used with `compact` or `format`. Disabled by
+///   default.
+///
+/// - `types` - Label every argument and the return value with its type, printing e.g.
+///   `[+] Entering largest(items: &[i32] = [3, 7, 5])` and `[-] Exiting largest -> i32 = 7`. Types
+///   are printed as written in the signature, except that types that depend on a type parameter
+///   are printed as the type the function was instantiated with (by `std::any::type_name`), so
+///   calls of a generic function with different types can be told apart. Can't be combined with
+///   `compact` or `format`. Disabled by default.
+///
+/// - `lenient` - Print arguments and return values whose types don't implement `Debug` as the
+///   name of their type (e.g. `conn = <my_crate::Connection>`) instead of failing to compile, like
+///   values whose type depends on an unbounded type parameter or is an `impl Trait` (as in
+///   `cb: impl Fn(u32)`) are, so `#[trace(lenient)]` can be applied to any function without
+///   listing those arguments in `disable`. Disabled by default.
+///
+/// - `auto_bound` - Add a `Debug` bound to every type parameter of a function that the types of
+///   its printed arguments or its return type mention, so their values are printed instead of the
+///   names of their types, e.g. `fn largest<T: PartialOrd>(items: &[T])` prints
+///   `items = [1, 5, 3]` instead of `items = <&[i32]>`. Callers then have to pass types that
+///   implement `Debug`. On an inherent `impl` (like `impl<T: Clone> Stack<T>`), its methods also
+///   get `where T: Debug` clauses for the type parameters of the `impl` they print, so a generic
+///   `impl` can be traced as a whole while its methods that aren't traced (or don't print a `T`)
+///   keep working for any `T`. Doesn't apply to the methods of `impl`s of traits, which can't
+///   require more than the trait does. Disabled by default.
+///
+/// - `quiet_fmt` - While the arguments and return value are being formatted, functions traced with
+///   `quiet_fmt` that are called by their `Debug` impls (e.g. accessors) don't print anything, so
+///   their output doesn't end up in the middle of this function's lines. Needs the
+///   `TRACE_FORMATTING` flag declared by [`init_depth_var!()`](macro@init_depth_var) or a traced
+///   `mod`. Disabled by default.
+///
+/// - `ret_format` - The format spec used to print the return value, e.g. `"{:#x}"`. Independent of
+///   how arguments are formatted. Disabled by default.
+///
+/// - `ret_fmt_with` - A path to a function taking a reference to the return value, whose result is
+///   printed (with `Display` unless `ret_format` is given) in place of the return value, e.g.
+///   `ret_fmt_with = "Vec::len"`. Disabled by default.
+///
+/// - `deferred` - Buffer the entering line and only print it, together with the exiting line, if
+///   the call returned an `Err` (for functions that return a `Result`, or an alias of it) or
+///   panicked. `deferred = 100` additionally prints calls that took at least 100 milliseconds.
+///   Since a call is only printed once it returns, nested calls are printed before their callers.
+///   Disabled by default.
+///
+/// - `errors_only` - Don't print the entering line, and only print the exiting line if the call
+///   returned an `Err`, e.g. to find the call in a deep chain that failed first. Functions that
+///   don't return a `Result` (or an alias of it) are only printed if they panic. Combine with
+///   `prefix_error` to mark the failing calls. Disabled by default.
+///
+/// - `receiver` - Print the receiver of methods (`self`, `&self` or `&mut self`) before the other
+///   arguments, e.g. `[+] Entering push(self = Stack { items: [] }, item = 1)`, which requires
+///   `Self` to implement `Debug`. Disabled by default.
+///
+/// - `instance_ids` - Number the instances of a type, so one object can be followed among many
+///   look-alikes. Methods print the id of their receiver as `self = #2` (or after its value with
+///   `receiver`), and constructors that return a `Box`, `Rc`, or `Arc` of `Self` print the id of
+///   the new instance after the return value. Instances are kept apart by their address, so an
+///   object that's moved gets a new id, and one created at the address of a dropped object gets
+///   its id. Only applies when `format_enter` and `format_exit` aren't given, and requires
+///   `init_depth_var!()`. Disabled by default.
 ///
-/// - `pretty` - Pretty print the output (use `{:#?}` instead of `{:?}`). Disabled by default.
+/// - `record_calls` - Record every call (printed or not) and its arguments, so tests can check
+///   them with [`assert_called!`](macro@assert_called) and
+///   [`assert_called_with!`](macro@assert_called_with). Requires
+///   [`init_depth_var!()`](macro@init_depth_var). Disabled by default.
 ///
-/// - `logging` - Use `log::trace!` from the `log` crate instead of `println`. Disabled by default.
+/// - `fingerprint` - Hash every call (printed or not) into a fingerprint of the run, which
+///   [`fingerprint!()`](macro@fingerprint) returns, e.g. to tell whether two builds make the same
+///   calls. Calls are told apart by the name of the function, and with `fingerprint = "args"` also
+///   by the values of the arguments that are printed (except for `redact`ed ones). Requires
+///   [`init_depth_var!()`](macro@init_depth_var). Disabled by default.
+///
+/// - `timing` - Print how long the call took at the end of the exiting line, e.g.
+///   `[-] Exiting foo = 42 (1.3ms)`. Disabled by default.
+///
+/// - `timing_threshold` - Like `timing`, but calls that took less than the given duration (in `ns`,
+///   `us`, `ms`, or `s`, e.g. `timing_threshold = "10ms"`) aren't printed at all. As with
+///   `deferred`, a call is only printed once it returns (or panics). Disabled by default.
 ///
 /// - `format_enter` - The format (anything after the prefix) of `println!` statements when a function
-/// is entered. Allows parameter interpolation like:
-/// ```rust
-/// #[trace(format_enter = "i is {i}")]
-/// fn foo(i: i32) {
-///     println!("foo")
-/// }
-/// ```
-/// Interpolation follows the same rules as `format!()` besides for the fact that there is no pretty printing,
-/// that is anything interpolated will be debug formatted. Disabled by default.
-
+///   is entered. Allows parameter interpolation like:
+///   ```rust
+///   # use trace::trace;
+///   # trace::init_depth_var!();
+///   #[trace(format_enter = "i is {i}")]
+///   fn foo(i: i32) {
+///       println!("foo")
+///   }
+///   ```
+///   Interpolation follows the same rules as `format!()`, so `{i}` is formatted with `Display`
+///   and `{i:?}` with `Debug`, except that with `pretty`, values interpolated without a format spec
+///   (like `{i}`, but not `{i:x}` or `{i:}`) are pretty printed with `{:#?}`. Besides parameters,
+///   field accesses, method calls, and indexing starting with a parameter can be interpolated
+///   too, as long as they don't contain spaces, e.g. `{items.len()}` or `{config.name:?}`. In
+///   methods, `{self:?}` interpolates the receiver. Like with `format!()`, an error in the format
+///   string (e.g. interpolating something that isn't a parameter) points at the placeholder it's
+///   about on nightly, and at the whole string otherwise. Disabled by default.
+///
 /// - `format_exit` - The format (anything after the prefix) of `println!` statements when a function
-/// is exited. To interpolate the return value use `{r}`:
-/// ```rust
-/// #[trace(format_exit = "returning {r}")]
-/// fn foo() -> i32 {
-///     1
-/// }
-/// ```
-/// Otherwise formatting follows the same rules as `format_enter`. Disabled by default.
+///   is exited. To interpolate the return value use `{r}`:
+///   ```rust
+///   # use trace::trace;
+///   # trace::init_depth_var!();
+///   #[trace(format_exit = "returning {r}")]
+///   fn foo() -> i32 {
+///       1
+///   }
+///   ```
+///   Otherwise formatting follows the same rules as `format_enter`, e.g. `{r.len()}` interpolates
+///   the length of the return value and `{r.0}` the first element of a returned tuple. To print
+///   just part of a `Result` or an `Option`, `{r.ok:?}` and `{r.err:?}` interpolate the `Ok` or
+///   the `Err` of a `Result` as an `Option`, and `{r?}` what's in the `Ok` or the `Some`, or else
+///   the whole `Err(..)` or `None`, printed with `Debug` either way. These work at the end of any
+///   interpolated expression (e.g. `{r.0?}`, or `{config?}` in `format_enter`), so a struct field
+///   called `ok` or `err` can't be interpolated. Disabled by default.
+///
+///   The parameters aren't in scope in `format_exit`, since they may have been moved by the time
+///   the function returns, so interpolating one is an error:
+///   ```compile_fail
+///   # use trace::trace;
+///   # trace::init_depth_var!();
+///   #[trace(format_exit = "{i} + 1 = {r}")]
+///   fn foo(i: i32) -> i32 {
+///       i + 1
+///   }
+///   ```
+///
+///   Both `format_enter` and `format_exit` can also interpolate counters and gauges set with
+///   [`counter!`](macro@counter) and [`gauge!`](macro@gauge), as `{counter:name}` and
+///   `{gauge:name}`.
+///
+/// - `depth_var` - The name of the depth variable to use instead of `DEPTH`, e.g.
+///   `depth_var = "MY_DEPTH"`, which can be declared with `init_depth_var!(MY_DEPTH)`. Functions
+///   using different depth variables are indented independently of each other. When applied to a
+///   `mod`, that's the name the variable is declared with. Defaults to `"DEPTH"`.
+///
+/// - `depth_path` - The path of the depth variable to use instead of looking up `DEPTH` (or
+///   `depth_var`) where the function is, e.g. `depth_path = "crate::DEPTH"` for a variable declared
+///   by `init_depth_var!()` at the root of the crate, or `depth_path = "other_crate::DEPTH"` for
+///   one declared by `init_depth_var!(pub)` in another crate. The path should work from every
+///   function it applies to, so applied to a `mod`, it's best started with `crate::` or the name of
+///   a crate. A traced `mod` doesn't declare a depth variable of its own then. Can't be used
+///   together with `depth_var`.
+///
+/// - `printer` - The path of the macro to print lines with instead of `println!`, e.g.
+///   `printer = "defmt::trace"` in embedded firmware. The function then doesn't need `std`: the
+///   macro is given a format string using only `{}` and `{:?}`, followed by the indentation and
+///   the values to print, and the depth variable is an `AtomicUsize` declared by
+///   [`init_atomic_depth_var!()`](macro@init_atomic_depth_var) (or by the traced `mod`), so calls
+///   on different threads or in interrupt handlers share it. The arguments and return value have
+///   to implement whatever the macro formats them with (e.g. `defmt::Format`). Prefixes can only
+///   use the `{fn}` placeholder, and only `prefix_enter`, `prefix_exit`, `word_enter`,
+///   `word_exit`, `enable`, `disable`, `hide_ret`, `no_args`, `redact`, `receiver`, `depth_var`,
+///   `depth_path`, `cfg`, `skip_cfg`, `skip_tests`, `async_only`, and `sync_only` can be used
+///   with it. Disabled by default.
+///
+/// - `cfg` - Only trace the item when the given `cfg` predicate holds, e.g.
+///   `cfg = "debug_assertions"` or `cfg = "feature = \"trace\""`. Otherwise the item is compiled
+///   exactly as it was written, so tracing costs nothing at all in those builds. Disabled by
+///   default.
+///
+/// - `async_only` - When applied to a `mod`, `impl`, or `trait`, only trace the `async fn`s in it.
+///   Disabled by default.
+///
+/// - `sync_only` - When applied to a `mod`, `impl`, or `trait`, only trace the functions in it
+///   that aren't `async`. Disabled by default.
 ///
-/// Note that `enable` and `disable` cannot be used together, and doing so will result in an error.
+/// - `emit` - Print the code the attribute expands to while compiling, with `emit = "stderr"`, to
+///   see what it generates (e.g. when reporting a bug, or to find out how it interacts with other
+///   attributes) without `cargo expand`. Everything the attribute is applied to is printed, so on a
+///   `mod` that's the whole `mod`. Not meant to be left in, since it's printed on every build:
+///   ```rust
+///   # use trace::trace;
+///   # trace::init_depth_var!();
+///   #[trace(emit = "stderr")]
+///   fn foo(i: i32) -> i32 {
+///       i + 1
+///   }
+///   ```
+///   Anything other than `"stderr"` is an error, since that's the only place it can be printed:
+///   ```compile_fail
+///   # use trace::trace;
+///   # trace::init_depth_var!();
+///   #[trace(emit = "stdout")]
+///   fn foo(i: i32) -> i32 {
+///       i + 1
+///   }
+///   ```
 ///
-/// Further note that `format_enter` or `format_exit` cannot be used together with with `pretty`, and doing so will result in an error.
+/// - `apply` - With `apply = "last"`, apply the attribute after the other attributes that follow
+///   it, so attribute macros among them are expanded before the item is traced, and the function
+///   they end up with is what's traced. `#[async_trait]`, `#[test]`, and the entry point
+///   attributes like `#[tokio::main]` work in either order without it, but another attribute macro
+///   that rewrites a function (e.g. by adding arguments or moving its body) only does when it's
+///   expanded first.
+///
+/// - `skip_cfg` - When applied to a `mod`, `impl`, or `trait`, leave the items in it that have a
+///   `#[cfg(...)]` attribute (e.g. a `#[cfg(test)] mod tests`) untraced, along with everything in
+///   them. Otherwise those items are traced like any other, and only when they're compiled in.
+///   Disabled by default.
+///
+/// - `skip_tests` - When applied to a `mod`, leave the `#[test]` and `#[bench]` functions in it
+///   (including `#[tokio::test]` and `#[async_std::test]` ones) untraced, so `cargo test` only
+///   prints the calls of the functions being tested. Disabled by default.
+///
+/// - `expand_macros` - When applied to a `mod`, also trace the functions that macros in it expand
+///   to, which can't be seen before they're expanded otherwise. The rules of every `macro_rules!`
+///   declared in the `mod` have their expansions traced wherever the macro is used (as long as a
+///   `DEPTH` variable is in scope there), and an `include!("file.rs")` of a file of items is
+///   replaced by the items, traced like the rest of the `mod` (see
+///   `examples/example_expand_macro_rules.rs` and `examples/example_expand_include.rs`). Macros
+///   declared elsewhere are left as they are, while an `include!` of anything but a string literal
+///   is an error. Disabled by default.
+///
+/// Note that `enable` and `disable` cannot be used together (and neither can `async_only` and
+/// `sync_only`), and doing so will result in an error.
+///
+/// `ret_format` cannot be used together with `format_exit` or `pretty`, and `ret_fmt_with` cannot be
+/// used together with `pretty` or `hide_ret`. `deferred` and `timing_threshold` cannot be used
+/// together, or with `pause`.
+///
+/// The body of a `#[track_caller]` function is run inline, so `Location::caller()` and panics in it
+/// still see its caller (see `examples/example_track_caller.rs`). A `return` or `?` in it leaves
+/// without printing the exit line, and its panics aren't caught for `panic_message` or `stubs`.
 #[proc_macro_attribute]
 pub fn trace(
     args: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
     let raw_args = syn::parse_macro_input!(args as syn::AttributeArgs);
-    let args = match args::Args::from_raw_args(raw_args) {
+    let args = match args::Args::from_raw_args(raw_args.clone()) {
         Ok(args) => args,
         Err(errors) => {
             return errors
@@ -182,254 +980,6859 @@ pub fn trace(
                 .into()
         }
     };
+    if let Some(args::Apply::Last) = args.apply {
+        if let Some(reordered) = apply_last(&raw_args, input.clone().into()) {
+            return reordered.into();
+        }
+    }
 
-    let output = if let Ok(item) = syn::Item::parse.parse(input.clone()) {
+    let renamed_input = rename_attrs(input.clone().into(), "unsafe", UNSAFE_ATTR);
+    let output = if let Ok(item) = syn::Item::parse.parse2(renamed_input.clone()) {
         expand_item(&args, item)
-    } else if let Ok(impl_item) = syn::ImplItem::parse.parse(input.clone()) {
+    } else if let Ok(impl_item) = syn::ImplItem::parse.parse2(renamed_input.clone()) {
         expand_impl_item(&args, impl_item)
+    } else if let Ok(file) = syn::File::parse.parse2(renamed_input) {
+        // used as an inner attribute at the root of a crate (`#![trace]`), the attribute is given
+        // the items of the crate, which are traced like the items of a traced `mod`
+        expand_crate(&args, file)
     } else {
-        let input2 = proc_macro2::TokenStream::from(input);
+        let input2 = proc_macro2::TokenStream::from(input.clone());
         syn::Error::new_spanned(input2, "expected one of: `fn`, `impl`, `mod`").to_compile_error()
     };
+    let output = rename_attrs(output, UNSAFE_ATTR, "unsafe");
+    if let Some(args::Emit::Stderr) = args.emit {
+        emit_expansion(&args, &output);
+    }
 
-    output.into()
+    // with `cfg`, the traced item is only compiled when the predicate holds, and the item as it was
+    // written is compiled otherwise
+    match args.cfg {
+        Some(ref predicate) => {
+            let input = proc_macro2::TokenStream::from(input);
+            quote! {
+                #[cfg(#predicate)]
+                #output
+                #[cfg(not(#predicate))]
+                #input
+            }
+            .into()
+        }
+        None => output.into(),
+    }
 }
 
-#[derive(Clone, Copy)]
-enum AttrApplied {
-    Directly,
-    Indirectly,
+// with `apply = "last"`, the attributes that follow `#[trace]` are moved in front of it, so the
+// attribute macros among them (which can't be told apart from other attributes here) are expanded
+// before the item is traced. `None` if there aren't any, and the item is traced right away
+fn apply_last(raw_args: &[syn::NestedMeta], input: TokenStream) -> Option<TokenStream> {
+    let mut tokens = input.into_iter().peekable();
+    let mut attrs = vec![];
+    while let Some(proc_macro2::TokenTree::Punct(pound)) = tokens.peek().cloned() {
+        if pound.as_char() != '#' {
+            break;
+        }
+        tokens.next();
+        match tokens.next() {
+            Some(proc_macro2::TokenTree::Group(group))
+                if group.delimiter() == proc_macro2::Delimiter::Bracket =>
+            {
+                attrs.push(quote!(#pound #group))
+            }
+            // an inner attribute (`#![..]`) belongs to what's being traced, so nothing is moved
+            _ => return None,
+        }
+    }
+    if attrs.is_empty() {
+        return None;
+    }
+    let raw_args = raw_args.iter().filter(
+        |arg| !matches!(**arg, syn::NestedMeta::Meta(ref meta) if meta.path().is_ident("apply")),
+    );
+    let item = tokens.collect::<TokenStream>();
+    Some(quote! {
+        #(#attrs)*
+        #[::trace::trace(#(#raw_args),*)]
+        #item
+    })
 }
 
-fn expand_item(args: &args::Args, mut item: syn::Item) -> proc_macro2::TokenStream {
-    transform_item(args, AttrApplied::Directly, &mut item);
+// with `emit = "stderr"`, the code the attribute expands to is printed while compiling, formatted
+// if it's a list of items (a method traced on its own isn't, and is printed as it is)
+fn emit_expansion(args: &args::Args, output: &TokenStream) {
+    let code = match syn::parse2::<syn::File>(output.clone()) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => format!("{}\n", output),
+    };
+    eprint!("// #[trace({})] expands to:\n{}", args.options, code);
+}
 
-    match item {
-        syn::Item::Fn(_) | syn::Item::Mod(_) | syn::Item::Impl(_) => item.into_token_stream(),
-        _ => syn::Error::new_spanned(item, "#[trace] is not supported for this item")
-            .to_compile_error(),
+/// What attributes written as `#[unsafe(...)]` are renamed to while the item is transformed
+const UNSAFE_ATTR: &str = "trace_unsafe_attr";
+
+// syn can't parse attributes like `#[unsafe(no_mangle)]` or `#[unsafe(export_name = "..")]` (which
+// edition 2024 requires for exported functions), since `unsafe` isn't an identifier, so they're
+// renamed to `#[trace_unsafe_attr(...)]` while the item is parsed and transformed, and renamed
+// back in the output
+fn rename_attrs(tokens: TokenStream, from: &str, to: &str) -> TokenStream {
+    let mut renamed = Vec::<proc_macro2::TokenTree>::new();
+    for token in tokens {
+        let token = match token {
+            proc_macro2::TokenTree::Group(group) => {
+                let after_pound = matches!(
+                    renamed.last(),
+                    Some(proc_macro2::TokenTree::Punct(ref punct))
+                        if punct.as_char() == '#' || punct.as_char() == '!'
+                );
+                let mut stream = group.stream().into_iter().collect::<Vec<_>>();
+                if let (
+                    true,
+                    proc_macro2::Delimiter::Bracket,
+                    [proc_macro2::TokenTree::Ident(ref mut ident), proc_macro2::TokenTree::Group(ref args)],
+                ) = (after_pound, group.delimiter(), stream.as_mut_slice())
+                {
+                    if *ident == from && args.delimiter() == proc_macro2::Delimiter::Parenthesis {
+                        *ident = proc_macro2::Ident::new(to, ident.span());
+                    }
+                }
+                let stream = rename_attrs(stream.into_iter().collect(), from, to);
+                let mut renamed_group = proc_macro2::Group::new(group.delimiter(), stream);
+                renamed_group.set_span(group.span());
+                proc_macro2::TokenTree::Group(renamed_group)
+            }
+            token => token,
+        };
+        renamed.push(token);
     }
+    renamed.into_iter().collect()
 }
 
-fn expand_impl_item(args: &args::Args, mut impl_item: syn::ImplItem) -> proc_macro2::TokenStream {
-    transform_impl_item(args, AttrApplied::Directly, &mut impl_item);
+/// Traces every test in a `mod` of tests
+///
+/// Each function in the `mod` with a `#[test]`, `#[tokio::test]`, or `#[async_std::test]` attribute
+/// is traced with `timing`, and the other items (like helper functions) are left as they are. By
+/// default only failing tests are printed, like with `deferred`, so a passing run stays quiet and a
+/// failing test prints its arguments, panic or `Err`, and how long it ran:
+/// ```ignore
+/// #[trace::trace_all_tests]
+/// mod tests {
+///     fn parse(s: &str) -> Result<u16, std::num::ParseIntError> {
+///         s.parse()
+///     }
+///
+///     #[test]
+///     fn parses_port() -> Result<(), std::num::ParseIntError> {
+///         assert_eq!(parse("8080")?, 8080);
+///         Ok(())
+///     }
+/// }
+/// ```
+///
+/// The attribute takes the same arguments as [`#[trace]`](macro@trace), except for `enable` and
+/// `disable`, plus `verbose` to print every test instead of only the failing ones. Only the tests
+/// directly in the `mod` are traced, not the ones in `mod`s nested in it.
+#[proc_macro_attribute]
+pub fn trace_all_tests(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let raw_args = syn::parse_macro_input!(args as syn::AttributeArgs);
+    let (verbose, raw_args): (Vec<_>, Vec<_>) = raw_args.into_iter().partition(|arg| {
+        matches!(*arg, syn::NestedMeta::Meta(syn::Meta::Path(ref path)) if path.is_ident("verbose"))
+    });
+    let mut args = match args::Args::from_raw_args(raw_args) {
+        Ok(args) => args,
+        Err(errors) => {
+            return errors
+                .iter()
+                .map(syn::Error::to_compile_error)
+                .collect::<proc_macro2::TokenStream>()
+                .into()
+        }
+    };
 
-    match impl_item {
-        syn::ImplItem::Method(_) => impl_item.into_token_stream(),
-        _ => syn::Error::new_spanned(impl_item, "#[trace] is not supported for this impl item")
-            .to_compile_error(),
+    let item_mod = match syn::ItemMod::parse.parse(input.clone()) {
+        Ok(item_mod) => item_mod,
+        Err(_) => {
+            let input = proc_macro2::TokenStream::from(input);
+            return syn::Error::new_spanned(
+                input,
+                "#[trace_all_tests] can only be applied to a `mod`",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+    if !matches!(args.filter, args::Filter::None) {
+        return syn::Error::new(
+            Span::call_site(),
+            "`enable` and `disable` can't be used with #[trace_all_tests], which traces the tests",
+        )
+        .to_compile_error()
+        .into();
+    }
+    if args.skip_tests {
+        return syn::Error::new(
+            Span::call_site(),
+            "`skip_tests` can't be used with #[trace_all_tests], which traces the tests",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let tests = item_mod
+        .content
+        .iter()
+        .flat_map(|(_, items)| items)
+        .filter_map(|item| match *item {
+            syn::Item::Fn(ref item_fn) if is_test(&item_fn.attrs) => {
+                Some(item_fn.sig.ident.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    args.filter = args::Filter::Enable(args::FilterNames {
+        idents: tests,
+        ..Default::default()
+    });
+    args.timing = args.timing_threshold.is_none();
+    if verbose.is_empty()
+        && args.deferred.is_none()
+        && args.timing_threshold.is_none()
+        && !args.errors_only
+        && args.pause.is_none()
+    {
+        args.deferred = Some(args::Deferred { threshold_ms: None });
+    }
+
+    let output = expand_item(&args, syn::Item::Mod(item_mod));
+    match args.cfg {
+        Some(ref predicate) => {
+            let input = proc_macro2::TokenStream::from(input);
+            quote! {
+                #[cfg(#predicate)]
+                #output
+                #[cfg(not(#predicate))]
+                #input
+            }
+            .into()
+        }
+        None => output.into(),
     }
 }
 
-fn transform_item(args: &args::Args, attr_applied: AttrApplied, item: &mut syn::Item) {
-    match *item {
-        syn::Item::Fn(ref mut item_fn) => transform_fn(args, attr_applied, item_fn),
-        syn::Item::Mod(ref mut item_mod) => transform_mod(args, attr_applied, item_mod),
-        syn::Item::Impl(ref mut item_impl) => transform_impl(args, attr_applied, item_impl),
-        _ => (),
+// the macro lines are printed with when no `writer` is given, which is `trace_runtime::print_line!`
+// with the `runtime` feature
+fn default_println() -> TokenStream {
+    if cfg!(feature = "runtime") {
+        quote!(::trace_runtime::print_line)
+    } else {
+        quote!(::std::println)
     }
 }
 
-fn transform_fn(args: &args::Args, attr_applied: AttrApplied, item_fn: &mut syn::ItemFn) {
-    item_fn.block = Box::new(construct_traced_block(
-        args,
-        attr_applied,
-        &item_fn.sig,
-        &item_fn.block,
-    ));
+// with the `runtime` feature, every `mod` uses the same `DEPTH` from `trace-runtime`, but depth
+// variables with other names are meant to be separate from it
+fn depth_decl(vis: &syn::Visibility, depth_var: &syn::Ident) -> TokenStream {
+    if cfg!(feature = "runtime") && depth_var == "DEPTH" {
+        quote! {
+            #[allow(unused_imports)]
+            #vis use ::trace_runtime::DEPTH;
+        }
+    } else {
+        quote! {
+            ::std::thread_local! {
+                #vis static #depth_var: ::std::cell::Cell<usize> = ::std::cell::Cell::new(0);
+            }
+        }
+    }
 }
 
-fn transform_mod(args: &args::Args, attr_applied: AttrApplied, item_mod: &mut syn::ItemMod) {
-    assert!(
-        (item_mod.content.is_some() && item_mod.semi.is_none())
-            || (item_mod.content.is_none() && item_mod.semi.is_some())
-    );
+// with `printer`, the depth variable is the only one traced functions need, and it's a counter
+// rather than a thread-local so it doesn't need `std`
+fn atomic_depth_decl(vis: &syn::Visibility, depth_var: &syn::Ident) -> TokenStream {
+    quote! {
+        #[allow(dead_code)]
+        #vis static #depth_var: ::core::sync::atomic::AtomicUsize =
+            ::core::sync::atomic::AtomicUsize::new(0);
+    }
+}
 
-    if item_mod.semi.is_some() {
-        unimplemented!();
+// the depth variable used by traced functions, which is looked up by name unless `depth_path` says
+// where it is
+fn depth_var_path(args: &args::Args) -> TokenStream {
+    match args.depth_path {
+        Some(ref path) => path.to_token_stream(),
+        None => args.depth_var.to_token_stream(),
     }
+}
 
-    if let Some((_, items)) = item_mod.content.as_mut() {
-        items.iter_mut().for_each(|item| {
-            if let AttrApplied::Directly = attr_applied {
-                match *item {
-                    syn::Item::Fn(syn::ItemFn {
-                        sig: syn::Signature { ref ident, .. },
-                        ..
-                    })
-                    | syn::Item::Mod(syn::ItemMod { ref ident, .. }) => match args.filter {
-                        args::Filter::Enable(ref idents) if !idents.contains(ident) => {
-                            return;
-                        }
-                        args::Filter::Disable(ref idents) if idents.contains(ident) => {
-                            return;
+// one of the other variables declared by `depth_var_decl`, which is looked up like the depth
+// variable: by name, or next to it if `depth_path` says where that is
+fn trace_var_path(args: &args::Args, name: &str) -> TokenStream {
+    let ident = syn::Ident::new(name, Span::call_site());
+    match args.depth_path {
+        Some(ref path) => {
+            let mut path = path.clone();
+            if let Some(segment) = path.segments.last_mut() {
+                segment.ident = ident;
+            }
+            path.to_token_stream()
+        }
+        None => ident.to_token_stream(),
+    }
+}
+
+// the names of the variables declared by `depth_var_decl`
+const DEPTH_VAR_NAMES: [&str; 21] = [
+    "DEPTH",
+    "TRACE_METRICS",
+    "TRACE_CORRELATION_ID",
+    "TRACE_SINK",
+    "TRACE_CALLS",
+    "TRACE_HIDDEN_CALLS",
+    "TRACE_REPEATS",
+    "TRACE_CURRENT_CALL",
+    "TRACE_START",
+    "TRACE_EVENTS",
+    "TRACE_STATS",
+    "TRACE_DROPPED_EVENTS",
+    "TRACE_DISABLED_GROUPS",
+    "TRACE_INSTANCE_IDS",
+    "TRACE_CALL_IDS",
+    "TRACE_FINGERPRINT",
+    "TRACE_COUNTS",
+    "TRACE_CALL_PATH",
+    "TRACE_BASELINE",
+    "TRACE_BUFFER",
+    "TRACE_FORMATTING",
+];
+
+// the (mostly thread-local) variables that traced functions need, declared by `init_depth_var!()` and in
+// traced `mod`s (which leave out the depth variable if it's declared elsewhere)
+fn depth_var_decl(vis: &syn::Visibility, depth_var: Option<&syn::Ident>) -> TokenStream {
+    // the depth variable is the only one every traced function can find, so it's also what writes
+    // the lines held back by `buffered` before another function prints one (see
+    // `flush_held_back_lines`), and what the outermost call writes the lines that are pending until
+    // it returns with (see `write_pending_lines`). `trace-runtime` does both itself for its `DEPTH`
+    let depth_decl = depth_var.map(|depth_var| {
+        if cfg!(feature = "runtime") && depth_var == "DEPTH" {
+            return depth_decl(vis, depth_var);
+        }
+        let flush_buffer = flush_buffer(&quote!(TRACE_BUFFER));
+        quote! {
+            #[doc(hidden)]
+            #vis struct TraceDepth(
+                ::std::cell::Cell<usize>,
+                ::std::cell::RefCell<::std::vec::Vec<fn()>>,
+            );
+            impl ::std::ops::Deref for TraceDepth {
+                type Target = ::std::cell::Cell<usize>;
+                fn deref(&self) -> &::std::cell::Cell<usize> {
+                    &self.0
+                }
+            }
+            impl TraceDepth {
+                #[allow(dead_code)]
+                #vis fn trace_flush_buffer(&self) {
+                    #flush_buffer
+                }
+                #[allow(dead_code)]
+                #vis fn trace_write_when_outermost_call_returns(&self, write: fn()) {
+                    let mut writes = ::std::cell::RefCell::borrow_mut(&self.1);
+                    if !::std::iter::Iterator::any(&mut writes.iter(), |w| *w as usize == write as usize) {
+                        writes.push(write);
+                    }
+                }
+                #[allow(dead_code)]
+                #vis fn trace_write_pending_lines(&self) {
+                    for write in ::std::cell::RefCell::take(&self.1) {
+                        write();
+                    }
+                }
+            }
+            ::std::thread_local! {
+                #vis static #depth_var: TraceDepth = TraceDepth(
+                    ::std::cell::Cell::new(0),
+                    ::std::cell::RefCell::new(::std::vec::Vec::new()),
+                );
+            }
+        }
+    });
+    quote! {
+        #depth_decl
+        ::std::thread_local! {
+            #[allow(dead_code)]
+            #vis static TRACE_METRICS: ::std::cell::RefCell<
+                ::std::collections::HashMap<&'static str, i64>,
+            > = ::std::cell::RefCell::new(::std::collections::HashMap::new());
+            #[allow(dead_code)]
+            #vis static TRACE_CORRELATION_ID: ::std::cell::RefCell<
+                ::std::option::Option<::std::string::String>,
+            > = ::std::cell::RefCell::new(::std::option::Option::None);
+            #[allow(dead_code)]
+            #vis static TRACE_SINK: ::std::cell::RefCell<::std::option::Option<::std::string::String>> =
+                ::std::cell::RefCell::new(::std::option::Option::None);
+            #[allow(dead_code)]
+            #vis static TRACE_CALLS: ::std::cell::RefCell<
+                ::std::vec::Vec<(&'static str, ::std::vec::Vec<(&'static str, ::std::string::String)>)>,
+            > = ::std::cell::RefCell::new(::std::vec::Vec::new());
+            #[allow(dead_code)]
+            #vis static TRACE_HIDDEN_CALLS: ::std::cell::Cell<usize> = ::std::cell::Cell::new(0);
+            // for `collapse_repeats`: the lines of the last call (and the start and end of the line reporting
+            // its repeats) if nothing was printed in between, how many times it was repeated, the
+            // enter line held back from a call that may be a repeat, and the last line printed if
+            // it was an enter line
+            #[allow(dead_code, clippy::type_complexity)]
+            #vis static TRACE_REPEATS: ::std::cell::RefCell<(
+                ::std::option::Option<(
+                    ::std::string::String,
+                    ::std::string::String,
+                    (::std::string::String, ::std::string::String),
+                )>,
+                u64,
+                ::std::option::Option<::std::string::String>,
+                ::std::option::Option<::std::string::String>,
+            )> = ::std::cell::RefCell::new((
+                ::std::option::Option::None,
+                0,
+                ::std::option::Option::None,
+                ::std::option::Option::None,
+            ));
+            #[allow(dead_code)]
+            #vis static TRACE_CURRENT_CALL: ::std::cell::Cell<
+                ::std::option::Option<(&'static str, u64)>,
+            > = ::std::cell::Cell::new(::std::option::Option::None);
+            // for `stubs`: the names of the calls traced with it that are running, and the level of
+            // the one a `todo!()` or `unimplemented!()` is unwinding from along with its message
+            #[allow(dead_code, clippy::type_complexity)]
+            #vis static TRACE_CALL_PATH: ::std::cell::RefCell<(
+                ::std::vec::Vec<&'static str>,
+                ::std::option::Option<(usize, ::std::string::String)>,
+            )> = ::std::cell::RefCell::new((::std::vec::Vec::new(), ::std::option::Option::None));
+            // for `buffered`: the lines that haven't been written to stdout yet
+            #[allow(dead_code)]
+            #vis static TRACE_BUFFER: ::std::cell::RefCell<::std::string::String> =
+                ::std::cell::RefCell::new(::std::string::String::new());
+            // for `quiet_fmt`: whether a function traced with it is formatting its arguments or
+            // return value
+            #[allow(dead_code)]
+            #vis static TRACE_FORMATTING: ::std::cell::Cell<bool> = ::std::cell::Cell::new(false);
+        }
+        #[allow(dead_code)]
+        #vis static TRACE_START: ::std::sync::OnceLock<::std::time::Instant> =
+            ::std::sync::OnceLock::new();
+        #[allow(dead_code)]
+        #vis static TRACE_EVENTS: ::std::sync::atomic::AtomicU64 = ::std::sync::atomic::AtomicU64::new(0);
+        #[allow(dead_code)]
+        #vis static TRACE_STATS: ::std::sync::Mutex<
+            ::std::collections::BTreeMap<
+                ::std::string::String,
+                // the number of calls, and their total, shortest, and longest time
+                (u64, ::std::time::Duration, ::std::time::Duration, ::std::time::Duration),
+            >,
+        > = ::std::sync::Mutex::new(::std::collections::BTreeMap::new());
+        #[allow(dead_code)]
+        #vis static TRACE_DROPPED_EVENTS: ::std::sync::atomic::AtomicU64 =
+            ::std::sync::atomic::AtomicU64::new(0);
+        #[allow(dead_code)]
+        #vis static TRACE_DISABLED_GROUPS: ::std::sync::RwLock<::std::vec::Vec<&'static str>> =
+            ::std::sync::RwLock::new(::std::vec::Vec::new());
+        #[allow(dead_code)]
+        #vis static TRACE_INSTANCE_IDS: ::std::sync::Mutex<
+            ::std::option::Option<::std::collections::HashMap<(&'static str, usize), u64>>,
+        > = ::std::sync::Mutex::new(::std::option::Option::None);
+        #[allow(dead_code)]
+        #vis static TRACE_CALL_IDS: ::std::sync::atomic::AtomicU64 = ::std::sync::atomic::AtomicU64::new(0);
+        #[allow(dead_code)]
+        #vis static TRACE_FINGERPRINT: ::std::sync::atomic::AtomicU64 =
+            ::std::sync::atomic::AtomicU64::new(0xcbf2_9ce4_8422_2325);
+        #[allow(dead_code)]
+        #vis static TRACE_COUNTS: ::std::sync::Mutex<
+            ::std::vec::Vec<(&'static str, &'static ::std::sync::atomic::AtomicU64)>,
+        > = ::std::sync::Mutex::new(::std::vec::Vec::new());
+        // the average time per call of each function in the baseline loaded by `load_baseline!`
+        #[allow(dead_code)]
+        #vis static TRACE_BASELINE: ::std::sync::Mutex<
+            ::std::option::Option<
+                ::std::collections::BTreeMap<::std::string::String, ::std::time::Duration>,
+            >,
+        > = ::std::sync::Mutex::new(::std::option::Option::None);
+    }
+}
+
+/// Increments a named counter, which can be interpolated in `format_enter` and `format_exit`
+///
+/// `counter!("name")` increments the counter by one and `counter!("name", n)` increments it by `n`.
+/// The counter is interpolated with `{counter:name}`:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace(format_exit = "{r} after {counter:cache_hits} hits")]
+/// fn lookup(key: u32) -> u32 {
+///     if key % 2 == 0 {
+///         trace::counter!("cache_hits");
+///     }
+///     key
+/// }
+/// # fn main() {
+/// #     lookup(2);
+/// # }
+/// ```
+///
+/// Counters are kept per thread, alongside the `DEPTH` variable declared by
+/// [`init_depth_var!()`](macro@init_depth_var).
+#[proc_macro]
+pub fn counter(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let name = input.parse::<syn::LitStr>()?;
+        let by = if input.is_empty() {
+            quote!(1)
+        } else {
+            input.parse::<syn::Token![,]>()?;
+            input.parse::<syn::Expr>()?.into_token_stream()
+        };
+        Ok((name, by))
+    };
+    let output = match parser.parse(input) {
+        Ok((name, by)) => quote! {
+            TRACE_METRICS.with(|metrics| {
+                *::std::cell::RefCell::borrow_mut(metrics).entry(#name).or_insert(0) += (#by) as i64;
+            })
+        },
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Sets a named gauge, which can be interpolated in `format_enter` and `format_exit`
+///
+/// `gauge!("name", value)` sets the gauge to `value`, which is interpolated with `{gauge:name}`.
+/// Like [`counter!`](macro@counter), gauges are kept per thread.
+#[proc_macro]
+pub fn gauge(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let name = input.parse::<syn::LitStr>()?;
+        input.parse::<syn::Token![,]>()?;
+        let value = input.parse::<syn::Expr>()?;
+        Ok((name, value))
+    };
+    let output = match parser.parse(input) {
+        Ok((name, value)) => quote! {
+            TRACE_METRICS.with(|metrics| {
+                ::std::cell::RefCell::borrow_mut(metrics).insert(#name, (#value) as i64);
+            })
+        },
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Runs a closure with a correlation id that is included in the trace lines printed meanwhile
+///
+/// Every line printed by functions traced with `correlation_id` while the closure runs starts with
+/// the id, so the output of e.g. concurrently handled requests can be told apart:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace(correlation_id)]
+/// fn handle(path: &str) -> usize {
+///     path.len()
+/// }
+/// # fn main() {
+/// let len = trace::with_correlation_id!("req-1", || handle("/index.html"));
+/// # assert_eq!(len, 11);
+/// # }
+/// ```
+///
+/// ```text
+/// [req-1] [+] Entering handle(path = "/index.html")
+/// [req-1] [-] Exiting handle = 11
+/// ```
+///
+/// The id can be anything implementing `Display`, and is kept per thread, next to the `DEPTH`
+/// variable declared by [`init_depth_var!()`](macro@init_depth_var). The previous id is restored
+/// once the closure returns.
+#[proc_macro]
+pub fn with_correlation_id(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let id = input.parse::<syn::Expr>()?;
+        input.parse::<syn::Token![,]>()?;
+        let f = input.parse::<syn::Expr>()?;
+        Ok((id, f))
+    };
+    let output = match parser.parse(input) {
+        Ok((id, f)) => quote! {{
+            // restores the previous id even if the closure panics
+            struct CorrelationIdGuard(::std::option::Option<::std::string::String>);
+            impl ::std::ops::Drop for CorrelationIdGuard {
+                fn drop(&mut self) {
+                    let previous = self.0.take();
+                    TRACE_CORRELATION_ID.with(|id| *::std::cell::RefCell::borrow_mut(id) = previous);
+                }
+            }
+            let correlation_id_guard = CorrelationIdGuard(TRACE_CORRELATION_ID.with(|id| {
+                ::std::cell::RefCell::replace(
+                    id,
+                    ::std::option::Option::Some(::std::string::ToString::to_string(&(#id))),
+                )
+            }));
+            let result = (#f)();
+            ::std::mem::drop(correlation_id_guard);
+            result
+        }},
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Runs a closure while capturing the lines printed by functions traced with `scoped_sink`
+///
+/// The lines printed by the current thread while the closure runs are written to the sink, which
+/// can be anything implementing `std::fmt::Write`, once it returns, instead of where they'd usually
+/// go. The rest of the program keeps printing as usual:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace(scoped_sink)]
+/// fn step(n: u32) -> u32 {
+///     n + 1
+/// }
+/// # fn main() {
+/// let mut captured = String::new();
+/// let n = trace::with_sink!(&mut captured, || step(1));
+/// assert_eq!(captured, "[+] Entering step(n = 1)\n[-] Exiting step = 2\n");
+/// # assert_eq!(n, 2);
+/// # }
+/// ```
+///
+/// The captured lines are kept per thread, next to the `DEPTH` variable declared by
+/// [`init_depth_var!()`](macro@init_depth_var). If the closure panics, they're dropped.
+#[proc_macro]
+pub fn with_sink(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let sink = input.parse::<syn::Expr>()?;
+        input.parse::<syn::Token![,]>()?;
+        let f = input.parse::<syn::Expr>()?;
+        Ok((sink, f))
+    };
+    let output = match parser.parse(input) {
+        Ok((sink, f)) => {
+            // the locals can't be seen by the sink and closure expressions, which might use the
+            // same names
+            let [sink_local, sink_guard, result, previous, captured] =
+                ["sink", "sink_guard", "result", "previous", "captured"]
+                    .map(|name| syn::Ident::new(name, Span::mixed_site()));
+            quote! {{
+                let #sink_local = #sink;
+                // restores the previous sink even if the closure panics
+                struct SinkGuard(
+                    ::std::option::Option<::std::option::Option<::std::string::String>>,
+                );
+                impl ::std::ops::Drop for SinkGuard {
+                    fn drop(&mut self) {
+                        if let ::std::option::Option::Some(previous) = self.0.take() {
+                            TRACE_SINK.with(|sink| *::std::cell::RefCell::borrow_mut(sink) = previous);
+                        }
+                    }
+                }
+                let mut #sink_guard = SinkGuard(::std::option::Option::Some(TRACE_SINK.with(|sink| {
+                    ::std::cell::RefCell::replace(
+                        sink,
+                        ::std::option::Option::Some(::std::string::String::new()),
+                    )
+                })));
+                let #result = (#f)();
+                let #previous = ::std::option::Option::unwrap_or_default(#sink_guard.0.take());
+                let #captured =
+                    TRACE_SINK.with(|sink| ::std::cell::RefCell::replace(sink, #previous));
+                if let ::std::option::Option::Some(#captured) = #captured {
+                    let _ = ::std::fmt::Write::write_str(#sink_local, &#captured);
+                }
+                #result
+            }}
+        }
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Asserts that a function traced with `record_calls` was called, or called a number of times
+///
+/// Only the calls made by the current thread are counted, so each test sees its own calls:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace(record_calls)]
+/// fn fetch(id: u32) -> u32 {
+///     id * 2
+/// }
+/// # fn main() {
+/// fetch(1);
+/// fetch(2);
+/// trace::assert_called!(fetch);
+/// trace::assert_called!(fetch, times = 2);
+/// # }
+/// ```
+///
+/// The calls are kept next to the `DEPTH` variable declared by
+/// [`init_depth_var!()`](macro@init_depth_var) (or a traced `mod`), and found by name like it, so
+/// the functions and the assertions have to see the same one. Functions are told apart by their
+/// name only.
+#[proc_macro]
+pub fn assert_called(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let fn_ident = input.parse::<syn::Ident>()?;
+        let mut times = None;
+        if input.parse::<Option<syn::Token![,]>>()?.is_some() && !input.is_empty() {
+            let name = input.parse::<syn::Ident>()?;
+            if name != "times" {
+                return Err(syn::Error::new(name.span(), "expected `times`"));
+            }
+            input.parse::<syn::Token![=]>()?;
+            times = Some(input.parse::<syn::Expr>()?);
+            input.parse::<Option<syn::Token![,]>>()?;
+        }
+        Ok((fn_ident, times))
+    };
+    let output = match parser.parse(input) {
+        Ok((fn_ident, times)) => {
+            let fn_name = fn_ident.to_string();
+            let calls = syn::Ident::new("calls", Span::mixed_site());
+            let count_calls = quote! {
+                let #calls = TRACE_CALLS.with(|calls| {
+                    ::std::iter::Iterator::count(::std::iter::Iterator::filter(
+                        ::std::cell::RefCell::borrow(calls).iter(),
+                        |(name, _)| *name == #fn_name,
+                    ))
+                });
+            };
+            match times {
+                Some(times) => quote! {{
+                    #count_calls
+                    let times: usize = #times;
+                    ::std::assert!(
+                        #calls == times,
+                        "expected `{}` to be called {} times, but it was called {} times",
+                        #fn_name,
+                        times,
+                        #calls,
+                    );
+                }},
+                None => quote! {{
+                    #count_calls
+                    ::std::assert!(#calls > 0, "expected `{}` to be called, but it wasn't", #fn_name);
+                }},
+            }
+        }
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Asserts that a function traced with `record_calls` was called with the given arguments
+///
+/// The arguments are compared by how they're printed with `Debug`, so `1` matches any integer
+/// argument equal to 1, and arguments that aren't given can be anything. At least one call made
+/// by the current thread has to match all of them:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace(record_calls)]
+/// fn greet(name: &str, times: u8) {}
+/// # fn main() {
+/// greet("world", 2);
+/// trace::assert_called_with!(greet, name = "world");
+/// trace::assert_called_with!(greet, name = "world", times = 2);
+/// # }
+/// ```
+///
+/// Only the arguments that would be printed are recorded, so ones hidden with `disable` (or not
+/// listed in `enable`) can't be checked.
+#[proc_macro]
+pub fn assert_called_with(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let fn_ident = input.parse::<syn::Ident>()?;
+        let mut expected_args = vec![];
+        while input.parse::<Option<syn::Token![,]>>()?.is_some() && !input.is_empty() {
+            let arg_ident = input.parse::<syn::Ident>()?;
+            input.parse::<syn::Token![=]>()?;
+            expected_args.push((arg_ident, input.parse::<syn::Expr>()?));
+        }
+        Ok((fn_ident, expected_args))
+    };
+    let output = match parser.parse(input) {
+        Ok((fn_ident, expected_args)) => {
+            let fn_name = fn_ident.to_string();
+            let expected_args = expected_args.iter().map(|(arg_ident, value)| {
+                let arg_name = arg_ident.to_string();
+                quote!((#arg_name, ::std::format!("{:?}", #value)))
+            });
+            let [expected, calls] =
+                ["expected", "calls"].map(|name| syn::Ident::new(name, Span::mixed_site()));
+            quote! {{
+                let #expected: ::std::vec::Vec<(&str, ::std::string::String)> =
+                    ::std::vec![#(#expected_args),*];
+                let #calls = TRACE_CALLS.with(|calls| {
+                    ::std::iter::Iterator::collect::<::std::vec::Vec<_>>(
+                        ::std::iter::Iterator::map(
+                            ::std::iter::Iterator::filter(
+                                ::std::cell::RefCell::borrow(calls).iter(),
+                                |(name, _)| *name == #fn_name,
+                            ),
+                            |(_, args)| ::std::clone::Clone::clone(args),
+                        ),
+                    )
+                });
+                let matched = ::std::iter::Iterator::any(&mut #calls.iter(), |args| {
+                    ::std::iter::Iterator::all(&mut #expected.iter(), |expected_arg| {
+                        args.contains(expected_arg)
+                    })
+                });
+                if !matched {
+                    let format_call_args = |args: &[(&str, ::std::string::String)]| {
+                        ::std::iter::Iterator::collect::<::std::vec::Vec<_>>(
+                            ::std::iter::Iterator::map(args.iter(), |(name, value)| {
+                                ::std::format!("{} = {}", name, value)
+                            }),
+                        )
+                        .join(", ")
+                    };
+                    let calls = ::std::iter::Iterator::collect::<::std::vec::Vec<_>>(
+                        ::std::iter::Iterator::map(#calls.iter(), |args| {
+                            ::std::format!("{}({})", #fn_name, format_call_args(args))
+                        }),
+                    );
+                    ::std::panic!(
+                        "expected `{}` to be called with {}, but its calls were: [{}]",
+                        #fn_name,
+                        format_call_args(&#expected),
+                        calls.join(", "),
+                    );
+                }
+            }}
+        }
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Asserts that functions traced with `record_calls` were called in the given order
+///
+/// Other calls may come in between, so this only checks that each function was called at some
+/// point after the one before it was:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// // a traced `mod` declares its own variables, so it's pointed at the ones checked here
+/// #[trace(record_calls, depth_path = "crate::DEPTH")]
+/// mod client {
+///     pub(super) fn init() {}
+///     pub(super) fn connect() {}
+///     pub(super) fn send(_byte: u8) {}
+/// }
+/// # fn main() {
+/// client::init();
+/// client::connect();
+/// client::send(1);
+/// client::send(2);
+/// trace::assert_call_order!(init, connect, send);
+/// trace::assert_call_order!(init, send, send);
+/// # }
+/// ```
+///
+/// As with [`assert_called!`](macro@assert_called), only the calls made by the current thread are
+/// looked at, in the order they were entered.
+#[proc_macro]
+pub fn assert_call_order(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = syn::punctuated::Punctuated::<syn::Ident, syn::Token![,]>::parse_terminated;
+    let output = match parser.parse(input) {
+        Ok(fn_idents) if fn_idents.is_empty() => syn::Error::new(
+            Span::call_site(),
+            "expected the names of the functions in the order they're called",
+        )
+        .to_compile_error(),
+        Ok(fn_idents) => {
+            let fn_names = fn_idents.iter().map(|fn_ident| fn_ident.to_string());
+            let expected = fn_idents
+                .iter()
+                .map(|fn_ident| format!("`{}`", fn_ident))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let [calls, remaining] =
+                ["calls", "remaining"].map(|name| syn::Ident::new(name, Span::mixed_site()));
+            quote! {{
+                let #calls = TRACE_CALLS.with(|calls| {
+                    ::std::iter::Iterator::collect::<::std::vec::Vec<&'static str>>(
+                        ::std::iter::Iterator::map(
+                            ::std::cell::RefCell::borrow(calls).iter(),
+                            |(name, _)| *name,
+                        ),
+                    )
+                });
+                // each function is looked for after the call matched for the one before it
+                let mut #remaining = #calls.iter();
+                let in_order = ::std::iter::Iterator::all(&mut [#(#fn_names),*].iter(), |fn_name| {
+                    ::std::iter::Iterator::any(&mut #remaining, |name| name == fn_name)
+                });
+                ::std::assert!(
+                    in_order,
+                    "expected {} to be called in that order, but the calls were: [{}]",
+                    #expected,
+                    #calls.join(", "),
+                );
+            }}
+        }
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Stops printing the functions in a group given to the `groups` argument of `#[trace]`
+///
+/// Groups are enabled until they're disabled, and can be enabled again with
+/// [`enable_group!`](macro@enable_group):
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace(groups(io(read, write), math(add)))]
+/// mod store {
+///     pub(super) fn read(key: u32) -> u32 {
+///         super::store::add(key, 1)
+///     }
+///
+///     pub(super) fn write(_key: u32, _value: u32) {}
+///
+///     pub(super) fn add(a: u32, b: u32) -> u32 {
+///         a + b
+///     }
+/// }
+/// # fn main() {
+/// trace::disable_group!("io");
+/// store::write(1, store::read(1));
+/// trace::enable_group!("io");
+/// # }
+/// ```
+///
+/// ```text
+///  [+] Entering add(a = 1, b = 1)
+///  [-] Exiting add = 2
+/// ```
+///
+/// Which groups are disabled is shared between threads, and kept next to the `DEPTH` variable
+/// declared by [`init_depth_var!()`](macro@init_depth_var) (or a traced `mod`), and found by name
+/// like it, so the functions and the macro have to see the same one. A function that's in several
+/// groups is printed only while none of them are disabled.
+#[proc_macro]
+pub fn disable_group(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let output = match syn::parse::<syn::LitStr>(input) {
+        Ok(group) => quote! {{
+            let mut disabled_groups = ::std::result::Result::unwrap_or_else(
+                TRACE_DISABLED_GROUPS.write(),
+                ::std::sync::PoisonError::into_inner,
+            );
+            if !disabled_groups.contains(&#group) {
+                disabled_groups.push(#group);
+            }
+        }},
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Prints the functions in a group disabled with [`disable_group!`](macro@disable_group) again
+#[proc_macro]
+pub fn enable_group(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let output = match syn::parse::<syn::LitStr>(input) {
+        Ok(group) => quote! {{
+            let mut disabled_groups = ::std::result::Result::unwrap_or_else(
+                TRACE_DISABLED_GROUPS.write(),
+                ::std::sync::PoisonError::into_inner,
+            );
+            disabled_groups.retain(|disabled_group| *disabled_group != #group);
+        }},
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// The number of trace lines that couldn't be written, as a `u64`
+///
+/// Only lines of functions traced with `on_error` are counted. The count is shared between threads
+/// and kept next to the `DEPTH` variable declared by [`init_depth_var!()`](macro@init_depth_var)
+/// (or a traced `mod`), and found by name like it.
+#[proc_macro]
+pub fn dropped_events(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let output = if input.is_empty() {
+        quote!(TRACE_DROPPED_EVENTS.load(::std::sync::atomic::Ordering::Relaxed))
+    } else {
+        let input2 = proc_macro2::TokenStream::from(input);
+        syn::Error::new_spanned(input2, "`dropped_events` takes no arguments").to_compile_error()
+    };
+
+    output.into()
+}
+
+/// The id of the traced call that's currently running, as an `Option<u64>`
+///
+/// Only functions whose prefixes use the `{call_id}` (or `{caller_fn}`) placeholder keep track of
+/// their calls, which are numbered from 1 in the order they start. This is the id of the innermost
+/// one of them that's running on the current thread (or `None` if there isn't one), so manual log
+/// lines can be matched up with the trace lines of the call they were printed in:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace(prefix_enter = "[+] #{call_id}", prefix_exit = "[-] #{call_id}")]
+/// fn handle(path: &str) -> usize {
+///     println!("#{:?} serving {}", trace::current_call_id!(), path);
+///     path.len()
+/// }
+/// # fn main() {
+/// #     handle("/index.html");
+/// # }
+/// ```
+///
+/// ```text
+/// [+] #1 Entering handle(path = "/index.html")
+/// #Some(1) serving /index.html
+/// [-] #1 Exiting handle = 11
+/// ```
+///
+/// Like the current function used for `{caller_fn}`, it's kept alongside the `DEPTH` variable
+/// declared by [`init_depth_var!()`](macro@init_depth_var).
+#[proc_macro]
+pub fn current_call_id(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let output = if input.is_empty() {
+        quote! {
+            TRACE_CURRENT_CALL.with(|c| {
+                ::std::option::Option::map(c.get(), |(_, call_id)| call_id)
+            })
+        }
+    } else {
+        let input2 = proc_macro2::TokenStream::from(input);
+        syn::Error::new_spanned(input2, "`current_call_id` takes no arguments").to_compile_error()
+    };
+
+    output.into()
+}
+
+/// The current depth, as a `usize`
+///
+/// Inside a traced function, this is the depth the functions it calls are printed at (and the one
+/// [`section!`](macro@section) uses), which is one more than the `{depth}` of its own lines.
+/// `current_depth!(MY_DEPTH)` reads the depth variable declared by `init_depth_var!(MY_DEPTH)`
+/// instead of `DEPTH`, and it can be given a path like `current_depth!(crate::DEPTH)` too.
+#[proc_macro]
+pub fn current_depth(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        if input.is_empty() {
+            Ok(syn::Ident::new("DEPTH", Span::call_site()).into())
+        } else {
+            input.parse::<syn::Path>()
+        }
+    };
+    let output = match parser.parse(input) {
+        Ok(depth_var) => quote!(#depth_var.with(|d| d.get())),
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// A hash of the calls made so far by functions traced with `fingerprint`, as a `u64`
+///
+/// The hash covers the names of the functions in the order they were called (and with
+/// `fingerprint = "args"`, the values of their printed arguments), so two
+/// runs that made the same calls have the same fingerprint, on any platform and with any version of
+/// Rust. This lets a script bisecting a regression tell whether a build behaves differently
+/// without comparing huge traces:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace(fingerprint = "args")]
+/// fn fib(n: u32) -> u32 {
+///     if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+/// }
+///
+/// # fn main() {
+/// fib(5);
+/// println!("fingerprint: {:016x}", trace::fingerprint!());
+/// # }
+/// ```
+///
+/// Calls are hashed whether they're printed or not. The hash is shared between threads, so calls
+/// made on several threads at once only hash the same between runs if they're always made in the
+/// same order. It's kept next to the `DEPTH` variable declared by
+/// [`init_depth_var!()`](macro@init_depth_var) (or a traced `mod`), and found by name like it.
+#[proc_macro]
+pub fn fingerprint(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let output = if input.is_empty() {
+        quote!(TRACE_FINGERPRINT.load(::std::sync::atomic::Ordering::Relaxed))
+    } else {
+        let input2 = proc_macro2::TokenStream::from(input);
+        syn::Error::new_spanned(input2, "`fingerprint` takes no arguments").to_compile_error()
+    };
+
+    output.into()
+}
+
+/// Prints the statistics collected for functions traced with `stats`
+///
+/// Every function (or method of each type) that was called gets a row in a table with the number
+/// of calls and the total, average, and longest time they took. The functions that took the most
+/// time in total come first, which makes hot (e.g. recursive) paths easy to spot without a profiler:
+/// ```text
+/// [stats] function                                       calls        total          avg          max
+/// [stats] my_crate::Cache<alloc::string::String>::get        2        2.1µs       1.05µs        1.5µs
+/// [stats] my_crate::Cache<u64>::get                          1        800ns        800ns        800ns
+/// ```
+///
+/// Methods of generic `impl`s and `trait`s traced with `stats` are counted separately for each
+/// type they're called on, and generic functions for each instantiation of their type parameters.
+/// Functions traced with `count_only` come last, with their calls but no times. With a baseline
+/// loaded by [`load_baseline!`](macro@load_baseline), every row ends with how the average time per
+/// call compares to it.
+/// The statistics are shared between threads and kept next to the `DEPTH` variable declared by
+/// [`init_depth_var!()`](macro@init_depth_var) (or a traced `mod`), and found by name like it, so
+/// a traced `mod` prints its own (see `examples/example_stats_mod.rs`).
+#[proc_macro]
+pub fn print_stats(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let output = if input.is_empty() {
+        let println = default_println();
+        let stats_table = stats_table(true);
+        quote! {
+            for line in #stats_table {
+                #println!("{}", line);
+            }
+        }
+    } else {
+        let input2 = proc_macro2::TokenStream::from(input);
+        syn::Error::new_spanned(input2, "`print_stats` takes no arguments").to_compile_error()
+    };
+
+    output.into()
+}
+
+/// Writes the statistics collected for functions traced with `stats` to a file, as a baseline for
+/// later runs
+///
+/// The file gets the same table [`print_stats!`](macro@print_stats) prints (without comparing it
+/// to a baseline), and the macro evaluates to the `std::io::Result<()>` of writing it. A later
+/// run can load it with [`load_baseline!`](macro@load_baseline).
+#[proc_macro]
+pub fn save_stats(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let output = match syn::parse::<syn::Expr>(input) {
+        Ok(path) => {
+            let stats_table = stats_table(false);
+            quote! {{
+                let mut table = #stats_table.join("\n");
+                table.push('\n');
+                ::std::fs::write(#path, table)
+            }}
+        }
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+/// Loads the statistics saved by a previous run, for [`print_stats!`](macro@print_stats) to compare
+/// the current ones to
+///
+/// Once a baseline is loaded, every row printed by `print_stats!` ends with how much slower or
+/// faster the function got on average per call, so a timing regression shows up in the output of
+/// e.g. a CI job:
+/// ```text
+/// [stats] function   calls        total          avg          max  vs baseline
+/// [stats] parse        100       3.21ms       32.1µs       80.2µs  +35% slower
+/// [stats] tokenize     100        1.1ms         11µs         20µs   -8% faster
+/// [stats] validate       3        9.5µs        3.1µs          4µs          new
+/// ```
+///
+/// The baseline is a file written by [`save_stats!`](macro@save_stats) (or the output of
+/// `print_stats!` from a run without a baseline), and the macro evaluates to the
+/// `std::io::Result<()>` of reading it. Lines that aren't rows of the table are skipped.
+/// Averages are compared as they were printed, so differences below the precision of the printed
+/// durations don't show up.
+#[proc_macro]
+pub fn load_baseline(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let output = match syn::parse::<syn::Expr>(input) {
+        Ok(path) => quote! {
+            ::std::fs::read_to_string(#path).map(|table| {
+                // durations are printed with `Debug`, e.g. `1.05µs`
+                let parse_duration = |duration: &str| {
+                    let split = duration.find(|c: char| c.is_alphabetic())?;
+                    let value = duration[..split].parse::<f64>().ok()?;
+                    let unit = match &duration[split..] {
+                        "s" => 1.0,
+                        "ms" => 1e-3,
+                        "µs" => 1e-6,
+                        "ns" => 1e-9,
+                        _ => return ::std::option::Option::None,
+                    };
+                    ::std::time::Duration::try_from_secs_f64(value * unit).ok()
+                };
+                let mut baseline = ::std::collections::BTreeMap::new();
+                for line in table.lines() {
+                    let ::std::option::Option::Some(row) = line.strip_prefix(#STATS_PREFIX) else {
+                        continue;
+                    };
+                    // names can have spaces in them (e.g. tuples as type parameters), but the
+                    // columns after them can't
+                    let mut columns = ::std::iter::Iterator::rev(row.split_whitespace());
+                    let (
+                        ::std::option::Option::Some(_max),
+                        ::std::option::Option::Some(avg),
+                        ::std::option::Option::Some(_total),
+                        ::std::option::Option::Some(calls),
+                    ) = (
+                        ::std::iter::Iterator::next(&mut columns),
+                        ::std::iter::Iterator::next(&mut columns),
+                        ::std::iter::Iterator::next(&mut columns),
+                        ::std::iter::Iterator::next(&mut columns),
+                    )
+                    else {
+                        continue;
+                    };
+                    if calls.parse::<u64>().is_err() {
+                        continue;
+                    }
+                    if let ::std::option::Option::Some(avg) = parse_duration(avg) {
+                        let name = ::std::iter::Iterator::collect::<::std::vec::Vec<_>>(
+                            ::std::iter::Iterator::rev(columns),
+                        )
+                        .join(" ");
+                        baseline.insert(name, avg);
+                    }
+                }
+                *::std::result::Result::unwrap_or_else(
+                    TRACE_BASELINE.lock(),
+                    ::std::sync::PoisonError::into_inner,
+                ) = ::std::option::Option::Some(baseline);
+            })
+        },
+        Err(e) => e.to_compile_error(),
+    };
+
+    output.into()
+}
+
+// the lines of the table printed by `print_stats!` and written by `save_stats!`, with a column
+// comparing the averages to the loaded baseline (if there is one) when `with_baseline` is set
+fn stats_table(with_baseline: bool) -> TokenStream {
+    let baseline = if with_baseline {
+        quote! {
+            ::std::clone::Clone::clone(&*::std::result::Result::unwrap_or_else(
+                TRACE_BASELINE.lock(),
+                ::std::sync::PoisonError::into_inner,
+            ))
+        }
+    } else {
+        quote!(
+            ::std::option::Option::<
+                ::std::collections::BTreeMap<::std::string::String, ::std::time::Duration>,
+            >::None
+        )
+    };
+    quote! {{
+        let stats = ::std::result::Result::unwrap_or_else(
+            TRACE_STATS.lock(),
+            ::std::sync::PoisonError::into_inner,
+        );
+        let baseline = #baseline;
+        // the functions that took the most time in total come first, so hot paths stand out
+        let mut rows = ::std::iter::Iterator::collect::<::std::vec::Vec<_>>(stats.iter());
+        rows.sort_by(|(name_a, (_, total_a, _, _)), (name_b, (_, total_b, _, _))| {
+            ::std::cmp::Ord::cmp(total_b, total_a).then_with(|| ::std::cmp::Ord::cmp(name_a, name_b))
+        });
+        // functions traced with `count_only` aren't timed, so they come last, by their calls
+        let mut counts = ::std::iter::Iterator::collect::<::std::vec::Vec<_>>(
+            ::std::iter::Iterator::map(
+                ::std::result::Result::unwrap_or_else(
+                    TRACE_COUNTS.lock(),
+                    ::std::sync::PoisonError::into_inner,
+                )
+                .iter(),
+                |(name, calls)| (*name, calls.load(::std::sync::atomic::Ordering::Relaxed)),
+            ),
+        );
+        counts.sort_by(|(name_a, calls_a), (name_b, calls_b)| {
+            ::std::cmp::Ord::cmp(calls_b, calls_a).then_with(|| ::std::cmp::Ord::cmp(name_a, name_b))
+        });
+        let name_width = ::std::iter::Iterator::fold(rows.iter(), "function".len(), |width, (name, _)| {
+            ::std::cmp::Ord::max(width, name.len())
+        });
+        let name_width = ::std::iter::Iterator::fold(counts.iter(), name_width, |width, (name, _)| {
+            ::std::cmp::Ord::max(width, name.len())
+        });
+        // how the average time per call compares to the baseline's, in whole percents
+        let versus_baseline = |name: &str, avg: ::std::time::Duration| match baseline {
+            ::std::option::Option::None => ::std::string::String::new(),
+            ::std::option::Option::Some(ref baseline) => {
+                let change = match baseline.get(name) {
+                    ::std::option::Option::Some(baseline_avg) if !baseline_avg.is_zero() => {
+                        let percent = ((avg.as_secs_f64() / baseline_avg.as_secs_f64() - 1.0) * 100.0).round();
+                        if percent > 0.0 {
+                            ::std::format!("+{}% slower", percent)
+                        } else if percent < 0.0 {
+                            ::std::format!("{}% faster", percent)
+                        } else {
+                            ::std::string::ToString::to_string("unchanged")
+                        }
+                    }
+                    ::std::option::Option::Some(_) => ::std::string::ToString::to_string("-"),
+                    ::std::option::Option::None => ::std::string::ToString::to_string("new"),
+                };
+                ::std::format!(" {:>12}", change)
+            }
+        };
+        let mut lines = ::std::vec![::std::format!(
+            "{} {:<name_width$} {:>8} {:>12} {:>12} {:>12}{}",
+            #STATS_PREFIX, "function", "calls", "total", "avg", "max",
+            if baseline.is_some() {
+                ::std::format!(" {:>12}", "vs baseline")
+            } else {
+                ::std::string::String::new()
+            },
+            name_width = name_width
+        )];
+        for (name, (calls, total, _, max)) in rows {
+            let avg = *total / ::std::cmp::Ord::max(*calls, 1) as u32;
+            lines.push(::std::format!(
+                "{} {:<name_width$} {:>8} {:>12} {:>12} {:>12}{}",
+                #STATS_PREFIX,
+                name,
+                calls,
+                ::std::format!("{:?}", total),
+                ::std::format!("{:?}", avg),
+                ::std::format!("{:?}", max),
+                versus_baseline(name, avg),
+                name_width = name_width
+            ));
+        }
+        for (name, calls) in counts {
+            lines.push(::std::format!(
+                "{} {:<name_width$} {:>8} {:>12} {:>12} {:>12}{}",
+                #STATS_PREFIX, name, calls, "-", "-", "-",
+                if baseline.is_some() {
+                    ::std::format!(" {:>12}", "-")
+                } else {
+                    ::std::string::String::new()
+                },
+                name_width = name_width
+            ));
+        }
+        lines
+    }}
+}
+
+/// The report of the functions traced with `bench`, as a `String`
+///
+/// Every function (or method of each type) that was called gets a row with the number of calls
+/// and the shortest, average, longest, and total time they took, the functions that took the most
+/// time in total first:
+/// ```text
+/// [bench] function      calls          min          avg          max        total
+/// [bench] parse           100       20.1µs       32.1µs       80.2µs       3.21ms
+/// [bench] tokenize        100        9.8µs         11µs         20µs        1.1ms
+/// ```
+///
+/// The report is printed automatically when `main` returns if `main` is traced with `bench` too.
+/// Functions traced with `stats` are included, and like with [`print_stats!`](macro@print_stats),
+/// the statistics are the ones next to the `DEPTH` variable in scope.
+#[proc_macro]
+pub fn bench_report(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let output = if input.is_empty() {
+        let bench_table = bench_table(&quote!(TRACE_STATS));
+        quote! {{
+            let mut report = #bench_table.join("\n");
+            report.push('\n');
+            report
+        }}
+    } else {
+        let input2 = proc_macro2::TokenStream::from(input);
+        syn::Error::new_spanned(input2, "`bench_report` takes no arguments").to_compile_error()
+    };
+
+    output.into()
+}
+
+// the lines of the report returned by `bench_report!`, from the statistics in `trace_stats`
+fn bench_table(trace_stats: &TokenStream) -> TokenStream {
+    quote! {{
+        let stats = ::std::result::Result::unwrap_or_else(
+            #trace_stats.lock(),
+            ::std::sync::PoisonError::into_inner,
+        );
+        let mut rows = ::std::iter::Iterator::collect::<::std::vec::Vec<_>>(stats.iter());
+        rows.sort_by(|(name_a, (_, total_a, _, _)), (name_b, (_, total_b, _, _))| {
+            ::std::cmp::Ord::cmp(total_b, total_a).then_with(|| ::std::cmp::Ord::cmp(name_a, name_b))
+        });
+        let name_width = ::std::iter::Iterator::fold(rows.iter(), "function".len(), |width, (name, _)| {
+            ::std::cmp::Ord::max(width, name.len())
+        });
+        let mut lines = ::std::vec![::std::format!(
+            "{} {:<name_width$} {:>8} {:>12} {:>12} {:>12} {:>12}",
+            #BENCH_PREFIX, "function", "calls", "min", "avg", "max", "total",
+            name_width = name_width
+        )];
+        for (name, (calls, total, min, max)) in rows {
+            let avg = *total / ::std::cmp::Ord::max(*calls, 1) as u32;
+            lines.push(::std::format!(
+                "{} {:<name_width$} {:>8} {:>12} {:>12} {:>12} {:>12}",
+                #BENCH_PREFIX,
+                name,
+                calls,
+                ::std::format!("{:?}", min),
+                ::std::format!("{:?}", avg),
+                ::std::format!("{:?}", max),
+                ::std::format!("{:?}", total),
+                name_width = name_width
+            ));
+        }
+        lines
+    }}
+}
+
+/// Prints a marker line for the start of a named phase of a function, at the current depth
+///
+/// This is useful for subdividing large traced functions into phases that show up inline with the
+/// rest of the tracing output:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace]
+/// fn build() {
+///     trace::section!("parse");
+///     // ...
+///     trace::section!("codegen", timed);
+///     // ...
+/// }
+/// # fn main() {
+/// #     build();
+/// # }
+/// ```
+///
+/// With `timed`, another line is printed when the enclosing scope ends, with the time spent since
+/// the start of the section:
+/// ```text
+/// [+] Entering build()
+///  [*] Section parse
+///  [*] Section codegen
+///  [*] End of section codegen after 1.2µs
+/// [-] Exiting build = ()
+/// ```
+#[proc_macro]
+pub fn section(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let name = input.parse::<syn::LitStr>()?;
+        let timed = if input.is_empty() {
+            false
+        } else {
+            input.parse::<syn::Token![,]>()?;
+            let timed = input.parse::<syn::Ident>()?;
+            if timed != "timed" {
+                return Err(syn::Error::new_spanned(timed, "expected `timed`"));
+            }
+            true
+        };
+        Ok((name, timed))
+    };
+    let (name, timed) = match parser.parse(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let println = default_println();
+    let flush_held_back_lines = flush_held_back_lines(&quote!(DEPTH));
+    let section_format = format!("{{:depth$}}{} Section {}", SECTION_PREFIX, name.value());
+    let print_section = quote! {
+        #flush_held_back_lines
+        #println!(#section_format, "", depth = DEPTH.with(|d| d.get()));
+    };
+
+    let output = if timed {
+        let end_format = format!(
+            "{{:depth$}}{} End of section {} after {{:?}}",
+            SECTION_PREFIX,
+            name.value()
+        );
+        // the guard is bound to a name that can't be seen by the caller, and prints the end of the
+        // section once it's dropped at the end of the caller's scope
+        let guard = syn::Ident::new("section_guard", Span::mixed_site());
+        quote! {
+            #print_section
+            let #guard = {
+                struct SectionGuard(::std::time::Instant);
+                impl ::std::ops::Drop for SectionGuard {
+                    fn drop(&mut self) {
+                        #flush_held_back_lines
+                        #println!(
+                            #end_format,
+                            "",
+                            ::std::time::Instant::elapsed(&self.0),
+                            depth = DEPTH.with(|d| d.get())
+                        );
+                    }
+                }
+                SectionGuard(::std::time::Instant::now())
+            };
+        }
+    } else {
+        print_section
+    };
+
+    output.into()
+}
+
+/// Traces a single expression instead of a whole function
+///
+/// The expression is printed before it's evaluated, and its value afterwards, at the current
+/// depth. Traced functions called while evaluating it are indented one level deeper, and the
+/// value is returned as is:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace]
+/// fn square(x: i32) -> i32 {
+///     x * x
+/// }
+/// # fn main() {
+/// let x = 3;
+/// let y = trace::trace_expr!(square(x) + 1);
+/// # assert_eq!(y, 10);
+/// # }
+/// ```
+///
+/// ```text
+/// [+] Evaluating square(x) + 1
+///  [+] Entering square(x = 3)
+///  [-] Exiting square = 9
+/// [-] square(x) + 1 = 10
+/// ```
+///
+/// The value has to implement `Debug`. `prefix_enter`, `prefix_exit`, `pretty`, `indent`, and
+/// `indent_width` can be given after the expression like they're given to `#[trace]`, e.g.
+/// `trace::trace_expr!(square(x), prefix_enter = "[IN]", pretty)`, and the expression is
+/// available as `{fn}` in the prefixes.
+#[proc_macro]
+pub fn trace_expr(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let expr = input.parse::<syn::Expr>()?;
+        let raw_args = if input.is_empty() {
+            syn::AttributeArgs::new()
+        } else {
+            input.parse::<syn::Token![,]>()?;
+            input
+                .parse_terminated::<_, syn::Token![,]>(syn::NestedMeta::parse)?
+                .into_iter()
+                .collect()
+        };
+        Ok((expr, raw_args))
+    };
+    let (expr, raw_args) = match parser.parse(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    // everything else configures how functions are traced, which doesn't apply to expressions
+    let unsupported_args = raw_args
+        .iter()
+        .filter_map(|nested_meta| match *nested_meta {
+            syn::NestedMeta::Meta(ref meta)
+                if ["prefix_enter", "prefix_exit", "pretty", "indent", "indent_width"]
+                    .iter()
+                    .any(|name| meta.path().is_ident(name)) =>
+            {
+                None
+            }
+            _ => Some(syn::Error::new_spanned(
+                nested_meta,
+                "`trace_expr!` only takes `prefix_enter`, `prefix_exit`, `pretty`, `indent`, and `indent_width`",
+            )),
+        })
+        .collect::<Vec<_>>();
+    if !unsupported_args.is_empty() {
+        return unsupported_args
+            .iter()
+            .map(syn::Error::to_compile_error)
+            .collect::<proc_macro2::TokenStream>()
+            .into();
+    }
+    let args = match args::Args::from_raw_args(raw_args) {
+        Ok(args) => args,
+        Err(errors) => {
+            return errors
+                .iter()
+                .map(syn::Error::to_compile_error)
+                .collect::<proc_macro2::TokenStream>()
+                .into()
+        }
+    };
+
+    // the `{fn}` placeholder of the prefixes is filled in with the expression, which `stringify!`
+    // prints closer to how it was written than the tokens' own `Display` does
+    let placeholder = syn::Ident::new("trace_expr_source", Span::call_site());
+    let (prefix_enter, enter_prefix_args) = match parse_prefix(&args.prefix_enter, &placeholder) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let (prefix_exit, exit_prefix_args) = match parse_prefix(&args.prefix_exit, &placeholder) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let fill_placeholder = |prefix: String| {
+        prefix
+            .split(&placeholder.to_string())
+            .map(|part| part.to_string())
+            .collect::<Vec<_>>()
+            .join("{source}")
+    };
+    let (prefix_enter, prefix_exit) = (
+        fill_placeholder(prefix_enter),
+        fill_placeholder(prefix_exit),
+    );
+    let value_format = if args.pretty { "{:#?}" } else { "{:?}" };
+    let entering_format = format!("{{:depth$}}{} Evaluating {{source}}", prefix_enter);
+    let exiting_format = format!("{{:depth$}}{} {{source}} = {}", prefix_exit, value_format);
+
+    // `{caller_fn}` and `{call_id}` are those of the traced call running when the expression is
+    // evaluated
+    let caller_fn = |prefix: &str| {
+        let mut current_call = quote!();
+        if prefix.contains("{caller_fn}") || prefix.contains("{call_id}") {
+            current_call.extend(quote!(let fn_caller = TRACE_CURRENT_CALL.with(|c| c.get());));
+        }
+        if prefix.contains("{call_id}") {
+            current_call.extend(quote! {
+                let fn_call_id = match fn_caller {
+                    ::std::option::Option::Some((_, call_id)) => ::std::string::ToString::to_string(&call_id),
+                    ::std::option::Option::None => ::std::string::ToString::to_string("<none>"),
+                };
+            });
+        }
+        current_call
+    };
+    let (enter_caller_fn, exit_caller_fn) =
+        (caller_fn(&args.prefix_enter), caller_fn(&args.prefix_exit));
+    let println = default_println();
+    let flush_held_back_lines = flush_held_back_lines(&quote!(DEPTH));
+    // the locals can't be seen by the expression, which might use the same names
+    let [depth, depth_guard, value] =
+        ["depth", "depth_guard", "value"].map(|name| syn::Ident::new(name, Span::mixed_site()));
+    let indent = match args.indent {
+        Some(ref indent) => quote!(&*::std::primitive::str::repeat(#indent, #depth)),
+        None => quote!(""),
+    };
+    let output = quote! {{
+        let #depth = DEPTH.with(|d| d.get());
+        {
+            #enter_caller_fn
+            #flush_held_back_lines
+            #println!(
+                #entering_format,
+                #indent,
+                #enter_prefix_args
+                source = ::std::stringify!(#expr),
+                depth = #depth
+            );
+        }
+        // restores the depth even if evaluating the expression panics
+        let #depth_guard = {
+            struct DepthGuard(usize);
+            impl ::std::ops::Drop for DepthGuard {
+                fn drop(&mut self) {
+                    DEPTH.with(|d| d.set(self.0));
+                }
+            }
+            DEPTH.with(|d| d.set(#depth + 1));
+            DepthGuard(#depth)
+        };
+        let #value = #expr;
+        ::std::mem::drop(#depth_guard);
+        {
+            #exit_caller_fn
+            #flush_held_back_lines
+            #println!(
+                #exiting_format,
+                #indent,
+                #value,
+                #exit_prefix_args
+                source = ::std::stringify!(#expr),
+                depth = #depth
+            );
+        }
+        #value
+    }};
+
+    output.into()
+}
+
+/// Traces a block instead of a whole function, under the name it's given
+///
+/// A line is printed when the block is entered and another when it's left, at the current depth,
+/// and traced functions called in the block are indented one level deeper. The block runs in
+/// place, so `return`, `?`, and `break` in it work like they would without the macro (and the exit
+/// line is still printed), and its value is returned as is:
+/// ```
+/// use trace::trace;
+///
+/// trace::init_depth_var!();
+///
+/// #[trace]
+/// fn parse(line: &str) -> usize {
+///     line.len()
+/// }
+/// # fn main() {
+/// let lines = ["a = 1", "b = 22"];
+/// let total = trace::trace_scope!("loading config", {
+///     lines.iter().map(|line| parse(line)).sum::<usize>()
+/// });
+/// # assert_eq!(total, 11);
+/// # }
+/// ```
+///
+/// ```text
+/// [+] Entering scope loading config
+///  [+] Entering parse(line = "a = 1")
+///  [-] Exiting parse = 5
+///  [+] Entering parse(line = "b = 22")
+///  [-] Exiting parse = 6
+/// [-] Exiting scope loading config
+/// ```
+///
+/// `prefix_enter`, `prefix_exit`, `timing`, `indent`, and `indent_width` can be given after the
+/// block like they're given to `#[trace]`, e.g.
+/// `trace::trace_scope!("flush", { .. }, prefix_enter = "[IN]", timing)`, and the name is
+/// available as `{fn}` in the prefixes.
+#[proc_macro]
+pub fn trace_scope(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let name = input.parse::<syn::LitStr>()?;
+        input.parse::<syn::Token![,]>()?;
+        let block = input.parse::<syn::Block>()?;
+        let raw_args = if input.is_empty() {
+            syn::AttributeArgs::new()
+        } else {
+            input.parse::<syn::Token![,]>()?;
+            input
+                .parse_terminated::<_, syn::Token![,]>(syn::NestedMeta::parse)?
+                .into_iter()
+                .collect()
+        };
+        Ok((name, block, raw_args))
+    };
+    let (name, block, raw_args) = match parser.parse(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    // everything else configures how functions are traced, which doesn't apply to blocks
+    let unsupported_args = raw_args
+        .iter()
+        .filter_map(|nested_meta| match *nested_meta {
+            syn::NestedMeta::Meta(ref meta)
+                if ["prefix_enter", "prefix_exit", "timing", "indent", "indent_width"]
+                    .iter()
+                    .any(|name| meta.path().is_ident(name)) =>
+            {
+                None
+            }
+            _ => Some(syn::Error::new_spanned(
+                nested_meta,
+                "`trace_scope!` only takes `prefix_enter`, `prefix_exit`, `timing`, `indent`, and `indent_width`",
+            )),
+        })
+        .collect::<Vec<_>>();
+    if !unsupported_args.is_empty() {
+        return unsupported_args
+            .iter()
+            .map(syn::Error::to_compile_error)
+            .collect::<proc_macro2::TokenStream>()
+            .into();
+    }
+    let args = match args::Args::from_raw_args(raw_args) {
+        Ok(args) => args,
+        Err(errors) => {
+            return errors
+                .iter()
+                .map(syn::Error::to_compile_error)
+                .collect::<proc_macro2::TokenStream>()
+                .into()
+        }
+    };
+
+    // the `{fn}` placeholder of the prefixes is filled in with the name of the scope
+    let placeholder = syn::Ident::new("trace_scope_name", Span::call_site());
+    let (prefix_enter, enter_prefix_args) = match parse_prefix(&args.prefix_enter, &placeholder) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let (prefix_exit, exit_prefix_args) = match parse_prefix(&args.prefix_exit, &placeholder) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let fill_placeholder = |prefix: String| {
+        prefix
+            .split(&placeholder.to_string())
+            .map(|part| part.to_string())
+            .collect::<Vec<_>>()
+            .join("{scope}")
+    };
+    let (prefix_enter, prefix_exit) = (
+        fill_placeholder(prefix_enter),
+        fill_placeholder(prefix_exit),
+    );
+    let timing = if args.timing { " ({elapsed:?})" } else { "" };
+    let entering_format = format!("{{:depth$}}{} Entering scope {{scope}}", prefix_enter);
+    let exiting_format = format!(
+        "{{:depth$}}{} Exiting scope {{scope}}{}",
+        prefix_exit, timing
+    );
+    let elapsed = if args.timing {
+        quote!(elapsed = ::std::time::Instant::elapsed(&self.1),)
+    } else {
+        quote!()
+    };
+
+    // `{caller_fn}` and `{call_id}` are those of the traced call running when the block is entered
+    // or left
+    let caller_fn = |prefix: &str| {
+        let mut current_call = quote!();
+        if prefix.contains("{caller_fn}") || prefix.contains("{call_id}") {
+            current_call.extend(quote!(let fn_caller = TRACE_CURRENT_CALL.with(|c| c.get());));
+        }
+        if prefix.contains("{call_id}") {
+            current_call.extend(quote! {
+                let fn_call_id = match fn_caller {
+                    ::std::option::Option::Some((_, call_id)) => ::std::string::ToString::to_string(&call_id),
+                    ::std::option::Option::None => ::std::string::ToString::to_string("<none>"),
+                };
+            });
+        }
+        current_call
+    };
+    let (enter_caller_fn, exit_caller_fn) =
+        (caller_fn(&args.prefix_enter), caller_fn(&args.prefix_exit));
+    let println = default_println();
+    let flush_held_back_lines = flush_held_back_lines(&quote!(DEPTH));
+    // the locals can't be seen by the block, which might use the same names
+    let [depth, scope_guard, value] =
+        ["depth", "scope_guard", "value"].map(|name| syn::Ident::new(name, Span::mixed_site()));
+    let indent = |depth: TokenStream| match args.indent {
+        Some(ref indent) => quote!(&*::std::primitive::str::repeat(#indent, #depth)),
+        None => quote!(""),
+    };
+    let (enter_indent, exit_indent) = (indent(quote!(#depth)), indent(quote!(self.0)));
+    let output = quote! {{
+        let #depth = DEPTH.with(|d| d.get());
+        {
+            #enter_caller_fn
+            #flush_held_back_lines
+            #println!(
+                #entering_format,
+                #enter_indent,
+                #enter_prefix_args
+                scope = #name,
+                depth = #depth
+            );
+        }
+        // prints the exit line and restores the depth however the block is left, including by
+        // `return`, `?`, `break`, or a panic
+        let #scope_guard = {
+            struct ScopeGuard(usize, ::std::time::Instant);
+            impl ::std::ops::Drop for ScopeGuard {
+                fn drop(&mut self) {
+                    DEPTH.with(|d| d.set(self.0));
+                    #exit_caller_fn
+                    #flush_held_back_lines
+                    #println!(
+                        #exiting_format,
+                        #exit_indent,
+                        #exit_prefix_args
+                        #elapsed
+                        scope = #name,
+                        depth = self.0
+                    );
+                }
+            }
+            DEPTH.with(|d| d.set(#depth + 1));
+            ScopeGuard(#depth, ::std::time::Instant::now())
+        };
+        let #value = #block;
+        ::std::mem::drop(#scope_guard);
+        #value
+    }};
+
+    output.into()
+}
+
+/// Applies one `#[trace]` configuration to each of a list of items
+///
+/// The items are traced as if they were inside a `mod` with `#[trace]` applied to it, so `enable`
+/// and `disable` take a list of function names. The configuration is given in parentheses before
+/// the items and can be left out to use the defaults:
+/// ```
+/// trace::init_depth_var!();
+///
+/// trace::trace_items! {
+///     (prefix_enter = "[IN]", prefix_exit = "[OUT]", disable(bar))
+///
+///     fn foo(a: i32) -> i32 {
+///         bar(a) + 1
+///     }
+///
+///     fn bar(a: i32) -> i32 {
+///         a * 2
+///     }
+/// }
+/// # fn main() {
+/// #     foo(1);
+/// # }
+/// ```
+///
+/// Unlike a traced `mod`, no `DEPTH` variable is declared, since the items are expanded in place.
+#[proc_macro]
+pub fn trace_items(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let raw_args = if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            content
+                .parse_terminated::<_, syn::Token![,]>(syn::NestedMeta::parse)?
+                .into_iter()
+                .collect()
+        } else {
+            syn::AttributeArgs::new()
+        };
+        let mut items = vec![];
+        while !input.is_empty() {
+            items.push(input.parse::<syn::Item>()?);
+        }
+        Ok((raw_args, items))
+    };
+    let (raw_args, mut items) = match parser.parse(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let args = match args::Args::from_raw_args(raw_args) {
+        Ok(args) => args,
+        Err(errors) => {
+            return errors
+                .iter()
+                .map(syn::Error::to_compile_error)
+                .collect::<proc_macro2::TokenStream>()
+                .into()
+        }
+    };
+
+    transform_items(&args, Some(&args.filter), &mut items);
+
+    quote!(#(#items)*).into()
+}
+
+// what the rules of a `macro_rules!` declared in a `mod` traced with `expand_macros` expand to, with
+// the configuration of the `mod` in parentheses before the expansion. An expansion that isn't a
+// list of items (like an expression) is left as it is
+#[doc(hidden)]
+#[proc_macro]
+pub fn trace_expansion(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let mut tokens = proc_macro2::TokenStream::from(input).into_iter();
+    let raw_args = match tokens.next() {
+        Some(proc_macro2::TokenTree::Group(group))
+            if group.delimiter() == proc_macro2::Delimiter::Parenthesis =>
+        {
+            let parser = syn::punctuated::Punctuated::<_, syn::Token![,]>::parse_terminated;
+            match parser.parse2(group.stream()) {
+                Ok(raw_args) => raw_args.into_iter().collect(),
+                Err(e) => return e.to_compile_error().into(),
+            }
+        }
+        _ => panic!("`trace_expansion!` is given the configuration in parentheses first"),
+    };
+    let expansion = tokens.collect::<proc_macro2::TokenStream>();
+    let parser = |input: syn::parse::ParseStream| {
+        let mut items = vec![];
+        while !input.is_empty() {
+            items.push(input.parse::<syn::Item>()?);
+        }
+        Ok(items)
+    };
+    let mut items = match parser.parse2(expansion.clone()) {
+        Ok(items) => items,
+        Err(_) => return expansion.into(),
+    };
+    let args = match args::Args::from_raw_args(raw_args) {
+        Ok(args) => args,
+        Err(errors) => {
+            return errors
+                .iter()
+                .map(syn::Error::to_compile_error)
+                .collect::<proc_macro2::TokenStream>()
+                .into()
+        }
+    };
+
+    transform_items(&args, Some(&args.filter), &mut items);
+
+    quote!(#(#items)*).into()
+}
+
+/// Traces a closure like `#[trace]` traces a function, under the name it's given
+///
+/// The name comes first, followed by the closure, and a configuration can be given in
+/// parentheses before them, e.g. `trace::trace_closure!((pretty), name, |x| x)`:
+/// ```
+/// trace::init_depth_var!();
+///
+/// # fn main() {
+/// let offset = 10;
+/// let shifted = [1, 2]
+///     .into_iter()
+///     .map(trace::trace_closure!(shift, |x: i32| x + offset))
+///     .collect::<Vec<_>>();
+/// # assert_eq!(shifted, [11, 12]);
+/// # }
+/// ```
+///
+/// ```text
+/// [+] Entering shift(x = 1)
+/// [-] Exiting shift = 11
+/// [+] Entering shift(x = 2)
+/// [-] Exiting shift = 12
+/// ```
+///
+/// The closure captures what it would without being traced, so a closure that's `Fn` or `FnMut`
+/// stays that way. `async` closures aren't supported.
+#[proc_macro]
+pub fn trace_closure(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parser = |input: syn::parse::ParseStream| {
+        let raw_args = if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            input.parse::<syn::Token![,]>()?;
+            content
+                .parse_terminated::<_, syn::Token![,]>(syn::NestedMeta::parse)?
+                .into_iter()
+                .collect()
+        } else {
+            syn::AttributeArgs::new()
+        };
+        let name = input.parse::<syn::Ident>()?;
+        input.parse::<syn::Token![,]>()?;
+        let closure = input.parse::<syn::ExprClosure>()?;
+        if !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+        }
+        Ok((raw_args, name, closure))
+    };
+    let (raw_args, name, mut closure) = match parser.parse(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    if let Some(asyncness) = closure.asyncness {
+        return syn::Error::new_spanned(asyncness, "`trace_closure!` can't trace `async` closures")
+            .to_compile_error()
+            .into();
+    }
+    let mut args = match args::Args::from_raw_args(raw_args) {
+        Ok(args) => args,
+        Err(errors) => {
+            return errors
+                .iter()
+                .map(syn::Error::to_compile_error)
+                .collect::<proc_macro2::TokenStream>()
+                .into()
+        }
+    };
+    args.closure = true;
+
+    // the closure is traced as a function with the same parameters and return type, where the
+    // types that are left out are inferred
+    let inputs = closure
+        .inputs
+        .iter()
+        .map(|pat| match *pat {
+            syn::Pat::Type(ref pat_type) => syn::FnArg::Typed(pat_type.clone()),
+            _ => syn::FnArg::Typed(syn::PatType {
+                attrs: vec![],
+                pat: Box::new(pat.clone()),
+                colon_token: Default::default(),
+                ty: Box::new(syn::Type::Infer(syn::TypeInfer {
+                    underscore_token: Default::default(),
+                })),
+            }),
+        })
+        .collect();
+    let sig = syn::Signature {
+        constness: None,
+        asyncness: None,
+        unsafety: None,
+        abi: None,
+        fn_token: Default::default(),
+        ident: name,
+        generics: Default::default(),
+        paren_token: Default::default(),
+        inputs,
+        variadic: None,
+        output: closure.output.clone(),
+    };
+    let body = &closure.body;
+    let traced_block = construct_traced_block(
+        &args,
+        AttrApplied::Directly,
+        &sig,
+        &parse_quote!({ #body }),
+        &[],
+    );
+    *closure.body = syn::Expr::Block(syn::ExprBlock {
+        attrs: vec![],
+        label: None,
+        block: traced_block,
+    });
+
+    closure.into_token_stream().into()
+}
+
+#[derive(Clone, Copy)]
+enum AttrApplied {
+    Directly,
+    Indirectly,
+}
+
+fn expand_item(args: &args::Args, mut item: syn::Item) -> proc_macro2::TokenStream {
+    transform_item(args, AttrApplied::Directly, &mut item);
+
+    match item {
+        syn::Item::Mod(syn::ItemMod { content: None, .. }) => syn::Error::new_spanned(
+            item,
+            "#[trace] can't be applied to a `mod` declared without a body, since its items are in another file\nto trace them, wrap the contents of that file in `trace::trace_items! { .. }` instead",
+        )
+        .to_compile_error(),
+        syn::Item::Fn(ref item_fn) if item_fn.sig.asyncness.is_none() => {
+            match entry_point_attr(&item_fn.attrs) {
+                Some(attr) => syn::Error::new_spanned(
+                    attr,
+                    "#[trace] can only be combined with this attribute on an `async fn`",
+                )
+                .to_compile_error(),
+                None => item.into_token_stream(),
+            }
+        }
+        syn::Item::Fn(_) | syn::Item::Mod(_) | syn::Item::Impl(_) | syn::Item::Trait(_) => {
+            item.into_token_stream()
+        }
+        _ => syn::Error::new_spanned(item, "#[trace] is not supported for this item")
+            .to_compile_error(),
+    }
+}
+
+fn expand_crate(args: &args::Args, mut file: syn::File) -> proc_macro2::TokenStream {
+    let depth_var = args.depth_path.is_none().then_some(&args.depth_var);
+    let depth_var_decl = match args.printer {
+        Some(_) => depth_var
+            .map(|depth_var| atomic_depth_decl(&syn::Visibility::Inherited, depth_var))
+            .unwrap_or_default(),
+        None => depth_var_decl(&syn::Visibility::Inherited, depth_var),
+    };
+    transform_items(args, Some(&args.filter), &mut file.items);
+    // the items include the import of the standard prelude that the compiler adds to every crate,
+    // which makes it the prelude of every module of the crate. It has to be kept as it is, but its
+    // `#[prelude_import]` is unstable once it's been through the attribute, so the crate has to
+    // enable it
+    let enables_prelude_import = file.attrs.iter().any(|attr| {
+        attr.path.is_ident("feature")
+            && attr
+                .parse_meta()
+                .ok()
+                .is_some_and(|meta| match meta {
+                    syn::Meta::List(list) => list.nested.iter().any(|nested| {
+                        matches!(*nested, syn::NestedMeta::Meta(syn::Meta::Path(ref path)) if path.is_ident("prelude_import"))
+                    }),
+                    _ => false,
+                })
+    });
+    let mut error = None;
+    for item in &mut file.items {
+        if let syn::Item::Use(ref mut item_use) = *item {
+            if let Some(attr) = item_use
+                .attrs
+                .iter()
+                .find(|attr| attr.path.is_ident("prelude_import"))
+            {
+                if !enables_prelude_import {
+                    error = Some(syn::Error::new_spanned(
+                        attr,
+                        "#[trace] at the root of a crate needs `#![feature(prelude_import)]` too, to keep the standard prelude in every module of the crate",
+                    )
+                    .to_compile_error());
+                }
+                item_use.attrs.push(parse_quote!(#[allow(unused_imports)]));
+            }
+        }
+    }
+    quote! {
+        #file
+        #error
+        #depth_var_decl
+    }
+}
+
+fn expand_impl_item(args: &args::Args, mut impl_item: syn::ImplItem) -> proc_macro2::TokenStream {
+    transform_impl_item(args, AttrApplied::Directly, &mut impl_item);
+
+    match impl_item {
+        syn::ImplItem::Method(_) => impl_item.into_token_stream(),
+        _ => syn::Error::new_spanned(impl_item, "#[trace] is not supported for this impl item")
+            .to_compile_error(),
+    }
+}
+
+fn transform_item(args: &args::Args, attr_applied: AttrApplied, item: &mut syn::Item) {
+    if let AttrApplied::Indirectly = attr_applied {
+        if transform_own_item(args, item) {
+            return;
+        }
+        let attrs = match *item {
+            syn::Item::Fn(ref item_fn) => &item_fn.attrs,
+            syn::Item::Mod(ref item_mod) => &item_mod.attrs,
+            syn::Item::Impl(ref item_impl) => &item_impl.attrs,
+            syn::Item::Trait(ref item_trait) => &item_trait.attrs,
+            _ => return,
+        };
+        if skips_cfg(args, attrs) {
+            return;
+        }
+        if let syn::Item::Fn(ref item_fn) = *item {
+            if skips_asyncness(args, &item_fn.sig) || skips_tests(args, &item_fn.attrs) {
+                return;
+            }
+        }
+    }
+    match *item {
+        syn::Item::Fn(ref mut item_fn) => transform_fn(args, attr_applied, item_fn),
+        syn::Item::Mod(ref mut item_mod) => transform_mod(args, attr_applied, item_mod),
+        syn::Item::Impl(ref mut item_impl) => transform_impl(args, attr_applied, item_impl),
+        syn::Item::Trait(ref mut item_trait) => transform_trait(args, attr_applied, item_trait),
+        _ => (),
+    }
+}
+
+// an item with a `#[trace]` of its own inside a traced `mod`, `impl`, or function is traced once,
+// with the arguments of both, instead of being traced by each of them. Returns whether it had one
+fn transform_own_item(args: &args::Args, item: &mut syn::Item) -> bool {
+    let own_args = match *item {
+        syn::Item::Fn(ref mut item_fn) => own_args(args, &mut item_fn.attrs),
+        // the items of a `mod` declared without a body can't be seen, so its `#[trace]` is left
+        // to report that on its own
+        syn::Item::Mod(ref mut item_mod) if item_mod.content.is_some() => {
+            own_args(args, &mut item_mod.attrs)
+        }
+        syn::Item::Impl(ref mut item_impl) => own_args(args, &mut item_impl.attrs),
+        syn::Item::Trait(ref mut item_trait) => own_args(args, &mut item_trait.attrs),
+        _ => None,
+    };
+    let own_args = match own_args {
+        Some(Ok(own_args)) => own_args,
+        Some(Err(errors)) => {
+            match *item {
+                syn::Item::Fn(ref mut item_fn) => item_fn
+                    .block
+                    .stmts
+                    .insert(0, syn::Stmt::Item(syn::Item::Verbatim(errors))),
+                syn::Item::Mod(syn::ItemMod {
+                    content: Some((_, ref mut items)),
+                    ..
+                }) => items.insert(0, syn::Item::Verbatim(errors)),
+                syn::Item::Impl(ref mut item_impl) => {
+                    item_impl.items.push(syn::ImplItem::Verbatim(errors))
+                }
+                syn::Item::Trait(ref mut item_trait) => {
+                    item_trait.items.push(syn::TraitItem::Verbatim(errors))
+                }
+                _ => (),
+            }
+            return true;
+        }
+        None => return false,
+    };
+    match *item {
+        syn::Item::Fn(ref mut item_fn) => transform_fn(&own_args, AttrApplied::Directly, item_fn),
+        // the `mod` keeps using the variables of the outer one, so the indentation continues
+        // into it
+        syn::Item::Mod(ref mut item_mod) => transform_filtered_mod(
+            &own_args,
+            AttrApplied::Indirectly,
+            Some(&own_args.filter),
+            item_mod,
+        ),
+        syn::Item::Impl(ref mut item_impl) => {
+            transform_impl(&own_args, AttrApplied::Directly, item_impl)
+        }
+        syn::Item::Trait(ref mut item_trait) => {
+            transform_trait(&own_args, AttrApplied::Directly, item_trait)
+        }
+        _ => (),
+    }
+    true
+}
+
+// takes the `#[trace]` out of `attrs`, returning the outer arguments merged with its own, or the
+// errors they were reported with. `None` if there's no `#[trace]`
+fn own_args(
+    args: &args::Args,
+    attrs: &mut Vec<syn::Attribute>,
+) -> Option<Result<args::Args, TokenStream>> {
+    let position = attrs.iter().position(|attr| {
+        let path = attr
+            .path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::");
+        path == "trace" || path == "trace::trace"
+    })?;
+    let attr = attrs.remove(position);
+    let own_raw_args = match attr.parse_meta() {
+        Ok(syn::Meta::Path(_)) => syn::AttributeArgs::new(),
+        Ok(syn::Meta::List(meta_list)) => meta_list.nested.into_iter().collect(),
+        Ok(meta @ syn::Meta::NameValue(_)) => {
+            return Some(Err(syn::Error::new_spanned(
+                meta,
+                "expected `#[trace]` or `#[trace(...)]`",
+            )
+            .to_compile_error()))
+        }
+        Err(e) => return Some(Err(e.to_compile_error())),
+    };
+    Some(args.merged_with(own_raw_args).map_err(|errors| {
+        errors
+            .iter()
+            .map(syn::Error::to_compile_error)
+            .collect::<TokenStream>()
+    }))
+}
+
+fn transform_fn(args: &args::Args, attr_applied: AttrApplied, item_fn: &mut syn::ItemFn) {
+    if let Some(error) = untraceable(&item_fn.attrs, &item_fn.sig) {
+        if let AttrApplied::Directly = attr_applied {
+            let error = error.into_compile_error();
+            *item_fn.block = parse_quote!({#error});
+        }
+        return;
+    }
+    if let AttrApplied::Indirectly = attr_applied {
+        transform_nested_items(args, &mut item_fn.block);
+    }
+    // when an entry point attribute like `#[tokio::main]` was expanded before `#[trace]`, the
+    // `async fn` it was applied to has already been turned into a regular function that runs the
+    // body on a runtime, so the body is traced like the `async fn` instead of that function
+    if item_fn.sig.asyncness.is_none() && item_fn.sig.inputs.is_empty() {
+        let mut async_sig = item_fn.sig.clone();
+        async_sig.asyncness = Some(Default::default());
+        if let Some(async_body) = entry_point_body(&mut item_fn.block) {
+            async_body.block =
+                construct_traced_block(args, attr_applied, &async_sig, &async_body.block, &[]);
+            return;
+        }
+    }
+    if args.auto_bound {
+        add_debug_bounds(args, attr_applied, &mut item_fn.sig, &[]);
+    }
+    let track_caller_args = track_caller_args(args, &item_fn.attrs);
+    let args = track_caller_args.as_ref().unwrap_or(args);
+    *item_fn.block = construct_traced_block(args, attr_applied, &item_fn.sig, &item_fn.block, &[]);
+}
+
+// `#[track_caller]` functions have their body run inline instead of in a closure, so
+// `Location::caller()` and the panics in it see the caller of the function. `None` for other
+// functions, which are traced with `args` as they are
+fn track_caller_args(args: &args::Args, attrs: &[syn::Attribute]) -> Option<args::Args> {
+    if !attrs.iter().any(|attr| attr.path.is_ident("track_caller")) {
+        return None;
+    }
+    let mut args = args.merged_with(syn::AttributeArgs::new()).ok()?;
+    args.track_caller = true;
+    Some(args)
+}
+
+// with `auto_bound`, the type parameters of a function that are mentioned by the types of its
+// printed arguments or its return type get a `Debug` bound, so those values are printed instead of
+// the names of their types. The ones of the `impl` a method is in (`outer_type_params`) are
+// bounded in the `where` clause of the method, so the rest of the `impl` doesn't need them to
+// implement `Debug`
+fn add_debug_bounds(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    sig: &mut syn::Signature,
+    outer_type_params: &[syn::Ident],
+) {
+    let printed_args = extract_arg_idents(args, attr_applied, sig)
+        .into_iter()
+        .filter(|ident| !args.no_args && !args.redact.contains(ident))
+        .collect::<Vec<_>>();
+    let mut printed_types = sig
+        .inputs
+        .iter()
+        .filter_map(|input| match *input {
+            syn::FnArg::Typed(ref pat_type) => match *pat_type.pat {
+                syn::Pat::Ident(ref pat_ident) if printed_args.contains(&pat_ident.ident) => {
+                    Some(&*pat_type.ty)
+                }
+                _ => None,
+            },
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect::<Vec<_>>();
+    match sig.output {
+        syn::ReturnType::Type(_, ref ty)
+            if !args.hide_ret
+                && !disables_return(args)
+                && !matches!(**ty, syn::Type::ImplTrait(_)) =>
+        {
+            printed_types.push(ty)
+        }
+        _ => (),
+    }
+    let is_printed = |ident: &syn::Ident| {
+        let type_params = [ident.to_string()];
+        printed_types
+            .iter()
+            .any(|ty| mentions_type_param(ty, &type_params))
+    };
+    let bounded = sig
+        .generics
+        .type_params()
+        .map(|type_param| type_param.ident.clone())
+        .filter(is_printed)
+        .collect::<Vec<_>>();
+    let outer_bounded = outer_type_params
+        .iter()
+        .filter(|ident| is_printed(ident))
+        .cloned()
+        .collect::<Vec<_>>();
+    let debug_bound: syn::TypeParamBound = parse_quote!(::std::fmt::Debug);
+    for ident in outer_bounded {
+        sig.generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#ident: #debug_bound));
+    }
+    // the bound goes wherever the parameter is already bounded, so it isn't bounded in two places
+    for ident in bounded {
+        let where_predicate = sig
+            .generics
+            .where_clause
+            .iter_mut()
+            .flat_map(|where_clause| where_clause.predicates.iter_mut())
+            .find_map(|predicate| match *predicate {
+                syn::WherePredicate::Type(ref mut predicate_type)
+                    if matches!(predicate_type.bounded_ty, syn::Type::Path(ref type_path) if type_path.path.is_ident(&ident)) =>
+                {
+                    Some(predicate_type)
+                }
+                _ => None,
+            });
+        match where_predicate {
+            Some(predicate_type) => predicate_type.bounds.push(debug_bound.clone()),
+            None => {
+                if let Some(type_param) = sig
+                    .generics
+                    .type_params_mut()
+                    .find(|type_param| type_param.ident == ident)
+                {
+                    type_param.colon_token.get_or_insert_with(Default::default);
+                    type_param.bounds.push(debug_bound.clone());
+                }
+            }
+        }
+    }
+}
+
+// the functions whose body can't be traced: a `const fn` can't print, and the body of a naked
+// function has to be a single `naked_asm!`. Applying `#[trace]` to one directly is an error, and
+// the ones in a traced `mod`, `impl`, or `trait` are left as they are. Anything else about the
+// signature (like `extern "C"`, `unsafe`, or C-variadic arguments) and the attributes (like
+// `#[no_mangle]`) is kept as it was written, so exported functions can be traced
+fn untraceable(attrs: &[syn::Attribute], sig: &syn::Signature) -> Option<syn::Error> {
+    if let Some(constness) = sig.constness {
+        return Some(syn::Error::new_spanned(
+            constness,
+            "#[trace] can't be applied to a `const fn`, which can't print",
+        ));
+    }
+    attrs
+        .iter()
+        .find(|attr| {
+            attr.path.is_ident("naked")
+                || (attr.path.is_ident(UNSAFE_ATTR) && attr.tokens.to_string() == "(naked)")
+        })
+        .map(|attr| {
+            syn::Error::new_spanned(
+                attr,
+                "#[trace] can't be applied to a naked function, whose body has to be a single `naked_asm!`",
+            )
+        })
+}
+
+// with `skip_cfg`, items behind a `#[cfg(...)]` aren't traced, whether or not they're compiled in
+fn skips_cfg(args: &args::Args, attrs: &[syn::Attribute]) -> bool {
+    args.skip_cfg && attrs.iter().any(|attr| attr.path.is_ident("cfg"))
+}
+
+// with `skip_tests`, `#[test]` and `#[bench]` functions aren't traced
+fn skips_tests(args: &args::Args, attrs: &[syn::Attribute]) -> bool {
+    args.skip_tests && (is_test(attrs) || attrs.iter().any(|attr| attr.path.is_ident("bench")))
+}
+
+// with `async_only` or `sync_only`, only `async fn`s or only regular functions are traced
+fn skips_asyncness(args: &args::Args, sig: &syn::Signature) -> bool {
+    if sig.asyncness.is_some() {
+        args.sync_only
+    } else {
+        args.async_only
+    }
+}
+
+// The entry point attributes that run the body of an `async fn` on a runtime
+const ENTRY_POINT_ATTRS: [&str; 4] = [
+    "tokio::main",
+    "tokio::test",
+    "async_std::main",
+    "async_std::test",
+];
+
+fn entry_point_attr(attrs: &[syn::Attribute]) -> Option<&syn::Attribute> {
+    attrs.iter().find(|attr| {
+        let path = attr
+            .path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::");
+        ENTRY_POINT_ATTRS.contains(&path.as_str())
+    })
+}
+
+// whether a function is a test, for `#[trace_all_tests]` and `skip_tests`
+fn is_test(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path.is_ident("test"))
+        || entry_point_attr(attrs).is_some_and(|attr| {
+            attr.path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "test")
+        })
+}
+
+// The async block that the function generated by an entry point attribute runs, which is either
+// stored in `body` first (`#[tokio::main]` and `#[tokio::test]`), or passed to `block_on` directly
+// (`#[async_std::test]`). `#[async_std::main]` keeps the `async fn` as an inner function, so
+// there's nothing to find for it.
+fn entry_point_body(block: &mut syn::Block) -> Option<&mut syn::ExprAsync> {
+    let single_stmt = block.stmts.len() == 1;
+    match block.stmts.first_mut()? {
+        syn::Stmt::Local(syn::Local {
+            pat: syn::Pat::Ident(syn::PatIdent { ref ident, .. }),
+            init: Some((_, ref mut init)),
+            ..
+        }) if ident == "body" => match **init {
+            syn::Expr::Async(ref mut async_body) => Some(async_body),
+            _ => None,
+        },
+        syn::Stmt::Expr(syn::Expr::Call(syn::ExprCall {
+            ref func,
+            ref mut args,
+            ..
+        })) if single_stmt && args.len() == 1 => match **func {
+            syn::Expr::Path(ref path) if path.path.segments.last()?.ident == "block_on" => {
+                match args.first_mut()? {
+                    syn::Expr::Async(ref mut async_body) => Some(async_body),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// The signature of the `async fn` that a function returning a boxed future (like a method that
+// `#[async_trait]` was expanded on) was written as, with the output of the future as its return
+// type, i.e. `async fn f() -> T` for `fn f() -> Pin<Box<dyn Future<Output = T> + Send>>`
+fn boxed_future_sig(sig: &syn::Signature) -> Option<syn::Signature> {
+    fn last_arg<'a>(ty: &'a syn::Type, name: &str) -> Option<&'a syn::GenericArgument> {
+        let segment = match *ty {
+            syn::Type::Path(ref type_path) => type_path.path.segments.last()?,
+            _ => return None,
+        };
+        match segment.arguments {
+            syn::PathArguments::AngleBracketed(ref arguments) if segment.ident == name => {
+                arguments.args.last()
+            }
+            _ => None,
+        }
+    }
+    if sig.asyncness.is_some() {
+        return None;
+    }
+    let ty = match sig.output {
+        syn::ReturnType::Type(_, ref ty) => ty,
+        syn::ReturnType::Default => return None,
+    };
+    let boxed = match *last_arg(ty, "Pin")? {
+        syn::GenericArgument::Type(ref boxed) => boxed,
+        _ => return None,
+    };
+    let bounds = match *last_arg(boxed, "Box")? {
+        syn::GenericArgument::Type(syn::Type::TraitObject(ref trait_object)) => {
+            &trait_object.bounds
+        }
+        _ => return None,
+    };
+    let output = bounds.iter().find_map(|bound| {
+        let segment = match *bound {
+            syn::TypeParamBound::Trait(ref trait_bound) => trait_bound.path.segments.last()?,
+            syn::TypeParamBound::Lifetime(_) => return None,
+        };
+        match segment.arguments {
+            syn::PathArguments::AngleBracketed(ref arguments) if segment.ident == "Future" => {
+                arguments.args.iter().find_map(|arg| match *arg {
+                    syn::GenericArgument::Binding(ref binding) if binding.ident == "Output" => {
+                        Some(binding.ty.clone())
+                    }
+                    _ => None,
+                })
+            }
+            _ => None,
+        }
+    })?;
+    let mut async_sig = sig.clone();
+    async_sig.asyncness = Some(Default::default());
+    async_sig.output = parse_quote!(-> #output);
+    Some(async_sig)
+}
+
+// The async block whose boxed future a function returns (`Box::pin(async move { .. })`, which is
+// what `#[async_trait]` turns the body of a method into)
+fn boxed_future_body(block: &mut syn::Block) -> Option<&mut syn::ExprAsync> {
+    if block.stmts.len() != 1 {
+        return None;
+    }
+    match block.stmts.first_mut()? {
+        syn::Stmt::Expr(syn::Expr::Call(syn::ExprCall {
+            ref func,
+            ref mut args,
+            ..
+        })) if args.len() == 1 => match **func {
+            syn::Expr::Path(ref path)
+                if path
+                    .path
+                    .segments
+                    .iter()
+                    .rev()
+                    .take(2)
+                    .map(|segment| segment.ident.to_string())
+                    .eq(["pin", "Box"]) =>
+            {
+                match args.first_mut()? {
+                    syn::Expr::Async(ref mut async_body) => Some(async_body),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn transform_mod(args: &args::Args, attr_applied: AttrApplied, item_mod: &mut syn::ItemMod) {
+    let filter = match attr_applied {
+        AttrApplied::Directly => Some(&args.filter),
+        AttrApplied::Indirectly => None,
+    };
+    transform_filtered_mod(args, attr_applied, filter, item_mod);
+}
+
+// `filter` is what the items of the `mod` are filtered by, which is the `enable` or `disable` of the
+// `#[trace]` applied to it, or the paths given to them that lead into it from a `mod` it's nested in
+fn transform_filtered_mod(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    filter: Option<&args::Filter>,
+    item_mod: &mut syn::ItemMod,
+) {
+    assert!(
+        (item_mod.content.is_some() && item_mod.semi.is_none())
+            || (item_mod.content.is_none() && item_mod.semi.is_some())
+    );
+
+    // the items of a `mod` declared without a body are in another file, which can't be seen here,
+    // so it's left as it is (and reported as an error by `expand_item` if `#[trace]` was applied
+    // to it directly)
+    if item_mod.semi.is_some() {
+        return;
+    }
+
+    if let Some((_, items)) = item_mod.content.as_mut() {
+        // only the outermost traced `mod` declares the variables, and the `mod`s nested in it use
+        // those, so the indentation continues across the whole tree of `mod`s. With `depth_path`,
+        // the depth variable is declared elsewhere
+        let depth_var = args.depth_path.is_none().then_some(&args.depth_var);
+        let depth_var_decl = match attr_applied {
+            AttrApplied::Directly if args.printer.is_some() => depth_var
+                .map(|depth_var| atomic_depth_decl(&syn::Visibility::Inherited, depth_var))
+                .unwrap_or_default(),
+            AttrApplied::Directly => depth_var_decl(&syn::Visibility::Inherited, depth_var),
+            AttrApplied::Indirectly => {
+                let names = DEPTH_VAR_NAMES.iter().filter_map(|name| match *name {
+                    "DEPTH" => depth_var.cloned(),
+                    _ if args.printer.is_some() => None,
+                    _ => Some(syn::Ident::new(name, Span::call_site())),
+                });
+                quote! {
+                    #[allow(unused_imports)]
+                    use super::{#(#names),*};
+                }
+            }
+        };
+        transform_items(args, filter, items);
+
+        let depth_var_decl: syn::File = parse_quote!(#depth_var_decl);
+        items.splice(0..0, depth_var_decl.items);
+    }
+}
+
+fn transform_items(args: &args::Args, filter: Option<&args::Filter>, items: &mut [syn::Item]) {
+    items.iter_mut().for_each(|item| {
+        if let syn::Item::Macro(ref mut item_macro) = *item {
+            if args.expand_macros {
+                if let Some(expanded) = expand_macro_item(args, filter, item_macro) {
+                    *item = syn::Item::Verbatim(expanded);
+                }
+            }
+            return;
+        }
+        // an item with a `#[trace]` of its own is traced even if the filter leaves it out
+        if transform_own_item(args, item) {
+            return;
+        }
+        let filter = match filter {
+            Some(filter) => filter,
+            None => return transform_item(args, AttrApplied::Indirectly, item),
+        };
+        match *item {
+            syn::Item::Fn(syn::ItemFn {
+                sig: syn::Signature { ref ident, .. },
+                ..
+            }) => match *filter {
+                args::Filter::Enable(ref names) if !names.matches(ident) => {
+                    return;
+                }
+                args::Filter::Disable(ref names) if names.matches(ident) => {
+                    return;
+                }
+                _ => (),
+            },
+            // a `mod` that paths lead into is filtered by the rest of those paths
+            syn::Item::Mod(ref mut item_mod) => match filter.nested(&item_mod.ident) {
+                Some(ref nested_filter) => {
+                    if !skips_cfg(args, &item_mod.attrs) {
+                        transform_filtered_mod(
+                            args,
+                            AttrApplied::Indirectly,
+                            Some(nested_filter),
+                            item_mod,
+                        );
+                    }
+                    return;
+                }
+                None => match *filter {
+                    args::Filter::Enable(ref names) if !names.matches(&item_mod.ident) => {
+                        return;
+                    }
+                    args::Filter::Disable(ref names) if names.matches(&item_mod.ident) => {
+                        return;
+                    }
+                    _ => (),
+                },
+            },
+            _ => (),
+        }
+
+        transform_item(args, AttrApplied::Indirectly, item);
+    });
+}
+
+// the functions that macros expand to can't be seen before they're expanded, so with
+// `expand_macros`, the rules of a `macro_rules!` have what they expand to traced by
+// `trace_expansion!`, and an `include!` of a file is replaced by the (traced) items in the file.
+// Returns what an `include!` is replaced by, or an error along with the macro if it can't be read
+fn expand_macro_item(
+    args: &args::Args,
+    filter: Option<&args::Filter>,
+    item_macro: &mut syn::ItemMacro,
+) -> Option<TokenStream> {
+    if item_macro.mac.path.is_ident("macro_rules") {
+        return match expand_macro_rules(args, &item_macro.mac) {
+            Ok(rules) => {
+                item_macro.mac.tokens = rules;
+                None
+            }
+            Err(e) => {
+                let error = e.to_compile_error();
+                Some(quote!(#item_macro #error))
+            }
+        };
+    }
+    if !item_macro.mac.path.is_ident("include") {
+        return None;
+    }
+
+    // only a literal path can be read here, which is relative to the file with the `include!`
+    let path = match syn::parse2::<syn::LitStr>(item_macro.mac.tokens.clone()) {
+        Ok(path) => path,
+        Err(_) => {
+            let error = syn::Error::new_spanned(
+                &item_macro.mac,
+                "`expand_macros` can only read the file of an `include!` of a string literal\nto trace the items of that file, wrap its contents in `trace::trace_items! { .. }` instead",
+            )
+            .to_compile_error();
+            return Some(quote!(#item_macro #error));
+        }
+    };
+    let included = match path.span().local_file() {
+        Some(file) => file.with_file_name(path.value()),
+        None => {
+            return Some(
+                syn::Error::new_spanned(
+                    &item_macro.mac,
+                    "`expand_macros` can't find the file included here with this compiler\nto trace its items, wrap the contents of that file in `trace::trace_items! { .. }` instead",
+                )
+                .to_compile_error(),
+            )
+        }
+    };
+    let mut file = match std::fs::read_to_string(&included)
+        .map_err(|e| {
+            syn::Error::new_spanned(
+                &path,
+                format!("couldn't read `{}`: {}", included.display(), e),
+            )
+        })
+        .and_then(|source| syn::parse_file(&source))
+    {
+        Ok(file) => file,
+        Err(e) => return Some(e.to_compile_error()),
+    };
+    transform_items(args, filter, &mut file.items);
+    // the file is still included, so the crate is rebuilt when it changes. With the span of the
+    // path, `include_bytes!` finds it relative to the file with the `include!` too
+    let items = file.items;
+    let include_bytes = quote_spanned!(path.span()=> ::core::include_bytes!(#path));
+    Some(quote! {
+        #(#items)*
+        const _: &[::core::primitive::u8] = #include_bytes;
+    })
+}
+
+// the rules of a `macro_rules!`, with what every rule expands to traced by `trace_expansion!`.
+// Every rule is `(matcher) => {transcriber}` (with any kind of brackets), and they're separated by
+// semicolons
+fn expand_macro_rules(args: &args::Args, mac: &syn::Macro) -> syn::Result<TokenStream> {
+    let raw_args = &args.raw_args;
+    let parser = |input: syn::parse::ParseStream| {
+        let mut rules = vec![];
+        while !input.is_empty() {
+            let matcher = input.parse::<proc_macro2::Group>()?;
+            let arrow = input.parse::<syn::Token![=>]>()?;
+            let transcriber = input.parse::<proc_macro2::Group>()?;
+            let expansion = transcriber.stream();
+            let mut traced = proc_macro2::Group::new(
+                transcriber.delimiter(),
+                quote!(::trace::trace_expansion! { (#(#raw_args),*) #expansion }),
+            );
+            traced.set_span(transcriber.span());
+            rules.push(quote!(#matcher #arrow #traced));
+            if input.is_empty() {
+                break;
+            }
+            let semi = input.parse::<syn::Token![;]>()?;
+            rules.push(quote!(#semi));
+        }
+        Ok(quote!(#(#rules)*))
+    };
+    parser.parse2(mac.tokens.clone()).map_err(|e| {
+        syn::Error::new_spanned(
+            mac,
+            format!(
+                "`expand_macros` can't read the rules of this `macro_rules!`: {}",
+                e
+            ),
+        )
+    })
+}
+
+fn transform_impl(args: &args::Args, attr_applied: AttrApplied, item_impl: &mut syn::ItemImpl) {
+    let mut outer_type_params = type_param_names(&item_impl.generics);
+    if !outer_type_params.is_empty() {
+        outer_type_params.push("Self".to_string());
+    }
+    // the associated types of a trait (like `Self::Item` of `Iterator`) may not implement `Debug`,
+    // and the methods of an `impl` of it can't require them to, so the values of methods whose
+    // signatures mention them are printed like those of a generic `impl`: with `Debug` if their
+    // type implements it, and by the name of their type otherwise
+    let assoc_type_params = if item_impl.trait_.is_some() && outer_type_params.is_empty() {
+        vec!["Self".to_string()]
+    } else {
+        outer_type_params.clone()
+    };
+    let method_type_params = |sig: &syn::Signature| {
+        if mentions_assoc_type(sig) {
+            &assoc_type_params
+        } else {
+            &outer_type_params
+        }
+    };
+    let trait_ident = item_impl
+        .trait_
+        .as_ref()
+        .and_then(|(_, path, _)| path.segments.last())
+        .map(|segment| &segment.ident);
+    // with `auto_bound`, the methods of an inherent `impl` get `Debug` bounds for the type
+    // parameters of the `impl` they print, while the methods of an `impl` of a trait can't have
+    // bounds the trait doesn't
+    let bounded_type_params = match item_impl.trait_ {
+        Some(_) => None,
+        None => Some(
+            item_impl
+                .generics
+                .type_params()
+                .map(|type_param| type_param.ident.clone())
+                .collect::<Vec<_>>(),
+        ),
+    };
+    item_impl.items.iter_mut().for_each(|impl_item| {
+        if let syn::ImplItem::Method(ref mut impl_item_method) = *impl_item {
+            match own_args(args, &mut impl_item_method.attrs) {
+                Some(Ok(mut own_args)) => {
+                    own_args.track_caller =
+                        track_caller_args(&own_args, &impl_item_method.attrs).is_some();
+                    if let (true, Some(type_params)) = (own_args.auto_bound, &bounded_type_params) {
+                        add_debug_bounds(
+                            &own_args,
+                            AttrApplied::Directly,
+                            &mut impl_item_method.sig,
+                            type_params,
+                        );
+                    }
+                    impl_item_method.block = construct_traced_block(
+                        &own_args,
+                        AttrApplied::Directly,
+                        &impl_item_method.sig,
+                        &impl_item_method.block,
+                        method_type_params(&impl_item_method.sig),
+                    );
+                    return;
+                }
+                Some(Err(errors)) => {
+                    impl_item_method
+                        .block
+                        .stmts
+                        .insert(0, syn::Stmt::Item(syn::Item::Verbatim(errors)));
+                    return;
+                }
+                None => (),
+            }
+            if let AttrApplied::Directly = attr_applied {
+                let ident = &impl_item_method.sig.ident;
+                // `Trait::method` only matches the method in an `impl` of a trait with that name
+                let matches = |names: &args::FilterNames| {
+                    names.matches(ident)
+                        || trait_ident.is_some_and(|trait_ident| {
+                            names
+                                .paths
+                                .contains(&vec![trait_ident.clone(), ident.clone()])
+                        })
+                };
+
+                match args.filter {
+                    args::Filter::Enable(ref names) if !matches(names) => {
+                        return;
+                    }
+                    args::Filter::Disable(ref names) if matches(names) => {
+                        return;
+                    }
+                    _ => (),
+                }
+            }
+            if skips_cfg(args, &impl_item_method.attrs)
+                || skips_asyncness(args, &impl_item_method.sig)
+                || untraceable(&impl_item_method.attrs, &impl_item_method.sig).is_some()
+            {
+                return;
+            }
+
+            transform_nested_items(args, &mut impl_item_method.block);
+            let track_caller_args = track_caller_args(args, &impl_item_method.attrs);
+            let args = track_caller_args.as_ref().unwrap_or(args);
+            if let (true, Some(type_params)) = (args.auto_bound, &bounded_type_params) {
+                add_debug_bounds(
+                    args,
+                    AttrApplied::Indirectly,
+                    &mut impl_item_method.sig,
+                    type_params,
+                );
+            }
+            impl_item_method.block = construct_traced_block(
+                args,
+                AttrApplied::Indirectly,
+                &impl_item_method.sig,
+                &impl_item_method.block,
+                method_type_params(&impl_item_method.sig),
+            );
+        }
+    });
+}
+
+// only the default methods of a trait have a body to trace
+fn transform_trait(args: &args::Args, attr_applied: AttrApplied, item_trait: &mut syn::ItemTrait) {
+    let mut outer_type_params = type_param_names(&item_trait.generics);
+    outer_type_params.push("Self".to_string());
+    item_trait.items.iter_mut().for_each(|trait_item| {
+        if let syn::TraitItem::Method(syn::TraitItemMethod {
+            ref mut attrs,
+            ref sig,
+            default: Some(ref mut block),
+            ..
+        }) = *trait_item
+        {
+            match own_args(args, attrs) {
+                Some(Ok(mut own_args)) => {
+                    own_args.track_caller = track_caller_args(&own_args, attrs).is_some();
+                    *block = construct_traced_block(
+                        &own_args,
+                        AttrApplied::Directly,
+                        sig,
+                        block,
+                        &outer_type_params,
+                    );
+                    return;
+                }
+                Some(Err(errors)) => {
+                    block
+                        .stmts
+                        .insert(0, syn::Stmt::Item(syn::Item::Verbatim(errors)));
+                    return;
+                }
+                None => (),
+            }
+            if skips_cfg(args, attrs)
+                || skips_asyncness(args, sig)
+                || untraceable(attrs, sig).is_some()
+            {
+                return;
+            }
+            if let AttrApplied::Directly = attr_applied {
+                match args.filter {
+                    args::Filter::Enable(ref names) if !names.matches(&sig.ident) => {
+                        return;
+                    }
+                    args::Filter::Disable(ref names) if names.matches(&sig.ident) => {
+                        return;
+                    }
+                    _ => (),
+                }
+            }
+
+            transform_nested_items(args, block);
+            let track_caller_args = track_caller_args(args, attrs);
+            let args = track_caller_args.as_ref().unwrap_or(args);
+            *block = construct_traced_block(
+                args,
+                AttrApplied::Indirectly,
+                sig,
+                block,
+                &outer_type_params,
+            );
+        }
+    });
+}
+
+// functions, `impl`s, and `mod`s declared inside the body of a function that is traced as part of a
+// `mod` or `impl` are traced as well
+fn transform_nested_items(args: &args::Args, block: &mut syn::Block) {
+    block.stmts.iter_mut().for_each(|stmt| {
+        if let syn::Stmt::Item(ref mut item) = *stmt {
+            transform_item(args, AttrApplied::Indirectly, item);
+        }
+    });
+}
+
+fn transform_impl_item(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    impl_item: &mut syn::ImplItem,
+) {
+    // Will probably add more cases in the future
+    #[allow(clippy::single_match)]
+    match *impl_item {
+        syn::ImplItem::Method(ref mut impl_item_method) => {
+            transform_method(args, attr_applied, impl_item_method)
+        }
+        _ => (),
+    }
+}
+
+fn transform_method(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    impl_item_method: &mut syn::ImplItemMethod,
+) {
+    if let Some(error) = untraceable(&impl_item_method.attrs, &impl_item_method.sig) {
+        let error = error.into_compile_error();
+        impl_item_method.block = parse_quote!({#error});
+        return;
+    }
+    let track_caller_args = track_caller_args(args, &impl_item_method.attrs);
+    let args = track_caller_args.as_ref().unwrap_or(args);
+    impl_item_method.block = construct_traced_block(
+        args,
+        attr_applied,
+        &impl_item_method.sig,
+        &impl_item_method.block,
+        &[],
+    );
+}
+
+// `outer_type_params` are the type parameters of the `impl` or `trait` the function is in (including
+// `Self` if it's generic)
+fn construct_traced_block(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+    outer_type_params: &[String],
+) -> syn::Block {
+    // when `#[async_trait]` was expanded before `#[trace]`, the method has already been turned
+    // into one returning its body as a boxed future, so the body is traced like the `async fn`
+    // instead of the method that only creates the future
+    if let Some(async_sig) = boxed_future_sig(sig) {
+        let mut block = original_block.clone();
+        if let Some(async_body) = boxed_future_body(&mut block) {
+            async_body.block = construct_traced_block(
+                args,
+                attr_applied,
+                &async_sig,
+                &async_body.block,
+                outer_type_params,
+            );
+            return block;
+        }
+    }
+    let traced_block =
+        construct_unhygienic_block(args, attr_applied, sig, original_block, outer_type_params);
+    hygienic(traced_block, sig, original_block)
+}
+
+// the locals the traced block introduces (which are all named `fn_*`) can't be seen by the body,
+// the arguments, or the expressions given to `#[trace]`, which might use the same names, so e.g. a
+// parameter called `fn_depth` isn't shadowed in the body. The tokens of the function itself are
+// told apart from the ones the block is made of by their source text: the macro's own tokens are
+// spanned at the attribute (or macro) it was invoked by, while the tokens of the signature, the
+// body, and the expressions given as strings (like `if = "fn_depth > 0"`, which are spanned at the
+// string) are spanned where they were written. Without source text, it falls back to the names
+// the user wrote
+fn hygienic(
+    traced_block: syn::Block,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+) -> syn::Block {
+    fn idents(tokens: TokenStream, names: &mut HashSet<String>) {
+        for token in tokens {
+            match token {
+                proc_macro2::TokenTree::Ident(ident) => {
+                    names.insert(ident.to_string());
+                }
+                proc_macro2::TokenTree::Group(group) => idents(group.stream(), names),
+                _ => (),
+            }
+        }
+    }
+    fn respan(
+        tokens: TokenStream,
+        call_site: &Option<String>,
+        user_names: &HashSet<String>,
+    ) -> TokenStream {
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                proc_macro2::TokenTree::Ident(mut ident) => {
+                    let name = ident.to_string();
+                    let internal = name.starts_with("fn_")
+                        && match (ident.span().source_text(), call_site) {
+                            (Some(source_text), Some(call_site)) => source_text == *call_site,
+                            _ => !user_names.contains(&name),
+                        };
+                    if internal {
+                        ident.set_span(Span::mixed_site().located_at(ident.span()));
+                    }
+                    proc_macro2::TokenTree::Ident(ident)
+                }
+                proc_macro2::TokenTree::Group(group) => {
+                    let mut respanned = proc_macro2::Group::new(
+                        group.delimiter(),
+                        respan(group.stream(), call_site, user_names),
+                    );
+                    respanned.set_span(group.span());
+                    proc_macro2::TokenTree::Group(respanned)
+                }
+                token => token,
+            })
+            .collect()
+    }
+    let mut user_names = HashSet::new();
+    idents(sig.to_token_stream(), &mut user_names);
+    idents(original_block.to_token_stream(), &mut user_names);
+    let call_site = Span::call_site().source_text();
+    let tokens = respan(traced_block.to_token_stream(), &call_site, &user_names);
+    syn::parse2(tokens).expect("the traced block is still a block")
+}
+
+fn construct_unhygienic_block(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+    outer_type_params: &[String],
+) -> syn::Block {
+    // a function returning `!` never exits normally, so it's traced like one returning `()` (whose
+    // exit line is only printed if it panics), and the type checker is told the traced body
+    // doesn't return either
+    if let syn::ReturnType::Type(_, ref ty) = sig.output {
+        if let syn::Type::Never(_) = **ty {
+            let mut unit_sig = sig.clone();
+            unit_sig.output = syn::ReturnType::Default;
+            let traced_block = construct_unhygienic_block(
+                args,
+                attr_applied,
+                &unit_sig,
+                original_block,
+                outer_type_params,
+            );
+            return parse_quote! {{
+                #traced_block
+                ::core::unreachable!()
+            }};
+        }
+    }
+    if let Some(ref printer) = args.printer {
+        return construct_printer_block(args, attr_applied, sig, original_block, printer);
+    }
+    if args.count_only {
+        return construct_counting_block(args, sig, original_block);
+    }
+    if args.poll {
+        return construct_polled_block(args, attr_applied, sig, original_block, outer_type_params);
+    }
+    if args.tracing {
+        return construct_tracing_block(args, attr_applied, sig, original_block);
+    }
+    if args.otel {
+        return construct_otel_block(args, attr_applied, sig, original_block);
+    }
+    match args.output {
+        Some(args::Output::ChromeTrace(ref path)) => {
+            return construct_chrome_trace_block(args, sig, original_block, path);
+        }
+        Some(args::Output::HappensBefore) => {
+            return construct_happens_before_block(args, sig, original_block);
+        }
+        Some(args::Output::CallGraph) => {
+            return construct_call_graph_block(args, sig, original_block);
+        }
+        None => (),
+    }
+    let traced_block =
+        construct_sync_traced_block(args, attr_applied, sig, original_block, outer_type_params);
+    if sig.asyncness.is_none() {
+        return traced_block;
+    }
+    let depth_var = &depth_var_path(args);
+
+    // the body of an `async fn` only runs once the future is polled, so the exit line is printed
+    // when the future completes. Since other tasks may run on the same thread while the future is
+    // pending, it keeps its own depth, which is swapped in for `DEPTH` whenever it's polled and
+    // starts out as the depth of whatever polls it first (the same goes for the current call)
+    let (init_task_current_fn, swap_in_current_fn, swap_out_current_fn) = if tracks_current_call(
+        args,
+    ) {
+        (
+            quote!(let mut fn_task_current_call = TRACE_CURRENT_CALL.with(|c| c.get());),
+            quote!(let fn_thread_current_call = TRACE_CURRENT_CALL.with(|c| c.replace(fn_task_current_call));),
+            quote!(fn_task_current_call = TRACE_CURRENT_CALL.with(|c| c.replace(fn_thread_current_call));),
+        )
+    } else {
+        (quote!(), quote!(), quote!())
+    };
+    parse_quote! {{
+        let mut fn_task_depth = #depth_var.with(|d| d.get());
+        #init_task_current_fn
+        let mut fn_traced_future = ::std::pin::pin!(async move #traced_block);
+        ::std::future::poll_fn(move |cx| {
+            let fn_thread_depth = #depth_var.with(|d| d.replace(fn_task_depth));
+            #swap_in_current_fn
+            let poll = ::std::future::Future::poll(fn_traced_future.as_mut(), cx);
+            fn_task_depth = #depth_var.with(|d| d.replace(fn_thread_depth));
+            #swap_out_current_fn
+            poll
+        })
+        .await
+    }}
+}
+
+// with `printer`, the lines are printed by the given macro instead of `println!`, and the depth is
+// kept in an `AtomicUsize` instead of a thread-local, so the function only needs `core`. The macro
+// is given a format string with only `{}` and `{:?}` in it and the values to fill them with, which
+// both the macros of `core::fmt` and those of `defmt` take
+fn construct_printer_block(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+    printer: &syn::Path,
+) -> syn::Block {
+    let depth_var = &depth_var_path(args);
+    // the placeholders other than `{fn}` need `std` or named arguments
+    let parse_prefix = |prefix: &str| match parse_prefix(prefix, &sig.ident) {
+        Ok((prefix, prefix_args))
+            if prefix_args.is_empty() && !prefix.replace("{{", "").contains('{') =>
+        {
+            Ok(prefix)
+        }
+        Ok(_) => Err(syn::Error::new(
+            Span::call_site(),
+            "only the `{fn}` placeholder can be used in prefixes with `printer`",
+        )),
+        Err(e) => Err(e),
+    };
+    let (prefix_enter, prefix_exit) = match (
+        parse_prefix(&args.prefix_enter),
+        parse_prefix(&args.prefix_exit),
+    ) {
+        (Ok(prefix_enter), Ok(prefix_exit)) => (prefix_enter, prefix_exit),
+        (Err(e), _) | (_, Err(e)) => {
+            let error = e.into_compile_error();
+            return parse_quote! {{#error}};
+        }
+    };
+    let escaped = |text: String| text.replace('{', "{{").replace('}', "}}");
+
+    let mut arg_idents = extract_arg_idents(args, attr_applied, sig)
+        .into_iter()
+        .map(|ident| ident.to_token_stream())
+        .collect::<Vec<_>>();
+    if args.receiver && sig.receiver().is_some() {
+        arg_idents.insert(0, quote!(self));
+    }
+    if args.no_args {
+        arg_idents.clear();
+    }
+    let is_redacted = |arg_ident: &TokenStream| {
+        args.redact
+            .iter()
+            .any(|ident| *ident == arg_ident.to_string())
+    };
+    let arg_formats = arg_idents
+        .iter()
+        .map(|arg_ident| {
+            if is_redacted(arg_ident) {
+                format!("{} = <redacted>", arg_ident)
+            } else {
+                format!("{} = {{:?}}", arg_ident)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let arg_values = arg_idents
+        .iter()
+        .filter(|arg_ident| !is_redacted(arg_ident))
+        .map(|arg_ident| quote!(&#arg_ident));
+    let entering_format = format!(
+        "{{}}{} {} {}({})",
+        prefix_enter, args.word_enter, sig.ident, arg_formats
+    );
+    let (exiting_format, exit_value) = match sig.output {
+        _ if hides_return(args, sig) => (
+            format!("{{}}{} {} {}", prefix_exit, args.word_exit, sig.ident),
+            quote!(),
+        ),
+        syn::ReturnType::Type(_, ref ty) if args.hide_ret => (
+            format!(
+                "{{}}{} {} {} = <{}>",
+                prefix_exit,
+                args.word_exit,
+                sig.ident,
+                escaped(type_to_string(ty))
+            ),
+            quote!(),
+        ),
+        syn::ReturnType::Default if args.hide_ret => (
+            format!(
+                "{{}}{} {} {} = <()>",
+                prefix_exit, args.word_exit, sig.ident
+            ),
+            quote!(),
+        ),
+        _ => (
+            format!(
+                "{{}}{} {} {} = {{:?}}",
+                prefix_exit, args.word_exit, sig.ident
+            ),
+            quote!(, &fn_return_value),
+        ),
+    };
+    let spaces = " ".repeat(PRINTER_MAX_INDENT);
+    let (ret_type_annotation, run_body) = run_body(args, sig, original_block);
+
+    parse_quote! {{
+        // the depth goes back down even if the body panics
+        struct FnDepthGuard;
+        impl ::core::ops::Drop for FnDepthGuard {
+            fn drop(&mut self) {
+                #depth_var.fetch_sub(1, ::core::sync::atomic::Ordering::Relaxed);
+            }
+        }
+        let fn_depth = #depth_var.fetch_add(1, ::core::sync::atomic::Ordering::Relaxed);
+        let fn_depth_guard = FnDepthGuard;
+        let fn_indent = &#spaces[..::core::cmp::min(fn_depth, #PRINTER_MAX_INDENT)];
+        #printer!(#entering_format, fn_indent, #(#arg_values),*);
+        let fn_return_value #ret_type_annotation = #run_body;
+        #printer!(#exiting_format, fn_indent #exit_value);
+        ::core::mem::drop(fn_depth_guard);
+        fn_return_value
+    }}
+}
+
+// with `count_only`, a call only increments the function's counter, which registers itself the
+// first time so `print_stats!` can find it
+fn construct_counting_block(
+    args: &args::Args,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+) -> syn::Block {
+    let fn_name = sig.ident.to_string();
+    let trace_counts = trace_var_path(args, "TRACE_COUNTS");
+    parse_quote! {{
+        static FN_CALLS: ::std::sync::atomic::AtomicU64 = ::std::sync::atomic::AtomicU64::new(0);
+        static FN_REGISTER_CALLS: ::std::sync::Once = ::std::sync::Once::new();
+        FN_REGISTER_CALLS.call_once(|| {
+            ::std::result::Result::unwrap_or_else(
+                #trace_counts.lock(),
+                ::std::sync::PoisonError::into_inner,
+            )
+            .push((#fn_name, &FN_CALLS));
+        });
+        FN_CALLS.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed);
+        #original_block
+    }}
+}
+
+// with `poll`, the enter line is printed when the function is called, but the future it returns is
+// wrapped in one that prints a line when it's first polled and whenever it's woken, and the exit
+// line once it completes. Its lines are all at the depth the function was called at, and traced
+// calls made while it's polled are nested in it
+fn construct_polled_block(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+    outer_type_params: &[String],
+) -> syn::Block {
+    if let Some(asyncness) = sig.asyncness {
+        let error = syn::Error::new_spanned(
+            asyncness,
+            "`poll` is for functions returning `impl Future`, `async fn`s are already traced when their future completes",
+        )
+        .into_compile_error();
+        return parse_quote! {{#error}};
+    }
+    if !matches!(sig.output, syn::ReturnType::Type(_, ref ty) if matches!(**ty, syn::Type::ImplTrait(_)))
+    {
+        let error = syn::Error::new_spanned(
+            &sig.output,
+            "`poll` requires the function to return `impl Future`",
+        )
+        .into_compile_error();
+        return parse_quote! {{#error}};
+    }
+    let depth_var = &depth_var_path(args);
+    let (prefix_enter, enter_prefix_args) = match parse_prefix(&args.prefix_enter, &sig.ident) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let error = e.into_compile_error();
+            return parse_quote! {{#error}};
+        }
+    };
+    let (prefix_exit, exit_prefix_args) = match parse_prefix(&args.prefix_exit, &sig.ident) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let error = e.into_compile_error();
+            return parse_quote! {{#error}};
+        }
+    };
+    let value_format = if args.pretty { "{:#?}" } else { "{:?}" };
+
+    let mut type_params = type_param_names(&sig.generics);
+    type_params.extend_from_slice(outer_type_params);
+    let arg_types = sig
+        .inputs
+        .iter()
+        .filter_map(|input| match *input {
+            syn::FnArg::Typed(ref pat_type) => match *pat_type.pat {
+                syn::Pat::Ident(ref pat_ident) => {
+                    Some((pat_ident.ident.to_string(), &*pat_type.ty))
+                }
+                _ => None,
+            },
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let mut arg_idents = extract_arg_idents(args, attr_applied, sig)
+        .into_iter()
+        .map(|ident| ident.to_token_stream())
+        .collect::<Vec<_>>();
+    if args.receiver && sig.receiver().is_some() {
+        arg_idents.insert(0, quote!(self));
+    }
+    if args.no_args {
+        arg_idents.clear();
+    }
+    let is_redacted = |arg_ident: &TokenStream| {
+        args.redact
+            .iter()
+            .any(|ident| *ident == arg_ident.to_string())
+    };
+    let arg_formats = arg_idents
+        .iter()
+        .map(|arg_ident| {
+            if is_redacted(arg_ident) {
+                format!("{} = <redacted>", arg_ident)
+            } else {
+                format!("{} = {}", arg_ident, value_format)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let arg_values = arg_idents
+        .iter()
+        .filter(|arg_ident| !is_redacted(arg_ident))
+        .map(|arg_ident| match arg_types.get(&arg_ident.to_string()) {
+            Some(ty)
+                if args.lenient
+                    || mentions_type_param(ty, &type_params)
+                    || contains_impl_trait(ty.to_token_stream()) =>
+            {
+                debug_or_type_name(arg_ident)
+            }
+            _ => arg_ident.clone(),
+        })
+        .collect::<Vec<_>>();
+    let entering_format = format!(
+        "{{:depth$}}{} Entering {}({})",
+        prefix_enter, sig.ident, arg_formats
+    );
+    let polling_format = format!("{{:depth$}}{} Polling {}", POLL_PREFIX, sig.ident);
+    let waking_format = format!("{{:depth$}}{} Woke {}", WAKE_PREFIX, sig.ident);
+    let (exiting_format, exit_value) = if disables_return(args) {
+        (
+            format!("{{:depth$}}{} Exiting {}", prefix_exit, sig.ident),
+            quote!(),
+        )
+    } else {
+        let exit_value = if args.lenient {
+            debug_or_type_name(&quote!(fn_return_value))
+        } else {
+            quote!(fn_return_value)
+        };
+        (
+            format!(
+                "{{:depth$}}{} Exiting {} = {}",
+                prefix_exit, sig.ident, value_format
+            ),
+            quote!(#exit_value,),
+        )
+    };
+    // the ids a call of the function would have, if its prefixes use them
+    let current_call = if tracks_current_call(args) {
+        quote! {
+            #[allow(unused_variables)]
+            let fn_caller = TRACE_CURRENT_CALL.with(|c| c.get());
+            #[allow(unused_variables)]
+            let fn_call_id = TRACE_CALL_IDS.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed) + 1;
+        }
+    } else {
+        quote!()
+    };
+    let mut fn_silenced = if args.quiet_fmt {
+        quote!(TRACE_FORMATTING.with(|f| f.get()))
+    } else {
+        quote!(false)
+    };
+    if cfg!(feature = "runtime") {
+        fn_silenced.extend(quote!(|| !::trace_runtime::enabled()));
+    }
+    let println = default_println();
+    let flush_held_back_lines = flush_held_back_lines(&quote!(#depth_var));
+    let (_, run_body) = run_body(args, sig, original_block);
+
+    parse_quote! {{
+        let fn_depth = #depth_var.with(|d| d.get());
+        let fn_silenced = #fn_silenced;
+        #current_call
+        if !fn_silenced {
+            #flush_held_back_lines
+            #println!(#entering_format, "", #(#arg_values,)* #enter_prefix_args depth = fn_depth);
+        }
+        // the depth is restored even if the body or polling the future panics
+        struct FnDepthGuard(usize);
+        impl ::std::ops::Drop for FnDepthGuard {
+            fn drop(&mut self) {
+                #depth_var.with(|d| d.set(self.0));
+            }
+        }
+        let fn_future = {
+            let _fn_depth_guard = FnDepthGuard(#depth_var.with(|d| d.replace(fn_depth + 1)));
+            #run_body
+        };
+        // the waker handed to the future prints a line before waking the task, from whichever
+        // thread wakes it
+        struct FnWaker {
+            waker: ::std::task::Waker,
+            depth: usize,
+            silenced: bool,
+        }
+        impl ::std::task::Wake for FnWaker {
+            fn wake(self: ::std::sync::Arc<Self>) {
+                ::std::task::Wake::wake_by_ref(&self);
+            }
+            fn wake_by_ref(self: &::std::sync::Arc<Self>) {
+                if !self.silenced {
+                    #println!(#waking_format, "", depth = self.depth);
+                }
+                self.waker.wake_by_ref();
+            }
+        }
+        let mut fn_future = ::std::boxed::Box::pin(fn_future);
+        let mut fn_polled = false;
+        ::std::future::poll_fn(move |cx| {
+            if !fn_polled && !fn_silenced {
+                #flush_held_back_lines
+                #println!(#polling_format, "", depth = fn_depth);
+            }
+            fn_polled = true;
+            let fn_waker = ::std::task::Waker::from(::std::sync::Arc::new(FnWaker {
+                waker: ::std::clone::Clone::clone(cx.waker()),
+                depth: fn_depth,
+                silenced: fn_silenced,
+            }));
+            let fn_poll = {
+                let _fn_depth_guard = FnDepthGuard(#depth_var.with(|d| d.replace(fn_depth + 1)));
+                ::std::future::Future::poll(
+                    ::std::pin::Pin::as_mut(&mut fn_future),
+                    &mut ::std::task::Context::from_waker(&fn_waker),
+                )
+            };
+            match fn_poll {
+                ::std::task::Poll::Ready(fn_return_value) => {
+                    if !fn_silenced {
+                        #flush_held_back_lines
+                        #println!(#exiting_format, "", #exit_value #exit_prefix_args depth = fn_depth);
+                    }
+                    ::std::task::Poll::Ready(fn_return_value)
+                }
+                ::std::task::Poll::Pending => ::std::task::Poll::Pending,
+            }
+        })
+    }}
+}
+
+// the expression that runs the original body, along with the type annotation for the binding its
+// value is stored in
+fn run_body(
+    args: &args::Args,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+) -> (TokenStream, TokenStream) {
+    // annotating the binding keeps the return type from being inferred from how the value is
+    // printed (e.g. `ret_fmt_with` taking a `&[T]` for a `Vec<T>`), which isn't possible when the
+    // return type is opaque
+    let (ret_type_annotation, ret_type_arg) = match sig.output {
+        syn::ReturnType::Type(_, ref ty) if !contains_impl_trait(ty.to_token_stream()) => {
+            (quote!(: #ty), quote!(#ty))
+        }
+        _ => (quote!(), quote!(_)),
+    };
+    // the body is run in its own closure (or async block), so a `return` or `?` in it only leaves
+    // the body, and the exit line is still printed with whatever value it returned early with.
+    // Anything else the body evaluates to, like the value a tail `loop` or labeled block is left
+    // with by `break`, is returned from the closure the same way, without rewriting the body. In
+    // a traced closure, moving into it would move what the traced closure captured, making it
+    // `FnOnce`, so it borrows instead. It only uses `core`, since it's also used with `printer`.
+    // The body of a `#[track_caller]` function is run inline, since `Location::caller()` would see
+    // the closure instead of the caller, so a `return` or `?` in it leaves without an exit line
+    let capture = if args.closure { quote!() } else { quote!(move) };
+    let run_body = if sig.asyncness.is_some() {
+        quote!(async move #original_block.await)
+    } else if args.track_caller {
+        quote!(#original_block)
+    } else {
+        quote! {{
+            fn fn_call_once<R, F: ::core::ops::FnOnce() -> R>(f: F) -> R {
+                f()
+            }
+            fn_call_once::<#ret_type_arg, _>(#capture || #original_block)
+        }}
+    };
+    (ret_type_annotation, run_body)
+}
+
+// with `tracing`, a call is a span of the `tracing` crate instead of a pair of lines, with the
+// arguments and the return value recorded as its fields
+fn construct_tracing_block(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+) -> syn::Block {
+    let fn_name = sig.ident.to_string();
+    let mut arg_idents = extract_arg_idents(args, attr_applied, sig)
+        .into_iter()
+        .map(|ident| ident.to_token_stream())
+        .collect::<Vec<_>>();
+    if args.receiver && sig.receiver().is_some() {
+        arg_idents.insert(0, quote!(self));
+    }
+    if args.no_args {
+        arg_idents.clear();
+    }
+    let impl_trait_args = impl_trait_arg_names(sig);
+    let arg_fields = arg_idents.into_iter().map(|ident| {
+        let field_name = ident.to_string();
+        if args.lenient || impl_trait_args.contains(&field_name) {
+            let value = debug_or_type_name(&ident);
+            quote!(#field_name = ::tracing::field::debug(#value))
+        } else {
+            quote!(#field_name = ::tracing::field::debug(&#ident))
+        }
+    });
+    let (ret_type_annotation, run_body) = run_body(args, sig, original_block);
+    // the span is entered while the body runs, which for an `async fn` means every time its future
+    // is polled
+    let run_body = if sig.asyncness.is_some() {
+        quote! {
+            ::tracing::Instrument::instrument(async move #original_block, ::tracing::Span::clone(&fn_span)).await
+        }
+    } else {
+        quote! {{
+            let _fn_span_guard = ::tracing::Span::enter(&fn_span);
+            #run_body
+        }}
+    };
+    let return_field = match sig.output {
+        syn::ReturnType::Type(_, ref ty)
+            if args.hide_ret || matches!(**ty, syn::Type::ImplTrait(_)) =>
+        {
+            let type_name = format!("<{}>", type_to_string(ty));
+            quote!(::tracing::field::display(&#type_name))
+        }
+        syn::ReturnType::Default if args.hide_ret => quote!(::tracing::field::display(&"<()>")),
+        _ => match args.ret_fmt_with {
+            Some(ref fmt_with) => quote!(::tracing::field::display(&#fmt_with(&fn_return_value))),
+            None if args.lenient => {
+                let value = debug_or_type_name(&quote!(fn_return_value));
+                quote!(::tracing::field::debug(#value))
+            }
+            None => quote!(::tracing::field::debug(&fn_return_value)),
+        },
+    };
+
+    let record_return = if disables_return(args) {
+        quote!()
+    } else {
+        quote!(::tracing::Span::record(&fn_span, "return", &#return_field);)
+    };
+
+    parse_quote! {{
+        let fn_span = ::tracing::span!(
+            ::tracing::Level::TRACE,
+            #fn_name,
+            #(#arg_fields,)*
+            "return" = ::tracing::field::Empty,
+        );
+        let fn_return_value #ret_type_annotation = #run_body;
+        #record_return
+        fn_return_value
+    }}
+}
+
+// with `otel`, a call is an OpenTelemetry span started and ended by `trace_runtime`, with the
+// arguments as its attributes and the return value as an event
+fn construct_otel_block(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+) -> syn::Block {
+    let fn_name = sig.ident.to_string();
+    let mut arg_idents = extract_arg_idents(args, attr_applied, sig)
+        .into_iter()
+        .map(|ident| ident.to_token_stream())
+        .collect::<Vec<_>>();
+    if args.receiver && sig.receiver().is_some() {
+        arg_idents.insert(0, quote!(self));
+    }
+    if args.no_args {
+        arg_idents.clear();
+    }
+    let impl_trait_args = impl_trait_arg_names(sig);
+    let attributes = arg_idents.into_iter().map(|ident| {
+        let attribute_name = ident.to_string();
+        let value = if args.redact.iter().any(|ident| *ident == attribute_name) {
+            quote!(::std::string::ToString::to_string("<redacted>"))
+        } else if args.lenient || impl_trait_args.contains(&attribute_name) {
+            let value = debug_or_type_name(&ident);
+            quote!(::std::format!("{:?}", #value))
+        } else {
+            quote!(::std::format!("{:?}", &#ident))
+        };
+        quote!((#attribute_name, #value))
+    });
+    let (ret_type_annotation, run_body) = run_body(args, sig, original_block);
+    // the span is current while the body runs, which for an `async fn` means every time its future
+    // is polled
+    let run_body = if sig.asyncness.is_some() {
+        quote!(fn_otel_call.instrument(async move #original_block).await)
+    } else {
+        quote! {{
+            let _fn_otel_guard = fn_otel_call.enter();
+            #run_body
+        }}
+    };
+    let return_event = match sig.output {
+        _ if disables_return(args) => quote!(::std::option::Option::None),
+        syn::ReturnType::Type(_, ref ty)
+            if args.hide_ret || matches!(**ty, syn::Type::ImplTrait(_)) =>
+        {
+            let type_name = format!("<{}>", type_to_string(ty));
+            quote!(::std::option::Option::Some(::std::string::ToString::to_string(#type_name)))
+        }
+        syn::ReturnType::Default if args.hide_ret => {
+            quote!(::std::option::Option::Some(
+                ::std::string::ToString::to_string("<()>")
+            ))
+        }
+        _ => {
+            let ret = match args.ret_fmt_with {
+                Some(ref fmt_with) => quote!(::std::format!("{}", #fmt_with(&fn_return_value))),
+                None if args.lenient => {
+                    let value = debug_or_type_name(&quote!(fn_return_value));
+                    quote!(::std::format!("{:?}", #value))
+                }
+                None => quote!(::std::format!("{:?}", &fn_return_value)),
+            };
+            quote!(::std::option::Option::Some(#ret))
+        }
+    };
+
+    parse_quote! {{
+        let fn_otel_call = ::trace_runtime::otel::Call::start(
+            #fn_name,
+            ::std::vec![#(#attributes),*],
+        );
+        let fn_return_value #ret_type_annotation = #run_body;
+        fn_otel_call.exit(#return_event);
+        fn_return_value
+    }}
+}
+
+// with `output = "chrome_trace"`, a call is a pair of begin and end events recorded by
+// `trace_runtime` instead of a pair of lines
+fn construct_chrome_trace_block(
+    args: &args::Args,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+    path: &str,
+) -> syn::Block {
+    let fn_name = sig.ident.to_string();
+    let (ret_type_annotation, run_body) = run_body(args, sig, original_block);
+
+    parse_quote! {{
+        // the end event is recorded even if the body panics (or the future of an `async fn` is
+        // dropped before it completes)
+        struct FnEndGuard;
+        impl ::std::ops::Drop for FnEndGuard {
+            fn drop(&mut self) {
+                ::trace_runtime::chrome_trace::end(#path, #fn_name);
+            }
+        }
+        ::trace_runtime::chrome_trace::begin(#path, #fn_name);
+        let fn_end_guard = FnEndGuard;
+        let fn_return_value #ret_type_annotation = #run_body;
+        ::std::mem::drop(fn_end_guard);
+        fn_return_value
+    }}
+}
+
+// with `output = "happens_before"`, a call is recorded by `trace_runtime::happens_before` along
+// with the clock of its thread when it's entered and exited
+fn construct_happens_before_block(
+    args: &args::Args,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+) -> syn::Block {
+    let fn_name = sig.ident.to_string();
+    let (ret_type_annotation, run_body) = run_body(args, sig, original_block);
+
+    // the exit is recorded when the guard is dropped, even if the body panics
+    parse_quote! {{
+        let fn_call_guard = ::trace_runtime::happens_before::enter(#fn_name);
+        let fn_return_value #ret_type_annotation = #run_body;
+        ::std::mem::drop(fn_call_guard);
+        fn_return_value
+    }}
+}
+
+// with `output = "call_graph"`, a call is recorded by `trace_runtime::call_graph` as an edge from
+// the traced function it was made by
+fn construct_call_graph_block(
+    args: &args::Args,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+) -> syn::Block {
+    let fn_name = sig.ident.to_string();
+    let (ret_type_annotation, run_body) = run_body(args, sig, original_block);
+
+    // the call stays the caller of the calls made by its body until the guard is dropped, even if
+    // the body panics
+    parse_quote! {{
+        let fn_call_guard = ::trace_runtime::call_graph::enter(#fn_name);
+        let fn_return_value #ret_type_annotation = #run_body;
+        ::std::mem::drop(fn_call_guard);
+        fn_return_value
+    }}
+}
+
+fn construct_sync_traced_block(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    sig: &syn::Signature,
+    original_block: &syn::Block,
+    outer_type_params: &[String],
+) -> syn::Block {
+    let depth_var = &depth_var_path(args);
+    // values whose type depends on a type parameter or is `impl Trait` (or any values, with
+    // `lenient`) are printed with `Debug` only if their type is known to implement it, and by the
+    // name of their type otherwise
+    let mut type_params = type_param_names(&sig.generics);
+    type_params.extend_from_slice(outer_type_params);
+    let may_lack_debug = |ty: &syn::Type| {
+        args.lenient
+            || mentions_type_param(ty, &type_params)
+            || contains_impl_trait(ty.to_token_stream())
+    };
+    let escaped = |text: String| text.replace('{', "{{").replace('}', "}}");
+    let mut arg_idents = extract_arg_idents(args, attr_applied, sig)
+        .iter()
+        .map(|ident| ident.to_token_stream())
+        .collect::<Vec<_>>();
+    // `self` is only printed with `receiver` (or as its id with `instance_ids`), but can always be
+    // interpolated in `format_enter`
+    if sig.receiver().is_some()
+        && (args.receiver || args.instance_ids || args.format_enter.is_some())
+    {
+        arg_idents.insert(0, quote!(self));
+    }
+    // the arguments that get their reference counts printed with `rc_counts`
+    let arg_types = sig
+        .inputs
+        .iter()
+        .filter_map(|input| match *input {
+            syn::FnArg::Typed(ref pat_type) => match *pat_type.pat {
+                syn::Pat::Ident(ref pat_ident) => {
+                    Some((pat_ident.ident.to_string(), &*pat_type.ty))
+                }
+                _ => None,
+            },
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let record_call = record_call(args, sig, &arg_idents, &arg_types, &may_lack_debug);
+    let fold_fingerprint = fold_fingerprint(args, sig, &arg_idents, &arg_types, &may_lack_debug);
+    let (count_heat, heat_arg) = count_heat(args);
+    // with `debugger_hook`, every call (printed or not) calls a function that does nothing but can
+    // have a breakpoint set on it
+    let (debugger_hook_enter, debugger_hook_exit) = if args.debugger_hook {
+        let fn_name = sig.ident.to_string();
+        (
+            quote!(::trace_runtime::debugger_hook(#fn_name, true);),
+            quote!(::trace_runtime::debugger_hook(#fn_name, false);),
+        )
+    } else {
+        (quote!(), quote!())
+    };
+    // with `breakpoint`, the calls that are printed stop in an attached debugger once their enter
+    // line is
+    let breakpoint = if args.breakpoint {
+        quote!(::trace_runtime::breakpoint();)
+    } else {
+        quote!()
+    };
+    // with `dedup`, every call is numbered so that repeated argument values can refer back to the
+    // call they were first printed in
+    let count_call = if args.dedup.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            let fn_call_number = {
+                static FN_CALLS: ::std::sync::atomic::AtomicU64 = ::std::sync::atomic::AtomicU64::new(0);
+                FN_CALLS.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed) + 1
+            };
+        }
+    };
+    // values interpolated in `format_enter` (like `{config:#?}`) or printed with `Display` may span
+    // several lines, so the whole enter line is indented like a pretty printed value once it's
+    // formatted, rather than every pretty printed argument on its own
+    let reindent_enter =
+        args.format.is_none() && (args.format_enter.is_some() || !args.display.is_empty());
+    // bare `pretty` applies to everything that's interpolated, and a list only to what it names
+    let pretty_interpolated = |ident: &str| match args.pretty_inputs {
+        None => args.pretty,
+        Some(args::PrettyArgs::All) => ident != "r" || args.pretty,
+        Some(args::PrettyArgs::Only(ref idents)) => {
+            (ident == "r" && args.pretty) || idents.iter().any(|arg_ident| arg_ident == ident)
+        }
+    };
+    let (enter_format, arg_idents) = if let Some(fmt_str) = &args.format_enter {
+        parse_fmt_str(fmt_str, arg_idents, None, &pretty_interpolated)
+    } else {
+        enter_args_format(
+            args,
+            sig,
+            arg_idents,
+            &arg_types,
+            &type_params,
+            &may_lack_debug,
+            reindent_enter,
+        )
+    };
+    // we set set exit val to be a vector with one element which is Ident called r
+    // this means that the format parser can indentify when then return value should be interprolated
+    // so if we want to use a different symbol to denote return value interpolation we just need to change the symbol in the following quote
+    // ie: `let exit_val = vec![quote!(return_value)];` if we wanted to use return_value to denote return value interpolation
+    let exit_val = vec![quote!(r)];
+    // return types that can't be printed (`impl Trait`, or anything when `hide_ret` is given) are
+    // shown by the name of the type written in the signature instead of by value
+    // `r` in `display` stands for the return value, like in `format_exit`
+    let ret_display = args.display.iter().any(|ident| ident == "r");
+    let hidden_ret_type = match sig.output {
+        syn::ReturnType::Type(_, ref ty)
+            if args.hide_ret || (matches!(**ty, syn::Type::ImplTrait(_)) && !ret_display) =>
+        {
+            Some(format!("<{}>", type_to_string(ty)))
+        }
+        syn::ReturnType::Default if args.hide_ret => Some("<()>".to_string()),
+        _ => None,
+    };
+    let ret_disabled = hides_return(args, sig);
+    let (exit_format, exit_val) = if ret_disabled {
+        (Ok(String::new()), vec![])
+    } else if let Some(fmt_str) = &args.format_exit {
+        // the parameters are named so that interpolating one gets a better error than a typo does
+        let mut params = extract_arg_idents(args, AttrApplied::Indirectly, sig)
+            .iter()
+            .map(|ident| ident.to_token_stream())
+            .collect::<Vec<_>>();
+        if sig.receiver().is_some() {
+            params.push(quote!(self));
+        }
+        parse_fmt_str(fmt_str, exit_val, Some(&params), &pretty_interpolated)
+    } else if let Some(ret_format) = &args.ret_format {
+        (Ok(ret_format.clone()), exit_val)
+    } else if hidden_ret_type.is_some() || args.ret_fmt_with.is_some() {
+        (Ok("{}".to_string()), exit_val)
+    } else {
+        let mut exit_format = match args.max_length {
+            Some(_) => "{}",
+            None if ret_display => "{}",
+            None if args.pretty => "{:#?}",
+            None => "{:?}",
+        }
+        .to_string();
+        let mut exit_val = exit_val;
+        let ret_rc_type = match sig.output {
+            syn::ReturnType::Type(_, ref ty) if args.rc_counts => rc_type(ty),
+            _ => None,
+        };
+        if let Some((rc_name, rc_path)) = ret_rc_type {
+            exit_format.push_str(&format!(" {}(strong={{}}, weak={{}})", rc_name));
+            exit_val.push(quote!(#rc_path::strong_count(&fn_return_value)));
+            exit_val.push(quote!(#rc_path::weak_count(&fn_return_value)));
+        }
+        // constructors that put the new instance behind a pointer know where it'll stay, so it
+        // gets its id right away
+        if args.instance_ids && sig.receiver().is_none() && returns_boxed_self(&sig.output) {
+            exit_format.push_str(" #{}");
+            exit_val.push(instance_id(args, &quote!(&*fn_return_value)));
+        }
+        (Ok(exit_format), exit_val)
+    };
+    // with `types`, the return value is preceded by its type, like the arguments are
+    let ret_typed =
+        args.types && !ret_disabled && args.format_exit.is_none() && args.ret_format.is_none();
+    let (exit_format, exit_val) = match sig.output {
+        _ if !ret_typed => (exit_format, exit_val),
+        syn::ReturnType::Type(_, ref ty) if mentions_type_param(ty, &type_params) => {
+            let mut typed_exit_val = vec![quote!(::std::any::type_name_of_val(&fn_return_value))];
+            typed_exit_val.extend(exit_val);
+            (
+                exit_format.map(|exit_format| format!("{{}} = {}", exit_format)),
+                typed_exit_val,
+            )
+        }
+        syn::ReturnType::Type(_, ref ty) => (
+            exit_format
+                .map(|exit_format| format!("{} = {}", escaped(type_to_string(ty)), exit_format)),
+            exit_val,
+        ),
+        syn::ReturnType::Default => (
+            exit_format.map(|exit_format| format!("() = {}", exit_format)),
+            exit_val,
+        ),
+    };
+    let (prefix_enter, enter_prefix_args) = match parse_prefix(&args.prefix_enter, &sig.ident) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let error = e.into_compile_error();
+            return parse_quote! {{#error}};
+        }
+    };
+    let (prefix_exit, exit_prefix_args) = match parse_prefix(&args.prefix_exit, &sig.ident) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let error = e.into_compile_error();
+            return parse_quote! {{#error}};
+        }
+    };
+    // with the `runtime` feature, the prefixes and the formats of the arguments and the return
+    // value can be overridden with environment variables read by `trace_runtime`, unless the lines
+    // have a fixed format
+    let runtime_overrides = cfg!(feature = "runtime") && !args.compact && args.format.is_none();
+    let (prefix_enter, enter_prefix_args) = if runtime_overrides {
+        overridable_prefix(
+            quote!(::trace_runtime::prefix_enter()),
+            prefix_enter,
+            enter_prefix_args,
+        )
+    } else {
+        (prefix_enter, enter_prefix_args)
+    };
+    let (prefix_exit, exit_prefix_args) = if runtime_overrides {
+        overridable_prefix(
+            quote!(::trace_runtime::prefix_exit()),
+            prefix_exit,
+            exit_prefix_args,
+        )
+    } else {
+        (prefix_exit, exit_prefix_args)
+    };
+    // calls that return an `Err` are told apart from other calls by the type of the return value
+    // (see `is_err`)
+    let prefix_error = match args.prefix_error {
+        Some(ref prefix_error) => match parse_prefix(prefix_error, &sig.ident) {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                let error = e.into_compile_error();
+                return parse_quote! {{#error}};
+            }
+        },
+        None => None,
+    };
+    let (line_start, line_end, line_args) = line_decorations(args, sig);
+    let (prefix_panic, panic_prefix_args) = match args.prefix_panic {
+        Some(ref prefix_panic) => match parse_prefix(prefix_panic, &sig.ident) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                let error = e.into_compile_error();
+                return parse_quote! {{#error}};
+            }
+        },
+        None => (prefix_exit.clone(), exit_prefix_args.clone()),
+    };
+    let (word_enter, word_exit, word_enter_arg, word_exit_arg) = words(args, runtime_overrides);
+    let (line_fn_name, fn_name_arg) = qualified_fn_name(args, sig);
+    let enter_prefix_args =
+        quote!(#enter_prefix_args #word_enter_arg #fn_name_arg #heat_arg #line_args);
+    // with `exit_args`, the name of the function in exit lines is followed by its arguments
+    let (exit_fn_name, exit_args_arg) = if args.exit_args {
+        (
+            format!("{}({{fn_exit_args}})", line_fn_name),
+            quote!(fn_exit_args = fn_exit_args,),
+        )
+    } else {
+        (line_fn_name.clone(), quote!())
+    };
+    let exit_prefix_args =
+        quote!(#exit_prefix_args #word_exit_arg #fn_name_arg #exit_args_arg #line_args);
+    let prefix_error = prefix_error.map(|(prefix, prefix_args)| {
+        (
+            prefix,
+            quote!(#prefix_args #word_exit_arg #fn_name_arg #exit_args_arg #line_args),
+        )
+    });
+    let panic_prefix_args = quote!(#panic_prefix_args #word_exit_arg #fn_name_arg #line_args);
+    let enter_format = match enter_format {
+        Ok(ok) => ok,
+        Err(e) => {
+            let error = e.into_compile_error();
+            return parse_quote! {{#error}};
+        }
+    };
+    // lines are indented by the (right-padded) argument before the `{:depth$}`, which is the
+    // `indent` repeated for every level, or nothing if it's the default of a space per level
+    let indentation = |depth: TokenStream| match args.indent {
+        Some(ref indent) => quote!(&*::std::primitive::str::repeat(#indent, #depth)),
+        None => quote!(""),
+    };
+    let (indent, guard_indent, summary_indent) = (
+        indentation(quote!(fn_depth)),
+        indentation(quote!(self.1)),
+        indentation(quote!(fn_depth + 1)),
+    );
+    // the arguments are formatted on their own, so `TRACE_FORMAT_ENTER` can put them anywhere
+    let (enter_format, arg_idents) = if runtime_overrides {
+        let args_format = format!("{{:.0}}{}", enter_format);
+        let formatted_args = quote! {{
+            let fn_args = ::std::format!(#args_format, "", #(#arg_idents),*);
+            match ::trace_runtime::format_enter() {
+                ::std::option::Option::Some(format) => format.replace("{args}", &fn_args),
+                ::std::option::Option::None => fn_args,
+            }
+        }};
+        ("{}".to_string(), vec![formatted_args])
+    } else {
+        (enter_format, arg_idents)
+    };
+    // with `summarize_below_depth`, the arguments of calls at that depth or deeper are printed as
+    // `…`, so deep recursion still shows its calls without their details
+    let (enter_format, arg_idents) = match args.summarize_below_depth {
+        Some(depth) if !enter_format.is_empty() => {
+            let depth = proc_macro2::Literal::u64_unsuffixed(depth);
+            let args_format = format!("{{:.0}}{}", enter_format);
+            let summarized_args = quote! {
+                if fn_depth >= #depth {
+                    ::std::string::ToString::to_string("…")
+                } else {
+                    ::std::format!(#args_format, "", #(#arg_idents),*)
+                }
+            };
+            ("{}".to_string(), vec![summarized_args])
+        }
+        _ => (enter_format, arg_idents),
+    };
+    // the depth is a field of JSON lines, so the `{:depth$}` indentation is left out with `{:.0}`
+    let heat = if args.heat { "{heat}" } else { "" };
+    let entering_format = if args.format.is_some() {
+        format!(
+            "{{:.0}}{{{{\"event\":\"enter\",\"fn\":\"{}\",\"depth\":{{depth}},\"thread\":{{json_thread}},\"args\":{{{{{}}}}}}}}}",
+            sig.ident, enter_format
+        )
+    } else if args.compact {
+        let enter_format = if enter_format.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", enter_format)
+        };
+        format!(
+            "{}{{:depth$}}> {}{}{}{}",
+            line_start, line_fn_name, enter_format, heat, line_end
+        )
+    } else {
+        format!(
+            "{}{{:depth$}}{} {} {}({}){}{}",
+            line_start, prefix_enter, word_enter, line_fn_name, enter_format, heat, line_end
+        )
+    };
+    // the enter line without the arguments, for writers of the info tier
+    let info_entering_format = format!(
+        "{}{{:depth$}}{} {} {}(){}{}",
+        line_start, prefix_enter, word_enter, line_fn_name, heat, line_end
+    );
+    let exit_format = match exit_format {
+        Ok(ok) => ok,
+        Err(e) => {
+            let error = e.into_compile_error();
+            return parse_quote! {{#error}};
+        }
+    };
+
+    let pause_stmt = pause_stmt(args);
+    let (pause_after_enter, pause_after_exit) = match args.pause {
+        Some(args::Pause {
+            on: Some(args::PauseOn::Enter),
+            ..
+        }) => (pause_stmt, quote!()),
+        Some(args::Pause {
+            on: Some(args::PauseOn::Exit),
+            ..
+        }) => (quote!(), pause_stmt),
+        _ => (pause_stmt.clone(), pause_stmt),
+    };
+
+    let print_line = |line_args: TokenStream| print_line(args, line_args);
+    let exit_value = match (&hidden_ret_type, &args.ret_fmt_with) {
+        (Some(type_name), _) => quote!(#type_name),
+        (None, Some(fmt_with)) => quote!(#fmt_with(&fn_return_value)),
+        (None, None) if args.format_exit.is_none() && args.ret_format.is_none() => {
+            let exit_value = match sig.output {
+                syn::ReturnType::Type(_, ref ty) if may_lack_debug(ty) && !ret_display => {
+                    debug_or_type_name(&quote!(fn_return_value))
+                }
+                _ => quote!(fn_return_value),
+            };
+            match args.max_length {
+                Some(max_length) => {
+                    let value_format = if ret_display {
+                        "{}"
+                    } else if args.pretty {
+                        "{:#?}"
+                    } else {
+                        "{:?}"
+                    };
+                    truncated(value_format, &exit_value, max_length)
+                }
+                None => exit_value,
+            }
+        }
+        (None, None) => quote!(fn_return_value),
+    };
+    // everything interpolated in the exit line besides the return value (e.g. counters) is passed
+    // to the printer as is, except that expressions starting with `r` (like `{r.len()}`) start
+    // with the return value instead
+    let mut exit_values = exit_val
+        .iter()
+        .map(|val| {
+            if val.to_string() == "r" {
+                return exit_value.clone();
+            }
+            let mut tokens = val.clone().into_iter();
+            match tokens.next() {
+                Some(proc_macro2::TokenTree::Ident(ref ident)) if ident == "r" => {
+                    let rest = tokens.collect::<TokenStream>();
+                    quote!(fn_return_value #rest)
+                }
+                _ => val.clone(),
+            }
+        })
+        .collect::<Vec<_>>();
+    let try_accessor_decl = try_accessor_decl(arg_idents.iter().chain(&exit_values));
+    // the return value is formatted on its own, so `TRACE_FORMAT_EXIT` can put it anywhere
+    let exit_format = if runtime_overrides && !ret_disabled {
+        let ret_format = format!("{{:.0}}{}", exit_format);
+        exit_values = vec![quote! {{
+            let fn_ret = ::std::format!(#ret_format, "", #(#exit_values),*);
+            match ::trace_runtime::format_exit() {
+                ::std::option::Option::Some(format) => format.replace("{r}", &fn_ret),
+                ::std::option::Option::None => fn_ret,
+            }
+        }}];
+        "{}".to_string()
+    } else {
+        exit_format
+    };
+    // the return values of summarized calls are printed as `…` like their arguments
+    let exit_format = match args.summarize_below_depth {
+        Some(depth) if !ret_disabled => {
+            let depth = proc_macro2::Literal::u64_unsuffixed(depth);
+            let ret_format = format!("{{:.0}}{}", exit_format);
+            exit_values = vec![quote! {
+                if fn_depth >= #depth {
+                    ::std::string::ToString::to_string("…")
+                } else {
+                    ::std::format!(#ret_format, "", #(#exit_values),*)
+                }
+            }];
+            "{}".to_string()
+        }
+        _ => exit_format,
+    };
+    // with `format = "json"`, the return value is a JSON string of what would be printed
+    let exit_format = if args.format.is_some() && ret_disabled {
+        "\"return\":null".to_string()
+    } else if args.format.is_some() {
+        exit_values = vec![json_string(quote!(
+            ::std::format!(#exit_format, #(#exit_values),*)
+        ))];
+        "\"return\":{}".to_string()
+    } else {
+        exit_format
+    };
+    // the exit line without anything after the return value, for writers of the debug tier
+    let (debug_exit_format, debug_exit_values) = (exit_format.clone(), exit_values.clone());
+    // with `timing`, the time the call took is printed at the end of the exit line
+    let timing = args.timing || args.timing_threshold.is_some();
+    let exit_format = if timing {
+        exit_values.push(quote!(fn_elapsed));
+        if args.format.is_some() {
+            format!("{},\"elapsed\":\"{{:?}}\"", exit_format)
+        } else if args.compact {
+            format!("{} {{:?}}", exit_format)
+        } else {
+            format!("{} ({{:?}})", exit_format)
+        }
+    } else {
+        exit_format
+    };
+    // with `alloc_peak`, the most memory the call had allocated at once (on top of what was already
+    // allocated when it started) is printed after that, as tracked by `trace_runtime`'s allocator
+    // (the peak is read as soon as the body returns, since formatting the exit line can allocate)
+    let (start_alloc_peak, stop_alloc_peak, exit_format) = if args.alloc_peak {
+        exit_values.push(quote!(fn_alloc_peak));
+        let exit_format = if args.format.is_some() {
+            format!("{},\"peak_alloc\":{{}}", exit_format)
+        } else {
+            format!("{} (peak alloc {{}} bytes)", exit_format)
+        };
+        (
+            quote!(let fn_alloc_peak = ::trace_runtime::AllocPeak::start();),
+            quote!(let fn_alloc_peak = fn_alloc_peak.peak();),
+            exit_format,
+        )
+    } else {
+        (quote!(), quote!(), exit_format)
+    };
+    let (ret_type_annotation, run_body) = run_body(args, sig, original_block);
+    // with `stubs`, a call that a `todo!()` or `unimplemented!()` panics out of is reported as
+    // returning `<unimplemented>` instead of panicking, along with the traced calls leading to it.
+    // The innermost call it panics out of marks itself as the stub while the panic is caught (and
+    // resumed), which its panic guard then reports. The body of an `async fn` can't be run under
+    // `catch_unwind`, and neither can the inline body of a `#[track_caller]` function, so their
+    // panics are reported like any other
+    let stubs = args.stubs && sig.asyncness.is_none() && !args.track_caller;
+    let run_body = if stubs {
+        catch_stub(sig, run_body)
+    } else {
+        run_body
+    };
+    // with `panic_message`, a panic is caught (and resumed) to hand its message to the panic guard
+    // for the exit line. The body of an `async fn` or a `#[track_caller]` function can't be run
+    // under `catch_unwind`, so its panics are reported without one
+    let panic_message = args.panic_message && sig.asyncness.is_none() && !args.track_caller;
+    let guard_message_index = syn::Index::from(if tracks_current_call(args) { 5 } else { 4 });
+    let run_body = if panic_message {
+        quote! {
+            match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| #run_body)) {
+                ::std::result::Result::Ok(value) => value,
+                ::std::result::Result::Err(payload) => {
+                    let message = match payload.downcast_ref::<&str>() {
+                        ::std::option::Option::Some(message) => ::std::string::ToString::to_string(*message),
+                        ::std::option::Option::None => match payload.downcast_ref::<::std::string::String>() {
+                            ::std::option::Option::Some(message) => ::std::clone::Clone::clone(message),
+                            ::std::option::Option::None => ::std::string::ToString::to_string("Box<dyn Any>"),
+                        },
+                    };
+                    fn_panic_guard.#guard_message_index.set(::std::option::Option::Some(message));
+                    ::std::panic::resume_unwind(payload)
+                }
+            }
+        }
+    } else {
+        run_body
+    };
+    let record_stats = record_stats(args, sig, outer_type_params);
+    // while a function traced with `quiet_fmt` formats its arguments or return value,
+    // `TRACE_FORMATTING` is set, and traced functions called by `Debug` impls in the meantime see
+    // that they're being called while formatting and don't print anything. A guard puts it back
+    // even if a `Debug` impl panics
+    let while_formatting = |print: TokenStream| {
+        if args.quiet_fmt {
+            quote! {
+                let fn_formatting_guard = {
+                    struct FnFormattingGuard(bool);
+                    impl ::std::ops::Drop for FnFormattingGuard {
+                        fn drop(&mut self) {
+                            TRACE_FORMATTING.with(|f| f.set(self.0));
+                        }
+                    }
+                    FnFormattingGuard(TRACE_FORMATTING.with(|f| f.replace(true)))
+                };
+                #print
+                ::std::mem::drop(fn_formatting_guard);
+            }
+        } else {
+            print
+        }
+    };
+    // with the `runtime` feature, enter and exit lines are written with less detail to the writers
+    // of lower tiers: without the arguments and return value for the info tier, and without the
+    // timing and allocations after the return value for the debug tier. Lines that don't go
+    // through `trace_runtime` are always written in full
+    let tiered = cfg!(feature = "runtime")
+        && args.logging.is_none()
+        && args.writer.is_none()
+        && args.on_error.is_none()
+        && !args.scoped_sink
+        && args.chunk_markers.is_none()
+        && !args.compact
+        && args.format.is_none();
+    let print_tiered_line = |info: TokenStream, debug: TokenStream, trace: TokenStream| {
+        if tiered {
+            quote! {
+                ::trace_runtime::write_tiered_line(
+                    ::std::format_args!(#info),
+                    ::std::format_args!(#debug),
+                    ::std::format_args!(#trace),
+                );
+            }
+        } else {
+            print_line(trace)
+        }
+    };
+    let exiting_format_with = |prefix: &str, exit_format: &str, ret_disabled: bool| {
+        if args.format.is_some() {
+            format!(
+                "{{:.0}}{{{{\"event\":\"exit\",\"fn\":\"{}\",\"depth\":{{depth}},\"thread\":{{json_thread}},{}}}}}",
+                sig.ident, exit_format
+            )
+        } else if args.compact {
+            let eq = if ret_disabled { "" } else { " =" };
+            format!(
+                "{}{{:depth$}}< {}{}{}{}",
+                line_start, line_fn_name, eq, exit_format, line_end
+            )
+        } else {
+            let eq = if ret_disabled {
+                ""
+            } else if ret_typed {
+                " -> "
+            } else {
+                " = "
+            };
+            format!(
+                "{}{{:depth$}}{} {} {}{}{}{}",
+                line_start, prefix, word_exit, exit_fn_name, eq, exit_format, line_end
+            )
+        }
+    };
+    let exiting_format_with_prefix =
+        |prefix: &str| exiting_format_with(prefix, &exit_format, ret_disabled);
+    // an exit line with a return value that may span several lines is indented once it's
+    // formatted, like the enter line
+    let reindent_exit =
+        args.format.is_none() && (args.pretty || args.format_exit.is_some() || ret_display);
+    let reindent_enter_line = if reindent_enter {
+        quote! {
+            let fn_enter_line = fn_enter_line.replace(
+                '\n',
+                &::std::format!("\n{:depth$}{} ", #indent, #PRETTY_CONTINUATION, depth = fn_depth),
+            );
+        }
+    } else {
+        quote!()
+    };
+    let print_exit_with_prefix = |prefix: &str, prefix_args: &TokenStream| {
+        let exiting_format = exiting_format_with_prefix(prefix);
+        if reindent_exit {
+            // every line of the pretty printed value after the first is indented to the current
+            // depth and marked as a continuation, so multi-line values don't break up the tree
+            let print_continued_exit_line =
+                print_line(quote!("{}", exit_line.replace('\n', &continuation)));
+            let format_exit_line = while_formatting(quote! {
+                let exit_line = ::std::format!(#exiting_format, #indent, #(#exit_values,)* #prefix_args depth = fn_depth);
+            });
+            quote! {{
+                #format_exit_line
+                let continuation = ::std::format!("\n{:depth$}{} ", #indent, #PRETTY_CONTINUATION, depth = fn_depth);
+                #print_continued_exit_line
+            }}
+        } else {
+            let info_exiting_format = exiting_format_with(prefix, "", true);
+            let debug_exiting_format =
+                exiting_format_with(prefix, &debug_exit_format, ret_disabled);
+            while_formatting(print_tiered_line(
+                quote!(#info_exiting_format, #indent, #prefix_args depth = fn_depth),
+                quote! {
+                    #debug_exiting_format, #indent, #(#debug_exit_values,)* #prefix_args depth = fn_depth
+                },
+                quote! {
+                    #exiting_format, #indent, #(#exit_values,)* #prefix_args depth = fn_depth
+                },
+            ))
+        }
+    };
+    let print_exit = print_exit_with_prefix(&prefix_exit, &exit_prefix_args);
+    let print_exit = match prefix_error {
+        Some((ref prefix_error, ref error_prefix_args)) => {
+            let print_error = print_exit_with_prefix(prefix_error, error_prefix_args);
+            let is_err = is_err(&quote!(fn_return_value));
+            quote! {
+                if #is_err {
+                    #print_error
+                } else {
+                    #print_exit
+                }
+            }
+        }
+        None => print_exit,
+    };
+    // with `max_depth_summary`, calls hidden by `max_depth` are counted, and the calls at the last
+    // printed level report how many calls below them were hidden before their exit line
+    let (count_hidden_calls, print_exit) = match args.max_depth {
+        Some(max_depth) if args.max_depth_summary => {
+            let max_depth = proc_macro2::Literal::u64_unsuffixed(max_depth);
+            let summary_format = format!(
+                "{}{{:depth$}}… {{}} deeper traced calls hidden (max {{}} levels){}",
+                line_start, line_end
+            );
+            let print_summary = print_line(quote! {
+                #summary_format, #summary_indent, fn_hidden_calls, #max_depth, #line_args depth = fn_depth + 1
+            });
+            (
+                quote! {
+                    let fn_hidden_calls = TRACE_HIDDEN_CALLS.with(|c| {
+                        if fn_depth >= #max_depth {
+                            c.set(c.get() + 1);
+                        }
+                        c.get()
+                    });
+                },
+                quote! {
+                    let fn_hidden_calls = TRACE_HIDDEN_CALLS.with(|c| c.get()) - fn_hidden_calls;
+                    if fn_hidden_calls > 0 && fn_depth + 1 == #max_depth {
+                        #print_summary
+                    }
+                    #print_exit
+                },
+            )
+        }
+        _ => (quote!(), print_exit),
+    };
+
+    let rate_limit = rate_limit(
+        args,
+        sig,
+        depth_var,
+        &indent,
+        &line_start,
+        line_end,
+        &line_args,
+    );
+
+    let formatting = if args.quiet_fmt {
+        quote!(TRACE_FORMATTING.with(|f| f.get()))
+    } else {
+        quote!(false)
+    };
+    let mut fn_silenced = fn_silenced(args, attr_applied, sig, &formatting);
+    let register_call = register_call(args, sig, &mut fn_silenced);
+    if let Some(ref filter_var) = args.runtime_filter {
+        fn_silenced.extend(runtime_filter(sig, filter_var));
+    }
+    if let Some(ref tag) = args.tag {
+        fn_silenced.extend(quote!(|| !::trace_runtime::tag_enabled(#tag)));
+    }
+    // with `sample`, every call is counted (even if it isn't printed for another reason), and only
+    // the first call and every `sample`th call after it are printed, along with the first
+    // `sample_first` calls
+    if args.sample.is_some() || args.sample_first.is_some() {
+        let every_sample = args.sample.map(|sample| {
+            let sample = proc_macro2::Literal::u64_unsuffixed(sample);
+            quote!(fn_sampled_call % #sample == 0)
+        });
+        let first_samples = args.sample_first.map(|sample_first| {
+            let sample_first = proc_macro2::Literal::u64_unsuffixed(sample_first);
+            quote!(fn_sampled_call < #sample_first)
+        });
+        let sampled = every_sample.into_iter().chain(first_samples);
+        fn_silenced = quote! {
+            {
+                static FN_SAMPLED_CALLS: ::std::sync::atomic::AtomicU64 =
+                    ::std::sync::atomic::AtomicU64::new(0);
+                let fn_sampled_call =
+                    FN_SAMPLED_CALLS.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed);
+                !(#(#sampled)||*)
+            } || #fn_silenced
+        };
+    }
+
+    // in a `trace_runtime::verbose_scope`, every call is printed, except for the ones made while
+    // formatting
+    if cfg!(feature = "runtime") {
+        fn_silenced = quote! {
+            #formatting || (!::trace_runtime::verbose() && (#fn_silenced))
+        };
+    }
+
+    // a panicking call still gets an exit line (after its enter line, if that was deferred), and
+    // leaves `DEPTH` and the current function as they were before the call while unwinding
+    let fn_name = sig.ident.to_string();
+    let (set_caller, restore_caller, guard_call_id) = track_current_call(args, &fn_name);
+    let (guard_call_id_field, guard_call_id, guard_read_call_id) = guard_call_id;
+    let panicking_format = if args.format.is_some() {
+        format!(
+            "{{:.0}}{{{{\"event\":\"exit\",\"fn\":\"{}\",\"depth\":{{depth}},\"thread\":{{json_thread}},\"panicked\":true}}}}",
+            sig.ident
+        )
+    } else if args.compact {
+        format!(
+            "{}{{:depth$}}< {} !panicked{}",
+            line_start, line_fn_name, line_end
+        )
+    } else {
+        format!(
+            "{}{{:depth$}}{} {} {} (panicked){}",
+            line_start, prefix_panic, word_exit, line_fn_name, line_end
+        )
+    };
+    let print_deferred_enter_line = print_line(quote!("{}", enter_line));
+    let print_panic_line = print_line(quote! {
+        #panicking_format, #guard_indent, #panic_prefix_args depth = self.1
+    });
+    // the message of a panic caught with `panic_message` goes where the return value would
+    let (guard_message_field, guard_message, print_panic_line) = if panic_message {
+        let message_format = if args.format.is_some() {
+            format!(
+                "{{:.0}}{{{{\"event\":\"exit\",\"fn\":\"{}\",\"depth\":{{depth}},\"thread\":{{json_thread}},\"panicked\":true,\"panic\":{{}}}}}}",
+                sig.ident
+            )
+        } else if args.compact {
+            format!(
+                "{}{{:depth$}}< {} !panicked {{:?}}{}",
+                line_start, line_fn_name, line_end
+            )
+        } else {
+            format!(
+                "{}{{:depth$}}{} {} {} = panicked: {{:?}}{}",
+                line_start, prefix_panic, word_exit, line_fn_name, line_end
+            )
+        };
+        let message = if args.format.is_some() {
+            json_string(quote!(fn_panic_message))
+        } else {
+            quote!(fn_panic_message)
+        };
+        let print_message_line = print_line(quote! {
+            #message_format, #guard_indent, #message, #panic_prefix_args depth = self.1
+        });
+        (
+            quote!(::std::cell::Cell<::std::option::Option<::std::string::String>>,),
+            quote!(::std::cell::Cell::new(::std::option::Option::None),),
+            quote! {
+                if let ::std::option::Option::Some(fn_panic_message) = self.#guard_message_index.take() {
+                    #print_message_line
+                } else {
+                    #print_panic_line
+                }
+            },
+        )
+    } else {
+        (quote!(), quote!(), print_panic_line)
+    };
+    // the call a `todo!()` or `unimplemented!()` panicked out of is the innermost one left on the
+    // call path, and every call traced with `stubs` takes itself off it while unwinding
+    let print_panic_line = if stubs {
+        let stub_format = if args.compact {
+            format!(
+                "{}{{:depth$}}< {} = <unimplemented{{}}> ({{}}){}",
+                line_start, line_fn_name, line_end
+            )
+        } else {
+            format!(
+                "{}{{:depth$}}{} {} {} = <unimplemented{{}}> ({{}}){}",
+                line_start, prefix_panic, word_exit, line_fn_name, line_end
+            )
+        };
+        let print_stub_line = print_line(quote! {
+            #stub_format, #guard_indent, fn_stub_message, fn_call_path, #panic_prefix_args depth = self.1
+        });
+        quote! {
+            if let ::std::option::Option::Some((fn_call_path, fn_stub_message)) = fn_stub {
+                #print_stub_line
+            } else {
+                #print_panic_line
+            }
+        }
+    } else {
+        print_panic_line
+    };
+    let take_stub = if stubs {
+        quote! {
+            let fn_stub = TRACE_CALL_PATH.with(|path| {
+                let mut path = ::std::cell::RefCell::borrow_mut(path);
+                let stub = match path.1 {
+                    ::std::option::Option::Some((level, ref message)) if level + 1 == path.0.len() => {
+                        ::std::option::Option::Some((path.0.join(" > "), ::std::clone::Clone::clone(message)))
+                    }
+                    _ => ::std::option::Option::None,
+                };
+                path.0.pop();
+                stub
+            });
+        }
+    } else {
+        quote!()
+    };
+    // the lines that were pending until the outermost traced call returns are written before its
+    // exit line
+    let write_pending_lines = write_pending_lines(depth_var);
+    // with `buffered`, the lines held back are written once the outermost traced call returns (or
+    // panics), after its exit line
+    let flush_guard = if args.buffered.is_some() {
+        let flush_buffer = flush_buffer(&trace_var_path(args, "TRACE_BUFFER"));
+        quote! {
+            struct FnFlushGuard;
+            impl ::std::ops::Drop for FnFlushGuard {
+                fn drop(&mut self) {
+                    #flush_buffer
+                }
+            }
+            let _fn_flush_guard = (fn_depth == 0).then_some(FnFlushGuard);
+        }
+    } else {
+        quote!()
+    };
+    // the inline body of a `#[track_caller]` function can be left with a `return` or `?`, which
+    // drops the panic guard without running what comes after the body, so the guard leaves `DEPTH`
+    // and the current function as they were then too. It's forgotten when the body ends normally
+    let (left_inline_body, drop_panic_guard) = if args.track_caller && sig.asyncness.is_none() {
+        (
+            quote! {
+                else {
+                    #depth_var.with(|d| d.set(d.get() - 1));
+                    let fn_caller = self.3;
+                    #restore_caller
+                }
+            },
+            quote!(::std::mem::forget(fn_panic_guard);),
+        )
+    } else {
+        (quote!(), quote!(::std::mem::drop(fn_panic_guard);))
+    };
+    let panic_guard_decl = quote! {
+        struct FnPanicGuard<'a>(
+            ::std::option::Option<&'a str>,
+            usize,
+            bool,
+            ::std::option::Option<(&'static str, u64)>,
+            #guard_call_id_field
+            #guard_message_field
+        );
+        impl ::std::ops::Drop for FnPanicGuard<'_> {
+            fn drop(&mut self) {
+                if ::std::thread::panicking() {
+                    #depth_var.with(|d| d.set(d.get() - 1));
+                    #[allow(unused_variables)]
+                    let fn_depth = self.1;
+                    let fn_caller = self.3;
+                    #guard_read_call_id
+                    #restore_caller
+                    #take_stub
+                    if !self.2 {
+                        if let ::std::option::Option::Some(enter_line) = self.0 {
+                            #print_deferred_enter_line
+                        }
+                        #print_panic_line
+                    }
+                } #left_inline_body
+            }
+        }
+    };
+
+    // with `on_enter` and `on_exit`, the calls that are printed (or would be, with `silent`) are
+    // also passed to the hooks, with their arguments and return value formatted like in their lines
+    // with `exit_args`, the arguments are formatted when the call is entered, since the body may
+    // move or change them, to be repeated in its exit line
+    let format_exit_args = if args.exit_args {
+        let args_format = format!("{{:.0}}{}", enter_format);
+        let format_args = while_formatting(quote! {
+            fn_exit_args = ::std::format!(#args_format, "", #(#arg_idents),*);
+        });
+        quote! {
+            #[allow(unused_assignments)]
+            let mut fn_exit_args = ::std::string::String::new();
+            if !fn_silenced {
+                #format_args
+            }
+        }
+    } else {
+        quote!()
+    };
+    let call_on_enter = match args.on_enter {
+        Some(ref on_enter) => {
+            let fn_name = sig.ident.to_string();
+            let args_format = format!("{{:.0}}{}", enter_format);
+            while_formatting(quote! {
+                #on_enter(#fn_name, fn_depth, &::std::format!(#args_format, "", #(#arg_idents),*));
+            })
+        }
+        None => quote!(),
+    };
+    let call_on_exit = match args.on_exit {
+        Some(ref on_exit) => {
+            let fn_name = sig.ident.to_string();
+            let ret_format = format!("{{:.0}}{}", debug_exit_format);
+            while_formatting(quote! {
+                #on_exit(#fn_name, fn_depth, &::std::format!(#ret_format, "", #(#debug_exit_values),*));
+            })
+        }
+        None => quote!(),
+    };
+    // with `silent`, nothing is printed, not even when the call panics
+    let guard_silenced = if args.silent || args.bench {
+        quote!(true)
+    } else {
+        quote!(fn_silenced)
+    };
+
+    // `timing_threshold` defers printing a call like `deferred` does, but only reports slow calls
+    let deferred = match (&args.deferred, args.timing_threshold) {
+        (Some(deferred), _) => Some((
+            true,
+            deferred
+                .threshold_ms
+                .map(|threshold_ms| quote!(::std::time::Duration::from_millis(#threshold_ms))),
+        )),
+        (None, Some(threshold)) => {
+            let threshold_ns = threshold.as_nanos() as u64;
+            Some((
+                false,
+                Some(quote!(::std::time::Duration::from_nanos(#threshold_ns))),
+            ))
+        }
+        (None, None) => None,
+    };
+    if let Some((report_errors, threshold)) = deferred {
+        let mut emit_conditions = vec![];
+        if report_errors {
+            emit_conditions.push(is_err(&quote!(fn_return_value)));
+        }
+        if let Some(threshold) = threshold {
+            emit_conditions.push(quote!(fn_elapsed >= #threshold));
+        }
+        if cfg!(feature = "runtime") {
+            emit_conditions.push(quote!(::trace_runtime::verbose()));
+        }
+        let print_enter_line = print_line(quote!("{}", fn_enter_line));
+        let emit_stmt = if emit_conditions.is_empty() {
+            quote!()
+        } else {
+            quote! {
+                if !fn_silenced && (#(#emit_conditions)||*) {
+                    #print_enter_line
+                    #print_exit
+                }
+            }
+        };
+        let format_enter_line = while_formatting(quote! {
+            let fn_enter_line = ::std::format!(#entering_format, #indent, #(#arg_idents,)* #enter_prefix_args depth = fn_depth);
+            #reindent_enter_line
+        });
+
+        // the enter line is formatted up front, since the arguments may be moved by the body, and
+        // only printed (along with the exit line) once we know the call is worth reporting
+        return parse_quote! {{
+            let fn_depth = #depth_var.with(|d| d.get());
+            #flush_guard
+            #set_caller
+            #register_call
+            let fn_silenced = #fn_silenced;
+            #rate_limit
+            #record_call
+            #fold_fingerprint
+            #count_heat
+            #count_call
+            #debugger_hook_enter
+            #count_hidden_calls
+            #format_exit_args
+            #format_enter_line
+            if !fn_silenced {
+                #call_on_enter
+            }
+            #panic_guard_decl
+            #try_accessor_decl
+            let fn_start = ::std::time::Instant::now();
+            #start_alloc_peak
+            #depth_var.with(|d| d.set(d.get() + 1));
+            let fn_panic_guard = FnPanicGuard(
+                ::std::option::Option::Some(&fn_enter_line),
+                fn_depth,
+                fn_silenced,
+                fn_caller,
+                #guard_call_id
+                #guard_message
+            );
+            let fn_return_value #ret_type_annotation = #run_body;
+            #stop_alloc_peak
+            #debugger_hook_exit
+            let fn_elapsed = ::std::time::Instant::elapsed(&fn_start);
+            #record_stats
+            #drop_panic_guard
+            #depth_var.with(|d| d.set(d.get() - 1));
+            #restore_caller
+            #write_pending_lines
+            if !fn_silenced {
+                #call_on_exit
+            }
+            #emit_stmt
+            fn_return_value
+        }};
+    }
+
+    // with `errors_only`, only the exit lines of calls that returned an `Err` are printed
+    let (print_enter, print_exit) = if args.errors_only {
+        let is_err = is_err(&quote!(fn_return_value));
+        let print_exit = quote! {
+            if #is_err {
+                #print_exit
+            }
+        };
+        (quote!(), print_exit)
+    } else {
+        let print_enter = if reindent_enter {
+            let print_continued_enter_line = print_tiered_line(
+                quote!(#info_entering_format, #indent, #enter_prefix_args depth = fn_depth),
+                quote!("{}", fn_enter_line),
+                quote!("{}", fn_enter_line),
+            );
+            while_formatting(quote! {
+                let fn_enter_line = ::std::format!(#entering_format, #indent, #(#arg_idents,)* #enter_prefix_args depth = fn_depth);
+                #reindent_enter_line
+                #print_continued_enter_line
+            })
+        } else {
+            while_formatting(print_tiered_line(
+                quote!(#info_entering_format, #indent, #enter_prefix_args depth = fn_depth),
+                quote! {
+                    #entering_format, #indent, #(#arg_idents,)* #enter_prefix_args depth = fn_depth
+                },
+                quote! {
+                    #entering_format, #indent, #(#arg_idents,)* #enter_prefix_args depth = fn_depth
+                },
+            ))
+        };
+        (print_enter, print_exit)
+    };
+    // with `collapse_repeats`, a call whose lines are the same as those of the call just before it
+    // (with nothing printed in between) is only counted, and the count is printed once something
+    // else is, or the outermost traced call returns. A call's enter line is held back while it
+    // might be a repeat, and printed as soon as anything else is printed after all
+    let (print_enter, print_exit) = if args.collapse_repeats {
+        let format_exit_line = |prefix: &str, prefix_args: &TokenStream| {
+            let exiting_format = exiting_format_with_prefix(prefix);
+            let exit_line = quote! {
+                ::std::format!(#exiting_format, #indent, #(#exit_values,)* #prefix_args depth = fn_depth)
+            };
+            if reindent_exit {
+                quote! {
+                    #exit_line.replace(
+                        '\n',
+                        &::std::format!("\n{:depth$}{} ", #indent, #PRETTY_CONTINUATION, depth = fn_depth),
+                    )
+                }
+            } else {
+                exit_line
+            }
+        };
+        let exit_line = format_exit_line(&prefix_exit, &exit_prefix_args);
+        let exit_line = match prefix_error {
+            Some((ref prefix_error, ref error_prefix_args)) => {
+                let error_line = format_exit_line(prefix_error, error_prefix_args);
+                let is_err = is_err(&quote!(fn_return_value));
+                quote! {
+                    if #is_err {
+                        #error_line
+                    } else {
+                        #exit_line
+                    }
+                }
+            }
+            None => exit_line,
+        };
+        // the line reporting the repeats is split where their number goes
+        let summary_format = format!("{}{{:depth$}}… repeated \0{}", line_start, line_end);
+        let print_lines = print_line(quote!("{}", fn_line));
+        // printing anything first reports the repeats of the last call and prints the enter line
+        // held back
+        let flush_repeats = quote! {
+            let mut fn_lines = ::std::vec::Vec::new();
+            if let ::std::option::Option::Some((_, _, (ref start, ref end))) = repeats.0 {
+                if repeats.1 > 0 {
+                    let times = if repeats.1 == 1 { "time" } else { "times" };
+                    fn_lines.push(::std::format!("{}{} {}{}", start, repeats.1, times, end));
+                }
+            }
+            repeats.0 = ::std::option::Option::None;
+            repeats.1 = 0;
+            ::std::iter::Extend::extend(&mut fn_lines, repeats.2.take());
+        };
+        let format_enter_line = while_formatting(quote! {
+            let fn_enter_line = ::std::format!(#entering_format, #indent, #(#arg_idents,)* #enter_prefix_args depth = fn_depth);
+            #reindent_enter_line
+        });
+        let format_exit_line = while_formatting(quote! {
+            let fn_exit_line = #exit_line;
+        });
+        // the repeats of calls in another traced call are also reported when the outermost one
+        // returns. Calls that aren't in one are still collapsed with the calls after them
+        let write_repeats_when_outermost_call_returns =
+            write_when_outermost_call_returns(depth_var, &quote!(fn_write_repeats));
+        (
+            quote! {
+                #format_enter_line
+                let fn_lines = TRACE_REPEATS.with(|repeats| {
+                    let mut repeats = ::std::cell::RefCell::borrow_mut(repeats);
+                    let is_repeat = repeats.2.is_none()
+                        && ::std::option::Option::is_some_and(repeats.0.as_ref(), |(enter_line, _, _)| {
+                            *enter_line == fn_enter_line
+                        });
+                    if is_repeat {
+                        repeats.2 = ::std::option::Option::Some(fn_enter_line);
+                        return ::std::vec::Vec::new();
+                    }
+                    #flush_repeats
+                    repeats.3 = ::std::option::Option::Some(::std::clone::Clone::clone(&fn_enter_line));
+                    fn_lines.push(fn_enter_line);
+                    fn_lines
+                });
+                for fn_line in fn_lines {
+                    #print_lines
+                }
+            },
+            quote! {
+                fn fn_write_repeats() {
+                    let fn_lines = TRACE_REPEATS.with(|repeats| {
+                        let mut repeats = ::std::cell::RefCell::borrow_mut(repeats);
+                        #flush_repeats
+                        fn_lines
+                    });
+                    for fn_line in fn_lines {
+                        #print_lines
+                    }
+                }
+                #format_exit_line
+                let fn_summary = ::std::format!(#summary_format, #indent, #line_args depth = fn_depth);
+                let fn_summary = match fn_summary.split_once('\0') {
+                    ::std::option::Option::Some((start, end)) => (
+                        ::std::string::ToString::to_string(start),
+                        ::std::string::ToString::to_string(end),
+                    ),
+                    ::std::option::Option::None => (fn_summary, ::std::string::String::new()),
+                };
+                let fn_lines = TRACE_REPEATS.with(|repeats| {
+                    let mut repeats = ::std::cell::RefCell::borrow_mut(repeats);
+                    // the enter line of this call is still held back if nothing was printed since
+                    if let ::std::option::Option::Some(fn_enter_line) = repeats.2.take() {
+                        let is_repeat = ::std::option::Option::is_some_and(repeats.0.as_ref(), |(_, exit_line, _)| {
+                            *exit_line == fn_exit_line
+                        });
+                        if is_repeat {
+                            repeats.1 += 1;
+                            if fn_depth > 0 {
+                                #write_repeats_when_outermost_call_returns
+                            }
+                            return ::std::vec::Vec::new();
+                        }
+                        #flush_repeats
+                        fn_lines.push(::std::clone::Clone::clone(&fn_enter_line));
+                        fn_lines.push(::std::clone::Clone::clone(&fn_exit_line));
+                        repeats.0 = ::std::option::Option::Some((fn_enter_line, fn_exit_line, fn_summary));
+                        repeats.3 = ::std::option::Option::None;
+                        return fn_lines;
+                    }
+                    // nothing was printed since the enter line if it's still the last line
+                    let fn_enter_line = repeats.3.take();
+                    #flush_repeats
+                    fn_lines.push(::std::clone::Clone::clone(&fn_exit_line));
+                    repeats.0 = fn_enter_line.map(|fn_enter_line| (fn_enter_line, fn_exit_line, fn_summary));
+                    fn_lines
+                });
+                for fn_line in fn_lines {
+                    #print_lines
+                }
+            },
+        )
+    } else {
+        (print_enter, print_exit)
+    };
+    // in a `trace_runtime::verbose_scope`, calls get lines of their own, with all of their
+    // arguments and their return value pretty printed, and how long they took (calls whose lines
+    // are JSON or deduplicated keep their usual lines)
+    let verbose = cfg!(feature = "runtime") && args.format.is_none() && !args.collapse_repeats;
+    let (print_enter, print_exit) = if verbose {
+        let verbose_args = sig
+            .inputs
+            .iter()
+            .filter_map(|input| match *input {
+                syn::FnArg::Receiver(_) => Some(quote!(self)),
+                syn::FnArg::Typed(ref pat_type) => match *pat_type.pat {
+                    syn::Pat::Ident(ref pat_ident) => Some(pat_ident.ident.to_token_stream()),
+                    _ => None,
+                },
+            })
+            .collect::<Vec<_>>();
+        let is_redacted =
+            |arg: &TokenStream| args.redact.iter().any(|ident| *ident == arg.to_string());
+        let verbose_args_format = verbose_args
+            .iter()
+            .map(|arg| {
+                if is_redacted(arg) {
+                    format!("{} = <redacted>", arg)
+                } else {
+                    format!("{} = {{:#?}}", arg)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let verbose_arg_values = verbose_args
+            .iter()
+            .filter(|arg| !is_redacted(arg))
+            .map(debug_or_type_name);
+        let heat = if args.heat { "{heat}" } else { "" };
+        let verbose_entering_format = format!(
+            "{}{{:depth$}}{} {} {}({}){}{}",
+            line_start, prefix_enter, word_enter, line_fn_name, verbose_args_format, heat, line_end
+        );
+        let verbose_exiting_format = format!(
+            "{}{{:depth$}}{} {} {} = {{:#?}} ({{:?}}){}",
+            line_start, prefix_exit, word_exit, exit_fn_name, line_end
+        );
+        let verbose_return_value = debug_or_type_name(&quote!(fn_return_value));
+        let print_verbose_line = print_line(quote!("{}", fn_line.replace('\n', &continuation)));
+        let print_verbose_enter = while_formatting(quote! {
+            let fn_line = ::std::format!(#verbose_entering_format, #indent, #(#verbose_arg_values,)* #enter_prefix_args depth = fn_depth);
+            let continuation = ::std::format!("\n{:depth$}{} ", #indent, #PRETTY_CONTINUATION, depth = fn_depth);
+            #print_verbose_line
+        });
+        let print_verbose_exit = while_formatting(quote! {
+            let fn_line = ::std::format!(#verbose_exiting_format, #indent, #verbose_return_value, fn_elapsed, #exit_prefix_args depth = fn_depth);
+            let continuation = ::std::format!("\n{:depth$}{} ", #indent, #PRETTY_CONTINUATION, depth = fn_depth);
+            #print_verbose_line
+        });
+        (
+            quote! {
+                if ::trace_runtime::verbose() {
+                    #print_verbose_enter
+                } else {
+                    #print_enter
+                }
+            },
+            quote! {
+                if ::trace_runtime::verbose() {
+                    #print_verbose_exit
+                } else {
+                    #print_exit
+                }
+            },
+        )
+    } else {
+        (print_enter, print_exit)
+    };
+    let (print_enter, print_exit) = if args.silent || args.bench {
+        (quote!(), quote!())
+    } else {
+        (print_enter, print_exit)
+    };
+    // with `bench`, the report is printed once `main` returns, if it's traced with `bench` too
+    let print_bench_report = if args.bench && sig.ident == "main" {
+        let println = default_println();
+        let bench_table = bench_table(&trace_var_path(args, "TRACE_STATS"));
+        quote! {
+            for line in #bench_table {
+                #println!("{}", line);
+            }
+        }
+    } else {
+        quote!()
+    };
+    let (start_timer, stop_timer) = if timing || args.stats || args.bench || verbose {
+        (
+            quote!(let fn_start = ::std::time::Instant::now();),
+            quote!(let fn_elapsed = ::std::time::Instant::elapsed(&fn_start);),
+        )
+    } else {
+        (quote!(), quote!())
+    };
+    parse_quote! {{
+        let fn_depth = #depth_var.with(|d| d.get());
+        #flush_guard
+        #set_caller
+        #register_call
+        let fn_silenced = #fn_silenced;
+        #rate_limit
+        #record_call
+        #fold_fingerprint
+        #count_heat
+        #count_call
+        #debugger_hook_enter
+        #count_hidden_calls
+        #format_exit_args
+        if !fn_silenced {
+            #call_on_enter
+            #print_enter
+            #breakpoint
+            #pause_after_enter
+        }
+        #panic_guard_decl
+        #try_accessor_decl
+        #depth_var.with(|d| d.set(d.get() + 1));
+        let fn_panic_guard = FnPanicGuard(
+            ::std::option::Option::None,
+            fn_depth,
+            #guard_silenced,
+            fn_caller,
+            #guard_call_id
+            #guard_message
+        );
+        #start_timer
+        #start_alloc_peak
+        let fn_return_value #ret_type_annotation = #run_body;
+        #stop_alloc_peak
+        #debugger_hook_exit
+        #stop_timer
+        #record_stats
+        #drop_panic_guard
+        #depth_var.with(|d| d.set(d.get() - 1));
+        #restore_caller
+        #write_pending_lines
+        if !fn_silenced {
+            #call_on_exit
+            #print_exit
+            #pause_after_exit
+        }
+        #print_bench_report
+        fn_return_value
+    }}
+}
+
+// runs the body of a function traced with `stubs`, noting which call a `todo!()` or
+// `unimplemented!()` panicked out of
+fn catch_stub(sig: &syn::Signature, run_body: TokenStream) -> TokenStream {
+    let fn_name = sig.ident.to_string();
+    quote! {{
+        TRACE_CALL_PATH.with(|path| {
+            let mut path = ::std::cell::RefCell::borrow_mut(path);
+            // a stub that was already reported is done with once calls are made at its level
+            // again
+            if ::std::option::Option::is_some_and(path.1.as_ref(), |&(level, _)| level >= path.0.len()) {
+                path.1 = ::std::option::Option::None;
+            }
+            path.0.push(#fn_name);
+        });
+        let fn_value = match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| #run_body)) {
+            ::std::result::Result::Ok(value) => value,
+            ::std::result::Result::Err(payload) => {
+                let message = match payload.downcast_ref::<&str>() {
+                    ::std::option::Option::Some(message) => ::std::option::Option::Some(*message),
+                    ::std::option::Option::None => payload
+                        .downcast_ref::<::std::string::String>()
+                        .map(::std::string::String::as_str),
+                };
+                let stub_message = message.and_then(|message| {
+                    message
+                        .strip_prefix("not yet implemented")
+                        .or_else(|| message.strip_prefix("not implemented"))
+                });
+                if let ::std::option::Option::Some(stub_message) = stub_message {
+                    TRACE_CALL_PATH.with(|path| {
+                        let mut path = ::std::cell::RefCell::borrow_mut(path);
+                        if path.1.is_none() {
+                            let level = path.0.len() - 1;
+                            path.1 = ::std::option::Option::Some((level, ::std::string::ToString::to_string(stub_message)));
+                        }
+                    });
+                }
+                ::std::panic::resume_unwind(payload)
+            }
+        };
+        TRACE_CALL_PATH.with(|path| {
+            ::std::cell::RefCell::borrow_mut(path).0.pop();
+        });
+        fn_value
+    }}
+}
+
+// with `rate_limit`, calls that would be printed take a token from a bucket that holds a
+// second's worth of calls and is refilled as time passes, and are dropped if it's empty. The
+// next call that's printed tells how many were dropped before its enter line, or if the calls
+// dropped are in another traced call, the outermost one does when it returns, at their depth
+fn rate_limit(
+    args: &args::Args,
+    sig: &syn::Signature,
+    depth_var: &TokenStream,
+    indent: &TokenStream,
+    line_start: &str,
+    line_end: &str,
+    line_args: &TokenStream,
+) -> TokenStream {
+    match args.rate_limit {
+        Some(rate_limit) => {
+            let dropped_format = if args.format.is_some() {
+                format!(
+                    "{{:.0}}{{{{\"event\":\"dropped\",\"fn\":\"{}\",\"depth\":{{depth}},\"thread\":{{json_thread}},\"calls\":{{}}}}}}",
+                    sig.ident
+                )
+            } else {
+                format!(
+                    "{}{{:depth$}}… dropped {{}} calls of {} (over {}/s){}",
+                    line_start, sig.ident, rate_limit, line_end
+                )
+            };
+            let print_dropped = print_line(
+                args,
+                quote! {
+                    #dropped_format, #indent, fn_dropped_calls, #line_args depth = fn_depth
+                },
+            );
+            let write_dropped_calls_when_outermost_call_returns =
+                write_when_outermost_call_returns(depth_var, &quote!(fn_write_dropped_calls));
+            let rate_limit = rate_limit as f64;
+            quote! {
+                let fn_silenced = fn_silenced || {
+                    static FN_RATE_LIMIT: ::std::sync::Mutex<
+                        ::std::option::Option<(::std::primitive::f64, ::std::time::Instant)>,
+                    > = ::std::sync::Mutex::new(::std::option::Option::None);
+                    static FN_DROPPED_CALLS: ::std::sync::atomic::AtomicU64 =
+                        ::std::sync::atomic::AtomicU64::new(0);
+                    static FN_DROPPED_CALLS_DEPTH: ::std::sync::atomic::AtomicUsize =
+                        ::std::sync::atomic::AtomicUsize::new(0);
+                    fn fn_write_dropped_calls() {
+                        let fn_dropped_calls =
+                            FN_DROPPED_CALLS.swap(0, ::std::sync::atomic::Ordering::Relaxed);
+                        let fn_depth = FN_DROPPED_CALLS_DEPTH.load(::std::sync::atomic::Ordering::Relaxed);
+                        if fn_dropped_calls > 0 {
+                            #print_dropped
                         }
-                        _ => (),
-                    },
-                    _ => (),
-                }
+                    }
+                    let fn_now = ::std::time::Instant::now();
+                    let fn_has_token = {
+                        let mut fn_bucket = ::std::result::Result::unwrap_or_else(
+                            FN_RATE_LIMIT.lock(),
+                            ::std::sync::PoisonError::into_inner,
+                        );
+                        let (fn_tokens, fn_refilled) = fn_bucket.get_or_insert((#rate_limit, fn_now));
+                        let fn_elapsed = ::std::time::Instant::duration_since(&fn_now, *fn_refilled);
+                        *fn_tokens = ::std::primitive::f64::min(
+                            *fn_tokens + fn_elapsed.as_secs_f64() * #rate_limit,
+                            #rate_limit,
+                        );
+                        *fn_refilled = fn_now;
+                        if *fn_tokens >= 1.0 {
+                            *fn_tokens -= 1.0;
+                            true
+                        } else {
+                            false
+                        }
+                    };
+                    FN_DROPPED_CALLS_DEPTH.store(fn_depth, ::std::sync::atomic::Ordering::Relaxed);
+                    if fn_has_token {
+                        fn_write_dropped_calls();
+                    } else {
+                        FN_DROPPED_CALLS.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed);
+                        if fn_depth > 0 {
+                            #write_dropped_calls_when_outermost_call_returns
+                        }
+                    }
+                    !fn_has_token
+                };
             }
+        }
+        None => quote!(),
+    }
+}
 
-            transform_item(args, AttrApplied::Indirectly, item);
-        });
+// without `format_enter`, the arguments are printed one after another, each formatted the way
+// its options say
+fn enter_args_format(
+    args: &args::Args,
+    sig: &syn::Signature,
+    arg_idents: Vec<TokenStream>,
+    arg_types: &HashMap<String, &syn::Type>,
+    type_params: &[String],
+    may_lack_debug: &dyn Fn(&syn::Type) -> bool,
+    reindent_enter: bool,
+) -> (Result<String, syn::Error>, Vec<TokenStream>) {
+    let rc_args = arg_types
+        .iter()
+        .filter(|_| args.rc_counts)
+        .filter_map(|(arg_name, ty)| Some((arg_name, rc_type(ty)?)))
+        .collect::<HashMap<_, _>>();
+    let mut arg_formats = vec![];
+    let mut arg_values = vec![];
+    for arg_ident in arg_idents.into_iter().filter(|_| !args.no_args) {
+        let is_in = |idents: &HashSet<proc_macro2::Ident>| {
+            idents.iter().any(|ident| ident == &arg_ident.to_string())
+        };
+        let is_pretty = match args.pretty_inputs {
+            Some(args::PrettyArgs::All) => true,
+            Some(args::PrettyArgs::Only(ref idents)) => is_in(idents),
+            None => false,
+        };
+        let arg_format = if is_in(&args.hex) {
+            "{:#x}"
+        } else if is_in(&args.bin) {
+            "{:#b}"
+        } else if is_in(&args.display) {
+            "{}"
+        } else if is_pretty {
+            "{:#?}"
+        } else {
+            "{:?}"
+        };
+        let arg_type = arg_types.get(&arg_ident.to_string());
+        // arguments are dereferenced through any references, and then through the smart pointer
+        // (or whatever else a listed argument derefs to)
+        let derefs = match (&args.deref, arg_type.map(|ty| deref_depth(ty))) {
+            (Some(args::Deref::All), Some((derefs, true))) => Some(derefs),
+            (Some(args::Deref::Only(ref idents)), Some((derefs, _))) if is_in(idents) => {
+                Some(derefs)
+            }
+            _ => None,
+        };
+        // a transformed argument is printed as the value of its expression, which is only
+        // evaluated for printing, so the argument itself stays as it is
+        let transform = args
+            .transform
+            .iter()
+            .find(|(ident, _)| ident == &arg_ident.to_string())
+            .map(|(_, expr)| expr);
+        let arg_value = match (transform, derefs) {
+            (Some(expr), _) => quote!((#expr)),
+            (None, Some(derefs)) => {
+                let derefs = std::iter::repeat_n(quote!(*), derefs);
+                quote!(#(#derefs)* #arg_ident)
+            }
+            (None, None) => arg_ident.clone(),
+        };
+        // locks are only printed if they can be acquired without blocking, which also keeps a
+        // thread from deadlocking on a lock it's holding itself
+        let lock_method = arg_type
+            .filter(|_| is_in(&args.try_lock))
+            .and_then(|ty| try_lock_method(ty));
+        let (arg_format, arg_value) = match lock_method {
+            Some(lock_method) => (
+                "{}",
+                quote! {
+                    match #arg_ident.#lock_method() {
+                        ::std::result::Result::Ok(guard) => ::std::format!(#arg_format, *guard),
+                        ::std::result::Result::Err(::std::sync::TryLockError::WouldBlock) => {
+                            ::std::string::ToString::to_string("<locked>")
+                        }
+                        ::std::result::Result::Err(::std::sync::TryLockError::Poisoned(_)) => {
+                            ::std::string::ToString::to_string("<poisoned>")
+                        }
+                    }
+                },
+            ),
+            None if (arg_format == "{:?}" || arg_format == "{:#?}")
+                && arg_type.map_or(args.lenient, |ty| may_lack_debug(ty)) =>
+            {
+                (arg_format, debug_or_type_name(&arg_value))
+            }
+            None => (arg_format, arg_value),
+        };
+        let (arg_format, arg_value) = if is_in(&args.dedup) {
+            ("{}", deduplicated(arg_format, &arg_value))
+        } else {
+            (arg_format, arg_value)
+        };
+        let rc_counts = rc_args
+            .get(&arg_ident.to_string())
+            .map(|(rc_name, rc_path)| {
+                let strong_count = quote!(#rc_path::strong_count(&#arg_ident));
+                let weak_count = quote!(#rc_path::weak_count(&#arg_ident));
+                (rc_name, [strong_count, weak_count])
+            });
+        // addresses are printed as thin pointers, since `{:p}` also prints the length of slices
+        let address = quote!(&*#arg_ident as *const _ as *const ());
+        let (arg_format, arg_value) = match args.max_length {
+            Some(max_length) => ("{}", truncated(arg_format, &arg_value, max_length)),
+            None => (arg_format, arg_value),
+        };
+        // a pretty printed argument continues on lines marked like those of a pretty printed
+        // return value
+        let (arg_format, arg_value) = if is_pretty && !reindent_enter {
+            let indent = match args.indent {
+                Some(ref indent) => quote!(&*::std::primitive::str::repeat(#indent, fn_depth)),
+                None => quote!(""),
+            };
+            (
+                "{}",
+                quote! {
+                    ::std::format!(#arg_format, #arg_value).replace(
+                        '\n',
+                        &::std::format!("\n{:depth$}{} ", #indent, #PRETTY_CONTINUATION, depth = fn_depth),
+                    )
+                },
+            )
+        } else {
+            (arg_format, arg_value)
+        };
+        // the compact format has no spaces around the `=` or the `@`
+        let (eq, at) = if args.compact {
+            ("=", "@")
+        } else {
+            (" = ", " @ ")
+        };
+        let values_start = arg_values.len();
+        // with `types`, arguments are labeled with their type as written in the signature, or
+        // with the type it was instantiated with if it depends on a type parameter (or if the
+        // argument is bound by a pattern)
+        let arg_label = match arg_type {
+            _ if !args.types || arg_ident.to_string() == "self" => arg_ident.to_string(),
+            Some(ty) if !mentions_type_param(ty, type_params) => {
+                let ty = type_to_string(ty).replace('{', "{{").replace('}', "}}");
+                format!("{}: {}", arg_ident, ty)
+            }
+            _ => {
+                arg_values.push(quote!(::std::any::type_name_of_val(&#arg_ident)));
+                format!("{}: {{}}", arg_ident)
+            }
+        };
+        if args.instance_ids && arg_ident.to_string() == "self" {
+            // a receiver taken by value is only where it is for the call, but it can still be
+            // told apart from the others
+            let receiver = match sig.receiver() {
+                Some(syn::FnArg::Receiver(syn::Receiver {
+                    reference: None, ..
+                })) => quote!(&self),
+                _ => quote!(&*self),
+            };
+            let instance_id = instance_id(args, &receiver);
+            if args.receiver {
+                arg_formats.push(format!("self{}{} #{{}}", eq, arg_format));
+                arg_values.push(arg_value);
+            } else {
+                arg_formats.push(format!("self{}#{{}}", eq));
+            }
+            arg_values.push(instance_id);
+        } else if is_in(&args.redact) {
+            arg_formats.push(format!("{}{}<redacted>", arg_label, eq));
+        } else if is_in(&args.addr_only) {
+            arg_formats.push(format!("{}{}{}{{:p}}", arg_label, eq, at.trim_start()));
+            arg_values.push(address);
+        } else if is_in(&args.addr) {
+            arg_formats.push(format!("{}{}{}{}{{:p}}", arg_label, eq, arg_format, at));
+            arg_values.push(arg_value);
+            arg_values.push(address);
+        } else {
+            arg_formats.push(format!("{}{}{}", arg_label, eq, arg_format));
+            arg_values.push(arg_value);
+        }
+        if let Some((rc_name, rc_counts)) = rc_counts {
+            if let Some(arg_format) = arg_formats.last_mut() {
+                arg_format.push_str(&format!(" {}(strong={{}}, weak={{}})", rc_name));
+            }
+            arg_values.extend(rc_counts);
+        }
+        // with `format = "json"`, what would be printed after the `=` is a JSON string instead
+        if args.format.is_some() {
+            if let Some(arg_format) = arg_formats.last_mut() {
+                let value_format = &arg_format[format!("{}{}", arg_ident, eq).len()..];
+                let values = arg_values.split_off(values_start);
+                arg_values.push(json_string(quote!(
+                    ::std::format!(#value_format, #(#values),*)
+                )));
+                *arg_format = format!("\"{}\":{{}}", arg_ident);
+            }
+        }
+    }
+    // with `zero_args`, functions without parameters show some context in their place
+    let has_params = sig
+        .inputs
+        .iter()
+        .any(|input| matches!(*input, syn::FnArg::Typed(_)));
+    if let (Some(ref zero_args), false, true) =
+        (&args.zero_args, has_params, arg_formats.is_empty())
+    {
+        let eq = if args.compact { "=" } else { " = " };
+        let (label, value_format, value) = match *zero_args {
+            args::ZeroArgs::Module => (quote!("module"), "{}", quote!(::std::module_path!())),
+            args::ZeroArgs::SelfType => (
+                quote!("Self"),
+                "{}",
+                quote!(::std::any::type_name::<Self>()),
+            ),
+            args::ZeroArgs::Expr(ref expr) => {
+                (quote!(::std::stringify!(#expr)), "{:?}", quote!(#expr))
+            }
+        };
+        arg_formats.push(format!("{{}}{}{}", eq, value_format));
+        arg_values.push(label);
+        arg_values.push(value);
+    }
+    if args.format.is_some() {
+        (Ok(arg_formats.join(",")), arg_values)
+    } else if args.compact {
+        (Ok(arg_formats.join(" ")), arg_values)
+    } else {
+        (Ok(arg_formats.join(", ")), arg_values)
+    }
+}
 
-        items.insert(
-            0,
-            parse_quote! {
-                ::std::thread_local! {
-                    static DEPTH: ::std::cell::Cell<usize> = ::std::cell::Cell::new(0);
+// takes the arguments of a `println!` and writes the line wherever it's supposed to go
+fn write_line_ignoring_errors(args: &args::Args, line_args: TokenStream) -> TokenStream {
+    match (&args.logging, &args.writer) {
+        (Some(level), _) => {
+            let log_macro = match *level {
+                args::LogLevel::Error => quote!(::log::error),
+                args::LogLevel::Warn => quote!(::log::warn),
+                args::LogLevel::Info => quote!(::log::info),
+                args::LogLevel::Debug => quote!(::log::debug),
+                args::LogLevel::Trace => quote!(::log::trace),
+            };
+            match args.log_target {
+                Some(ref target) => quote!(#log_macro!(target: #target, #line_args);),
+                None => quote!(#log_macro!(#line_args);),
+            }
+        }
+        // with `buffered`, the line is added to the lines held back, which are written together
+        // once there are enough of them. A line written while another one is being formatted
+        // into the buffer (by a traced function called from a `Debug` impl) is written right away
+        (None, None) if args.buffered.is_some() => {
+            let println = default_println();
+            let buffer_size = args.buffered.map(proc_macro2::Literal::u64_unsuffixed);
+            let trace_buffer = trace_var_path(args, "TRACE_BUFFER");
+            let write_buffer = write_buffer();
+            // `trace-runtime` is told to write the lines held back before the next line it's
+            // given, since it's the one writing the lines of functions in other `mod`s too
+            let flush_before_next_line = if cfg!(feature = "runtime") {
+                let flush_buffer = flush_buffer(&trace_buffer);
+                quote! {
+                    fn fn_flush_buffer() {
+                        #flush_buffer
+                    }
+                    ::trace_runtime::flush_before_next_line(fn_flush_buffer);
                 }
-            },
-        );
+            } else {
+                quote!()
+            };
+            quote! {
+                let fn_line_buffered = #trace_buffer.with(|buffer| {
+                    match ::std::cell::RefCell::try_borrow_mut(buffer) {
+                        ::std::result::Result::Ok(mut buffer) => {
+                            let _ = ::std::fmt::Write::write_fmt(
+                                &mut *buffer,
+                                ::std::format_args!("{}\n", ::std::format_args!(#line_args)),
+                            );
+                            if buffer.len() >= #buffer_size {
+                                #write_buffer
+                            }
+                            #flush_before_next_line
+                            true
+                        }
+                        ::std::result::Result::Err(_) => false,
+                    }
+                });
+                if !fn_line_buffered {
+                    #println!(#line_args);
+                }
+            }
+        }
+        // the lines held back by functions traced with `buffered` are written first, so they
+        // stay in the order they were printed in
+        (None, None) => {
+            let println = default_println();
+            let flush_held_back_lines = flush_held_back_lines(&depth_var_path(args));
+            quote! {
+                #flush_held_back_lines
+                #println!(#line_args);
+            }
+        }
+        (None, Some(args::Writer::Stderr)) => quote!(::std::eprintln!(#line_args);),
+        // GUI applications on Windows often have no console, but a debugger attached to them (or
+        // DebugView) still shows what's passed to `OutputDebugStringW`
+        (None, Some(args::Writer::Debugger)) => quote! {{
+            #[cfg(windows)]
+            {
+                #[link(name = "kernel32")]
+                unsafe extern "system" {
+                    fn OutputDebugStringW(output_string: *const u16);
+                }
+                let line = ::std::format!("{}\n", ::std::format_args!(#line_args));
+                let line = ::std::iter::Iterator::collect::<::std::vec::Vec<u16>>(
+                    ::std::iter::Iterator::chain(line.encode_utf16(), ::std::iter::once(0)),
+                );
+                unsafe { OutputDebugStringW(line.as_ptr()) };
+            }
+            #[cfg(not(windows))]
+            ::std::eprintln!(#line_args);
+        }},
+        // the subsystem is the package, and the category is the `log_target` (or the module), so
+        // the lines can be filtered in Console.app and Instruments
+        (None, Some(args::Writer::OsLog)) => {
+            let category = match args.log_target {
+                Some(ref target) => quote!(#target),
+                None => quote!(::std::module_path!()),
+            };
+            quote! {{
+                #[cfg(target_vendor = "apple")]
+                {
+                    static TRACE_OS_LOG: ::std::sync::OnceLock<::oslog::OsLog> =
+                        ::std::sync::OnceLock::new();
+                    let os_log = TRACE_OS_LOG.get_or_init(|| {
+                        ::oslog::OsLog::new(::std::env!("CARGO_PKG_NAME"), #category)
+                    });
+                    os_log.with_level(::oslog::Level::Debug, &::std::format!(#line_args));
+                }
+                #[cfg(not(target_vendor = "apple"))]
+                ::std::eprintln!(#line_args);
+            }}
+        }
+        // lines are appended to the file, which is opened once per traced function
+        (None, Some(args::Writer::File(path))) => quote! {{
+            static TRACE_FILE: ::std::sync::OnceLock<
+                ::std::option::Option<::std::sync::Mutex<::std::fs::File>>,
+            > = ::std::sync::OnceLock::new();
+            let trace_file = TRACE_FILE.get_or_init(|| {
+                ::std::result::Result::ok(
+                    ::std::fs::OpenOptions::new().create(true).append(true).open(#path),
+                )
+                .map(::std::sync::Mutex::new)
+            });
+            if let ::std::option::Option::Some(trace_file) = trace_file {
+                let mut trace_file = ::std::result::Result::unwrap_or_else(
+                    trace_file.lock(),
+                    ::std::sync::PoisonError::into_inner,
+                );
+                let _ = ::std::io::Write::write_fmt(
+                    &mut *trace_file,
+                    ::std::format_args!("{}\n", ::std::format_args!(#line_args)),
+                );
+            }
+        }},
+        (None, Some(args::Writer::Function(path))) => {
+            quote!(#path(::std::format_args!(#line_args));)
+        }
+        (None, Some(args::Writer::Macro(path))) => quote!(#path!(#line_args);),
     }
 }
 
-fn transform_impl(args: &args::Args, attr_applied: AttrApplied, item_impl: &mut syn::ItemImpl) {
-    item_impl.items.iter_mut().for_each(|impl_item| {
-        if let syn::ImplItem::Method(ref mut impl_item_method) = *impl_item {
-            if let AttrApplied::Directly = attr_applied {
-                let ident = &impl_item_method.sig.ident;
+// with `on_error`, lines are written to stdout, stderr, or the file in a way that reports errors,
+// and lines that couldn't be written are counted as dropped
+fn write_line_reporting_errors(args: &args::Args, line_args: TokenStream) -> TokenStream {
+    let on_error = match args.on_error {
+        Some(ref on_error) => on_error,
+        None => return write_line_ignoring_errors(args, line_args),
+    };
+    let line = quote!(::std::format_args!("{}\n", ::std::format_args!(#line_args)));
+    let write_result = match args.writer {
+        Some(args::Writer::Stderr) => {
+            quote!(::std::io::Write::write_fmt(&mut ::std::io::stderr().lock(), #line))
+        }
+        Some(args::Writer::File(ref path)) => quote! {{
+            static TRACE_FILE: ::std::sync::OnceLock<
+                ::std::option::Option<::std::sync::Mutex<::std::fs::File>>,
+            > = ::std::sync::OnceLock::new();
+            let trace_file = TRACE_FILE.get_or_init(|| {
+                ::std::result::Result::ok(
+                    ::std::fs::OpenOptions::new().create(true).append(true).open(#path),
+                )
+                .map(::std::sync::Mutex::new)
+            });
+            match trace_file {
+                ::std::option::Option::Some(trace_file) => {
+                    let mut trace_file = ::std::result::Result::unwrap_or_else(
+                        trace_file.lock(),
+                        ::std::sync::PoisonError::into_inner,
+                    );
+                    ::std::io::Write::write_fmt(&mut *trace_file, #line)
+                }
+                ::std::option::Option::None => ::std::result::Result::Err(
+                    ::std::io::Error::other(::std::format!("couldn't open `{}`", #path)),
+                ),
+            }
+        }},
+        _ => quote!(::std::io::Write::write_fmt(&mut ::std::io::stdout().lock(), #line)),
+    };
+    let handle_error = match *on_error {
+        args::OnError::Ignore => quote!(),
+        args::OnError::Stderr => quote! {
+            let _ = ::std::io::Write::write_fmt(
+                &mut ::std::io::stderr(),
+                ::std::format_args!("failed to write trace line: {}\n", e),
+            );
+        },
+        args::OnError::Panic => quote!(::std::panic!("failed to write trace line: {}", e);),
+    };
+    let trace_dropped_events = trace_var_path(args, "TRACE_DROPPED_EVENTS");
+    quote! {
+        if let ::std::result::Result::Err(e) = #write_result {
+            #trace_dropped_events.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed);
+            #handle_error
+        }
+    }
+}
 
-                match args.filter {
-                    args::Filter::Enable(ref idents) if !idents.contains(ident) => {
-                        return;
-                    }
-                    args::Filter::Disable(ref idents) if idents.contains(ident) => {
-                        return;
+// with `scoped_sink`, lines written while `with_sink!` runs are captured instead
+fn write_line(args: &args::Args, line_args: TokenStream) -> TokenStream {
+    let write = write_line_reporting_errors(args, line_args.clone());
+    if args.scoped_sink {
+        quote! {
+            let fn_line_captured = TRACE_SINK.with(|sink| {
+                match *::std::cell::RefCell::borrow_mut(sink) {
+                    ::std::option::Option::Some(ref mut sink) => {
+                        let _ = ::std::fmt::Write::write_fmt(
+                            sink,
+                            ::std::format_args!("{}\n", ::std::format_args!(#line_args)),
+                        );
+                        true
                     }
-                    _ => (),
+                    ::std::option::Option::None => false,
                 }
+            });
+            if !fn_line_captured {
+                #write
             }
+        }
+    } else {
+        write
+    }
+}
 
-            impl_item_method.block = construct_traced_block(
+// with `chunk_markers`, every line counts as an event, and every `chunk_markers`th event is
+// followed by a marker line
+fn print_line(args: &args::Args, line_args: TokenStream) -> TokenStream {
+    let write_event = write_line(args, line_args);
+    match args.chunk_markers {
+        Some(chunk_markers) => {
+            let chunk_markers = proc_macro2::Literal::u64_unsuffixed(chunk_markers);
+            let write_marker = write_line(
                 args,
-                AttrApplied::Indirectly,
-                &impl_item_method.sig,
-                &impl_item_method.block,
+                quote! {
+                    "--- trace chunk {} ended after {} events at {}.{:06}s since the Unix epoch ---",
+                    fn_events / #chunk_markers,
+                    fn_events,
+                    fn_timestamp.as_secs(),
+                    fn_timestamp.subsec_micros()
+                },
             );
+            let trace_events = trace_var_path(args, "TRACE_EVENTS");
+            quote! {{
+                #write_event
+                let fn_events = #trace_events
+                    .fetch_add(1, ::std::sync::atomic::Ordering::Relaxed)
+                    + 1;
+                if fn_events % #chunk_markers == 0 {
+                    let fn_timestamp = ::std::result::Result::unwrap_or_default(
+                        ::std::time::SystemTime::now().duration_since(::std::time::UNIX_EPOCH),
+                    );
+                    #write_marker
+                }
+            }}
         }
-    });
+        None => write_event,
+    }
 }
 
-fn transform_impl_item(
+// the parts of every line around its prefix: the marker, the color codes, the timestamps, and the
+// thread and correlation id, along with the arguments of `format!` they need
+fn line_decorations(
     args: &args::Args,
-    attr_applied: AttrApplied,
-    impl_item: &mut syn::ImplItem,
-) {
-    // Will probably add more cases in the future
-    #[allow(clippy::single_match)]
-    match *impl_item {
-        syn::ImplItem::Method(ref mut impl_item_method) => {
-            transform_method(args, attr_applied, impl_item_method)
+    sig: &syn::Signature,
+) -> (String, &'static str, TokenStream) {
+    // colored lines are wrapped in ANSI escape codes, with the color code filled in at runtime
+    let (mut line_start, line_end, mut line_args) = match args.color {
+        Some(args::Color::Thread) => {
+            let thread_colors = LINE_COLORS.iter();
+            (
+                "\x1b[{color}m".to_string(),
+                "\x1b[0m",
+                quote! {
+                    color = {
+                        // thread ids are handed out sequentially, so threads started one after
+                        // another get different colors
+                        let thread_id = ::std::format!("{:?}", ::std::thread::current().id());
+                        let thread_id = ::std::iter::Iterator::collect::<::std::string::String>(
+                            ::std::iter::Iterator::filter(thread_id.chars(), char::is_ascii_digit),
+                        );
+                        let colors = [#(#thread_colors),*];
+                        colors[::std::result::Result::unwrap_or(thread_id.parse::<usize>(), 0) % colors.len()]
+                    },
+                },
+            )
         }
-        _ => (),
+        // unlike with `"thread"`, lines are only colored if stdout is a terminal
+        Some(ref color @ (args::Color::Depth | args::Color::Fn)) => {
+            let color = match *color {
+                args::Color::Fn => {
+                    let fn_hash = sig.ident.to_string().bytes().fold(0usize, |hash, b| {
+                        hash.wrapping_mul(31).wrapping_add(b as usize)
+                    });
+                    let color = LINE_COLORS[fn_hash % LINE_COLORS.len()];
+                    quote!(#color)
+                }
+                _ => {
+                    let depth_colors = LINE_COLORS.iter();
+                    quote! {{
+                        let colors = [#(#depth_colors),*];
+                        colors[fn_depth % colors.len()]
+                    }}
+                }
+            };
+            let is_terminal = quote! {{
+                static TRACE_IS_TERMINAL: ::std::sync::OnceLock<bool> = ::std::sync::OnceLock::new();
+                // `CLICOLOR_FORCE` (set to anything but `0`) colors the lines even when they're
+                // piped, like it does for other command line tools
+                *TRACE_IS_TERMINAL.get_or_init(|| {
+                    ::std::env::var_os("CLICOLOR_FORCE").map_or(false, |force| force != "0")
+                        || ::std::io::IsTerminal::is_terminal(&::std::io::stdout())
+                })
+            }};
+            (
+                "{color_start}".to_string(),
+                "{color_end}",
+                quote! {
+                    color_start = if #is_terminal {
+                        ::std::format!("\x1b[{}m", #color)
+                    } else {
+                        ::std::string::String::new()
+                    },
+                    color_end = if #is_terminal { "\x1b[0m" } else { "" },
+                },
+            )
+        }
+        None => (String::new(), "", quote!()),
+    };
+    // JSON lines always have the name of the thread, or its id if it doesn't have a name
+    if args.format.is_some() {
+        let thread_label = json_string(quote! {{
+            let thread = ::std::thread::current();
+            match ::std::thread::Thread::name(&thread) {
+                ::std::option::Option::Some(name) => ::std::string::ToString::to_string(name),
+                ::std::option::Option::None => ::std::format!("{:?}", thread.id()),
+            }
+        }});
+        line_args.extend(quote!(json_thread = #thread_label,));
+    }
+    // the wall-clock time the line is printed at
+    if let Some(args::Timestamp::Rfc3339) = args.timestamp {
+        line_start.push_str("{timestamp} ");
+        let timestamp = rfc3339_timestamp();
+        line_args.extend(quote!(timestamp = #timestamp,));
     }
+    // the time since the first line printed with `since_start`, which is shared by all threads
+    if args.since_start || matches!(args.timestamp, Some(args::Timestamp::Elapsed)) {
+        line_start.push_str("+{since_start:.3}s ");
+        line_args.extend(quote! {
+            since_start = ::std::time::Duration::as_secs_f64(&::std::time::Instant::elapsed(
+                ::std::sync::OnceLock::get_or_init(&TRACE_START, ::std::time::Instant::now),
+            )),
+        });
+    }
+    // lines start with the name of the thread, or its id if it doesn't have a name
+    if args.thread {
+        line_start.push_str("[{thread_label}] ");
+        line_args.extend(quote! {
+            thread_label = {
+                let thread = ::std::thread::current();
+                match ::std::thread::Thread::name(&thread) {
+                    ::std::option::Option::Some(name) => ::std::string::ToString::to_string(name),
+                    ::std::option::Option::None => ::std::format!("{:?}", thread.id()),
+                }
+            },
+        });
+    }
+    // lines start with the correlation id set by `with_correlation_id!`, if there is one
+    if args.correlation_id {
+        line_start.push_str("{correlation}");
+        line_args.extend(quote! {
+            correlation = TRACE_CORRELATION_ID.with(|id| {
+                match *::std::cell::RefCell::borrow(id) {
+                    ::std::option::Option::Some(ref id) => ::std::format!("[{}] ", id),
+                    ::std::option::Option::None => ::std::string::String::new(),
+                }
+            }),
+        });
+    }
+    // the marker comes before everything else, even the color codes, so trace lines can be told
+    // apart from the program's own output by their first characters
+    if let Some(ref marker) = args.marker {
+        line_start.insert_str(0, &marker.replace('{', "{{").replace('}', "}}"));
+    }
+    (line_start, line_end, line_args)
 }
 
-fn transform_method(
+// nothing is printed for calls made while formatting (with `quiet_fmt`), that don't match
+// `only_when` or `if`, that are nested too deeply for `max_depth`, or that are in a group
+// disabled with `disable_group!`
+fn fn_silenced(
     args: &args::Args,
     attr_applied: AttrApplied,
-    impl_item_method: &mut syn::ImplItemMethod,
+    sig: &syn::Signature,
+    formatting: &TokenStream,
+) -> TokenStream {
+    let mut fn_silenced = match matches_only_when(args, attr_applied, sig) {
+        Some(fn_matched) => quote!(#formatting || !(#fn_matched)),
+        None => formatting.clone(),
+    };
+    if let Some(ref predicate) = args.predicate {
+        fn_silenced.extend(quote!(|| !(#predicate)));
+    }
+    if let Some(max_depth) = args.max_depth {
+        let max_depth = proc_macro2::Literal::u64_unsuffixed(max_depth);
+        fn_silenced.extend(quote!(|| fn_depth >= #max_depth));
+    }
+    let fn_groups = args
+        .groups
+        .iter()
+        .filter(|(_, fn_idents)| fn_idents.contains(&sig.ident))
+        .map(|(group_name, _)| group_name)
+        .collect::<Vec<_>>();
+    if !fn_groups.is_empty() {
+        let trace_disabled_groups = trace_var_path(args, "TRACE_DISABLED_GROUPS");
+        fn_silenced.extend(quote! {
+            || {
+                let disabled_groups = ::std::result::Result::unwrap_or_else(
+                    #trace_disabled_groups.read(),
+                    ::std::sync::PoisonError::into_inner,
+                );
+                ::std::iter::Iterator::any(&mut [#(#fn_groups),*].iter(), |group| disabled_groups.contains(group))
+            }
+        });
+    }
+    if cfg!(feature = "runtime") {
+        fn_silenced.extend(quote!(|| !::trace_runtime::enabled()));
+    }
+    fn_silenced
+}
+
+// with `runtime_filter`, the function is silenced unless the variable has a pattern matching its
+// name or path
+fn runtime_filter(sig: &syn::Signature, filter_var: &str) -> TokenStream {
+    let fn_name = sig.ident.to_string();
+    quote! {
+        || {
+            // the filter is only read the first time the function is called, so changing the
+            // variable later doesn't change which functions are printed
+            static FN_ENABLED: ::std::sync::OnceLock<::std::primitive::bool> =
+                ::std::sync::OnceLock::new();
+            !*::std::sync::OnceLock::get_or_init(&FN_ENABLED, || {
+                fn glob_matches(pattern: &[::std::primitive::u8], name: &[::std::primitive::u8]) -> ::std::primitive::bool {
+                    match pattern.split_first() {
+                        ::std::option::Option::None => name.is_empty(),
+                        ::std::option::Option::Some((b'*', rest)) => {
+                            ::std::iter::Iterator::any(&mut (0..=name.len()), |skipped| {
+                                glob_matches(rest, &name[skipped..])
+                            })
+                        }
+                        ::std::option::Option::Some((c, rest)) => {
+                            name.first() == ::std::option::Option::Some(c) && glob_matches(rest, &name[1..])
+                        }
+                    }
+                }
+                let fn_path = ::std::concat!(::std::module_path!(), "::", #fn_name);
+                // the path without the crate name, so `bar::*` matches `my_crate::bar::baz`
+                let fn_crate_path = ::std::option::Option::map_or(
+                    fn_path.split_once("::"),
+                    fn_path,
+                    |(_, path)| path,
+                );
+                match ::std::env::var(#filter_var) {
+                    ::std::result::Result::Ok(filter) => {
+                        let mut patterns = ::std::iter::Iterator::filter(
+                            ::std::iter::Iterator::map(filter.split(','), ::std::primitive::str::trim),
+                            |pattern| !pattern.is_empty(),
+                        );
+                        ::std::iter::Iterator::any(&mut patterns, |pattern| {
+                            ::std::iter::Iterator::any(&mut [#fn_name, fn_path, fn_crate_path].iter(), |name| {
+                                glob_matches(pattern.as_bytes(), name.as_bytes())
+                            })
+                        })
+                    }
+                    ::std::result::Result::Err(_) => false,
+                }
+            })
+        }
+    }
+}
+
+// with `registry`, the function registers itself with `trace_runtime` when the program starts,
+// along with the flag it can be disabled with and the number of times it was called
+fn register_call(
+    args: &args::Args,
+    sig: &syn::Signature,
+    fn_silenced: &mut TokenStream,
+) -> TokenStream {
+    if args.registry {
+        let fn_name = sig.ident.to_string();
+        let options = &args.options;
+        let line = quote_spanned!(sig.ident.span()=> ::std::line!());
+        fn_silenced.extend(quote!(|| !fn_registry_enabled));
+        quote! {
+            let fn_registry_enabled = {
+                static FN_REGISTRY_ENABLED: ::std::sync::atomic::AtomicBool =
+                    ::std::sync::atomic::AtomicBool::new(true);
+                static FN_REGISTRY_CALLS: ::std::sync::atomic::AtomicU64 =
+                    ::std::sync::atomic::AtomicU64::new(0);
+                ::trace_runtime::inventory::submit! {
+                    ::trace_runtime::TracedFn::new(
+                        #fn_name,
+                        ::std::module_path!(),
+                        ::std::file!(),
+                        #line,
+                        #options,
+                        &FN_REGISTRY_ENABLED,
+                        &FN_REGISTRY_CALLS,
+                    )
+                }
+                FN_REGISTRY_CALLS.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed);
+                FN_REGISTRY_ENABLED.load(::std::sync::atomic::Ordering::Relaxed)
+            };
+        }
+    } else {
+        quote!()
+    }
+}
+
+// with `pause(min_depth = .., max_depth = ..)` (or `pause_if`), only calls at those depths wait
+// for enter
+fn pause_stmt(args: &args::Args) -> TokenStream {
+    match args.pause {
+        Some(ref pause) => {
+            // the prompt is only shown to someone who can press enter
+            let prompt = match args.pause_prompt {
+                Some(ref prompt) => quote! {
+                    if ::std::io::IsTerminal::is_terminal(&stdin) {
+                        ::std::eprint!("{}", #prompt);
+                        let _ = ::std::io::Write::flush(&mut ::std::io::stderr());
+                    }
+                },
+                None => quote!(),
+            };
+            // once there's nothing left to read from stdin (e.g. it's a pipe that was closed, or
+            // `/dev/null`), pausing waits for `sleep_ms` instead
+            let read_line = match pause.sleep_ms {
+                0 => quote!(::std::iter::Iterator::next(&mut lines);),
+                sleep_ms => quote! {
+                    if !::std::matches!(
+                        ::std::iter::Iterator::next(&mut lines),
+                        ::std::option::Option::Some(::std::result::Result::Ok(_))
+                    ) {
+                        ::std::thread::sleep(::std::time::Duration::from_millis(#sleep_ms));
+                    }
+                },
+            };
+            let wait_for_enter = quote! {{
+                let stdin = ::std::io::stdin();
+                #prompt
+                let mut lines = ::std::io::BufRead::lines(stdin.lock());
+                #read_line
+            }};
+            let min_depth = pause.min_depth.map(proc_macro2::Literal::u64_unsuffixed);
+            let max_depth = pause.max_depth.map(proc_macro2::Literal::u64_unsuffixed);
+            let mut conditions = match (min_depth, max_depth) {
+                (None, None) => vec![],
+                (min_depth, None) => {
+                    vec![quote!(::std::ops::RangeBounds::contains(&(#min_depth..), &fn_depth))]
+                }
+                (min_depth, Some(max_depth)) => vec![quote! {
+                    ::std::ops::RangeBounds::contains(&(#min_depth..=#max_depth), &fn_depth)
+                }],
+            };
+            if let Some(ref condition) = args.pause_if {
+                conditions.push(quote! {{
+                    #[allow(unused_variables)]
+                    let depth: usize = fn_depth;
+                    #condition
+                }});
+            }
+            if conditions.is_empty() {
+                wait_for_enter
+            } else {
+                quote!(if #(#conditions)&&* #wait_for_enter)
+            }
+        }
+        None => quote!(),
+    }
+}
+
+// the words after the prefixes can be overridden at runtime like the prefixes themselves
+fn words(args: &args::Args, runtime_overrides: bool) -> (String, String, TokenStream, TokenStream) {
+    if runtime_overrides {
+        let word_enter = &args.word_enter;
+        let word_exit = &args.word_exit;
+        (
+            "{runtime_word_enter}".to_string(),
+            "{runtime_word_exit}".to_string(),
+            quote! {
+                runtime_word_enter = ::std::option::Option::unwrap_or(
+                    ::trace_runtime::word_enter(),
+                    #word_enter,
+                ),
+            },
+            quote! {
+                runtime_word_exit = ::std::option::Option::unwrap_or(
+                    ::trace_runtime::word_exit(),
+                    #word_exit,
+                ),
+            },
+        )
+    } else {
+        let escape = |word: &str| word.replace('{', "{{").replace('}', "}}");
+        (
+            escape(&args.word_enter),
+            escape(&args.word_exit),
+            quote!(),
+            quote!(),
+        )
+    }
+}
+
+// with `qualified`, the name of the function is prefixed with the path of its module (and
+// where it is), which the macros only know once they're expanded in the function. `line!()`
+// gets the span of the name, so it's the line of the function even when a whole `mod` is traced
+fn qualified_fn_name(args: &args::Args, sig: &syn::Signature) -> (String, TokenStream) {
+    match args.qualified {
+        Some(ref qualified) => {
+            let fn_name = sig.ident.to_string();
+            let location = match *qualified {
+                args::Qualified::Module => quote!(),
+                args::Qualified::Location => {
+                    let line = quote_spanned!(sig.ident.span()=> ::std::line!());
+                    quote!(::std::file!(), ":", #line, ": ",)
+                }
+            };
+            (
+                "{fn_qualified_name}".to_string(),
+                quote! {
+                    fn_qualified_name = ::std::concat!(#location ::std::module_path!(), "::", #fn_name),
+                },
+            )
+        }
+        None => (sig.ident.to_string(), quote!()),
+    }
+}
+
+// with `stats`, every call (printed or not) is counted under the name of the function, which
+// includes the concrete types of `Self` and the function's type parameters if they're generic
+fn record_stats(
+    args: &args::Args,
+    sig: &syn::Signature,
+    outer_type_params: &[String],
+) -> TokenStream {
+    if args.stats || args.bench {
+        let fn_name = sig.ident.to_string();
+        let stats_key_self = if outer_type_params.iter().any(|param| param == "Self") {
+            quote! {
+                let fn_stats_key = ::std::format!("{}::{}", ::std::any::type_name::<Self>(), fn_stats_key);
+            }
+        } else {
+            quote!()
+        };
+        let fn_type_params = sig
+            .generics
+            .type_params()
+            .map(|type_param| &type_param.ident)
+            .collect::<Vec<_>>();
+        let stats_key_type_params = if fn_type_params.is_empty() {
+            quote!()
+        } else {
+            quote! {
+                let fn_stats_key = ::std::format!(
+                    "{}::<{}>",
+                    fn_stats_key,
+                    [#(::std::any::type_name::<#fn_type_params>()),*].join(", "),
+                );
+            }
+        };
+        let trace_stats = trace_var_path(args, "TRACE_STATS");
+        quote! {{
+            let fn_stats_key = ::std::string::ToString::to_string(#fn_name);
+            #stats_key_self
+            #stats_key_type_params
+            let mut stats = ::std::result::Result::unwrap_or_else(
+                #trace_stats.lock(),
+                ::std::sync::PoisonError::into_inner,
+            );
+            let (calls, total, min, max) = stats.entry(fn_stats_key).or_insert((
+                0,
+                ::std::time::Duration::ZERO,
+                ::std::time::Duration::MAX,
+                ::std::time::Duration::ZERO,
+            ));
+            *calls += 1;
+            *total += fn_elapsed;
+            *min = ::std::cmp::Ord::min(*min, fn_elapsed);
+            *max = ::std::cmp::Ord::max(*max, fn_elapsed);
+        }}
+    } else {
+        quote!()
+    }
+}
+
+// functions using `{caller_fn}` or `{call_id}` keep track of which call of theirs is currently
+// running, so they can tell which one called them and `current_call_id!()` can tell which one
+// it's in
+fn track_current_call(
+    args: &args::Args,
+    fn_name: &str,
+) -> (
+    TokenStream,
+    TokenStream,
+    (TokenStream, TokenStream, TokenStream),
 ) {
-    impl_item_method.block = construct_traced_block(
-        args,
-        attr_applied,
-        &impl_item_method.sig,
-        &impl_item_method.block,
-    );
+    if tracks_current_call(args) {
+        (
+            quote! {
+                let fn_call_id =
+                    TRACE_CALL_IDS.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed) + 1;
+                let fn_caller = TRACE_CURRENT_CALL.with(|c| {
+                    c.replace(::std::option::Option::Some((#fn_name, fn_call_id)))
+                });
+            },
+            quote!(TRACE_CURRENT_CALL.with(|c| c.set(fn_caller));),
+            // the panic guard keeps the id of the call, for its exit line
+            (
+                quote!(u64,),
+                quote!(fn_call_id,),
+                quote! {
+                    #[allow(unused_variables)]
+                    let fn_call_id = self.4;
+                },
+            ),
+        )
+    } else {
+        (
+            quote!(let fn_caller = ::std::option::Option::<(&'static str, u64)>::None;),
+            quote!(),
+            (quote!(), quote!(), quote!()),
+        )
+    }
 }
 
-fn construct_traced_block(
+// with `record_calls`, every call (printed or not) is recorded along with its arguments, for
+// `assert_called!` and `assert_called_with!`
+fn record_call(
     args: &args::Args,
-    attr_applied: AttrApplied,
     sig: &syn::Signature,
-    original_block: &syn::Block,
-) -> syn::Block {
-    let arg_idents = extract_arg_idents(args, attr_applied, sig)
-        .iter()
-        .map(|ident| ident.to_token_stream())
-        .collect();
-    let (enter_format, arg_idents) = if let Some(fmt_str) = &args.format_enter {
-        parse_fmt_str(fmt_str, arg_idents)
+    arg_idents: &[TokenStream],
+    arg_types: &HashMap<String, &syn::Type>,
+    may_lack_debug: &dyn Fn(&syn::Type) -> bool,
+) -> TokenStream {
+    if args.record_calls {
+        let fn_name = sig.ident.to_string();
+        let recorded_args = arg_idents
+            .iter()
+            .filter(|arg_ident| arg_ident.to_string() != "self")
+            .map(|arg_ident| {
+                let arg_name = arg_ident.to_string();
+                if args.redact.iter().any(|ident| *ident == arg_name) {
+                    return quote!((#arg_name, ::std::string::ToString::to_string("<redacted>")));
+                }
+                let arg_value = match arg_types.get(&arg_name) {
+                    Some(ty) if may_lack_debug(ty) => debug_or_type_name(arg_ident),
+                    _ => arg_ident.clone(),
+                };
+                quote!((#arg_name, ::std::format!("{:?}", #arg_value)))
+            });
+        let trace_calls = trace_var_path(args, "TRACE_CALLS");
+        quote! {
+            #trace_calls.with(|calls| {
+                ::std::cell::RefCell::borrow_mut(calls).push((#fn_name, ::std::vec![#(#recorded_args),*]));
+            });
+        }
     } else {
+        quote!()
+    }
+}
+
+// with `fingerprint`, every call (printed or not) is folded into a hash of all calls made so
+// far, with FNV-1a so the hash stays the same between Rust versions and platforms
+fn fold_fingerprint(
+    args: &args::Args,
+    sig: &syn::Signature,
+    arg_idents: &[TokenStream],
+    arg_types: &HashMap<String, &syn::Type>,
+    may_lack_debug: &dyn Fn(&syn::Type) -> bool,
+) -> TokenStream {
+    match args.fingerprint {
+        Some(ref fingerprint) => {
+            let fn_name = sig.ident.to_string();
+            let fn_call = match *fingerprint {
+                args::Fingerprint::Names => quote!(#fn_name),
+                args::Fingerprint::Args => {
+                    let hashed_args = arg_idents
+                        .iter()
+                        .filter(|arg_ident| arg_ident.to_string() != "self")
+                        .collect::<Vec<_>>();
+                    let call_format = format!(
+                        "{}({})",
+                        fn_name,
+                        hashed_args
+                            .iter()
+                            .map(|arg_ident| {
+                                if args
+                                    .redact
+                                    .iter()
+                                    .any(|ident| *ident == arg_ident.to_string())
+                                {
+                                    format!("{} = <redacted>", arg_ident)
+                                } else {
+                                    format!("{} = {{:?}}", arg_ident)
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    let arg_values = hashed_args
+                        .iter()
+                        .filter(|arg_ident| {
+                            !args
+                                .redact
+                                .iter()
+                                .any(|ident| *ident == arg_ident.to_string())
+                        })
+                        .map(|arg_ident| match arg_types.get(&arg_ident.to_string()) {
+                            Some(ty) if may_lack_debug(ty) => debug_or_type_name(arg_ident),
+                            _ => (*arg_ident).clone(),
+                        });
+                    quote!(&*::std::format!(#call_format, #(#arg_values),*))
+                }
+            };
+            let trace_fingerprint = trace_var_path(args, "TRACE_FINGERPRINT");
+            quote! {{
+                let fn_call: &str = #fn_call;
+                let _ = #trace_fingerprint.fetch_update(
+                    ::std::sync::atomic::Ordering::Relaxed,
+                    ::std::sync::atomic::Ordering::Relaxed,
+                    |hash| {
+                        let bytes = ::std::iter::Iterator::chain(fn_call.bytes(), [b'\n']);
+                        ::std::option::Option::Some(::std::iter::Iterator::fold(bytes, hash, |hash, byte| {
+                            (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3)
+                        }))
+                    },
+                );
+            }}
+        }
+        None => quote!(),
+    }
+}
+
+// with `heat`, every call (printed or not) is counted, and the enter line tells how many calls
+// there have been and how often the function has been called per second since its first call
+fn count_heat(args: &args::Args) -> (TokenStream, TokenStream) {
+    if args.heat {
+        let (first_call_format, heat_format) = if args.compact {
+            (" #{}", " #{} ~{}/s")
+        } else {
+            (" [call #{}]", " [call #{}, ~{}/s]")
+        };
         (
-            Ok(arg_idents
-                .iter()
-                .map(|arg_ident| format!("{} = {{:?}}", arg_ident))
-                .collect::<Vec<_>>()
-                .join(", ")),
-            arg_idents,
+            quote! {
+                let fn_heat = {
+                    static FN_HEAT_CALLS: ::std::sync::atomic::AtomicU64 =
+                        ::std::sync::atomic::AtomicU64::new(0);
+                    static FN_HEAT_START: ::std::sync::OnceLock<::std::time::Instant> =
+                        ::std::sync::OnceLock::new();
+                    let fn_heat_start = *FN_HEAT_START.get_or_init(::std::time::Instant::now);
+                    (
+                        FN_HEAT_CALLS.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed) + 1,
+                        ::std::time::Instant::elapsed(&fn_heat_start),
+                    )
+                };
+            },
+            quote! {
+                heat = {
+                    let (calls, elapsed) = fn_heat;
+                    if calls > 1 && elapsed.as_secs_f64() > 0.0 {
+                        // the calls after the first are the ones made in `elapsed`
+                        let rate = (calls - 1) as f64 / elapsed.as_secs_f64();
+                        let rate = if rate >= 1e6 {
+                            ::std::format!("{:.0}M", rate / 1e6)
+                        } else if rate >= 1e3 {
+                            ::std::format!("{:.0}k", rate / 1e3)
+                        } else {
+                            ::std::format!("{:.0}", rate)
+                        };
+                        ::std::format!(#heat_format, calls, rate)
+                    } else {
+                        ::std::format!(#first_call_format, calls)
+                    }
+                },
+            },
         )
-    };
-    // we set set exit val to be a vector with one element which is Ident called r
-    // this means that the format parser can indentify when then return value should be interprolated
-    // so if we want to use a different symbol to denote return value interpolation we just need to change the symbol in the following quote
-    // ie: `let exit_val = vec![quote!(return_value)];` if we wanted to use return_value to denote return value interpolation
-    let exit_val = vec![quote!(r)];
-    let (exit_format, exit_val) = if let Some(fmt_str) = &args.format_exit {
-        parse_fmt_str(fmt_str, exit_val)
-    } else if args.pretty {
-        (Ok("{:#?}".to_string()), exit_val)
     } else {
-        (Ok("{:?}".to_string()), exit_val)
-    };
-    let should_interpolate = !exit_val.is_empty();
-    let entering_format = format!(
-        "{{:depth$}}{} Entering {}({})",
-        args.prefix_enter,
-        sig.ident,
-        match enter_format {
-            Ok(ok) => ok,
-            Err(e) => {
-                let error = e.into_compile_error();
-                return parse_quote! {{#error}};
+        (quote!(), quote!())
+    }
+}
+
+// writes the lines held back by `buffered` on this thread (in `trace_buffer`, a `TRACE_BUFFER`),
+// unless they're being added to
+fn flush_buffer(trace_buffer: &TokenStream) -> TokenStream {
+    let write_buffer = write_buffer();
+    quote! {
+        #trace_buffer.with(|buffer| {
+            if let ::std::result::Result::Ok(mut buffer) =
+                ::std::cell::RefCell::try_borrow_mut(buffer)
+            {
+                #write_buffer
+            }
+        });
+    }
+}
+
+// writes the lines held back by functions traced with `buffered` before a line is printed, so
+// they stay in the order they were printed in. They're written by the depth variable declared
+// along with them, and a depth variable declared by itself is a plain `Cell<usize>`, which has
+// none. With the `runtime` feature, `trace-runtime` writes them before any line it's given
+fn flush_held_back_lines(depth_var: &TokenStream) -> TokenStream {
+    if cfg!(feature = "runtime") {
+        return quote!();
+    }
+    quote! {{
+        trait FnFlushBuffer {
+            fn trace_flush_buffer(&self) {}
+        }
+        impl<T: ?::std::marker::Sized> FnFlushBuffer for T {}
+        #depth_var.with(|depth| depth.trace_flush_buffer());
+    }}
+}
+
+// has `write` (the name of a `fn()` that writes lines that are pending, like the repeats counted by
+// `collapse_repeats`) called before the exit line of the outermost traced call of the thread, in
+// case no other line comes first. Like with `flush_held_back_lines`, that's done by the depth
+// variable, or by `trace-runtime` with the `runtime` feature
+fn write_when_outermost_call_returns(depth_var: &TokenStream, write: &TokenStream) -> TokenStream {
+    if cfg!(feature = "runtime") {
+        return quote!(::trace_runtime::write_when_outermost_call_returns(#write););
+    }
+    quote! {{
+        trait FnWriteWhenOutermostCallReturns {
+            fn trace_write_when_outermost_call_returns(&self, _: fn()) {}
+        }
+        impl<T: ?::std::marker::Sized> FnWriteWhenOutermostCallReturns for T {}
+        #depth_var.with(|depth| depth.trace_write_when_outermost_call_returns(#write));
+    }}
+}
+
+// the outermost traced call of the thread writes the lines that are pending until it returns (see
+// `write_when_outermost_call_returns`)
+fn write_pending_lines(depth_var: &TokenStream) -> TokenStream {
+    let write_pending_lines = if cfg!(feature = "runtime") {
+        quote!(::trace_runtime::write_pending_lines();)
+    } else {
+        quote! {
+            trait FnWritePendingLines {
+                fn trace_write_pending_lines(&self) {}
             }
+            impl<T: ?::std::marker::Sized> FnWritePendingLines for T {}
+            #depth_var.with(|depth| depth.trace_write_pending_lines());
         }
-    );
-    let exiting_format = format!(
-        "{{:depth$}}{} Exiting {} = {}",
-        args.prefix_exit,
-        sig.ident,
-        match exit_format {
-            Ok(ok) => ok,
-            Err(e) => {
-                let error = e.into_compile_error();
-                return parse_quote! {{#error}};
-            }
+    };
+    quote! {
+        if fn_depth == 0 {
+            #write_pending_lines
         }
-    );
+    }
+}
 
-    let pause_stmt = if args.pause {
-        quote! {{
-            use std::io::{self, BufRead};
-            let stdin = io::stdin();
-            stdin.lock().lines().next();
-        }}
+// writes the lines held back by `buffered` (in `buffer`, a `RefMut` of `TRACE_BUFFER`) to stdout
+// at once, or with the `runtime` feature, passes them to the writers as one line
+fn write_buffer() -> TokenStream {
+    let write = if cfg!(feature = "runtime") {
+        quote! {
+            ::trace_runtime::print_line!(
+                "{}",
+                ::std::option::Option::unwrap_or(buffer.strip_suffix('\n'), &buffer)
+            );
+        }
     } else {
-        quote!()
+        quote! {
+            let _ = ::std::io::Write::write_all(&mut ::std::io::stdout().lock(), buffer.as_bytes());
+        }
     };
+    quote! {
+        if !buffer.is_empty() {
+            #write
+            buffer.clear();
+        }
+    }
+}
 
-    let printer = if args.logging {
-        quote! { log::trace! }
-    } else {
-        quote! { println! }
+// with `disable(return)`, the return value isn't printed, so its type doesn't need to implement
+// `Debug`
+fn disables_return(args: &args::Args) -> bool {
+    matches!(args.filter, args::Filter::Disable(ref names) if names.idents.iter().any(|ident| ident == "return"))
+}
+
+// whether the exit line leaves out the return value, which it also does for a function returning
+// `()` with `hide_unit_return`
+fn hides_return(args: &args::Args, sig: &syn::Signature) -> bool {
+    let returns_unit = match sig.output {
+        syn::ReturnType::Default => true,
+        syn::ReturnType::Type(_, ref ty) => {
+            matches!(**ty, syn::Type::Tuple(ref tuple) if tuple.elems.is_empty())
+        }
     };
-    let print_exit = if should_interpolate {
-        quote! {{#printer(#exiting_format, "",fn_return_value, depth = DEPTH.with(|d| d.get()));}}
+    disables_return(args) || (args.hide_unit_return && returns_unit)
+}
+
+fn tracks_current_call(args: &args::Args) -> bool {
+    [
+        Some(&args.prefix_enter),
+        Some(&args.prefix_exit),
+        args.prefix_error.as_ref(),
+        args.prefix_panic.as_ref(),
+    ]
+    .iter()
+    .flatten()
+    .any(|prefix| prefix.contains("{caller_fn}") || prefix.contains("{call_id}"))
+}
+
+// turns a prefix into a piece of a format string, filling in the `{fn}` placeholder with the name
+// of the function and passing `{depth}`, `{thread}`, `{caller_fn}`, and `{call_id}` through to be formatted at
+// runtime (the named arguments needed for them are returned as well)
+fn parse_prefix(prefix: &str, fn_ident: &syn::Ident) -> Result<(String, TokenStream), syn::Error> {
+    let mut fixed_prefix = String::new();
+    let mut uses_thread = false;
+    let mut uses_caller_fn = false;
+    let mut uses_call_id = false;
+    let mut prefix_iter = prefix.chars().peekable();
+    while let Some(prefix_char) = prefix_iter.next() {
+        match prefix_char {
+            '{' if prefix_iter.peek() == Some(&'{') => {
+                prefix_iter.next();
+                fixed_prefix.push_str("{{");
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                let mut closed = false;
+                for placeholder_char in prefix_iter.by_ref() {
+                    if placeholder_char == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(placeholder_char);
+                }
+                if !closed {
+                    return Err(syn::Error::new(
+                        Span::call_site(),
+                        "invalid prefix: expected `'}'` but string was terminated\nif you intended to print `{`, you can escape it using `{{`.",
+                    ));
+                }
+                match placeholder.as_str() {
+                    "fn" => fixed_prefix.push_str(&fn_ident.to_string()),
+                    "depth" => fixed_prefix.push_str("{depth}"),
+                    "thread" => {
+                        uses_thread = true;
+                        fixed_prefix.push_str("{thread}");
+                    }
+                    "caller_fn" => {
+                        uses_caller_fn = true;
+                        fixed_prefix.push_str("{caller_fn}");
+                    }
+                    "call_id" => {
+                        uses_call_id = true;
+                        fixed_prefix.push_str("{call_id}");
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            Span::call_site(),
+                            format!("unknown prefix placeholder `{{{placeholder}}}`, expected one of: `{{fn}}`, `{{depth}}`, `{{thread}}`, `{{caller_fn}}`, `{{call_id}}`"),
+                        ))
+                    }
+                }
+            }
+            '}' if prefix_iter.peek() == Some(&'}') => {
+                prefix_iter.next();
+                fixed_prefix.push_str("}}");
+            }
+            '}' => {
+                return Err(syn::Error::new(
+                    Span::call_site(),
+                    "invalid prefix: unmatched `}` found\nif you intended to print `}`, you can escape it using `}}`",
+                ))
+            }
+            _ => fixed_prefix.push(prefix_char),
+        }
+    }
+
+    let mut prefix_args = quote!();
+    if uses_thread {
+        prefix_args.extend(quote! {
+            thread = ::std::option::Option::unwrap_or(
+                ::std::thread::Thread::name(&::std::thread::current()),
+                "<unnamed>",
+            ),
+        });
+    }
+    // the caller is the traced function that was running when the call was made
+    if uses_caller_fn {
+        prefix_args.extend(quote! {
+            caller_fn = ::std::option::Option::map_or(fn_caller, "<none>", |(caller_fn, _)| caller_fn),
+        });
+    }
+    if uses_call_id {
+        prefix_args.extend(quote!(call_id = fn_call_id,));
+    }
+    Ok((fixed_prefix, prefix_args))
+}
+
+// The prefix is printed as a single argument, which is either the prefix set at runtime or the
+// compiled-in prefix with its placeholders filled in
+fn overridable_prefix(
+    runtime_prefix: TokenStream,
+    prefix: String,
+    prefix_args: TokenStream,
+) -> (String, TokenStream) {
+    let depth_arg = if prefix.replace("{{", "").contains("{depth}") {
+        quote!(depth = fn_depth)
     } else {
-        quote!(#printer(#exiting_format, "", depth = DEPTH.with(|d| d.get()));)
+        quote!()
     };
-    parse_quote! {{
-        #printer(#entering_format, "", #(#arg_idents,)* depth = DEPTH.with(|d| d.get()));
-        #pause_stmt
-        DEPTH.with(|d| d.set(d.get() + 1));
-        let fn_return_value = #original_block;
-        DEPTH.with(|d| d.set(d.get() - 1));
-        #print_exit
-        #pause_stmt
-        fn_return_value
-    }}
+    (
+        "{runtime_prefix}".to_string(),
+        quote! {
+            runtime_prefix = match #runtime_prefix {
+                ::std::option::Option::Some(prefix) => ::std::borrow::Cow::Borrowed(prefix),
+                ::std::option::Option::None => {
+                    ::std::borrow::Cow::Owned(::std::format!(#prefix, #prefix_args #depth_arg))
+                }
+            },
+        },
+    )
 }
+
 // how interpolation parsing works:
 // we get a format string, we scan until we find a {,
 // once we find a { we check if we find another { right after for just escaping the interpolation
@@ -449,33 +7852,45 @@ fn construct_traced_block(
 // 2. It's already in keep_arg_idents its already been interpolated once
 //    so we just put as the index the index of the ident from keep_arg_idents
 // if there is any custom formatting information we put that right after the index in the
-// interpolation
+// interpolation, and if there isn't and `pretty` applies to it, we put `#?` there so it's pretty printed
 // otherwise if we are not in interpolation we didn't find a { we just add the char to the string
 // we are outputting
+// errors point at the part of the literal they're about, see `fmt_span`
 fn parse_fmt_str(
-    fmt_str: &str,
+    fmt_lit: &syn::LitStr,
     mut arg_idents: Vec<TokenStream>,
+    exit_params: Option<&[TokenStream]>,
+    pretty: &dyn Fn(&str) -> bool,
 ) -> (Result<String, syn::Error>, Vec<TokenStream>) {
+    let fmt_str = fmt_lit.value();
     let mut fixed_format_str = String::new();
     let mut kept_arg_idents = Vec::new();
-    let mut fmt_iter = fmt_str.chars().peekable();
-    while let Some(fmt_char) = fmt_iter.next() {
+    let mut fmt_iter = fmt_str.char_indices().peekable();
+    while let Some((offset, fmt_char)) = fmt_iter.next() {
         match fmt_char {
             '{' => {
-                if let Some('{') = fmt_iter.peek() {
+                if let Some(&(_, '{')) = fmt_iter.peek() {
                     fixed_format_str.push_str("{{");
                     fmt_iter.next();
                 } else {
-                    match parse_interpolated(&mut fmt_iter, &mut arg_idents, &mut kept_arg_idents) {
+                    match parse_interpolated(
+                        fmt_lit,
+                        offset,
+                        &mut fmt_iter,
+                        &mut arg_idents,
+                        &mut kept_arg_idents,
+                        exit_params,
+                        pretty,
+                    ) {
                         Ok(interpolated) => fixed_format_str.push_str(&interpolated),
                         Err(e) => return (Err(e), kept_arg_idents),
                     }
                 }
             }
             '}' => {
-                if fmt_iter.next() != Some('}') {
+                if fmt_iter.next().map(|(_, c)| c) != Some('}') {
                     return (Err(syn::Error::new(
-                            Span::call_site(),
+                            fmt_span(fmt_lit, offset..offset + 1),
                             "invalid format string: unmatched `}` found\nif you intended to print `}`, you can escape it using `}}`"
                         )), kept_arg_idents);
                 }
@@ -488,22 +7903,119 @@ fn parse_fmt_str(
     (Ok(fixed_format_str), kept_arg_idents)
 }
 
+// The span of the part of a format string literal at `range` (byte offsets into its value), so an
+// error points at the placeholder it's about, like the ones of `format_args!` do. Compilers that
+// can't point into a literal (stable ones), and literals whose source text isn't their value
+// (because of escapes), get the span of the whole literal instead
+fn fmt_span(fmt_lit: &syn::LitStr, range: Range<usize>) -> Span {
+    let token = fmt_lit.token();
+    let source = token.to_string();
+    let value = fmt_lit.value();
+    // `"` or `r#"`
+    let prefix_len = source.find('"').map_or(0, |quote| quote + 1);
+    let verbatim = source.get(prefix_len..prefix_len + value.len()) == Some(value.as_str())
+        && source
+            .get(prefix_len + value.len()..)
+            .is_some_and(|suffix| suffix.starts_with('"') && suffix[1..].chars().all(|c| c == '#'));
+    if !verbatim {
+        return fmt_lit.span();
+    }
+    token
+        .subspan(prefix_len + range.start..prefix_len + range.end)
+        .unwrap_or_else(|| fmt_lit.span())
+}
+
+// the parameter whose name is closest to `ident`, if one is close enough that `ident` is likely a
+// typo of it
+fn similar_name<'a>(
+    ident: &str,
+    names: impl IntoIterator<Item = &'a TokenStream>,
+) -> Option<String> {
+    // the number of characters inserted, removed, replaced, or swapped with the next one to turn
+    // `a` into `b`
+    fn edit_distance(a: &str, b: &str) -> usize {
+        let (a, b) = (a.chars().collect::<Vec<_>>(), b.chars().collect::<Vec<_>>());
+        let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for (i, row) in distances.iter_mut().enumerate() {
+            row[0] = i;
+        }
+        for (j, distance) in distances[0].iter_mut().enumerate() {
+            *distance = j;
+        }
+        for i in 1..=a.len() {
+            for j in 1..=b.len() {
+                let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+                let mut distance = substitution
+                    .min(distances[i - 1][j] + 1)
+                    .min(distances[i][j - 1] + 1);
+                if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                    distance = distance.min(distances[i - 2][j - 2] + 1);
+                }
+                distances[i][j] = distance;
+            }
+        }
+        distances[a.len()][b.len()]
+    }
+    let max_distance = ident.chars().count().max(3) / 3;
+    names
+        .into_iter()
+        .filter_map(|name| syn::parse2::<syn::Ident>(name.clone()).ok())
+        .map(|name| name.to_string())
+        .map(|name| (edit_distance(ident, &name), name))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, name)| name)
+}
+
+// `span` is the span of the placeholder `ident` was interpolated with, and `exit_params` the
+// parameters of the function when parsing `format_exit`, where they can't be interpolated
 fn fix_interpolated(
+    span: Span,
     last_char: char,
     ident: String,
     arg_idents: &mut Vec<TokenStream>,
     kept_arg_idents: &mut Vec<TokenStream>,
+    exit_params: Option<&[TokenStream]>,
+    pretty: &dyn Fn(&str) -> bool,
 ) -> Result<String, syn::Error> {
     if last_char != '}' {
         return Err(syn::Error::new(
-            Span::call_site(),
+            span,
             "invalid format string: expected `'}}'` but string was terminated\nif you intended to print `{{`, you can escape it using `{{`.",
         ));
     }
+    // counters and gauges are read from the thread-local map that `counter!` and `gauge!` write to
+    if let Some(metric) = ident
+        .strip_prefix("counter:")
+        .or_else(|| ident.strip_prefix("gauge:"))
+    {
+        let (name, custom_format) = metric.split_once(':').unwrap_or((metric, ""));
+        let metric_value = quote! {
+            TRACE_METRICS.with(|metrics| {
+                ::std::cell::RefCell::borrow(metrics).get(#name).copied().unwrap_or(0)
+            })
+        };
+        let index = match kept_arg_idents
+            .iter()
+            .position(|kept| kept.to_string() == metric_value.to_string())
+        {
+            Some(index) => index,
+            None => {
+                kept_arg_idents.push(metric_value);
+                kept_arg_idents.len() - 1
+            }
+        };
+        return Ok(format!("{{{}:{}}}", index + 1, custom_format));
+    }
+
     // just parsing to colon means we are relying on the format! macro to do the actual custom
-    // formatting stuff
-    let custom_format = ident.split_once(":");
-    let (ident, custom_format) = custom_format.unwrap_or((&ident, ""));
+    // formatting stuff. With `pretty`, only values without a colon are pretty printed, so `{x:}`
+    // still formats `x` with `Display`
+    let (ident, custom_format) = match ident.split_once(":") {
+        Some((ident, custom_format)) => (ident, custom_format),
+        None if pretty(&ident) => (ident.as_str(), "#?"),
+        None => (ident.as_str(), ""),
+    };
     let predicate = |arg_ident: &TokenStream| arg_ident.to_string() == ident;
 
     // we always put colon even if there is not custom format string, because we do not have to do
@@ -514,34 +8026,184 @@ fn fix_interpolated(
     } else if let Some(index) = arg_idents.iter().position(predicate) {
         kept_arg_idents.push(arg_idents.remove(index));
         Ok(format!("{{{}:{}}}", kept_arg_idents.len(), custom_format))
-    } else {
+    } else if let Some(expr) = syn::LitStr::new(ident, span)
+        .parse::<syn::Expr>()
+        .ok()
+        .filter(|expr| {
+            // an expression like `items.len()` can be interpolated if it starts with a parameter
+            !matches!(*expr, syn::Expr::Path(_))
+                && interpolated_expr_root(expr).is_some_and(|root| {
+                    kept_arg_idents
+                        .iter()
+                        .chain(arg_idents.iter())
+                        .any(|arg_ident| *root == arg_ident.to_string())
+                })
+        })
+    {
+        let expr = expand_accessors(expr, span)?.to_token_stream();
+        let index = match kept_arg_idents
+            .iter()
+            .position(|kept| kept.to_string() == expr.to_string())
+        {
+            Some(index) => index,
+            None => {
+                kept_arg_idents.push(expr);
+                kept_arg_idents.len() - 1
+            }
+        };
+        Ok(format!("{{{}:{}}}", index + 1, custom_format))
+    } else if exit_params.is_some_and(|params| params.iter().any(predicate)) {
         Err(syn::Error::new(
-            Span::call_site(),
-            // TODO: better error message
-            format!("cannot find `{ident}` in this scope."),
+            span,
+            format!(
+                "cannot interpolate `{ident}` in `format_exit`, parameters are not in scope there\nhelp: only the return value can be interpolated, as `{{r}}`"
+            ),
         ))
+    } else {
+        let mut message = format!("cannot find `{ident}` in this scope");
+        if let Some(name) = similar_name(ident, kept_arg_idents.iter().chain(arg_idents.iter())) {
+            if exit_params.is_some() {
+                message.push_str(&format!(
+                    "\nhelp: the return value is interpolated as `{{{name}}}`"
+                ));
+            } else {
+                message.push_str(&format!(
+                    "\nhelp: a parameter with a similar name exists: `{name}`"
+                ));
+            }
+        }
+        Err(syn::Error::new(span, message))
+    }
+}
+
+// `{r.ok}` and `{r.err}` interpolate what's in the `Ok` or the `Err` of a `Result` (as an
+// `Option`), and `{r?}` what's in the `Ok` or the `Some`, or else the whole `Err` or `None`, so just
+// the interesting part of the return value is printed
+fn expand_accessors(expr: syn::Expr, span: Span) -> Result<syn::Expr, syn::Error> {
+    fn expand(expr: syn::Expr, span: Span) -> Result<syn::Expr, syn::Error> {
+        match expr {
+            syn::Expr::Field(mut expr_field) => {
+                *expr_field.base = expand(*expr_field.base, span)?;
+                let base = &expr_field.base;
+                match expr_field.member {
+                    syn::Member::Named(ref ident) if ident == "ok" || ident == "err" => {
+                        Ok(parse_quote!(#base.as_ref().#ident()))
+                    }
+                    _ => Ok(syn::Expr::Field(expr_field)),
+                }
+            }
+            syn::Expr::MethodCall(mut expr_method_call) => {
+                *expr_method_call.receiver = expand(*expr_method_call.receiver, span)?;
+                Ok(syn::Expr::MethodCall(expr_method_call))
+            }
+            syn::Expr::Index(mut expr_index) => {
+                *expr_index.expr = expand(*expr_index.expr, span)?;
+                Ok(syn::Expr::Index(expr_index))
+            }
+            syn::Expr::Try(_) => Err(syn::Error::new(
+                span,
+                "`?` can only come at the end of an interpolated expression",
+            )),
+            expr => Ok(expr),
+        }
+    }
+    match expr {
+        syn::Expr::Try(expr_try) => {
+            let expr = expand(*expr_try.expr, span)?;
+            Ok(parse_quote!(#expr.fn_try()))
+        }
+        expr => expand(expr, span),
+    }
+}
+
+// the trait `{r?}` is interpolated with, which has to be declared in the traced block if any
+// interpolated expression uses it
+fn try_accessor_decl<'a>(interpolated: impl IntoIterator<Item = &'a TokenStream>) -> TokenStream {
+    let uses_try = interpolated
+        .into_iter()
+        .any(|tokens| tokens.to_string().contains("fn_try"));
+    if !uses_try {
+        return quote!();
+    }
+    quote! {
+        // the value is printed with `Debug`, even when it's interpolated without a format spec
+        struct FnTried<'a>(&'a dyn ::std::fmt::Debug);
+        impl ::std::fmt::Debug for FnTried<'_> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::fmt::Debug::fmt(self.0, f)
+            }
+        }
+        impl ::std::fmt::Display for FnTried<'_> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::fmt::Debug::fmt(self.0, f)
+            }
+        }
+        trait FnTry {
+            fn fn_try(&self) -> FnTried<'_>;
+        }
+        impl<T: ::std::fmt::Debug> FnTry for ::std::option::Option<T> {
+            fn fn_try(&self) -> FnTried<'_> {
+                match *self {
+                    ::std::option::Option::Some(ref value) => FnTried(value),
+                    ::std::option::Option::None => FnTried(self),
+                }
+            }
+        }
+        impl<T: ::std::fmt::Debug, E: ::std::fmt::Debug> FnTry for ::std::result::Result<T, E> {
+            fn fn_try(&self) -> FnTried<'_> {
+                match *self {
+                    ::std::result::Result::Ok(ref value) => FnTried(value),
+                    ::std::result::Result::Err(_) => FnTried(self),
+                }
+            }
+        }
+    }
+}
+
+// the parameter a field access, method call, or indexing expression starts with, e.g. `items` in
+// `items[0].len()`
+fn interpolated_expr_root(expr: &syn::Expr) -> Option<&syn::Ident> {
+    match *expr {
+        syn::Expr::Field(ref expr_field) => interpolated_expr_root(&expr_field.base),
+        syn::Expr::MethodCall(ref expr_method_call) => {
+            interpolated_expr_root(&expr_method_call.receiver)
+        }
+        syn::Expr::Index(ref expr_index) => interpolated_expr_root(&expr_index.expr),
+        syn::Expr::Try(ref expr_try) => interpolated_expr_root(&expr_try.expr),
+        syn::Expr::Path(ref expr_path) if expr_path.qself.is_none() => expr_path.path.get_ident(),
+        _ => None,
     }
 }
 
+// `open` is the offset of the `{` the placeholder starts with
 fn parse_interpolated(
-    fmt_iter: &mut Peekable<Chars>,
+    fmt_lit: &syn::LitStr,
+    open: usize,
+    fmt_iter: &mut Peekable<CharIndices>,
     arg_idents: &mut Vec<TokenStream>,
     kept_arg_idents: &mut Vec<TokenStream>,
+    exit_params: Option<&[TokenStream]>,
+    pretty: &dyn Fn(&str) -> bool,
 ) -> Result<String, syn::Error> {
     let mut last_char = ' ';
     let mut ident = String::new();
-    while let Some(ident_char) = fmt_iter.next() {
+    let mut close = fmt_lit.value().len();
+    while let Some((offset, ident_char)) = fmt_iter.next() {
         match ident_char {
             '}' => {
                 last_char = '}';
+                close = offset + 1;
                 break;
             }
             _ => {
                 last_char = ident_char;
                 if !ident_char.is_whitespace() {
                     ident.push(ident_char);
-                } else {
-                    skip_whitespace_and_check(fmt_iter, &mut last_char, ident_char)?;
+                } else if let Some(offset) =
+                    skip_whitespace_and_check(fmt_lit, fmt_iter, &mut last_char, ident_char)?
+                {
+                    close = offset + 1;
+                    break;
                 }
             }
         }
@@ -550,32 +8212,465 @@ fn parse_interpolated(
     // inf fix_interpolated we will check that has the same string representation as one of the
     // functions parameters, but if we did this is how we would do it
     // syn::parse_str::<syn::Ident>(&ident)?;
-    fix_interpolated(last_char, ident, arg_idents, kept_arg_idents)
+    let span = fmt_span(fmt_lit, open..close);
+    fix_interpolated(
+        span,
+        last_char,
+        ident,
+        arg_idents,
+        kept_arg_idents,
+        exit_params,
+        pretty,
+    )
 }
 
+// the offset of the `}` that closes the placeholder, if whitespace was followed by one
 fn skip_whitespace_and_check(
-    fmt_iter: &mut Peekable<Chars>,
+    fmt_lit: &syn::LitStr,
+    fmt_iter: &mut Peekable<CharIndices>,
     last_char: &mut char,
     ident_char: char,
-) -> Result<(), syn::Error> {
-    for blank_char in fmt_iter.by_ref() {
+) -> Result<Option<usize>, syn::Error> {
+    for (offset, blank_char) in fmt_iter.by_ref() {
         match blank_char {
             '}' => {
                 *last_char = '}';
-                break;
+                return Ok(Some(offset));
             }
             c if c.is_whitespace() => {
                 *last_char = ident_char;
             }
             _ => {
                 return Err(syn::Error::new(
-                    Span::call_site(),
+                    fmt_span(fmt_lit, offset..offset + blank_char.len_utf8()),
                     format!("invalid format string: expected `'}}'`, found `'{blank_char}'`\nif you intended to print `{{`, you can escape it using `{{`."),
                 ))
             }
         }
     }
-    Ok(())
+    Ok(None)
+}
+
+// renders a type the way it would be written in source, since the token stream's `Display` puts
+// spaces around every punctuation token (`impl Iterator < Item = u32 >`)
+fn type_to_string(ty: &syn::Type) -> String {
+    let spaced = ty.to_token_stream().to_string();
+    let mut out = String::with_capacity(spaced.len());
+    let mut chars = spaced.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ' ' {
+            let prev = out.chars().last();
+            let next = chars.peek().copied();
+            let tight_before = matches!(next, Some('<' | '>' | ',' | ')' | ':'))
+                || (next == Some('(') && prev != Some('>'));
+            let tight_after = matches!(prev, Some('<' | '(' | '&' | ':'));
+            if tight_before || tight_after {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+// an expression checking the arguments against the `only_when` matchers, if there are any. When
+// `#[trace]` is applied to a `mod` or `impl`, matchers for arguments that a function doesn't have
+// are left out for that function
+fn matches_only_when(
+    args: &args::Args,
+    attr_applied: AttrApplied,
+    sig: &syn::Signature,
+) -> Option<TokenStream> {
+    let all_arg_idents = extract_arg_idents(args, AttrApplied::Indirectly, sig);
+    let matchers = args
+        .only_when
+        .iter()
+        .filter(|(ident, _)| {
+            matches!(attr_applied, AttrApplied::Directly) || all_arg_idents.contains(ident)
+        })
+        .map(|(ident, lit)| match *lit {
+            // strings match either the `Debug` output itself (e.g. for enum variants) or a string
+            // argument with the same contents
+            syn::Lit::Str(_) => quote! {{
+                let fn_arg_debug = ::std::format!("{:?}", #ident);
+                fn_arg_debug == #lit || fn_arg_debug == ::std::format!("{:?}", #lit)
+            }},
+            syn::Lit::Int(ref lit_int) => {
+                let digits = lit_int.base10_digits();
+                quote!((::std::format!("{:?}", #ident) == #digits))
+            }
+            syn::Lit::Float(ref lit_float) => {
+                let digits = lit_float.base10_digits();
+                quote!((::std::format!("{:?}", #ident) == #digits))
+            }
+            _ => {
+                let text = lit.to_token_stream().to_string();
+                quote!((::std::format!("{:?}", #ident) == #text))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if matchers.is_empty() {
+        None
+    } else {
+        Some(quote!(#(#matchers)&&*))
+    }
+}
+
+// whether `value` is an `Err`, which is decided by its type rather than by how the return type is
+// spelled, so aliases of `Result` count too. `FnIsErr` gives `Result`s their `fn_is_err`, which
+// takes the value by reference and is found before the one `FnIsNotErr` gives every other type,
+// which takes a reference to the reference
+fn is_err(value: &TokenStream) -> TokenStream {
+    quote! {{
+        trait FnIsErr {
+            fn fn_is_err(&self) -> bool;
+        }
+        impl<T, E> FnIsErr for ::std::result::Result<T, E> {
+            fn fn_is_err(&self) -> bool {
+                ::std::result::Result::is_err(self)
+            }
+        }
+        trait FnIsNotErr {
+            fn fn_is_err(&self) -> bool {
+                false
+            }
+        }
+        impl<T: ?::std::marker::Sized> FnIsNotErr for &T {}
+        (&#value).fn_is_err()
+    }}
+}
+
+// the reference counted pointer (`Rc` or `Arc`) a type is spelled as, looking through references,
+// along with the path to its `strong_count` and `weak_count` functions
+fn rc_type(ty: &syn::Type) -> Option<(&'static str, TokenStream)> {
+    match *ty {
+        syn::Type::Reference(ref type_reference) => rc_type(&type_reference.elem),
+        syn::Type::Paren(ref type_paren) => rc_type(&type_paren.elem),
+        syn::Type::Group(ref type_group) => rc_type(&type_group.elem),
+        syn::Type::Path(ref type_path) => match type_path.path.segments.last() {
+            Some(segment) if segment.ident == "Rc" => Some(("Rc", quote!(::std::rc::Rc))),
+            Some(segment) if segment.ident == "Arc" => Some(("Arc", quote!(::std::sync::Arc))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Whether the function returns a `Box`, `Rc`, or `Arc` of `Self`
+fn returns_boxed_self(output: &syn::ReturnType) -> bool {
+    let ty = match *output {
+        syn::ReturnType::Type(_, ref ty) => ty,
+        syn::ReturnType::Default => return false,
+    };
+    let segment = match **ty {
+        syn::Type::Path(ref type_path) => match type_path.path.segments.last() {
+            Some(segment) => segment,
+            None => return false,
+        },
+        _ => return false,
+    };
+    let pointee = match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref arguments) if arguments.args.len() == 1 => {
+            &arguments.args[0]
+        }
+        _ => return false,
+    };
+    ["Box", "Rc", "Arc"]
+        .iter()
+        .any(|name| segment.ident == name)
+        && matches!(
+            *pointee,
+            syn::GenericArgument::Type(syn::Type::Path(ref type_path))
+                if type_path.path.is_ident("Self")
+        )
+}
+
+// Instances are told apart by their address (and type, since e.g. a struct and its first field
+// have the same address), and numbered in the order they're first seen
+fn instance_id(args: &args::Args, instance: &TokenStream) -> TokenStream {
+    let trace_instance_ids = trace_var_path(args, "TRACE_INSTANCE_IDS");
+    quote! {{
+        let mut ids = ::std::result::Result::unwrap_or_else(
+            #trace_instance_ids.lock(),
+            ::std::sync::PoisonError::into_inner,
+        );
+        let ids = ::std::option::Option::get_or_insert_with(&mut *ids, ::std::collections::HashMap::new);
+        let next_id = ids.len() as u64 + 1;
+        let address = (#instance) as *const Self as *const () as usize;
+        *ids.entry((::std::any::type_name::<Self>(), address)).or_insert(next_id)
+    }}
+}
+
+// the method that tries to lock an argument of this type without blocking, if it's spelled as a
+// `Mutex` or an `RwLock` (or a reference or a smart pointer to one)
+fn try_lock_method(ty: &syn::Type) -> Option<syn::Ident> {
+    match *ty {
+        syn::Type::Reference(ref type_reference) => try_lock_method(&type_reference.elem),
+        syn::Type::Paren(ref type_paren) => try_lock_method(&type_paren.elem),
+        syn::Type::Group(ref type_group) => try_lock_method(&type_group.elem),
+        syn::Type::Path(ref type_path) => {
+            let segment = type_path.path.segments.last()?;
+            if segment.ident == "Mutex" {
+                Some(syn::Ident::new("try_lock", Span::call_site()))
+            } else if segment.ident == "RwLock" {
+                Some(syn::Ident::new("try_read", Span::call_site()))
+            } else if ["Box", "Rc", "Arc"]
+                .iter()
+                .any(|name| segment.ident == name)
+            {
+                match segment.arguments {
+                    syn::PathArguments::AngleBracketed(ref generic_args) => {
+                        match generic_args.args.first()? {
+                            syn::GenericArgument::Type(ref ty) => try_lock_method(ty),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+// how many times an argument of this type has to be dereferenced to get through its references and
+// the pointer behind them, and whether that pointer is spelled as a smart pointer
+fn deref_depth(ty: &syn::Type) -> (usize, bool) {
+    const SMART_POINTERS: &[&str] = &[
+        "Box",
+        "Rc",
+        "Arc",
+        "Ref",
+        "RefMut",
+        "MutexGuard",
+        "RwLockReadGuard",
+        "RwLockWriteGuard",
+    ];
+    match *ty {
+        syn::Type::Reference(ref type_reference) => {
+            let (derefs, is_smart_pointer) = deref_depth(&type_reference.elem);
+            (derefs + 1, is_smart_pointer)
+        }
+        syn::Type::Paren(ref type_paren) => deref_depth(&type_paren.elem),
+        syn::Type::Group(ref type_group) => deref_depth(&type_group.elem),
+        syn::Type::Path(ref type_path) => (
+            1,
+            type_path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| SMART_POINTERS.iter().any(|name| segment.ident == name)),
+        ),
+        _ => (1, false),
+    }
+}
+
+fn type_param_names(generics: &syn::Generics) -> Vec<String> {
+    generics
+        .type_params()
+        .map(|type_param| type_param.ident.to_string())
+        .collect()
+}
+
+fn mentions_type_param(ty: &syn::Type, type_params: &[String]) -> bool {
+    fn mentions(tokens: TokenStream, type_params: &[String]) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => type_params.contains(&ident.to_string()),
+            proc_macro2::TokenTree::Group(group) => mentions(group.stream(), type_params),
+            _ => false,
+        })
+    }
+    mentions(ty.to_token_stream(), type_params)
+}
+
+// whether the arguments or the return type of `sig` mention an associated type of `Self`, like
+// `Self::Item` or `<Self as Iterator>::Item`
+fn mentions_assoc_type(sig: &syn::Signature) -> bool {
+    fn mentions(tokens: TokenStream) -> bool {
+        let tokens = tokens.into_iter().collect::<Vec<_>>();
+        tokens.iter().enumerate().any(|(i, token)| match *token {
+            proc_macro2::TokenTree::Ident(ref ident) if ident == "Self" => {
+                match tokens.get(i + 1) {
+                    Some(proc_macro2::TokenTree::Punct(punct)) => punct.as_char() == ':',
+                    Some(proc_macro2::TokenTree::Ident(ident)) => ident == "as",
+                    _ => false,
+                }
+            }
+            proc_macro2::TokenTree::Group(ref group) => mentions(group.stream()),
+            _ => false,
+        })
+    }
+    mentions(sig.inputs.to_token_stream()) || mentions(sig.output.to_token_stream())
+}
+
+// the names of the arguments whose types are (or contain) an `impl Trait`
+fn impl_trait_arg_names(sig: &syn::Signature) -> Vec<String> {
+    sig.inputs
+        .iter()
+        .filter_map(|input| match *input {
+            syn::FnArg::Typed(ref pat_type)
+                if contains_impl_trait(pat_type.ty.to_token_stream()) =>
+            {
+                match *pat_type.pat {
+                    syn::Pat::Ident(ref pat_ident) => Some(pat_ident.ident.to_string()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// an expression that prints `value` with `Debug` if its type is known to implement it, and as
+// `<type name>` otherwise. The choice is made with autoref specialization: the method of
+// `TraceViaDebug` is found first, but only applies if the type implements `Debug`
+fn debug_or_type_name(value: &TokenStream) -> TokenStream {
+    quote! {{
+        struct TraceValue<'a, T: ?::std::marker::Sized>(&'a T);
+        struct TraceTypeName(&'static str);
+        impl ::std::fmt::Debug for TraceTypeName {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::write!(f, "<{}>", self.0)
+            }
+        }
+        trait TraceViaDebug<'a> {
+            fn trace_value(&self) -> &'a dyn ::std::fmt::Debug;
+        }
+        impl<'a, T: ::std::fmt::Debug> TraceViaDebug<'a> for TraceValue<'a, T> {
+            fn trace_value(&self) -> &'a dyn ::std::fmt::Debug {
+                self.0
+            }
+        }
+        trait TraceViaTypeName {
+            fn trace_value(&self) -> TraceTypeName;
+        }
+        impl<T: ?::std::marker::Sized> TraceViaTypeName for &TraceValue<'_, T> {
+            fn trace_value(&self) -> TraceTypeName {
+                TraceTypeName(::std::any::type_name::<T>())
+            }
+        }
+        (&TraceValue(&#value)).trace_value()
+    }}
+}
+
+// the value formatted with `format`, cut off after `max_length` characters (followed by `…` if it
+// was longer). The writer it's formatted into fails once it's full, which stops well-behaved
+// `Debug` impls from formatting the rest of a huge value (or an endless one)
+fn truncated(format: &str, value: &TokenStream, max_length: u64) -> TokenStream {
+    let max_length = proc_macro2::Literal::u64_unsuffixed(max_length);
+    quote! {{
+        struct FnBoundedWriter(::std::string::String, u64, bool);
+        impl ::std::fmt::Write for FnBoundedWriter {
+            fn write_str(&mut self, s: &str) -> ::std::fmt::Result {
+                for c in s.chars() {
+                    if self.1 == #max_length {
+                        self.2 = true;
+                        return ::std::result::Result::Err(::std::fmt::Error);
+                    }
+                    self.0.push(c);
+                    self.1 += 1;
+                }
+                ::std::result::Result::Ok(())
+            }
+        }
+        let mut fn_bounded_writer = FnBoundedWriter(::std::string::String::new(), 0, false);
+        // the error is the writer being full, which is reported with the `…`
+        let _ = ::std::fmt::write(&mut fn_bounded_writer, ::std::format_args!(#format, #value));
+        if fn_bounded_writer.2 {
+            fn_bounded_writer.0.push('…');
+        }
+        fn_bounded_writer.0
+    }}
+}
+
+// the current time in UTC, formatted as RFC 3339 with milliseconds, e.g.
+// `2024-05-01T12:34:56.789Z` (the date is worked out from the days since the Unix epoch with
+// Howard Hinnant's `civil_from_days`)
+fn rfc3339_timestamp() -> TokenStream {
+    quote! {{
+        let since_epoch = ::std::result::Result::unwrap_or_default(::std::time::SystemTime::duration_since(
+            &::std::time::SystemTime::now(),
+            ::std::time::UNIX_EPOCH,
+        ));
+        let secs = since_epoch.as_secs();
+        let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+        let days = days + 719_468;
+        let (era, day_of_era) = (days / 146_097, days % 146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+        ::std::format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year,
+            month,
+            day,
+            secs_of_day / 3_600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60,
+            since_epoch.subsec_millis()
+        )
+    }}
+}
+
+// Strings are escaped the way JSON needs, which isn't quite the way `Debug` escapes them
+fn json_string(value: TokenStream) -> TokenStream {
+    quote! {{
+        let value = #value;
+        let mut json = ::std::string::String::with_capacity(value.len() + 2);
+        json.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if (c as ::std::primitive::u32) < 0x20 => {
+                    json.push_str(&::std::format!("\\u{:04x}", c as ::std::primitive::u32));
+                }
+                c => json.push(c),
+            }
+        }
+        json.push('"');
+        json
+    }}
+}
+
+// Only a hash of each value is kept, so that memory isn't used up by the (presumably large) values
+fn deduplicated(format: &str, value: &TokenStream) -> TokenStream {
+    quote! {{
+        static FN_SEEN: ::std::sync::Mutex<::std::option::Option<::std::collections::HashMap<u64, u64>>> =
+            ::std::sync::Mutex::new(::std::option::Option::None);
+        let value = ::std::format!(#format, #value);
+        let mut hasher = ::std::collections::hash_map::DefaultHasher::new();
+        ::std::hash::Hash::hash(&value, &mut hasher);
+        let hash = ::std::hash::Hasher::finish(&hasher);
+        let mut seen = FN_SEEN.lock().unwrap_or_else(::std::sync::PoisonError::into_inner);
+        match ::std::option::Option::get_or_insert_with(&mut *seen, ::std::collections::HashMap::new).entry(hash) {
+            ::std::collections::hash_map::Entry::Occupied(entry) => {
+                ::std::format!("<same as call #{}>", entry.get())
+            }
+            ::std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(fn_call_number);
+                value
+            }
+        }
+    }}
+}
+
+fn contains_impl_trait(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ref ident) => ident == "impl",
+        proc_macro2::TokenTree::Group(ref group) => contains_impl_trait(group.stream()),
+        _ => false,
+    })
 }
 
 fn extract_arg_idents(
@@ -595,10 +8690,10 @@ fn extract_arg_idents(
 
                 if let AttrApplied::Directly = attr_applied {
                     match args.filter {
-                        args::Filter::Enable(ref idents) if !idents.contains(ident) => {
+                        args::Filter::Enable(ref names) if !names.matches(ident) => {
                             return;
                         }
-                        args::Filter::Disable(ref idents) if idents.contains(ident) => {
+                        args::Filter::Disable(ref names) if names.matches(ident) => {
                             return;
                         }
                         _ => (),
@@ -606,13 +8701,58 @@ fn extract_arg_idents(
                 }
 
                 arg_idents.push(ident.clone());
+
+                // `ident @ subpat` binds names in the subpattern as well
+                if let Some((_, ref subpat)) = pat_ident.subpat {
+                    process_pat(args, attr_applied, subpat, arg_idents);
+                }
             }
             syn::Pat::Tuple(ref pat_tuple) => {
                 pat_tuple.elems.iter().for_each(|pat| {
                     process_pat(args, attr_applied, pat, arg_idents);
                 });
             }
-            _ => unimplemented!(),
+            syn::Pat::TupleStruct(ref pat_tuple_struct) => {
+                pat_tuple_struct.pat.elems.iter().for_each(|pat| {
+                    process_pat(args, attr_applied, pat, arg_idents);
+                });
+            }
+            syn::Pat::Struct(ref pat_struct) => {
+                pat_struct.fields.iter().for_each(|field_pat| {
+                    process_pat(args, attr_applied, &field_pat.pat, arg_idents);
+                });
+            }
+            syn::Pat::Slice(ref pat_slice) => {
+                pat_slice.elems.iter().for_each(|pat| {
+                    process_pat(args, attr_applied, pat, arg_idents);
+                });
+            }
+            syn::Pat::Reference(ref pat_reference) => {
+                process_pat(args, attr_applied, &pat_reference.pat, arg_idents);
+            }
+            syn::Pat::Box(ref pat_box) => {
+                process_pat(args, attr_applied, &pat_box.pat, arg_idents);
+            }
+            syn::Pat::Type(ref pat_type) => {
+                process_pat(args, attr_applied, &pat_type.pat, arg_idents);
+            }
+            // every case of an or-pattern binds the same names
+            syn::Pat::Or(ref pat_or) => {
+                if let Some(pat) = pat_or.cases.first() {
+                    process_pat(args, attr_applied, pat, arg_idents);
+                }
+            }
+            // `_`, `..`, and paths to unit structs don't bind anything. Neither do literals and
+            // ranges, which the compiler rejects as refutable, and the names a macro binds can't be
+            // seen before it's expanded, so those aren't printed
+            syn::Pat::Wild(_)
+            | syn::Pat::Rest(_)
+            | syn::Pat::Path(_)
+            | syn::Pat::Lit(_)
+            | syn::Pat::Range(_)
+            | syn::Pat::Macro(_)
+            | syn::Pat::Verbatim(_) => (),
+            _ => (),
         }
     }
 
@@ -0,0 +1,139 @@
+//! A handler for controlling tracing over HTTP, e.g. on a debug port of a service
+//!
+//! It doesn't depend on any HTTP library: the [`Request`] is made from the method and path of the
+//! request the server received, and the [`Response`] is turned into whatever response type the
+//! server uses. The paths are relative to where the handler is mounted:
+//!
+//! - `GET /functions` lists the functions traced with `registry` as a JSON array, with their path,
+//!   file, line, arguments to `#[trace]`, whether they're enabled, and how many times they've been
+//!   called so far.
+//! - `POST /functions/<path>/enable` and `POST /functions/<path>/disable` enable or disable the
+//!   function with that path, e.g. `/functions/my_crate::parser::parse/disable`.
+//! - `GET /enabled` tells whether tracing is enabled at all, and `POST /enable` and `POST /disable`
+//!   enable or disable it, like [`set_enabled`](crate::set_enabled).
+//!
+//! ```
+//! use trace_runtime::admin::{handle, Request};
+//!
+//! let response = handle(&Request {
+//!     method: "POST",
+//!     path: "/disable",
+//! });
+//! assert_eq!(response.status, 200);
+//! assert!(!trace_runtime::enabled());
+//! # trace_runtime::set_enabled(true);
+//! ```
+
+use crate::{registry, TracedFn};
+use std::fmt::Write;
+
+/// The parts of an HTTP request the handler looks at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Request<'a> {
+    /// The method, e.g. `GET`
+    pub method: &'a str,
+    /// The path, relative to where the handler is mounted. A query string is ignored.
+    pub path: &'a str,
+}
+
+/// What to respond with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    /// The value of the `Content-Type` header
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn json(body: String) -> Self {
+        Response {
+            status: 200,
+            content_type: "application/json",
+            body,
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{}\n", message),
+        }
+    }
+}
+
+/// Handles a request to the admin endpoint, see the [module documentation](self)
+pub fn handle(request: &Request) -> Response {
+    let path = request.path.split('?').next().unwrap_or_default();
+    let path = path.trim_end_matches('/');
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    match (request.method, segments.as_slice()) {
+        ("GET", ["functions"]) => {
+            let mut traced_fns = registry().collect::<Vec<_>>();
+            traced_fns.sort_by_key(|traced_fn| (traced_fn.module, traced_fn.name));
+            let traced_fns = traced_fns
+                .into_iter()
+                .map(traced_fn_json)
+                .collect::<Vec<_>>();
+            Response::json(format!("[{}]", traced_fns.join(",")))
+        }
+        ("POST", ["functions", fn_path, action @ ("enable" | "disable")]) => {
+            let mut found = false;
+            for traced_fn in registry().filter(|traced_fn| traced_fn.path() == *fn_path) {
+                traced_fn.set_enabled(*action == "enable");
+                found = true;
+            }
+            if found {
+                Response::json(format!("{{\"enabled\":{}}}", *action == "enable"))
+            } else {
+                Response::error(404, &format!("no function `{}` is traced", fn_path))
+            }
+        }
+        ("GET", ["enabled"]) => Response::json(format!("{{\"enabled\":{}}}", crate::enabled())),
+        ("POST", [action @ ("enable" | "disable")]) => {
+            crate::set_enabled(*action == "enable");
+            Response::json(format!("{{\"enabled\":{}}}", *action == "enable"))
+        }
+        (
+            _,
+            ["functions" | "enabled" | "enable" | "disable"]
+            | ["functions", _, "enable" | "disable"],
+        ) => Response::error(405, "method not allowed"),
+        _ => Response::error(404, "not found"),
+    }
+}
+
+fn traced_fn_json(traced_fn: &TracedFn) -> String {
+    format!(
+        "{{\"path\":{},\"file\":{},\"line\":{},\"options\":{},\"enabled\":{},\"calls\":{}}}",
+        json_string(&traced_fn.path()),
+        json_string(traced_fn.file),
+        traced_fn.line,
+        json_string(traced_fn.options),
+        traced_fn.enabled(),
+        traced_fn.calls()
+    )
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
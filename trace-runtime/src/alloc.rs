@@ -0,0 +1,95 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The number of bytes currently allocated through [`TrackingAllocator`]
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// The highest value of `ALLOCATED` since the innermost running [`AllocPeak`] started
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// A global allocator that keeps track of how much memory is allocated, for `#[trace(alloc_peak)]`
+///
+/// It passes all allocations on to [`System`]:
+/// ```
+/// #[global_allocator]
+/// static ALLOCATOR: trace_runtime::TrackingAllocator = trace_runtime::TrackingAllocator;
+/// # fn main() {}
+/// ```
+pub struct TrackingAllocator;
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            added(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            added(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            if new_size > layout.size() {
+                added(new_size - layout.size());
+            } else {
+                ALLOCATED.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
+
+fn added(size: usize) {
+    let allocated = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(allocated, Ordering::Relaxed);
+}
+
+/// The number of bytes currently allocated through [`TrackingAllocator`]
+pub fn allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// Measures the peak allocation while it's alive, e.g. during a traced call
+///
+/// Measurements can be nested: once an inner one is dropped, the outer one still sees the peak
+/// reached during it.
+pub struct AllocPeak {
+    base: usize,
+    outer_peak: usize,
+}
+
+impl AllocPeak {
+    /// Starts measuring from the amount of memory allocated right now
+    pub fn start() -> Self {
+        let base = ALLOCATED.load(Ordering::Relaxed);
+        let outer_peak = PEAK.swap(base, Ordering::Relaxed);
+        AllocPeak { base, outer_peak }
+    }
+
+    /// The most bytes that were allocated at once since [`AllocPeak::start`], on top of what was
+    /// already allocated then
+    pub fn peak(&self) -> usize {
+        PEAK.load(Ordering::Relaxed).saturating_sub(self.base)
+    }
+}
+
+impl Drop for AllocPeak {
+    fn drop(&mut self) {
+        PEAK.fetch_max(self.outer_peak, Ordering::Relaxed);
+    }
+}
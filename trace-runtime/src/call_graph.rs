@@ -0,0 +1,124 @@
+//! Recording which traced functions call which, to be drawn with Graphviz
+//!
+//! Functions traced with `#[trace(output = "call_graph")]` record an edge from the traced function
+//! that was running on the same thread when they were called (if there was one), counting how
+//! many times each function called each other one. [`dot`] turns what was recorded so far into a
+//! graph in the DOT language, e.g. to be rendered with `dot -Tsvg calls.dot -o calls.svg`.
+//!
+//! ```
+//! # fn parse() {
+//! #     let _call = trace_runtime::call_graph::enter("parse");
+//! #     lex();
+//! #     lex();
+//! # }
+//! # fn lex() {
+//! #     let _call = trace_runtime::call_graph::enter("lex");
+//! # }
+//! // `parse` and `lex` are traced with `#[trace(output = "call_graph")]`
+//! parse();
+//! assert_eq!(
+//!     trace_runtime::call_graph::dot(),
+//!     "digraph calls {\n    \"lex\" [label=\"lex\\n2 calls\"];\n    \"parse\" [label=\"parse\\n1 call\"];\n    \"parse\" -> \"lex\" [label=\"2\"];\n}\n"
+//! );
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs, io,
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
+
+/// The number of calls of every function, and of every function by every other one
+struct Graph {
+    calls: BTreeMap<&'static str, u64>,
+    edges: BTreeMap<(&'static str, &'static str), u64>,
+}
+
+static GRAPH: Mutex<Graph> = Mutex::new(Graph {
+    calls: BTreeMap::new(),
+    edges: BTreeMap::new(),
+});
+
+thread_local! {
+    /// The traced functions running on the current thread, innermost last
+    static STACK: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records a call of the function `name` by the innermost traced function running on the current
+/// thread, which it stays until the returned guard is dropped
+///
+/// Used by functions traced with `#[trace(output = "call_graph")]`.
+pub fn enter(name: &'static str) -> CallGuard {
+    let caller = STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let caller = stack.last().copied();
+        stack.push(name);
+        caller
+    });
+    let mut graph = GRAPH.lock().unwrap_or_else(PoisonError::into_inner);
+    *graph.calls.entry(name).or_default() += 1;
+    if let Some(caller) = caller {
+        *graph.edges.entry((caller, name)).or_default() += 1;
+    }
+    CallGuard(name)
+}
+
+/// Ends a call when it's dropped, see [`enter`]
+#[must_use]
+pub struct CallGuard(&'static str);
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        // the future of an `async fn` may be dropped while other calls made on the thread are
+        // still running, so the call is removed wherever it is
+        STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            if let Some(position) = stack.iter().rposition(|&name| name == self.0) {
+                stack.remove(position);
+            }
+        });
+    }
+}
+
+/// The calls recorded so far, as a graph in the DOT language
+///
+/// Every traced function that was called is a node labeled with how many times it was called,
+/// and every edge is labeled with how many times its caller called its callee.
+pub fn dot() -> String {
+    let graph = GRAPH.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut dot = String::from("digraph calls {\n");
+    for (name, &calls) in &graph.calls {
+        let _ = writeln!(
+            dot,
+            "    \"{}\" [label=\"{}\\n{} call{}\"];",
+            name,
+            name,
+            calls,
+            if calls == 1 { "" } else { "s" }
+        );
+    }
+    for (&(caller, callee), &calls) in &graph.edges {
+        let _ = writeln!(
+            dot,
+            "    \"{}\" -> \"{}\" [label=\"{}\"];",
+            caller, callee, calls
+        );
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Writes the graph returned by [`dot`] to the file at `path`
+pub fn write_dot(path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, dot())
+}
+
+/// Forgets the calls recorded so far
+pub fn clear() {
+    let mut graph = GRAPH.lock().unwrap_or_else(PoisonError::into_inner);
+    graph.calls.clear();
+    graph.edges.clear();
+}
@@ -0,0 +1,101 @@
+//! Recording calls in the Trace Event Format of `chrome://tracing` and Perfetto
+//!
+//! Events are buffered, and written to their files when the program exits.
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, Once, OnceLock, PoisonError,
+    },
+    time::Instant,
+};
+
+/// The open trace files, by path
+static FILES: Mutex<Option<HashMap<&'static str, BufWriter<File>>>> = Mutex::new(None);
+
+/// When the first event was recorded, which timestamps are relative to
+static START: OnceLock<Instant> = OnceLock::new();
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// A small number identifying the current thread in the trace
+    static THREAD_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+static REGISTER_FLUSH: Once = Once::new();
+
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> std::os::raw::c_int;
+}
+
+extern "C" fn flush_at_exit() {
+    flush();
+}
+
+/// Records that the function `name` was entered, in the Trace Event Format file at `path`
+///
+/// The file is created (or truncated) the first time an event is recorded for it.
+pub fn begin(path: &'static str, name: &'static str) {
+    record(path, name, 'B');
+}
+
+/// Records that the function `name` was exited, in the Trace Event Format file at `path`
+pub fn end(path: &'static str, name: &'static str) {
+    record(path, name, 'E');
+}
+
+fn record(path: &'static str, name: &'static str, phase: char) {
+    let ts = START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1_000_000.0;
+    let tid = THREAD_ID.with(|id| {
+        if id.get() == 0 {
+            id.set(NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed));
+        }
+        id.get()
+    });
+    let mut files = FILES.lock().unwrap_or_else(PoisonError::into_inner);
+    let files = files.get_or_insert_with(HashMap::new);
+    if !files.contains_key(path) {
+        let Ok(file) = File::create(path) else {
+            return;
+        };
+        let mut file = BufWriter::new(file);
+        let _ = file.write_all(b"[\n");
+        files.insert(path, file);
+        // the events are buffered, so they're written out when the program exits
+        REGISTER_FLUSH.call_once(|| unsafe {
+            atexit(flush_at_exit);
+        });
+    }
+    if let Some(file) = files.get_mut(path) {
+        let _ = writeln!(
+            file,
+            r#"{{"name":"{}","cat":"trace","ph":"{}","ts":{:.3},"pid":{},"tid":{}}},"#,
+            name,
+            phase,
+            ts,
+            std::process::id(),
+            tid
+        );
+    }
+}
+
+// writes the buffered events to their files and finishes them, so they're valid JSON. This happens
+// when the program exits normally (including with `std::process::exit`), but not if it's killed or
+// aborts, in which case the files are cut off, which the viewers accept too
+fn flush() {
+    let mut files = FILES.lock().unwrap_or_else(PoisonError::into_inner);
+    for (_, mut file) in files.take().into_iter().flatten() {
+        // the metadata event ends the array without a trailing comma
+        let _ = writeln!(
+            file,
+            r#"{{"name":"process_name","ph":"M","pid":{},"args":{{"name":"trace"}}}}]"#,
+            std::process::id()
+        );
+        let _ = file.flush();
+    }
+}
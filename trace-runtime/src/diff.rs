@@ -0,0 +1,376 @@
+//! Comparing two runs recorded with `#[trace(format = "json")]`
+//!
+//! A recording is what the traced program printed, one JSON object per line, e.g. captured with
+//! [`set_writer`](crate::set_writer) or by redirecting stdout to a file. Lines that aren't events
+//! of a traced call (like the program's own output) are skipped.
+
+use std::{collections::HashMap, fmt};
+
+/// A call read from a recording
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Call {
+    /// The name of the function
+    pub name: String,
+    /// How deeply the call was nested in other traced calls
+    pub depth: usize,
+    /// The arguments, as they were printed
+    pub args: Vec<(String, String)>,
+    /// The return value as it was printed, or `None` if the call panicked (or never returned)
+    pub ret: Option<String>,
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}(", self.name)?;
+        for (i, (name, value)) in self.args.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} = {}", name, value)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// A difference between two recordings
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A call that's only in the second recording
+    Added(Call),
+    /// A call that's only in the first recording
+    Removed(Call),
+    /// A call that's in both recordings, but in a different place, given as its position among
+    /// the calls of each recording
+    Moved { call: Call, from: usize, to: usize },
+    /// A call with the same arguments in both recordings that returned something else
+    Returned { before: Call, after: Call },
+}
+
+/// The differences between two recordings, see [`diff`]
+///
+/// It's printed with one line per change: `+ foo(a = 1)` for added calls, `- foo(a = 1)` for
+/// removed ones, `~ foo(a = 1) moved from #3 to #5` for moved ones, and
+/// `! foo(a = 1) = 2 -> 3` for changed return values, indented by the depth of the call.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Whether both recordings made the same calls, in the same order, with the same return values
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ret = |call: &Call| match call.ret {
+            Some(ref ret) => ret.clone(),
+            None => "(panicked)".to_string(),
+        };
+        for change in &self.changes {
+            match *change {
+                Change::Added(ref call) => writeln!(f, "{:2$}+ {}", "", call, call.depth)?,
+                Change::Removed(ref call) => writeln!(f, "{:2$}- {}", "", call, call.depth)?,
+                Change::Moved { ref call, from, to } => writeln!(
+                    f,
+                    "{:4$}~ {} moved from #{} to #{}",
+                    "", call, from, to, call.depth
+                )?,
+                Change::Returned {
+                    ref before,
+                    ref after,
+                } => writeln!(
+                    f,
+                    "{:4$}! {} = {} -> {}",
+                    "",
+                    after,
+                    ret(before),
+                    ret(after),
+                    after.depth
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares the calls recorded in `before` with the ones in `after`
+///
+/// Calls are matched up by their name, depth, and arguments, keeping them in order, so the calls
+/// that are left over were added, removed, or (if there's a leftover call like it in the other
+/// recording) moved. Matched calls whose return values differ are reported too.
+///
+/// ```
+/// let before = r#"{"event":"enter","fn":"load","depth":0,"thread":"main","args":{"id":"1"}}
+/// {"event":"exit","fn":"load","depth":0,"thread":"main","return":"Ok(7)"}
+/// {"event":"enter","fn":"save","depth":0,"thread":"main","args":{}}
+/// {"event":"exit","fn":"save","depth":0,"thread":"main","return":"()"}"#;
+/// let after = r#"{"event":"enter","fn":"load","depth":0,"thread":"main","args":{"id":"1"}}
+/// {"event":"exit","fn":"load","depth":0,"thread":"main","return":"Err(NotFound)"}"#;
+///
+/// let diff = trace_runtime::diff::diff(before, after);
+/// assert_eq!(diff.to_string(), "! load(id = 1) = Ok(7) -> Err(NotFound)\n- save()\n");
+/// ```
+///
+/// Matching takes time and memory proportional to the product of the numbers of calls, so very
+/// long recordings are best cut down to the part of the run that's of interest first.
+pub fn diff(before: &str, after: &str) -> Diff {
+    let before = calls(before);
+    let after = calls(after);
+    let key = |call: &Call| (call.name.clone(), call.depth, call.args.clone());
+
+    // the longest common subsequence of the calls, by their keys
+    let mut lengths = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lengths[i][j] = if key(&before[i]) == key(&after[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    enum Step {
+        Same(usize, usize),
+        Removed(usize),
+        Added(usize),
+    }
+    let mut steps = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && key(&before[i]) == key(&after[j]) {
+            steps.push(Step::Same(i, j));
+            i += 1;
+            j += 1;
+        } else if j == after.len() || (i < before.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            steps.push(Step::Removed(i));
+            i += 1;
+        } else {
+            steps.push(Step::Added(j));
+            j += 1;
+        }
+    }
+
+    // a call that was removed in one place and added in another was moved, and is reported where
+    // it ends up
+    let mut removed = HashMap::<_, Vec<usize>>::new();
+    for step in &steps {
+        if let Step::Removed(i) = *step {
+            removed.entry(key(&before[i])).or_default().push(i);
+        }
+    }
+    let mut moved_from = HashMap::new();
+    for step in &steps {
+        if let Step::Added(j) = *step {
+            if let Some(is) = removed.get_mut(&key(&after[j])) {
+                if !is.is_empty() {
+                    moved_from.insert(j, is.remove(0));
+                }
+            }
+        }
+    }
+    let moved = moved_from.values().copied().collect::<Vec<_>>();
+
+    let changes = steps
+        .into_iter()
+        .filter_map(|step| match step {
+            Step::Same(i, j) if before[i].ret != after[j].ret => Some(Change::Returned {
+                before: before[i].clone(),
+                after: after[j].clone(),
+            }),
+            Step::Same(..) => None,
+            Step::Removed(i) if moved.contains(&i) => None,
+            Step::Removed(i) => Some(Change::Removed(before[i].clone())),
+            Step::Added(j) => match moved_from.get(&j) {
+                Some(&i) => Some(Change::Moved {
+                    call: after[j].clone(),
+                    from: i + 1,
+                    to: j + 1,
+                }),
+                None => Some(Change::Added(after[j].clone())),
+            },
+        })
+        .collect();
+    Diff { changes }
+}
+
+/// The calls of a recording, in the order they were entered
+fn calls(recording: &str) -> Vec<Call> {
+    let mut calls = Vec::<Call>::new();
+    // the calls that haven't exited yet, by thread
+    let mut open = HashMap::<String, Vec<usize>>::new();
+    for line in recording.lines() {
+        let Some(Json::Object(fields)) = Parser::new(line.trim()).parse_line() else {
+            continue;
+        };
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(field_name, _)| field_name == name)
+                .map(|(_, value)| value)
+        };
+        let (Some(Json::String(event)), Some(Json::String(name)), Some(Json::Number(depth))) =
+            (field("event"), field("fn"), field("depth"))
+        else {
+            continue;
+        };
+        let thread = match field("thread") {
+            Some(Json::String(thread)) => thread.clone(),
+            _ => String::new(),
+        };
+        let stack = open.entry(thread).or_default();
+        match event.as_str() {
+            "enter" => {
+                let args = match field("args") {
+                    Some(Json::Object(args)) => args
+                        .iter()
+                        .filter_map(|(name, value)| match *value {
+                            Json::String(ref value) => Some((name.clone(), value.clone())),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => vec![],
+                };
+                stack.push(calls.len());
+                calls.push(Call {
+                    name: name.clone(),
+                    depth: depth.parse().unwrap_or(0),
+                    args,
+                    ret: None,
+                });
+            }
+            "exit" => {
+                // calls exit in the reverse order they were entered on a thread, but the enter
+                // event of a call (e.g. one traced with `errors_only`) might be missing
+                let position = stack.iter().rposition(|&i| calls[i].name == *name);
+                match position {
+                    Some(position) => {
+                        let i = stack[position];
+                        stack.truncate(position);
+                        if let Some(Json::String(ret)) = field("return") {
+                            calls[i].ret = Some(ret.clone());
+                        }
+                    }
+                    None => calls.push(Call {
+                        name: name.clone(),
+                        depth: depth.parse().unwrap_or(0),
+                        args: vec![],
+                        ret: match field("return") {
+                            Some(Json::String(ret)) => Some(ret.clone()),
+                            _ => None,
+                        },
+                    }),
+                }
+            }
+            _ => (),
+        }
+    }
+    calls
+}
+
+/// The JSON values that events are made of
+enum Json {
+    String(String),
+    Number(String),
+    Object(Vec<(String, Json)>),
+    Other,
+}
+
+/// Just enough of a JSON parser for the events printed with `format = "json"`
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(line: &'a str) -> Self {
+        Parser {
+            chars: line.chars().peekable(),
+        }
+    }
+
+    fn parse_line(mut self) -> Option<Json> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        self.chars.peek().is_none().then_some(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+        match *self.chars.peek()? {
+            '{' => self.object(),
+            '"' => self.string().map(Json::String),
+            '-' | '0'..='9' => {
+                let mut number = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| "+-.eE".contains(*c) || c.is_ascii_digit())
+                {
+                    number.push(c);
+                }
+                Some(Json::Number(number))
+            }
+            _ => {
+                // `true`, `false`, and `null`
+                let mut word = String::new();
+                while let Some(c) = self.chars.next_if(char::is_ascii_alphabetic) {
+                    word.push(c);
+                }
+                matches!(word.as_str(), "true" | "false" | "null").then_some(Json::Other)
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<Json> {
+        self.chars.next();
+        let mut fields = vec![];
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_some() {
+            return Some(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let name = self.string()?;
+            self.skip_whitespace();
+            self.chars.next_if_eq(&':')?;
+            fields.push((name, self.value()?));
+            self.skip_whitespace();
+            match self.chars.next()? {
+                ',' => (),
+                '}' => return Some(Json::Object(fields)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.chars.next_if_eq(&'"')?;
+        let mut string = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(string),
+                '\\' => match self.chars.next()? {
+                    'n' => string.push('\n'),
+                    't' => string.push('\t'),
+                    'r' => string.push('\r'),
+                    'b' => string.push('\u{8}'),
+                    'f' => string.push('\u{c}'),
+                    'u' => {
+                        let hex = (0..4)
+                            .map(|_| self.chars.next())
+                            .collect::<Option<String>>()?;
+                        string.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                    }
+                    c => string.push(c),
+                },
+                c => string.push(c),
+            }
+        }
+    }
+}
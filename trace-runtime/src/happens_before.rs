@@ -0,0 +1,332 @@
+//! Recording calls along with the order they're known to happen in, for testing concurrent code
+//!
+//! Functions traced with `#[trace(output = "happens_before")]` record when they're entered and
+//! exited while [`record`] runs, on its thread and on the threads it starts with [`spawn`] (and
+//! the threads those start, and so on). Calls on the same thread happen in the order they were
+//! made. Between threads, everything a thread did before [`spawn`] happens before everything the
+//! new thread does, and everything a thread did happens before whatever the thread that
+//! [`join`](JoinHandle::join)s it does afterwards. Two calls that aren't ordered either way could
+//! have run at the same time, whether or not they actually did in this run, which is what
+//! [`Recording::assert_never_concurrent`] checks for.
+//!
+//! Only `spawn` and `join` order threads, so calls that are kept apart by a lock or a channel are
+//! still reported as possibly concurrent.
+//!
+//! ```
+//! use trace_runtime::happens_before::{self, record, spawn};
+//!
+//! # fn load() {
+//! #     let _call = happens_before::enter("load");
+//! # }
+//! # fn save() {
+//! #     let _call = happens_before::enter("save");
+//! # }
+//! // `load` and `save` are traced with `#[trace(output = "happens_before")]`
+//! let ((), recording) = record(|| {
+//!     let loader = spawn(load);
+//!     loader.join().unwrap();
+//!     save();
+//! });
+//! recording.assert_never_concurrent("load", "save");
+//! recording.assert_happens_before("load", "save");
+//! ```
+
+use std::{
+    cell::RefCell,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+};
+
+/// A vector clock, with the number of events seen from each thread of a recording
+type Clock = Vec<u64>;
+
+/// What the threads of a recording share
+struct Shared {
+    calls: Mutex<Vec<Call>>,
+    threads: AtomicUsize,
+}
+
+/// The recording the current thread takes part in
+struct Context {
+    shared: Arc<Shared>,
+    thread: usize,
+    clock: Clock,
+}
+
+impl Context {
+    /// Counts an event on the current thread, returning the clock as of it
+    fn tick(&mut self) -> Clock {
+        if self.clock.len() <= self.thread {
+            self.clock.resize(self.thread + 1, 0);
+        }
+        self.clock[self.thread] += 1;
+        self.clock.clone()
+    }
+
+    fn merge(&mut self, other: &[u64]) {
+        if self.clock.len() < other.len() {
+            self.clock.resize(other.len(), 0);
+        }
+        for (count, &other_count) in self.clock.iter_mut().zip(other) {
+            *count = (*count).max(other_count);
+        }
+    }
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+// whether everything up to `a` is known to have happened by `b`
+fn clock_le(a: &[u64], b: &[u64]) -> bool {
+    a.iter()
+        .enumerate()
+        .all(|(thread, &count)| count <= b.get(thread).copied().unwrap_or(0))
+}
+
+/// A call recorded by [`record`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Call {
+    /// The name of the function
+    pub name: &'static str,
+    /// The thread the call was made on, numbered in the order the threads joined the recording,
+    /// starting with 0 for the one that called [`record`]
+    pub thread: usize,
+    enter: Clock,
+    /// `None` if the call was still running when the recording ended
+    exit: Option<Clock>,
+}
+
+impl Call {
+    /// Whether this call returned before `other` was made, as far as the order of the recording
+    /// goes
+    pub fn happens_before(&self, other: &Call) -> bool {
+        self.exit
+            .as_ref()
+            .is_some_and(|exit| clock_le(exit, &other.enter))
+    }
+
+    /// Whether this call and `other` were made on different threads and neither happens before
+    /// the other, so they could have run at the same time
+    pub fn concurrent_with(&self, other: &Call) -> bool {
+        self.thread != other.thread && !self.happens_before(other) && !other.happens_before(self)
+    }
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` on thread {}", self.name, self.thread)
+    }
+}
+
+/// The calls made while [`record`] ran
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    calls: Vec<Call>,
+}
+
+impl Recording {
+    /// Every recorded call, in the order they were made in
+    pub fn calls(&self) -> &[Call] {
+        &self.calls
+    }
+
+    /// The recorded calls of the function `name`
+    pub fn calls_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Call> + 'a {
+        self.calls.iter().filter(move |call| call.name == name)
+    }
+
+    /// The pairs of calls of `f` and `g` that could have run at the same time
+    pub fn concurrent(&self, f: &str, g: &str) -> Vec<(&Call, &Call)> {
+        let mut concurrent = vec![];
+        for f_call in self.calls.iter().filter(|call| call.name == f) {
+            for g_call in self.calls.iter().filter(|call| call.name == g) {
+                if f_call.concurrent_with(g_call) {
+                    concurrent.push((f_call, g_call));
+                }
+            }
+        }
+        concurrent
+    }
+
+    /// Panics if a call of `f` could have run at the same time as a call of `g`
+    #[track_caller]
+    pub fn assert_never_concurrent(&self, f: &str, g: &str) {
+        let concurrent = self.concurrent(f, g);
+        if let Some(&(f_call, g_call)) = concurrent.first() {
+            panic!(
+                "{} may run concurrently with {} ({} pair(s) of calls aren't ordered)",
+                f_call,
+                g_call,
+                concurrent.len()
+            );
+        }
+    }
+
+    /// Panics if a call of `f` doesn't happen before every call of `g`
+    #[track_caller]
+    pub fn assert_happens_before(&self, f: &str, g: &str) {
+        for f_call in self.calls_of(f) {
+            if let Some(g_call) = self
+                .calls_of(g)
+                .find(|g_call| !f_call.happens_before(g_call))
+            {
+                panic!("{} doesn't happen before {}", f_call, g_call);
+            }
+        }
+    }
+}
+
+/// Runs `f`, recording the calls made by it and by the threads it starts with [`spawn`]
+///
+/// Calls made by threads that are still running when `f` returns are recorded up to that point.
+pub fn record<T>(f: impl FnOnce() -> T) -> (T, Recording) {
+    let shared = Arc::new(Shared {
+        calls: Mutex::new(vec![]),
+        threads: AtomicUsize::new(1),
+    });
+    let context = Context {
+        shared: Arc::clone(&shared),
+        thread: 0,
+        clock: vec![],
+    };
+
+    // whatever the thread was recording before is restored even if `f` panics
+    struct Restore(Option<Context>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CONTEXT.with(|c| *c.borrow_mut() = self.0.take());
+        }
+    }
+    let _restore = Restore(CONTEXT.with(|c| c.borrow_mut().replace(context)));
+    let value = f();
+    let calls = shared
+        .calls
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    (value, Recording { calls })
+}
+
+/// Records that the function `name` was entered, and that it exited once the returned guard is
+/// dropped
+///
+/// Used by functions traced with `#[trace(output = "happens_before")]`. Does nothing outside of
+/// [`record`].
+pub fn enter(name: &'static str) -> CallGuard {
+    let call = CONTEXT.with(|c| {
+        let mut context = c.borrow_mut();
+        let context = context.as_mut()?;
+        let enter = context.tick();
+        let mut calls = context
+            .shared
+            .calls
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        calls.push(Call {
+            name,
+            thread: context.thread,
+            enter,
+            exit: None,
+        });
+        Some((Arc::clone(&context.shared), calls.len() - 1))
+    });
+    CallGuard(call)
+}
+
+/// Records that a call exited when it's dropped, see [`enter`]
+#[must_use]
+pub struct CallGuard(Option<(Arc<Shared>, usize)>);
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        let Some((ref shared, index)) = self.0 else {
+            return;
+        };
+        // the future of an `async fn` may finish on a thread that isn't part of the recording,
+        // in which case its call never ends as far as the recording goes
+        CONTEXT.with(|c| {
+            let mut context = c.borrow_mut();
+            let Some(context) = context.as_mut().filter(|c| Arc::ptr_eq(&c.shared, shared)) else {
+                return;
+            };
+            let exit = context.tick();
+            let mut calls = shared.calls.lock().unwrap_or_else(PoisonError::into_inner);
+            calls[index].exit = Some(exit);
+        });
+    }
+}
+
+/// Like [`std::thread::spawn`], but the new thread takes part in the recording of the current
+/// thread (if there is one), and whatever it does happens after what the current thread did so far
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let child = CONTEXT.with(|c| {
+        let mut context = c.borrow_mut();
+        let context = context.as_mut()?;
+        let clock = context.tick();
+        Some(Context {
+            shared: Arc::clone(&context.shared),
+            thread: context.shared.threads.fetch_add(1, Ordering::Relaxed),
+            clock,
+        })
+    });
+    let final_clock = Arc::new(Mutex::new(None));
+
+    // the clock of the thread is handed back even if it panics, so joining it still orders it
+    struct HandBack(Arc<Mutex<Option<Clock>>>);
+    impl Drop for HandBack {
+        fn drop(&mut self) {
+            let clock = CONTEXT.with(|c| c.borrow_mut().take().map(|context| context.clock));
+            *self.0.lock().unwrap_or_else(PoisonError::into_inner) = clock;
+        }
+    }
+    let hand_back = HandBack(Arc::clone(&final_clock));
+    let handle = thread::spawn(move || {
+        let _hand_back = hand_back;
+        CONTEXT.with(|c| *c.borrow_mut() = child);
+        f()
+    });
+    JoinHandle {
+        handle,
+        final_clock,
+    }
+}
+
+/// A thread started with [`spawn`]
+pub struct JoinHandle<T> {
+    handle: thread::JoinHandle<T>,
+    final_clock: Arc<Mutex<Option<Clock>>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Like [`std::thread::JoinHandle::join`], but whatever the current thread does afterwards
+    /// happens after everything the joined thread did
+    pub fn join(self) -> thread::Result<T> {
+        let result = self.handle.join();
+        let final_clock = self
+            .final_clock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        CONTEXT.with(|c| {
+            if let (Some(context), Some(final_clock)) = (c.borrow_mut().as_mut(), final_clock) {
+                context.merge(&final_clock);
+                context.tick();
+            }
+        });
+        result
+    }
+
+    /// The thread, like [`std::thread::JoinHandle::thread`]
+    pub fn thread(&self) -> &thread::Thread {
+        self.handle.thread()
+    }
+}
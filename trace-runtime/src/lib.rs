@@ -0,0 +1,614 @@
+//! Runtime support for the `runtime` feature of the `trace` crate
+//!
+//! Without it, every traced `mod` declares its own `DEPTH`, so indentation isn't shared between
+//! `mod`s, and there's no way to change what's traced for the whole program at once. With the
+//! `runtime` feature of `trace` enabled (and this crate added as a dependency):
+//!
+//! - `trace::init_depth_var!()` and traced `mod`s use [`DEPTH`] instead of declaring their own, so
+//!   indentation is consistent across all `mod`s and crates using it.
+//! - Traced functions print nothing while tracing is disabled with [`set_enabled`], and print
+//!   everything about their calls while the current thread is in a [`verbose_scope`].
+//! - Lines that would have been printed to stdout are printed with [`print_line!`] instead, so
+//!   they can be redirected with [`set_writer`], or collected with [`capture`] to be checked by a
+//!   test.
+//! - More writers can be added with [`add_writer`], each with its own [`Tier`] of detail, e.g. to
+//!   keep the console terse while a file records everything.
+//! - The prefixes, the words after them, and the formats of the arguments and return values can
+//!   be overridden for a run with environment variables, see [`prefix_enter`], [`prefix_exit`],
+//!   [`word_enter`], [`word_exit`], [`format_enter`], and [`format_exit`].
+//!
+//! With the `tracking-allocator` feature, it also provides [`TrackingAllocator`], which keeps track
+//! of how much memory is allocated so `#[trace(alloc_peak)]` can print the peak allocation of each
+//! call.
+//!
+//! Functions traced with `#[trace(tag = "...")]` are only printed while their tag is enabled, see
+//! [`enable_tag`] and [`disable_tag`], so whole subsystems can be traced or not without
+//! recompiling.
+//!
+//! Functions traced with `#[trace(output = "chrome_trace")]` record their calls with
+//! [`chrome_trace::begin`] and [`chrome_trace::end`], to be opened in `chrome://tracing` or
+//! Perfetto.
+//!
+//! With the `otel` feature, functions traced with `#[trace(otel)]` export their calls as
+//! OpenTelemetry spans with `otel::Call`, in the `otel` module.
+//!
+//! With the `registry` feature, functions traced with `#[trace(registry)]` can be listed with
+//! [`registry()`] and enabled or disabled one by one. With the `admin` feature, the handler in the
+//! `admin` module lets that be done over HTTP.
+//!
+//! Functions traced with `#[trace(output = "happens_before")]` record their calls in the
+//! `happens_before` module, along with which calls are known to happen before which, so tests of
+//! concurrent code can check that two functions never run at the same time.
+//!
+//! Functions traced with `#[trace(output = "call_graph")]` record which traced functions called
+//! them, and how many times, in the `call_graph` module, which can write it out as a Graphviz
+//! graph.
+//!
+//! Two runs recorded with `#[trace(format = "json")]` can be compared with [`diff::diff`], e.g. to
+//! find the calls that changed between a commit that worked and one that doesn't.
+//!
+//! Functions traced with `#[trace(debugger_hook)]` call [`debugger_hook`] when they're entered and
+//! exited, so a single breakpoint on it catches every traced call. Functions traced with
+//! `#[trace(breakpoint)]` call [`breakpoint`] when they're entered instead, which stops the program
+//! in an attached debugger without setting any breakpoints.
+//!
+//! ```
+//! trace_runtime::set_enabled(false);
+//! assert!(!trace_runtime::enabled());
+//!
+//! trace_runtime::set_writer(|line| eprintln!("{}", line));
+//! trace_runtime::write_line(format_args!("[+] Entering foo(a = {})", 1));
+//! ```
+
+#[cfg(feature = "admin")]
+pub mod admin;
+#[cfg(feature = "tracking-allocator")]
+mod alloc;
+pub mod call_graph;
+pub mod chrome_trace;
+pub mod diff;
+pub mod happens_before;
+#[cfg(feature = "otel")]
+pub mod otel;
+#[cfg(feature = "registry")]
+mod registry;
+
+#[cfg(feature = "tracking-allocator")]
+pub use alloc::{allocated, AllocPeak, TrackingAllocator};
+#[cfg(feature = "registry")]
+pub use registry::{registry, TracedFn};
+// for the registrations generated by `#[trace(registry)]`
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+pub use inventory;
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    env, fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        OnceLock, PoisonError, RwLock,
+    },
+};
+
+thread_local! {
+    /// The depth used for indenting the output, shared by all traced functions
+    ///
+    /// Like the `DEPTH` declared by `trace::init_depth_var!()`, it's thread local, so every thread
+    /// is indented separately.
+    pub static DEPTH: Cell<usize> = const { Cell::new(0) };
+
+    /// How many calls of [`verbose_scope`] the current thread is in
+    static VERBOSE_SCOPES: Cell<usize> = const { Cell::new(0) };
+
+    /// The lines written by the current thread while it's running a closure passed to [`capture`]
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+
+    /// What writes the lines the current thread's functions traced with `buffered` are holding
+    /// back, before the next line it writes, see [`flush_before_next_line`]
+    static FLUSH_BEFORE_NEXT_LINE: Cell<Option<fn()>> = const { Cell::new(None) };
+
+    /// What writes the lines the current thread is holding back until its outermost traced call
+    /// returns, see [`write_when_outermost_call_returns`]
+    static WRITE_WHEN_OUTERMOST_CALL_RETURNS: RefCell<Vec<fn()>> = const { RefCell::new(Vec::new()) };
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+type Writer = Box<dyn Fn(fmt::Arguments) + Send + Sync>;
+
+static WRITER: RwLock<Option<Writer>> = RwLock::new(None);
+
+static TIER: AtomicU8 = AtomicU8::new(Tier::Trace as u8);
+
+static WRITERS: RwLock<Vec<(Tier, Writer)>> = RwLock::new(Vec::new());
+
+/// How much detail of a traced call a writer gets, see [`set_tier`] and [`add_writer`]
+///
+/// Every tier includes what the ones before it do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tier {
+    /// Only which functions were entered and exited, e.g. `[+] Entering foo()`
+    Info,
+    /// The arguments and return values too, e.g. `[+] Entering foo(a = 1)`
+    Debug,
+    /// Everything else too, like how long calls took with `timing`
+    Trace,
+}
+
+/// The formatting set with environment variables, which are only read once
+struct Overrides {
+    prefix_enter: Option<String>,
+    prefix_exit: Option<String>,
+    format_enter: Option<String>,
+    format_exit: Option<String>,
+    word_enter: Option<String>,
+    word_exit: Option<String>,
+}
+
+static OVERRIDES: OnceLock<Overrides> = OnceLock::new();
+
+fn overrides() -> &'static Overrides {
+    OVERRIDES.get_or_init(|| Overrides {
+        prefix_enter: env::var("TRACE_PREFIX_ENTER").ok(),
+        prefix_exit: env::var("TRACE_PREFIX_EXIT").ok(),
+        format_enter: env::var("TRACE_FORMAT_ENTER").ok(),
+        format_exit: env::var("TRACE_FORMAT_EXIT").ok(),
+        word_enter: env::var("TRACE_WORD_ENTER").ok(),
+        word_exit: env::var("TRACE_WORD_EXIT").ok(),
+    })
+}
+
+/// Enables or disables printing for all traced functions. Enabled by default.
+///
+/// Calls made while tracing is disabled still count towards the depth, so lines printed after
+/// enabling it again are indented correctly.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether traced functions print anything, see [`set_enabled`]
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Which tags are enabled: the ones that were enabled or disabled by name, and whether the others
+/// are
+struct Tags {
+    named: HashMap<String, bool>,
+    others: bool,
+}
+
+static TAGS: OnceLock<RwLock<Tags>> = OnceLock::new();
+
+// the tags listed in `TRACE_TAGS` are the only ones enabled to begin with, if it's set
+fn tags() -> &'static RwLock<Tags> {
+    TAGS.get_or_init(|| {
+        let tags = match env::var("TRACE_TAGS") {
+            Ok(listed) => Tags {
+                named: listed
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(|tag| (tag.to_string(), true))
+                    .collect(),
+                others: false,
+            },
+            Err(_) => Tags {
+                named: HashMap::new(),
+                others: true,
+            },
+        };
+        RwLock::new(tags)
+    })
+}
+
+/// Enables printing the functions traced with `#[trace(tag = "...")]` and the given tag
+///
+/// Every tag is enabled by default, unless the `TRACE_TAGS` environment variable is set, in which
+/// case only the tags it lists (separated by commas, e.g. `TRACE_TAGS=parser,io`) are.
+///
+/// ```
+/// trace_runtime::disable_tag("parser");
+/// assert!(!trace_runtime::tag_enabled("parser"));
+/// trace_runtime::enable_tag("parser");
+/// assert!(trace_runtime::tag_enabled("parser"));
+/// ```
+pub fn enable_tag(tag: &str) {
+    let mut tags = tags().write().unwrap_or_else(PoisonError::into_inner);
+    tags.named.insert(tag.to_string(), true);
+}
+
+/// Disables printing the functions traced with the given tag, see [`enable_tag`]
+pub fn disable_tag(tag: &str) {
+    let mut tags = tags().write().unwrap_or_else(PoisonError::into_inner);
+    tags.named.insert(tag.to_string(), false);
+}
+
+/// Whether the functions traced with the given tag are printed, see [`enable_tag`]
+pub fn tag_enabled(tag: &str) -> bool {
+    let tags = tags().read().unwrap_or_else(PoisonError::into_inner);
+    tags.named.get(tag).copied().unwrap_or(tags.others)
+}
+
+/// Runs `f` with every traced function called by the current thread in the meantime printed in
+/// full detail, and returns what it returns
+///
+/// Calls are printed even if they'd be left out otherwise, e.g. because tracing is disabled with
+/// [`set_enabled`], or by `max_depth`, `sample`, `only_when`, or a disabled group. Their enter
+/// lines show all of their arguments (except `redact`ed ones) pretty printed, and their exit lines
+/// show the pretty printed return value and how long the call took. That way one part of a
+/// program can be looked at closely while the rest of it is traced tersely, or not at all:
+///
+/// ```
+/// trace_runtime::set_enabled(false);
+/// # let handle_request = || ();
+/// let response = trace_runtime::verbose_scope(|| handle_request());
+/// ```
+///
+/// Functions traced with `format = "json"` keep printing their usual lines.
+pub fn verbose_scope<T>(f: impl FnOnce() -> T) -> T {
+    // leaves the scope even if `f` panics
+    struct Scope;
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            VERBOSE_SCOPES.with(|scopes| scopes.set(scopes.get() - 1));
+        }
+    }
+    VERBOSE_SCOPES.with(|scopes| scopes.set(scopes.get() + 1));
+    let _scope = Scope;
+    f()
+}
+
+/// Whether the current thread is running a closure passed to [`verbose_scope`]
+pub fn verbose() -> bool {
+    VERBOSE_SCOPES.with(|scopes| scopes.get() > 0)
+}
+
+/// Sends the lines of all traced functions that print to stdout by default to `writer` instead
+///
+/// `writer` is called with the contents of each line, without a trailing newline. It must not call
+/// [`set_writer`] or [`reset_writer`] itself.
+pub fn set_writer(writer: impl Fn(fmt::Arguments) + Send + Sync + 'static) {
+    *WRITER.write().unwrap_or_else(PoisonError::into_inner) = Some(Box::new(writer));
+}
+
+/// Prints lines to stdout again after [`set_writer`]
+pub fn reset_writer() {
+    *WRITER.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Sets how much detail the lines printed to stdout (or written with the writer given to
+/// [`set_writer`]) have. [`Tier::Trace`] by default.
+pub fn set_tier(tier: Tier) {
+    TIER.store(tier as u8, Ordering::Relaxed);
+}
+
+/// How much detail the lines printed to stdout have, see [`set_tier`]
+pub fn tier() -> Tier {
+    match TIER.load(Ordering::Relaxed) {
+        0 => Tier::Info,
+        1 => Tier::Debug,
+        _ => Tier::Trace,
+    }
+}
+
+/// Sends the lines of traced functions to `writer` as well, with the detail of `tier`
+///
+/// Unlike [`set_writer`], this adds to where lines go, so e.g. stdout can get [`Tier::Info`] lines
+/// while a file gets [`Tier::Trace`] ones:
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use trace_runtime::Tier;
+///
+/// let lines = Arc::new(Mutex::new(vec![]));
+/// let recorded = Arc::clone(&lines);
+/// trace_runtime::add_writer(Tier::Debug, move |line| {
+///     recorded.lock().unwrap().push(line.to_string())
+/// });
+/// trace_runtime::set_tier(Tier::Info);
+/// trace_runtime::write_tiered_line(
+///     format_args!("[-] Exiting foo"),
+///     format_args!("[-] Exiting foo = 2"),
+///     format_args!("[-] Exiting foo = 2 (1.2ms)"),
+/// );
+/// assert_eq!(*lines.lock().unwrap(), ["[-] Exiting foo = 2"]);
+/// # trace_runtime::remove_writers();
+/// # trace_runtime::set_tier(Tier::Trace);
+/// ```
+///
+/// Like with [`set_writer`], `writer` must not add or remove writers itself.
+pub fn add_writer(tier: Tier, writer: impl Fn(fmt::Arguments) + Send + Sync + 'static) {
+    WRITERS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push((tier, Box::new(writer)));
+}
+
+/// Removes all the writers added with [`add_writer`]
+pub fn remove_writers() {
+    WRITERS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+/// Writes a line with the writer given to [`set_writer`], or prints it to stdout if there isn't
+/// one, and writes it with every writer added with [`add_writer`]
+pub fn write_line(line: fmt::Arguments) {
+    write_tiered_line(line, line, line);
+}
+
+/// Like [`write_line`], but with a line for every [`Tier`], each of which is only formatted if a
+/// writer of that tier is there to write it
+pub fn write_tiered_line(info: fmt::Arguments, debug: fmt::Arguments, trace: fmt::Arguments) {
+    if let Some(flush) = FLUSH_BEFORE_NEXT_LINE.with(Cell::take) {
+        flush();
+    }
+    let line = |tier| match tier {
+        Tier::Info => info,
+        Tier::Debug => debug,
+        Tier::Trace => trace,
+    };
+    let captured = CAPTURED.with(|captured| match *captured.borrow_mut() {
+        Some(ref mut lines) => {
+            lines.push(trace.to_string());
+            true
+        }
+        None => false,
+    });
+    if captured {
+        return;
+    }
+    match *WRITER.read().unwrap_or_else(PoisonError::into_inner) {
+        Some(ref writer) => writer(line(tier())),
+        None => println!("{}", line(tier())),
+    }
+    for (tier, writer) in WRITERS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+    {
+        writer(line(*tier));
+    }
+}
+
+/// Has `flush` called before the next line the current thread writes, which is how functions
+/// traced with `buffered` get the lines they're holding back written before the lines of other
+/// traced functions
+#[doc(hidden)]
+pub fn flush_before_next_line(flush: fn()) {
+    FLUSH_BEFORE_NEXT_LINE.with(|flush_before_next_line| flush_before_next_line.set(Some(flush)));
+}
+
+/// Has `write` called when the outermost traced call of the current thread returns, before its
+/// exit line, which is how the calls counted by `collapse_repeats` or dropped by `rate_limit` are
+/// still reported when no other line of the function follows them
+#[doc(hidden)]
+pub fn write_when_outermost_call_returns(write: fn()) {
+    WRITE_WHEN_OUTERMOST_CALL_RETURNS.with(|writes| {
+        let mut writes = writes.borrow_mut();
+        if !writes.iter().any(|w| *w as usize == write as usize) {
+            writes.push(write);
+        }
+    });
+}
+
+/// Calls what was given to [`write_when_outermost_call_returns`] on the current thread, which the
+/// outermost traced call does when it returns
+#[doc(hidden)]
+pub fn write_pending_lines() {
+    for write in WRITE_WHEN_OUTERMOST_CALL_RETURNS.with(RefCell::take) {
+        write();
+    }
+}
+
+/// Runs `f` and returns the lines the current thread wrote in the meantime, instead of printing
+/// them or passing them to any writers
+///
+/// The lines have all the detail of [`Tier::Trace`] and no trailing newline, so tests can check
+/// exactly which calls were made without capturing stdout:
+///
+/// ```
+/// let lines = trace_runtime::capture(|| {
+///     trace_runtime::write_line(format_args!("[+] Entering foo(a = 1)"));
+///     trace_runtime::write_line(format_args!("[-] Exiting foo = 2"));
+/// });
+/// assert_eq!(lines, ["[+] Entering foo(a = 1)", "[-] Exiting foo = 2"]);
+/// ```
+///
+/// Only the lines of the current thread are captured, so tests running in parallel don't see each
+/// other's lines, and only the ones that would have been printed to stdout, not the ones of
+/// functions traced with `writer` or `logging`. If `f` panics, the lines are dropped. Capturing
+/// again inside `f` takes the lines written meanwhile away from the outer capture.
+pub fn capture(f: impl FnOnce()) -> Vec<String> {
+    // whatever the thread was capturing before is restored even if `f` panics
+    struct Restore(Option<Vec<String>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CAPTURED.with(|captured| *captured.borrow_mut() = self.0.take());
+        }
+    }
+    let _restore = Restore(CAPTURED.with(|captured| captured.borrow_mut().replace(vec![])));
+    f();
+    CAPTURED
+        .with(|captured| captured.borrow_mut().take())
+        .unwrap_or_default()
+}
+
+/// The prefix of enter lines set with the `TRACE_PREFIX_ENTER` environment variable, which
+/// replaces the one given to `#[trace]` (e.g. `[+]`)
+///
+/// Like all the overrides, the variable is read the first time a traced function is called, and
+/// doesn't apply to functions traced with `compact` or `format = "json"`. The prefix is printed as
+/// is, without filling in placeholders like `{depth}`.
+pub fn prefix_enter() -> Option<&'static str> {
+    overrides().prefix_enter.as_deref()
+}
+
+/// The prefix of exit lines (and of the lines of calls that panicked, unless they have their own
+/// prefix) set with the `TRACE_PREFIX_EXIT` environment variable, see [`prefix_enter`]
+pub fn prefix_exit() -> Option<&'static str> {
+    overrides().prefix_exit.as_deref()
+}
+
+/// The word after the prefix of enter lines set with the `TRACE_WORD_ENTER` environment variable,
+/// which replaces the one given to `#[trace]` (`Entering` by default), see [`prefix_enter`]
+pub fn word_enter() -> Option<&'static str> {
+    overrides().word_enter.as_deref()
+}
+
+/// The word after the prefix of exit lines set with the `TRACE_WORD_EXIT` environment variable,
+/// which replaces the one given to `#[trace]` (`Exiting` by default), see [`prefix_enter`]
+pub fn word_exit() -> Option<&'static str> {
+    overrides().word_exit.as_deref()
+}
+
+/// What's printed between the parentheses of enter lines, set with the `TRACE_FORMAT_ENTER`
+/// environment variable, where `{args}` is replaced by the arguments as they'd otherwise be printed
+///
+/// E.g. with `TRACE_FORMAT_ENTER='…'`, `foo(a = 1, b = 2)` is printed as `foo(…)`.
+pub fn format_enter() -> Option<&'static str> {
+    overrides().format_enter.as_deref()
+}
+
+/// What's printed after the `=` of exit lines, set with the `TRACE_FORMAT_EXIT` environment
+/// variable, where `{r}` is replaced by the return value as it'd otherwise be printed
+pub fn format_exit() -> Option<&'static str> {
+    overrides().format_exit.as_deref()
+}
+
+/// Called with the name of the function whenever a function traced with `debugger_hook` is
+/// entered or exited
+///
+/// It does nothing, and only exists to have a breakpoint set on it, e.g. with
+/// `break trace_runtime::debugger_hook` in GDB or `b trace_runtime::debugger_hook` in LLDB, where
+/// the arguments tell which function it is and whether it's being entered or exited.
+#[inline(never)]
+pub fn debugger_hook(fn_name: &'static str, entering: bool) {
+    std::hint::black_box((fn_name, entering));
+}
+
+/// Stops the program in the attached debugger, as if a breakpoint was set where it's called
+///
+/// Functions traced with `breakpoint` call it when they're entered (and their call is printed), so
+/// running the program in a debugger stops at exactly those calls. Nothing happens if no debugger
+/// is attached, which is checked on Linux, Android, Windows, and Apple's platforms, while elsewhere
+/// it's assumed that none is, so the program isn't stopped (e.g. by `SIGTRAP`) without one. It also
+/// does nothing on architectures other than x86, ARM, and RISC-V.
+#[inline(always)]
+pub fn breakpoint() {
+    if debugger_attached() {
+        trap();
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn debugger_attached() -> bool {
+    // a process being debugged is traced by the debugger
+    std::fs::read_to_string("/proc/self/status").is_ok_and(|status| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("TracerPid:"))
+            .is_some_and(|pid| pid.trim() != "0")
+    })
+}
+
+#[cfg(windows)]
+fn debugger_attached() -> bool {
+    extern "system" {
+        fn IsDebuggerPresent() -> i32;
+    }
+    unsafe { IsDebuggerPresent() != 0 }
+}
+
+#[cfg(target_vendor = "apple")]
+fn debugger_attached() -> bool {
+    use std::{ffi::c_void, mem, ptr};
+
+    extern "C" {
+        fn getpid() -> i32;
+        fn sysctl(
+            name: *mut i32,
+            namelen: u32,
+            oldp: *mut c_void,
+            oldlenp: *mut usize,
+            newp: *mut c_void,
+            newlen: usize,
+        ) -> i32;
+    }
+    const CTL_KERN: i32 = 1;
+    const KERN_PROC: i32 = 14;
+    const KERN_PROC_PID: i32 = 1;
+    const P_TRACED: i32 = 0x800;
+    // a process being debugged is marked as traced in the `p_flag` of its `kinfo_proc`, which
+    // comes after a union the size of two pointers and two pointers
+    let p_flag_offset = 4 * mem::size_of::<usize>();
+
+    let mut name = [CTL_KERN, KERN_PROC, KERN_PROC_PID, unsafe { getpid() }];
+    let mut info = [0u64; 128];
+    let mut size = mem::size_of_val(&info);
+    let result = unsafe {
+        sysctl(
+            name.as_mut_ptr(),
+            name.len() as u32,
+            info.as_mut_ptr().cast(),
+            &mut size,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if result != 0 || size < p_flag_offset + mem::size_of::<i32>() {
+        return false;
+    }
+    let p_flag = unsafe {
+        info.as_ptr()
+            .cast::<u8>()
+            .add(p_flag_offset)
+            .cast::<i32>()
+            .read()
+    };
+    p_flag & P_TRACED != 0
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    windows,
+    target_vendor = "apple"
+)))]
+fn debugger_attached() -> bool {
+    false
+}
+
+#[inline(always)]
+fn trap() {
+    // the same instructions debuggers use for their own breakpoints, which they know to continue
+    // after
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    unsafe {
+        std::arch::asm!("int3", options(nomem, nostack));
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        std::arch::asm!("brk #0xf000", options(nomem, nostack));
+    }
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        std::arch::asm!("udf #254", options(nomem, nostack));
+    }
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe {
+        std::arch::asm!("ebreak", options(nomem, nostack));
+    }
+}
+
+/// Like `println!`, but writes the line with [`write_line`]
+#[macro_export]
+macro_rules! print_line {
+    ($($arg:tt)*) => {
+        $crate::write_line(::std::format_args!($($arg)*))
+    };
+}
@@ -0,0 +1,68 @@
+//! Exporting calls as OpenTelemetry spans
+//!
+//! Every call of a function traced with `#[trace(otel)]` is a span of the tracer named `trace`
+//! from the global tracer provider, which is set with
+//! `opentelemetry::global::set_tracer_provider` (until it is, spans go nowhere). The span is named
+//! after the function, has the arguments as attributes, and gets a `return` event with the return
+//! value when the call returns. Calls made while a span is current are its children.
+
+use std::future::Future;
+
+use opentelemetry::{
+    context::{FutureExt, WithContext},
+    global,
+    trace::{Status, TraceContextExt, Tracer},
+    Context, ContextGuard, KeyValue,
+};
+
+/// The span of a call, which ends when it's dropped
+pub struct Call {
+    cx: Context,
+}
+
+impl Call {
+    /// Starts the span of a call of the function `name` with the given arguments, as a child of
+    /// the current span
+    pub fn start(name: &'static str, args: Vec<(&'static str, String)>) -> Self {
+        let tracer = global::tracer("trace");
+        let span = tracer
+            .span_builder(name)
+            .with_attributes(
+                args.into_iter()
+                    .map(|(name, value)| KeyValue::new(name, value)),
+            )
+            .start(&tracer);
+        Call {
+            cx: Context::current_with_span(span),
+        }
+    }
+
+    /// Makes the span current until the returned guard is dropped
+    pub fn enter(&self) -> ContextGuard {
+        self.cx.clone().attach()
+    }
+
+    /// Makes the span current whenever `future` is polled
+    pub fn instrument<F: Future>(&self, future: F) -> WithContext<F> {
+        future.with_context(self.cx.clone())
+    }
+
+    /// Ends the span, with a `return` event holding the return value if it's given
+    pub fn exit(self, ret: Option<String>) {
+        if let Some(ret) = ret {
+            self.cx
+                .span()
+                .add_event("return", vec![KeyValue::new("return", ret)]);
+        }
+    }
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        // a call that didn't return either panicked, or was an `async fn` whose future was dropped
+        if std::thread::panicking() {
+            self.cx.span().set_status(Status::error("panicked"));
+        }
+        self.cx.span().end();
+    }
+}
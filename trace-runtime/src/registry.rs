@@ -0,0 +1,80 @@
+//! The functions traced with `#[trace(registry)]`, which can be listed and toggled at runtime
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// A function traced with `registry`, see [`registry`]
+#[derive(Debug)]
+pub struct TracedFn {
+    /// The name of the function
+    pub name: &'static str,
+    /// The path of the module the function is in
+    pub module: &'static str,
+    /// The file the function is in
+    pub file: &'static str,
+    /// The line the function is on
+    pub line: u32,
+    /// The arguments given to `#[trace]`, as they were written
+    pub options: &'static str,
+    enabled: &'static AtomicBool,
+    calls: &'static AtomicU64,
+}
+
+impl TracedFn {
+    #[doc(hidden)]
+    pub const fn new(
+        name: &'static str,
+        module: &'static str,
+        file: &'static str,
+        line: u32,
+        options: &'static str,
+        enabled: &'static AtomicBool,
+        calls: &'static AtomicU64,
+    ) -> Self {
+        TracedFn {
+            name,
+            module,
+            file,
+            line,
+            options,
+            enabled,
+            calls,
+        }
+    }
+
+    /// The path of the function, e.g. `my_crate::parser::parse`
+    pub fn path(&self) -> String {
+        format!("{}::{}", self.module, self.name)
+    }
+
+    /// Whether the function prints anything when it's called. Enabled by default.
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// How many times the function has been called so far, whether or not it was enabled
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Enables or disables printing for the function. Like with [`set_enabled`](crate::set_enabled),
+    /// calls made while it's disabled still count towards the depth.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+}
+
+inventory::collect!(TracedFn);
+
+/// All the functions traced with `registry` in the program, in no particular order
+///
+/// Every such function registers itself when the program starts, whether or not it's ever called,
+/// so e.g. an admin endpoint can show everything that can be traced in the binary.
+///
+/// ```
+/// for traced_fn in trace_runtime::registry() {
+///     println!("{} ({}:{})", traced_fn.path(), traced_fn.file, traced_fn.line);
+/// }
+/// ```
+pub fn registry() -> impl Iterator<Item = &'static TracedFn> {
+    inventory::iter::<TracedFn>.into_iter()
+}